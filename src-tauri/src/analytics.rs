@@ -0,0 +1,217 @@
+// Periodic view/like/comment counts for uploaded shorts
+use crate::db::{Database, Short, ShortStats, VideoStatistics};
+use crate::dry_run;
+use crate::notifications::{self, Event};
+use crate::prompt::HookStyle;
+use crate::youtube_api::YoutubeClient;
+use crate::{youtube_upload, AppState};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Shorts stop being polled this long after upload
+const MAX_AGE_DAYS: i64 = 90;
+
+/// videos.list takes at most 50 ids per call
+const BATCH_SIZE: usize = 50;
+
+/// Wait before looking again when there is no API key or the fetch failed
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Fetch statistics for uploaded shorts every `stats_refresh_hours` for as
+/// long as the app runs
+pub async fn run(app: AppHandle) {
+    loop {
+        let state = app.state::<AppState>();
+        let wait = match refresh(&state).await {
+            Ok(Some(hours)) => Duration::from_secs(hours.max(1) as u64 * 3600),
+            Ok(None) => RETRY_INTERVAL,
+            Err(e) => {
+                tracing::warn!("Short statistics refresh failed: {}", e);
+                if youtube_upload::is_quota_error(&e) {
+                    if let Ok(config) = state.db.load_config() {
+                        notifications::notify(&config, Event::QuotaExhausted, None, "Statistics of uploaded shorts can't be refreshed until the quota resets");
+                    }
+                }
+                RETRY_INTERVAL
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Store a statistics sample for every recent upload. Returns the refresh
+/// interval, or `None` when no API key is configured.
+pub async fn refresh(state: &AppState) -> Result<Option<u32>, String> {
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    let since = chrono::Utc::now() - chrono::Duration::days(MAX_AGE_DAYS);
+    let mut videos = state.db.list_published("youtube", since).map_err(|e| e.to_string())?;
+    videos.retain(|(_, video_id)| !dry_run::is_fake_video_id(video_id));
+    if config.youtube_api_key.is_none() {
+        return Ok(None);
+    }
+    let youtube = state.clients.youtube(&config)?;
+    for batch in videos.chunks(BATCH_SIZE) {
+        let ids: Vec<&str> = batch.iter().map(|(_, video_id)| video_id.as_str()).collect();
+        let statistics = fetch_statistics(&youtube, &ids).await?;
+        let db = &state.db;
+        for (short_id, video_id) in batch {
+            // Missing from the response: deleted or made private
+            if let Some(stats) = statistics.get(video_id) {
+                db.insert_short_stats(*short_id, stats).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    let hook_styles = compare_hook_styles(&state.db).map_err(|e| e.to_string())?;
+    if let Some(best) = hook_styles.first() {
+        tracing::info!(
+            "Hook style {} leads A/B tests: won {} of {} variant group(s), {:.0} views on average",
+            best.hook_style.as_str(),
+            best.wins,
+            best.groups,
+            best.avg_views
+        );
+    }
+    Ok(Some(config.stats_refresh_hours))
+}
+
+/// Public statistics of up to 50 videos, keyed by video id
+async fn fetch_statistics(youtube: &impl YoutubeClient, video_ids: &[&str]) -> Result<HashMap<String, VideoStatistics>, String> {
+    let videos = youtube.videos(video_ids).await?;
+    let count = |s: Option<String>| s.and_then(|s| s.parse().ok());
+    Ok(videos
+        .into_iter()
+        .map(|video| {
+            let statistics = video.statistics.unwrap_or_default();
+            let stats = VideoStatistics {
+                views: count(statistics.view_count).unwrap_or(0),
+                likes: count(statistics.like_count),
+                comments: count(statistics.comment_count),
+            };
+            (video.id, stats)
+        })
+        .collect())
+}
+
+/// A short of a variant group with its latest statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct Variant {
+    pub short: Short,
+    pub latest: Option<ShortStats>,
+    /// Has the most views of its group. Only set once every variant has
+    /// statistics.
+    pub winner: bool,
+}
+
+/// How one hook style has done in the variant groups that are decided
+#[derive(Debug, Clone, Serialize)]
+pub struct HookStyleResult {
+    pub hook_style: HookStyle,
+    /// Decided groups it took part in
+    pub groups: usize,
+    /// Groups where it got the most views
+    pub wins: usize,
+    pub avg_views: f64,
+}
+
+/// The shorts of a variant group, first variant first, marking the winner
+pub fn variants(db: &Database, group_id: i64) -> rusqlite::Result<Vec<Variant>> {
+    let mut variants = Vec::new();
+    for short in db.list_variants(group_id)? {
+        let latest = match short.id {
+            Some(id) => db.get_short_stats(id)?.pop(),
+            None => None,
+        };
+        variants.push(Variant { short, latest, winner: false });
+    }
+    let views: Vec<_> = variants.iter().map(|v| v.latest.as_ref().map(|s| s.views)).collect();
+    if let Some(i) = winner(&views) {
+        variants[i].winner = true;
+    }
+    Ok(variants)
+}
+
+/// Hook styles ranked by how often their variant got the most views,
+/// counting only groups where every variant has statistics
+pub fn compare_hook_styles(db: &Database) -> rusqlite::Result<Vec<HookStyleResult>> {
+    let rows = db.list_variant_views()?;
+    let mut totals: HashMap<HookStyle, (usize, usize, i64)> = HashMap::new();
+    for group in rows.chunk_by(|a, b| a.group_id == b.group_id) {
+        let views: Vec<_> = group.iter().map(|row| row.views).collect();
+        let Some(best) = winner(&views) else { continue };
+        for (i, row) in group.iter().enumerate() {
+            let Some(hook_style) = row.hook_style else { continue };
+            let entry = totals.entry(hook_style).or_default();
+            entry.0 += 1;
+            entry.1 += usize::from(i == best);
+            entry.2 += row.views.unwrap_or(0);
+        }
+    }
+    let mut results: Vec<_> = totals
+        .into_iter()
+        .map(|(hook_style, (groups, wins, views))| HookStyleResult {
+            hook_style,
+            groups,
+            wins,
+            avg_views: views as f64 / groups as f64,
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        let rate = |r: &HookStyleResult| r.wins as f64 / r.groups as f64;
+        rate(b).total_cmp(&rate(a)).then(b.avg_views.total_cmp(&a.avg_views))
+    });
+    Ok(results)
+}
+
+/// Index of the most viewed variant, the first one on a tie. `None` until
+/// there are two variants and all of them have been counted.
+fn winner(views: &[Option<i64>]) -> Option<usize> {
+    if views.len() < 2 {
+        return None;
+    }
+    let views: Vec<i64> = views.iter().copied().collect::<Option<_>>()?;
+    let max = *views.iter().max()?;
+    views.iter().position(|&v| v == max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn group(db: &Database, views: &[Option<i64>]) -> Vec<i64> {
+        let trend_id = crate::trends::add_topic(db, "octopus facts").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let ids = db.create_shorts_for_languages(job_id, &["en".to_string()], views.len()).unwrap();
+        for (id, views) in ids.iter().zip(views) {
+            if let Some(views) = views {
+                db.insert_short_stats(*id, &VideoStatistics { views: *views, likes: None, comments: None }).unwrap();
+            }
+        }
+        ids
+    }
+    
+    #[test]
+    fn picks_the_most_viewed_variant() {
+        let db = Database::new_in_memory().unwrap();
+        let ids = group(&db, &[Some(100), Some(900), Some(300)]);
+        let variants = variants(&db, ids[0]).unwrap();
+        let winners: Vec<_> = variants.iter().map(|v| v.winner).collect();
+        assert_eq!(winners, [false, true, false]);
+        assert_eq!(variants[1].short.hook_style, Some(HookStyle::ALL[1]));
+    }
+    
+    #[test]
+    fn ranks_hook_styles_by_wins() {
+        let db = Database::new_in_memory().unwrap();
+        group(&db, &[Some(100), Some(900)]);
+        group(&db, &[Some(50), Some(70)]);
+        // Not decided until both have been counted
+        group(&db, &[Some(5_000), None]);
+        let results = compare_hook_styles(&db).unwrap();
+        assert_eq!(results[0].hook_style, HookStyle::ALL[1]);
+        assert_eq!((results[0].wins, results[0].groups), (2, 2));
+        assert_eq!(results[1].wins, 0);
+        assert_eq!(results[1].avg_views, 75.0);
+    }
+}
@@ -0,0 +1,210 @@
+// Local REST API for controlling the pipeline from scripts, Home Assistant
+// or a browser; off unless enabled in the settings
+use crate::db::{Actor, DashboardStats, Short, Trend, TrendState};
+use crate::error::AppError;
+use crate::health::{self, HealthReport};
+use crate::pipeline::{self, QueueStatus};
+use crate::{trends, with_db, AppState};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Settings of the local API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiServerSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1
+    pub port: u16,
+    /// Sent as `Authorization: Bearer <token>`; the server won't start
+    /// without one
+    pub token: String,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8765,
+            token: String::new(),
+        }
+    }
+}
+
+/// Serve the API for as long as the app runs, if it is enabled. Settings
+/// are read once; changes apply after a restart.
+pub async fn run(app: AppHandle) {
+    let settings = match app.state::<AppState>().db.load_config() {
+        Ok(config) => config.api_server,
+        Err(e) => {
+            tracing::warn!("API server not started, failed to load config: {}", e);
+            return;
+        }
+    };
+    if !settings.enabled {
+        return;
+    }
+    if settings.token.trim().is_empty() {
+        tracing::warn!("API server not started: set a token first");
+        return;
+    }
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, settings.port));
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!("API server failed to listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    tracing::info!("API server listening on http://{}", addr);
+    if let Err(e) = axum::serve(listener, router(app, settings.token)).await {
+        tracing::warn!("API server stopped: {}", e);
+    }
+}
+
+fn router(app: AppHandle, token: String) -> Router {
+    Router::new()
+        .route("/api/status", get(queue_status))
+        .route("/api/queue/pause", post(pause_queue))
+        .route("/api/queue/resume", post(resume_queue))
+        .route("/api/stats", get(stats))
+        .route("/api/health", get(health_report))
+        .route("/api/trends", get(list_trends).post(add_trend))
+        .route("/api/shorts/awaiting", get(awaiting_approval))
+        .route("/api/shorts/:id/approve", post(approve_short))
+        .route("/api/shorts/:id/reject", post(reject_short))
+        .layer(middleware::from_fn_with_state(token, authorize))
+        .with_state(app)
+}
+
+/// Reject requests without the token, compared in constant time
+async fn authorize(State(token): State<String>, request: Request, next: Next) -> Response {
+    let given = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+    let matches = given.len() == token.len()
+        && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+    if !matches {
+        return (StatusCode::UNAUTHORIZED, "Missing or wrong token").into_response();
+    }
+    next.run(request).await
+}
+
+/// `AppError` as a JSON body with a matching status code
+struct ApiError(AppError);
+
+impl From<AppError> for ApiError {
+    fn from(e: AppError) -> Self {
+        ApiError(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::NotConfigured(_) => StatusCode::CONFLICT,
+            AppError::Http(_) | AppError::Llm(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self.0)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+async fn queue_status(State(app): State<AppHandle>) -> ApiResult<QueueStatus> {
+    Ok(Json(with_db(&app.state::<AppState>(), pipeline::status).await?))
+}
+
+async fn pause_queue(State(app): State<AppHandle>) -> ApiResult<QueueStatus> {
+    set_paused(&app, true).await
+}
+
+async fn resume_queue(State(app): State<AppHandle>) -> ApiResult<QueueStatus> {
+    set_paused(&app, false).await
+}
+
+async fn set_paused(app: &AppHandle, paused: bool) -> ApiResult<QueueStatus> {
+    pipeline::set_paused(paused);
+    health::queue_toggled();
+    let status = with_db(&app.state::<AppState>(), pipeline::status).await?;
+    let _ = app.emit("queue:status", &status);
+    Ok(Json(status))
+}
+
+async fn stats(State(app): State<AppHandle>) -> ApiResult<DashboardStats> {
+    Ok(Json(with_db(&app.state::<AppState>(), |db| db.get_stats()).await?))
+}
+
+async fn health_report(State(app): State<AppHandle>) -> Json<HealthReport> {
+    Json(health::check(&app.state::<AppState>()).await)
+}
+
+/// Filters of the list endpoints
+#[derive(Deserialize)]
+struct ListFilter {
+    #[serde(default)]
+    starred_only: bool,
+    state: Option<TrendState>,
+    limit: Option<u32>,
+}
+
+async fn list_trends(State(app): State<AppHandle>, Query(filter): Query<ListFilter>) -> ApiResult<Vec<Trend>> {
+    let limit = filter.limit.unwrap_or(100);
+    Ok(Json(with_db(&app.state::<AppState>(), move |db| db.list_trends(filter.starred_only, filter.state, limit)).await?))
+}
+
+#[derive(Deserialize)]
+struct AddTrend {
+    /// YouTube link or video id
+    url: String,
+}
+
+#[derive(Serialize)]
+struct TrendAdded {
+    trend_id: i64,
+}
+
+async fn add_trend(State(app): State<AppHandle>, Json(body): Json<AddTrend>) -> ApiResult<TrendAdded> {
+    if trends::video_id_from_url(&body.url).is_none() {
+        return Err(AppError::Validation(format!("Not a YouTube video link: {}", body.url)).into());
+    }
+    let trend_id = trends::add_from_url(&app.state::<AppState>(), &body.url).await
+        .map_err(AppError::Http)?;
+    let _ = app.emit("trend:added", serde_json::json!({ "trend_id": trend_id }));
+    Ok(Json(TrendAdded { trend_id }))
+}
+
+async fn awaiting_approval(State(app): State<AppHandle>, Query(filter): Query<ListFilter>) -> ApiResult<Vec<(Short, Trend)>> {
+    Ok(Json(with_db(&app.state::<AppState>(), move |db| db.list_shorts_awaiting_approval(filter.starred_only)).await?))
+}
+
+async fn approve_short(State(app): State<AppHandle>, Path(short_id): Path<i64>) -> ApiResult<()> {
+    crate::approve(&app.state::<AppState>(), short_id, Actor::Api).await?;
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct Reject {
+    reason: Option<String>,
+}
+
+async fn reject_short(
+    State(app): State<AppHandle>,
+    Path(short_id): Path<i64>,
+    body: Option<Json<Reject>>,
+) -> ApiResult<()> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    crate::reject(&app.state::<AppState>(), short_id, reason.as_deref(), Actor::Api)?;
+    Ok(Json(()))
+}
@@ -0,0 +1,248 @@
+// Audio post-processing for TTS output
+use crate::process::run;
+use serde::Deserialize;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Silence this close to either end of a file counts as touching it
+const EDGE_TOLERANCE_SECS: f64 = 0.05;
+
+/// Silence kept before the first word and after the last
+const EDGE_PAD_SECS: f64 = 0.1;
+
+/// Shorter cuts aren't worth a pass over the audio
+const MIN_CUT_SECS: f64 = 0.02;
+
+/// Quieter than this counts as a pause in narration; TTS pauses are
+/// close to digital silence
+const PAUSE_NOISE_DB: f64 = -50.0;
+
+/// Shortest pause worth detecting, in seconds
+const MIN_PAUSE_SECS: f64 = 0.1;
+
+/// Loudness measured by ffmpeg's `loudnorm` filter
+#[derive(Debug, Clone)]
+pub struct LoudnessReport {
+    /// Integrated loudness of the TTS output before normalization (LUFS)
+    pub input_lufs: f64,
+    pub input_true_peak: f64,
+    /// Integrated loudness after normalization (LUFS)
+    pub output_lufs: f64,
+    pub output_true_peak: f64,
+}
+
+/// Values printed by `loudnorm` with `print_format=json`; ffmpeg emits numbers as strings
+#[derive(Debug, Deserialize)]
+struct LoudnormStats {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+    output_i: Option<String>,
+    output_tp: Option<String>,
+}
+
+/// Normalize `input` to the target integrated loudness and true peak,
+/// writing the result to `output`. Uses the two-pass `loudnorm` method:
+/// the first pass measures, the second applies a linear gain.
+pub async fn normalize_loudness(
+    ffmpeg: &str,
+    input: &Path,
+    output: &Path,
+    target_lufs: f64,
+    true_peak_db: f64,
+) -> Result<LoudnessReport, String> {
+    let measure_filter = format!("loudnorm=I={}:TP={}:LRA=11:print_format=json", target_lufs, true_peak_db);
+    let measured = run(Command::new(ffmpeg)
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(input)
+        .args(["-af", &measure_filter, "-f", "null", "-"]))
+    .await?;
+    let first = parse_stats(&String::from_utf8_lossy(&measured.stderr))?;
+    
+    let apply_filter = format!(
+        "loudnorm=I={}:TP={}:LRA=11:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true:print_format=json",
+        target_lufs, true_peak_db, first.input_i, first.input_tp, first.input_lra, first.input_thresh, first.target_offset
+    );
+    let applied = run(Command::new(ffmpeg)
+        .args(["-hide_banner", "-nostats", "-y", "-i"])
+        .arg(input)
+        .args(["-af", &apply_filter, "-ar", "48000", "-c:a", "libmp3lame", "-b:a", "192k"])
+        .arg(output))
+    .await?;
+    let second = parse_stats(&String::from_utf8_lossy(&applied.stderr))?;
+    
+    Ok(LoudnessReport {
+        input_lufs: parse_number(&first.input_i)?,
+        input_true_peak: parse_number(&first.input_tp)?,
+        output_lufs: parse_number(second.output_i.as_deref().unwrap_or(&second.input_i))?,
+        output_true_peak: parse_number(second.output_tp.as_deref().unwrap_or(&second.input_tp))?,
+    })
+}
+
+/// A quiet stretch of audio, in seconds from the start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Silence {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Quiet stretches of `input`, which is `duration` seconds long, in order.
+/// Quieter than `noise_db` for at least `min_secs` counts as silence.
+pub async fn detect_silences(
+    ffmpeg: &str,
+    input: &Path,
+    duration: f64,
+    noise_db: f64,
+    min_secs: f64,
+) -> Result<Vec<Silence>, String> {
+    let filter = format!("silencedetect=noise={}dB:d={}", noise_db, min_secs);
+    let output = run(Command::new(ffmpeg)
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(input)
+        .args(["-vn", "-af", &filter, "-f", "null", "-"]))
+    .await?;
+    Ok(parse_silences(&String::from_utf8_lossy(&output.stderr), duration))
+}
+
+/// Where the silence at the end of `input` starts, or `None` when the sound
+/// runs to the end
+pub async fn trailing_silence_start(
+    ffmpeg: &str,
+    input: &Path,
+    duration: f64,
+    noise_db: f64,
+    min_secs: f64,
+) -> Result<Option<f64>, String> {
+    let silences = detect_silences(ffmpeg, input, duration, noise_db, min_secs).await?;
+    Ok(silences.last().filter(|s| s.end >= duration - EDGE_TOLERANCE_SECS).map(|s| s.start))
+}
+
+/// `silencedetect` logs `silence_start` and `silence_end` for each gap. A
+/// gap still open at the end of the log runs to the end of the file.
+fn parse_silences(stderr: &str, duration: f64) -> Vec<Silence> {
+    let value = |line: &str, key: &str| -> Option<f64> {
+        line.split(key).nth(1)?.split_whitespace().next()?.parse().ok()
+    };
+    let mut silences = Vec::new();
+    let mut open = None;
+    for line in stderr.lines() {
+        if let Some(start) = value(line, "silence_start: ") {
+            open = Some(start.max(0.0));
+        } else if let (Some(end), Some(start)) = (value(line, "silence_end: "), open) {
+            silences.push(Silence { start, end: end.min(duration) });
+            open = None;
+        }
+    }
+    if let Some(start) = open {
+        silences.push(Silence { start, end: duration });
+    }
+    silences
+}
+
+/// Cut the silence before and after narration at `path`, `duration`
+/// seconds long, and shorten pauses longer than `max_pause` seconds, in
+/// place. Returns the spans cut from the original, in order, so timing
+/// taken from it can be moved to match.
+pub async fn tighten_pacing(ffmpeg: &str, path: &Path, duration: f64, max_pause: f64) -> Result<Vec<(f64, f64)>, String> {
+    let silences = detect_silences(ffmpeg, path, duration, PAUSE_NOISE_DB, MIN_PAUSE_SECS).await?;
+    let cuts = plan_cuts(&silences, duration, max_pause);
+    if cuts.is_empty() {
+        return Ok(cuts);
+    }
+    let tightened = path.with_extension("paced.mp3");
+    remove_spans(ffmpeg, path, &tightened, &cuts).await?;
+    tokio::fs::rename(&tightened, path).await.map_err(|e| e.to_string())?;
+    Ok(cuts)
+}
+
+/// Spans to cut from narration, `duration` seconds long, with `silences`:
+/// the silence before the first word and after the last, but for
+/// `EDGE_PAD_SECS`, and the middle of every pause longer than `max_pause`
+/// so that `max_pause` is left. In order, as (start, end).
+fn plan_cuts(silences: &[Silence], duration: f64, max_pause: f64) -> Vec<(f64, f64)> {
+    let at_start = |s: &Silence| s.start <= EDGE_TOLERANCE_SECS;
+    let at_end = |s: &Silence| s.end >= duration - EDGE_TOLERANCE_SECS;
+    // Silent all the way through: nothing to keep, leave it for the caller to notice
+    if silences.iter().any(|s| at_start(s) && at_end(s)) {
+        return Vec::new();
+    }
+    silences
+        .iter()
+        .map(|s| {
+            if at_start(s) {
+                (0.0, s.end - EDGE_PAD_SECS)
+            } else if at_end(s) {
+                (s.start + EDGE_PAD_SECS, duration)
+            } else {
+                let excess = (s.end - s.start - max_pause).max(0.0);
+                let start = s.start + (s.end - s.start - excess) / 2.0;
+                (start, start + excess)
+            }
+        })
+        .filter(|(start, end)| end - start >= MIN_CUT_SECS)
+        .collect()
+}
+
+/// Write `input` without the `cuts` spans to `output` as MP3
+async fn remove_spans(ffmpeg: &str, input: &Path, output: &Path, cuts: &[(f64, f64)]) -> Result<(), String> {
+    let spans: Vec<String> = cuts.iter()
+        .map(|(start, end)| format!("between(t,{:.3},{:.3})", start, end))
+        .collect();
+    let filter = format!("aselect='not({})',asetpts=N/SR/TB", spans.join("+"));
+    run(Command::new(ffmpeg)
+        .args(["-hide_banner", "-y", "-i"])
+        .arg(input)
+        .args(["-af", &filter, "-ar", "48000", "-c:a", "libmp3lame", "-b:a", "192k"])
+        .arg(output))
+    .await?;
+    Ok(())
+}
+
+/// Extract the JSON block `loudnorm` prints at the end of stderr
+fn parse_stats(stderr: &str) -> Result<LoudnormStats, String> {
+    let start = stderr.rfind('{').ok_or("loudnorm printed no statistics")?;
+    let end = stderr[start..].find('}').ok_or("loudnorm statistics are truncated")? + start;
+    serde_json::from_str(&stderr[start..=end]).map_err(|e| format!("Invalid loudnorm statistics: {}", e))
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    // Silent input measures as "-inf"
+    value.trim().parse::<f64>().map_err(|_| format!("Unexpected loudnorm value: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_gaps_including_one_running_to_the_end() {
+        let stderr = "[silencedetect @ 0x1] silence_start: 12.5\n\
+            [silencedetect @ 0x1] silence_end: 13.1 | silence_duration: 0.6\n\
+            [silencedetect @ 0x1] silence_start: 58.204\n";
+        let silences = parse_silences(stderr, 61.0);
+        assert_eq!(silences, [Silence { start: 12.5, end: 13.1 }, Silence { start: 58.204, end: 61.0 }]);
+        // Newer ffmpeg builds close the last gap themselves
+        let flushed = format!("{}[silencedetect @ 0x1] silence_end: 61 | silence_duration: 2.796\n", stderr);
+        assert_eq!(parse_silences(&flushed, 61.0), silences);
+    }
+    
+    #[test]
+    fn cuts_edges_and_long_pauses() {
+        let silences = [
+            Silence { start: 0.0, end: 0.8 },
+            Silence { start: 5.0, end: 5.4 },
+            Silence { start: 9.0, end: 12.0 },
+            Silence { start: 20.0, end: 22.0 },
+        ];
+        let cuts = plan_cuts(&silences, 22.0, 0.6);
+        let rounded: Vec<_> = cuts.iter().map(|(a, b)| ((a * 100.0).round() / 100.0, (b * 100.0).round() / 100.0)).collect();
+        assert_eq!(rounded, [(0.0, 0.7), (9.3, 11.7), (20.1, 22.0)]);
+    }
+    
+    #[test]
+    fn leaves_silent_audio_alone() {
+        assert!(plan_cuts(&[Silence { start: 0.0, end: 3.0 }], 3.0, 0.6).is_empty());
+    }
+}
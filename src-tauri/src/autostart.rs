@@ -0,0 +1,24 @@
+// Launching the app on login, started hidden so the pipeline resumes unattended
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// Argument the login item starts the app with
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+/// Whether this launch came from the login item, or was asked to start hidden
+pub fn started_minimized() -> bool {
+    std::env::args().any(|arg| arg == MINIMIZED_ARG)
+}
+
+/// Add or remove the login item so it matches `launch_on_startup`
+pub fn apply(app: &AppHandle, launch_on_startup: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let enabled = autolaunch.is_enabled().map_err(|e| e.to_string())?;
+    match (launch_on_startup, enabled) {
+        (true, false) => autolaunch.enable().map_err(|e| e.to_string())?,
+        (false, true) => autolaunch.disable().map_err(|e| e.to_string())?,
+        _ => return Ok(()),
+    }
+    tracing::info!("Launch on startup {}", if launch_on_startup { "enabled" } else { "disabled" });
+    Ok(())
+}
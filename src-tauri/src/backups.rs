@@ -0,0 +1,78 @@
+// Daily database backups kept in the app data dir
+use crate::AppState;
+use chrono::{Local, NaiveDate};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often to check whether today's backup exists
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Daily backups are named `shotauto-YYYY-MM-DD.db`
+const DAILY_PREFIX: &str = "shotauto-";
+
+pub fn dir(app_dir: &Path) -> PathBuf {
+    app_dir.join("backups")
+}
+
+/// Where the current database is copied before a restore overwrites it
+pub fn safety_copy_path(app_dir: &Path) -> PathBuf {
+    dir(app_dir).join(format!("pre-restore-{}.db", Local::now().format("%Y%m%d-%H%M%S")))
+}
+
+/// Take a backup each day while `auto_backup` is on, for as long as the app runs
+pub async fn run(app: AppHandle) {
+    loop {
+        let state = app.state::<AppState>();
+        match backup_if_due(&state).await {
+            Ok(Some(path)) => tracing::info!("Backed up the database to {}", path.display()),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Daily database backup failed: {}", e),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Write today's backup unless it exists, then delete daily backups older
+/// than `backup_retention_days`. Returns the new backup's path.
+async fn backup_if_due(state: &AppState) -> Result<Option<PathBuf>, String> {
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    if !config.auto_backup {
+        return Ok(None);
+    }
+    let dir = dir(&state.app_dir);
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    let today = Local::now().date_naive();
+    let path = dir.join(format!("{}{}.db", DAILY_PREFIX, today.format("%Y-%m-%d")));
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Ok(None);
+    }
+    
+    let db = state.db.clone();
+    let dest = path.clone();
+    tauri::async_runtime::spawn_blocking(move || db.backup_to(&dest))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    prune(&dir, today - chrono::Duration::days(config.backup_retention_days as i64)).await;
+    Ok(Some(path))
+}
+
+/// Delete daily backups dated before `oldest`
+async fn prune(dir: &Path, oldest: NaiveDate) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let date = name
+            .strip_prefix(DAILY_PREFIX)
+            .and_then(|rest| rest.strip_suffix(".db"))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        if date.is_some_and(|date| date < oldest) {
+            if let Err(e) = tokio::fs::remove_file(entry.path()).await {
+                tracing::warn!("Failed to delete old backup {}: {}", name, e);
+            }
+        }
+    }
+}
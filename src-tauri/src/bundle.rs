@@ -0,0 +1,224 @@
+// Portable zip of a rendered short: the video with everything needed to
+// publish it by hand or hand it to an editor
+use crate::db::{Short, Trend};
+use crate::prompt::HookStyle;
+use crate::timing::{self, SpeechTiming};
+use crate::youtube_upload::VideoMetadata;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bundle layout version; newer bundles are refused
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const VIDEO: &str = "video.mp4";
+const THUMBNAIL: &str = "thumbnail.jpg";
+const SCRIPT: &str = "script.txt";
+const METADATA: &str = "metadata.json";
+const CAPTIONS: &str = "captions.srt";
+const TIMING: &str = "timing.json";
+
+/// What a bundle holds besides its files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Title of the trend or topic the short was made from
+    pub topic: String,
+    pub language: String,
+    pub duration_sec: Option<f64>,
+    pub hook_style: Option<HookStyle>,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Everything read back from a bundle except the media files
+#[derive(Debug, Clone)]
+pub struct Contents {
+    pub manifest: Manifest,
+    pub script: String,
+    pub metadata: Option<VideoMetadata>,
+    pub timing: Option<SpeechTiming>,
+    pub has_thumbnail: bool,
+}
+
+/// Write `short` to a zip at `dest`. Blocking.
+pub fn export(short: &Short, trend: &Trend, metadata: &VideoMetadata, dest: &Path) -> Result<(), String> {
+    let video = short.video_path.as_deref().ok_or("Short has not been rendered yet")?;
+    let script = short.script.as_deref().ok_or("Short has no script")?;
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        topic: trend.title.clone(),
+        language: short.language.clone(),
+        duration_sec: short.duration_sec,
+        hook_style: short.hook_style,
+        exported_at: Utc::now(),
+    };
+    
+    let file = File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let text = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Video and JPEG are compressed already
+    let media = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    
+    let mut add = |name: &str, options: SimpleFileOptions, content: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content).map_err(|e| e.to_string())
+    };
+    add(MANIFEST, text, &to_json(&manifest)?)?;
+    add(SCRIPT, text, script.as_bytes())?;
+    add(METADATA, text, &to_json(metadata)?)?;
+    if let Some(ref timing) = short.timing {
+        add(CAPTIONS, text, timing::to_srt(&timing.sentences).as_bytes())?;
+        add(TIMING, text, &to_json(timing)?)?;
+    }
+    
+    copy_in(&mut zip, VIDEO, media, Path::new(video))?;
+    if let Some(thumbnail) = short.thumbnail_path.as_deref().filter(|p| Path::new(p).is_file()) {
+        copy_in(&mut zip, THUMBNAIL, media, Path::new(thumbnail))?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| e.to_string())
+}
+
+/// Stream a file into the zip so large videos stay out of memory
+fn copy_in(zip: &mut ZipWriter<File>, name: &str, options: SimpleFileOptions, path: &Path) -> Result<(), String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    std::io::copy(&mut file, zip).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Read the manifest, script, metadata and timing of the bundle at `path`. Blocking.
+pub fn read(path: &Path) -> Result<Contents, String> {
+    let mut zip = open(path)?;
+    let manifest = read_text(&mut zip, MANIFEST)?.ok_or("Not a short bundle: no manifest")?;
+    let manifest: Manifest = serde_json::from_str(&manifest).map_err(|e| format!("Invalid bundle manifest: {}", e))?;
+    if manifest.version > FORMAT_VERSION {
+        return Err(format!("Bundle version {} is newer than this app supports", manifest.version));
+    }
+    let script = read_text(&mut zip, SCRIPT)?.ok_or("Bundle has no script")?;
+    if zip.by_name(VIDEO).is_err() {
+        return Err("Bundle has no video".to_string());
+    }
+    Ok(Contents {
+        metadata: read_json(&mut zip, METADATA)?,
+        timing: read_json(&mut zip, TIMING)?,
+        has_thumbnail: zip.by_name(THUMBNAIL).is_ok(),
+        manifest,
+        script,
+    })
+}
+
+/// Extract the bundle's video and, when it has one, thumbnail. Blocking.
+pub fn extract_media(path: &Path, video: &Path, thumbnail: &Path) -> Result<(), String> {
+    let mut zip = open(path)?;
+    extract(&mut zip, VIDEO, video)?;
+    if zip.by_name(THUMBNAIL).is_ok() {
+        extract(&mut zip, THUMBNAIL, thumbnail)?;
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    ZipArchive::new(file).map_err(|e| format!("Not a zip file: {}", e))
+}
+
+fn read_text(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<String>, String> {
+    let Ok(mut entry) = zip.by_name(name) else {
+        return Ok(None);
+    };
+    let mut text = String::new();
+    entry.read_to_string(&mut text).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(Some(text))
+}
+
+/// An optional JSON file; one that doesn't parse counts as missing
+fn read_json<T: DeserializeOwned>(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<T>, String> {
+    Ok(read_text(zip, name)?.and_then(|text| serde_json::from_str(&text).ok()))
+}
+
+fn extract(zip: &mut ZipArchive<File>, name: &str, dest: &Path) -> Result<(), String> {
+    let mut entry = zip.by_name(name).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let mut file = File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    std::io::copy(&mut entry, &mut file).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, ImportedShort};
+    use crate::storage;
+    use crate::timing::{TimedText, TimingSource};
+    use crate::trends;
+    
+    #[test]
+    fn a_short_survives_export_and_import() {
+        let dir = storage::scratch_dir("bundle-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let video = dir.join("short.mp4");
+        std::fs::write(&video, b"not really a video").unwrap();
+        
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "Why cats can't taste sugar").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let short_id = db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap()[0];
+        db.set_short_script(short_id, "Cats can't taste sugar. Here's why.").unwrap();
+        db.set_short_video(short_id, &video.to_string_lossy(), 3.5).unwrap();
+        let timing = SpeechTiming::from_sentences(TimingSource::Estimated, vec![
+            TimedText { text: "Cats can't taste sugar.".to_string(), start: 0.0, end: 1.5 },
+            TimedText { text: "Here's why.".to_string(), start: 1.5, end: 3.5 },
+        ]);
+        db.set_short_timing(short_id, &timing).unwrap();
+        let (short, trend) = db.get_short_with_trend(short_id).unwrap().unwrap();
+        let metadata = VideoMetadata {
+            title: "Cats and sugar".to_string(),
+            description: "Here's why.".to_string(),
+            tags: vec!["cats".to_string()],
+        };
+        
+        let zip = dir.join("short.zip");
+        export(&short, &trend, &metadata, &zip).unwrap();
+        let contents = read(&zip).unwrap();
+        assert_eq!(contents.manifest.topic, "Why cats can't taste sugar");
+        assert_eq!((contents.manifest.language.as_str(), contents.manifest.duration_sec), ("en", Some(3.5)));
+        assert_eq!(contents.script, "Cats can't taste sugar. Here's why.");
+        assert_eq!(contents.metadata.as_ref().map(|m| m.title.as_str()), Some("Cats and sugar"));
+        assert_eq!(contents.timing.as_ref().map(|t| t.sentences.len()), Some(2));
+        assert!(!contents.has_thumbnail);
+        
+        let extracted = dir.join("extracted.mp4");
+        extract_media(&zip, &extracted, &dir.join("extracted.jpg")).unwrap();
+        assert_eq!(std::fs::read(&extracted).unwrap(), b"not really a video");
+        assert!(!dir.join("extracted.jpg").exists());
+        
+        let (_, imported_id) = db.import_short(&trends::topic_trend(&contents.manifest.topic), &ImportedShort {
+            language: contents.manifest.language,
+            script: contents.script,
+            video_path: extracted.to_string_lossy().to_string(),
+            duration_sec: 3.5,
+            thumbnail_path: None,
+            timing: contents.timing,
+            metadata: contents.metadata,
+        })
+        .unwrap();
+        let (imported, imported_trend) = db.get_short_with_trend(imported_id).unwrap().unwrap();
+        assert_eq!(imported_trend.id, Some(trend_id));
+        assert_eq!(imported.script, short.script);
+        assert_eq!(imported.duration_sec, Some(3.5));
+        assert_eq!(imported.timing.map(|t| t.sentences[1].end), Some(3.5));
+        assert_eq!(imported.upload_metadata.map(|m| m.tags), Some(vec!["cats".to_string()]));
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
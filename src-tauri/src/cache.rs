@@ -0,0 +1,274 @@
+// Downloaded and generated media, kept by content hash so identical files
+// are stored once, and pruned least recently used first to a size limit
+use crate::db::{Config, Database};
+use crate::storage::{self, CleanupReport};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// What a cached file is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    /// Stock footage clips
+    Stock,
+    /// Generated scene images
+    Image,
+    /// Trend videos downloaded with yt-dlp
+    Source,
+    /// Raw TTS narration
+    Tts,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 4] = [CacheKind::Stock, CacheKind::Image, CacheKind::Source, CacheKind::Tts];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheKind::Stock => "stock",
+            CacheKind::Image => "image",
+            CacheKind::Source => "source",
+            CacheKind::Tts => "tts",
+        }
+    }
+}
+
+/// A cache hit
+#[derive(Debug, Clone)]
+pub struct Cached {
+    pub path: PathBuf,
+    /// Whatever was stored alongside the file, such as TTS word timing
+    pub meta: Option<String>,
+}
+
+/// Entries and disk use of one kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub kind: String,
+    /// Keys that resolve to a file
+    pub entries: i64,
+    /// Distinct files on disk
+    pub files: i64,
+    pub bytes: i64,
+    /// Bytes that would be stored again without deduplication
+    pub saved_bytes: i64,
+}
+
+/// Where the cache keeps its files
+pub fn root(app_dir: &Path) -> PathBuf {
+    app_dir.join("cache")
+}
+
+/// Whether `path` is a cached file, which only the cache may delete
+pub fn contains(app_dir: &Path, path: &Path) -> bool {
+    path.starts_with(root(app_dir))
+}
+
+/// Cache key for a request: hex SHA-256 of everything that determines the result
+pub fn key(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Scratch file in the cache for a download or render to write before `store`
+pub async fn scratch_path(app_dir: &Path, kind: CacheKind, extension: &str) -> Result<PathBuf, String> {
+    let dir = root(app_dir).join("incoming");
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    Ok(dir.join(format!("{}-{}.{}", kind.as_str(), nanos, extension)))
+}
+
+/// The file cached under `key`, marked as just used. Entries whose file
+/// has gone missing are dropped and count as a miss.
+pub fn lookup(db: &Database, kind: CacheKind, key: &str) -> Result<Option<Cached>, String> {
+    let Some((path, meta)) = db.get_cache_entry(kind.as_str(), key).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        db.delete_cache_entry(kind.as_str(), key).map_err(|e| e.to_string())?;
+        return Ok(None);
+    }
+    db.touch_cache_entry(kind.as_str(), key).map_err(|e| e.to_string())?;
+    Ok(Some(Cached { path, meta }))
+}
+
+/// Move `file` into the cache under `key` and return where it went. A file
+/// with the same content already cached is reused and `file` deleted. The
+/// cache is then pruned to `cache_limit_mb`, never deleting this entry.
+pub async fn store(
+    db: &Database,
+    config: &Config,
+    app_dir: &Path,
+    kind: CacheKind,
+    key: &str,
+    file: &Path,
+    meta: Option<&str>,
+) -> Result<PathBuf, String> {
+    let hash = hash_file(file).await?;
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or("bin").to_lowercase();
+    let dir = root(app_dir).join(kind.as_str());
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.{}", hash, extension));
+    if path.is_file() {
+        tokio::fs::remove_file(file).await.map_err(|e| e.to_string())?;
+    } else {
+        tokio::fs::rename(file, &path).await.map_err(|e| e.to_string())?;
+    }
+    let size = tokio::fs::metadata(&path).await.map_err(|e| e.to_string())?.len();
+    
+    let path_str = path.to_string_lossy().to_string();
+    db.put_cache_entry(kind.as_str(), key, &hash, &path_str, size as i64, meta)
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = prune(db, config.cache_limit_mb.saturating_mul(1024 * 1024), &path_str) {
+        tracing::warn!("Failed to prune the media cache: {}", e);
+    }
+    Ok(path)
+}
+
+/// Delete least recently used files until the cache fits in `limit_bytes`.
+/// Files a short or scene still points at are kept, as they may be in the
+/// middle of a render.
+fn prune(db: &Database, limit_bytes: u64, keep: &str) -> Result<(), String> {
+    let files = db.list_cache_files().map_err(|e| e.to_string())?;
+    let referenced = db.referenced_media_paths().map_err(|e| e.to_string())?;
+    let mut total: u64 = files.iter().map(|(_, size)| *size as u64).sum();
+    for (path, size) in files {
+        if total <= limit_bytes {
+            break;
+        }
+        if path == keep || referenced.contains(&path) {
+            continue;
+        }
+        let mut report = CleanupReport::default();
+        storage::remove_file(Path::new(&path), &mut report);
+        db.delete_cache_file(&path).map_err(|e| e.to_string())?;
+        total = total.saturating_sub(size as u64);
+    }
+    Ok(())
+}
+
+/// Empty the cache, or only one kind of it, except for files a short or
+/// scene still points at. Blocking.
+pub fn clear(db: &Database, app_dir: &Path, kind: Option<CacheKind>) -> Result<CleanupReport, String> {
+    let mut report = CleanupReport::default();
+    let referenced = db.referenced_media_paths().map_err(|e| e.to_string())?;
+    let kinds = match kind {
+        Some(kind) => vec![kind],
+        None => CacheKind::ALL.to_vec(),
+    };
+    for kind in kinds {
+        let Ok(entries) = std::fs::read_dir(root(app_dir).join(kind.as_str())) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let path_str = path.to_string_lossy().to_string();
+            if !entry.file_type().is_ok_and(|t| t.is_file()) || referenced.contains(&path_str) {
+                continue;
+            }
+            storage::remove_file(&path, &mut report);
+            db.delete_cache_file(&path_str).map_err(|e| e.to_string())?;
+        }
+    }
+    if kind.is_none() {
+        storage::clear_dir(&root(app_dir).join("incoming"), &mut report);
+    }
+    Ok(report)
+}
+
+/// Hex SHA-256 of a file's content, read in chunks so large videos stay out of memory
+pub async fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = tokio::fs::File::open(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn app_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shotauto-cache-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    async fn write(app_dir: &Path, content: &[u8]) -> PathBuf {
+        let path = scratch_path(app_dir, CacheKind::Stock, "mp4").await.unwrap();
+        tokio::fs::write(&path, content).await.unwrap();
+        path
+    }
+    
+    #[tokio::test]
+    async fn stores_identical_content_once() {
+        let db = Database::new_in_memory().unwrap();
+        let config = Config::default();
+        let dir = app_dir("dedup");
+        let first = store(&db, &config, &dir, CacheKind::Stock, &key(&["pexels", "cats"]), &write(&dir, b"clip").await, None)
+            .await
+            .unwrap();
+        let second = store(&db, &config, &dir, CacheKind::Stock, &key(&["pexels", "kittens"]), &write(&dir, b"clip").await, None)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        
+        let hit = lookup(&db, CacheKind::Stock, &key(&["pexels", "kittens"])).unwrap().unwrap();
+        assert_eq!(hit.path, first);
+        assert!(lookup(&db, CacheKind::Stock, &key(&["pexels", "dogs"])).unwrap().is_none());
+        let stats = db.cache_stats().unwrap();
+        assert_eq!((stats[0].entries, stats[0].files, stats[0].bytes, stats[0].saved_bytes), (2, 1, 4, 4));
+    }
+    
+    #[tokio::test]
+    async fn prunes_the_least_recently_used_file() {
+        let db = Database::new_in_memory().unwrap();
+        let config = Config { cache_limit_mb: 0, ..Default::default() };
+        let dir = app_dir("prune");
+        let old = store(&db, &config, &dir, CacheKind::Stock, "old", &write(&dir, b"old clip").await, None)
+            .await
+            .unwrap();
+        let new = store(&db, &config, &dir, CacheKind::Stock, "new", &write(&dir, b"new clip").await, None)
+            .await
+            .unwrap();
+        assert!(!old.exists());
+        assert!(new.exists());
+        assert!(lookup(&db, CacheKind::Stock, "old").unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn keeps_files_a_short_uses() {
+        let db = Database::new_in_memory().unwrap();
+        let config = Config { cache_limit_mb: 0, ..Default::default() };
+        let dir = app_dir("referenced");
+        let used = store(&db, &config, &dir, CacheKind::Stock, "used", &write(&dir, b"used clip").await, None)
+            .await
+            .unwrap();
+        let trend_id = crate::trends::add_topic(&db, "home espresso").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let short_id = db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap()[0];
+        db.set_short_video(short_id, &used.to_string_lossy(), 1.0).unwrap();
+        
+        store(&db, &config, &dir, CacheKind::Stock, "new", &write(&dir, b"new clip").await, None)
+            .await
+            .unwrap();
+        assert!(used.exists());
+        clear(&db, &dir, None).unwrap();
+        assert!(used.exists());
+        assert!(lookup(&db, CacheKind::Stock, "new").unwrap().is_none());
+    }
+}
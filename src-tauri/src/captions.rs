@@ -0,0 +1,214 @@
+// Burned-in captions (ASS subtitles) for rendered shorts
+use crate::timing::{SpeechTiming, TimedText};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Vertical placement of captions on the frame
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPosition {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl CaptionPosition {
+    /// ASS numpad alignment, horizontally centered
+    fn alignment(&self) -> u8 {
+        match self {
+            CaptionPosition::Top => 8,
+            CaptionPosition::Middle => 5,
+            CaptionPosition::Bottom => 2,
+        }
+    }
+}
+
+/// How captions are timed on screen
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionMode {
+    /// One caption per sentence
+    Static,
+    /// A few words at a time, following the narration
+    #[default]
+    Line,
+    /// Like `Line`, highlighting each word as it is spoken (ASS `\k` tags)
+    Karaoke,
+}
+
+/// Caption appearance, stored in config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionStyle {
+    pub enabled: bool,
+    pub mode: CaptionMode,
+    pub font: String,
+    pub font_size: u32,
+    pub bold: bool,
+    /// Text color as `#RRGGBB`
+    pub color: String,
+    /// Color of the word being spoken in karaoke mode, as `#RRGGBB`
+    pub highlight_color: String,
+    /// Outline color as `#RRGGBB`
+    pub outline_color: String,
+    pub outline_width: f32,
+    pub position: CaptionPosition,
+    /// Distance from the top/bottom edge in pixels (1080x1920 frame)
+    pub margin_v: u32,
+    /// Words shown at once; short lines read better on a phone
+    pub max_words_per_line: usize,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: CaptionMode::Line,
+            font: "Arial".to_string(),
+            font_size: 84,
+            bold: true,
+            color: "#FFFFFF".to_string(),
+            highlight_color: "#FFE600".to_string(),
+            outline_color: "#000000".to_string(),
+            outline_width: 5.0,
+            position: CaptionPosition::Middle,
+            margin_v: 200,
+            max_words_per_line: 4,
+        }
+    }
+}
+
+/// Build an ASS subtitle document for a 1080x1920 render
+pub fn build_ass(timing: &SpeechTiming, style: &CaptionStyle) -> String {
+    let mut ass = String::new();
+    let _ = write!(
+        ass,
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: 1080\n\
+         PlayResY: 1920\n\
+         WrapStyle: 0\n\
+         ScaledBorderAndShadow: yes\n\n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+         Style: Default,{font},{size},{primary},{secondary},{outline},&H80000000,{bold},0,0,0,100,100,0,0,1,{outline_width},0,{alignment},60,60,{margin_v},1\n\n\
+         [Events]\n\
+         Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        font = style.font,
+        size = style.font_size,
+        // In karaoke, ASS fills the secondary color and sweeps to the primary as each word is sung
+        primary = ass_color(if style.mode == CaptionMode::Karaoke { &style.highlight_color } else { &style.color }),
+        secondary = ass_color(&style.color),
+        outline = ass_color(&style.outline_color),
+        bold = if style.bold { -1 } else { 0 },
+        outline_width = style.outline_width,
+        alignment = style.position.alignment(),
+        margin_v = style.margin_v,
+    );
+    
+    let events: Vec<(f64, f64, String)> = match style.mode {
+        CaptionMode::Static => timing
+            .sentences
+            .iter()
+            .map(|s| (s.start, s.end, escape_text(&s.text)))
+            .collect(),
+        CaptionMode::Line => caption_lines(timing, style.max_words_per_line)
+            .into_iter()
+            .map(|words| {
+                let line = merge(&words);
+                (line.start, line.end, escape_text(&line.text))
+            })
+            .collect(),
+        CaptionMode::Karaoke => caption_lines(timing, style.max_words_per_line)
+            .into_iter()
+            .map(|words| {
+                let line = merge(&words);
+                (line.start, line.end, karaoke_text(&words, line.end))
+            })
+            .collect(),
+    };
+    
+    for (start, end, text) in events {
+        let _ = writeln!(
+            ass,
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}",
+            ass_time(start),
+            ass_time(end),
+            text
+        );
+    }
+    ass
+}
+
+/// Group timed words into caption lines, breaking at sentence ends
+fn caption_lines(timing: &SpeechTiming, max_words: usize) -> Vec<Vec<&TimedText>> {
+    let max_words = max_words.max(1);
+    let mut lines = Vec::new();
+    let mut current: Vec<&TimedText> = Vec::new();
+    for word in &timing.words {
+        current.push(word);
+        let sentence_end = word.text.ends_with(['.', '!', '?', '。', '！', '？']);
+        if current.len() >= max_words || sentence_end {
+            lines.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Line text with a `\k` tag before each word. A word's duration runs until
+/// the next word starts, so pauses keep the previous word highlighted.
+fn karaoke_text(words: &[&TimedText], line_end: f64) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let until = words.get(i + 1).map(|next| next.start).unwrap_or(line_end);
+            let centis = ((until - word.start).max(0.0) * 100.0).round() as u64;
+            format!("{{\\k{}}}{}", centis, escape_text(&word.text))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn merge(words: &[&TimedText]) -> TimedText {
+    TimedText {
+        text: words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+        start: words.first().map(|w| w.start).unwrap_or(0.0),
+        end: words.last().map(|w| w.end).unwrap_or(0.0),
+    }
+}
+
+/// `#RRGGBB` to ASS `&H00BBGGRR`; invalid input falls back to white
+fn ass_color(hex: &str) -> String {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return "&H00FFFFFF".to_string();
+    }
+    format!("&H00{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_uppercase()
+}
+
+/// Seconds to ASS `H:MM:SS.cc`
+fn ass_time(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        (centis / 6_000) % 60,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+/// Keep caption text from being read as ASS override tags or line breaks
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('{', "(").replace('}', ")").replace('\n', " ")
+}
+
+/// Escape a file path for use as an ffmpeg filter argument (e.g. `ass=...`)
+pub fn escape_filter_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    format!("'{}'", path.replace(':', "\\:").replace('\'', "'\\''"))
+}
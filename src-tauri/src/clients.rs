@@ -0,0 +1,71 @@
+// Clients for the outside APIs, built from the config on demand. Kept in
+// `AppState` so tests can point every call at a local server.
+use crate::db::{Config, TtsBackend};
+use crate::http;
+use crate::llm;
+use crate::telegram::TelegramApi;
+use crate::tts;
+use crate::youtube_api::YoutubeApi;
+use reqwest::Client;
+
+pub const YOUTUBE_URL: &str = "https://www.googleapis.com/youtube/v3";
+pub const TELEGRAM_URL: &str = "https://api.telegram.org";
+pub const OPENAI_URL: &str = "https://api.openai.com/v1";
+
+/// Base URLs of the services and the HTTP client used to reach them
+#[derive(Clone)]
+pub struct Clients {
+    pub http: Client,
+    pub youtube_url: String,
+    pub telegram_url: String,
+    pub openai_url: String,
+}
+
+impl Default for Clients {
+    fn default() -> Self {
+        Self {
+            http: http::client(),
+            youtube_url: YOUTUBE_URL.to_string(),
+            telegram_url: TELEGRAM_URL.to_string(),
+            openai_url: OPENAI_URL.to_string(),
+        }
+    }
+}
+
+impl Clients {
+    /// Every service at `base_url`, for tests against a mock server
+    #[cfg(test)]
+    pub fn at(base_url: &str, http: Client) -> Self {
+        Self {
+            http,
+            youtube_url: base_url.to_string(),
+            telegram_url: base_url.to_string(),
+            openai_url: base_url.to_string(),
+        }
+    }
+    
+    pub fn youtube(&self, config: &Config) -> Result<YoutubeApi, String> {
+        Ok(YoutubeApi {
+            http: self.http.clone(),
+            base_url: self.youtube_url.clone(),
+            api_key: config.youtube_api_key.clone().ok_or("YouTube API key is not configured")?,
+        })
+    }
+    
+    pub fn telegram(&self, token: &str) -> TelegramApi {
+        TelegramApi {
+            http: self.http.clone(),
+            base_url: self.telegram_url.clone(),
+            token: token.to_string(),
+        }
+    }
+    
+    /// The configured LLM backend
+    pub fn llm(&self, config: &Config) -> Result<llm::Llm, String> {
+        llm::client(self, config)
+    }
+    
+    pub fn tts(&self, config: &Config, backend: TtsBackend) -> Result<tts::Engine, String> {
+        tts::engine(self, config, backend)
+    }
+}
@@ -0,0 +1,399 @@
+// Settings checks shown inline on the settings screen, so a bad value is
+// caught before a job fails on it halfway through the pipeline
+use crate::db::{Config, ImageBackend, LlmBackend, StockProvider, TranscriptionBackend, TtsBackend};
+use crate::deps;
+use crate::http;
+use crate::image_gen;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Polling faster than this burns through the YouTube Data API quota
+const MIN_SANE_POLL_SECS: u64 = 300;
+
+/// Polling slower than this misses most of a trend's rise
+const MAX_SANE_POLL_SECS: u64 = 24 * 3600;
+
+/// Longest video YouTube counts as a Short
+const MAX_SHORT_SECS: u32 = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The pipeline will fail with this setting
+    Error,
+    /// Works, but probably not as intended
+    Warning,
+}
+
+/// One problem with one setting
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Config field the problem is about
+    pub field: String,
+    pub severity: Severity,
+    pub message: String,
+    /// What to do about it
+    pub fix_hint: Option<String>,
+}
+
+impl Diagnostic {
+    fn error(field: &str, message: impl Into<String>, fix_hint: Option<&str>) -> Self {
+        Self {
+            field: field.to_string(),
+            severity: Severity::Error,
+            message: message.into(),
+            fix_hint: fix_hint.map(str::to_string),
+        }
+    }
+    
+    fn warning(field: &str, message: impl Into<String>, fix_hint: Option<&str>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(field, message, fix_hint)
+        }
+    }
+}
+
+/// Check `config`. Checks that reach out to tools, services or the disk run
+/// concurrently. Errors come first.
+pub async fn validate(config: &Config, app_dir: &Path) -> Vec<Diagnostic> {
+    let (tools, ollama, image, dirs) = tokio::join!(
+        check_tools(config),
+        check_ollama(config),
+        check_image_backend(config),
+        check_writable(app_dir),
+    );
+    let mut diagnostics = check_fields(config);
+    diagnostics.extend(tools);
+    diagnostics.extend(ollama);
+    diagnostics.extend(image);
+    diagnostics.extend(dirs);
+    diagnostics.sort_by_key(|d| d.severity != Severity::Error);
+    diagnostics
+}
+
+/// Checks on the values alone
+fn check_fields(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    
+    match config.youtube_api_key.as_deref() {
+        None => diagnostics.push(Diagnostic::warning(
+            "youtube_api_key",
+            "No YouTube API key; trends can't be fetched",
+            Some("Create an API key with the YouTube Data API v3 enabled in Google Cloud Console"),
+        )),
+        Some(key) if !is_google_api_key(key) => diagnostics.push(Diagnostic::error(
+            "youtube_api_key",
+            "This doesn't look like a Google API key",
+            Some("Google API keys are 39 characters long and start with \"AIza\""),
+        )),
+        Some(_) => {}
+    }
+    
+    if let Some(token) = config.telegram_bot_token.as_deref() {
+        if !is_telegram_token(token) {
+            diagnostics.push(Diagnostic::error(
+                "telegram_bot_token",
+                "This doesn't look like a Telegram bot token",
+                Some("Copy the token @BotFather gave you; it looks like 123456789:AA..."),
+            ));
+        }
+        if config.telegram_chat_id.is_none() {
+            diagnostics.push(Diagnostic::warning(
+                "telegram_chat_id",
+                "The bot has no chat to send to",
+                Some("Message the bot, then use your numeric chat id"),
+            ));
+        }
+    }
+    if let Some(chat_id) = config.telegram_chat_id.as_deref() {
+        if !is_chat_id(chat_id) {
+            diagnostics.push(Diagnostic::error(
+                "telegram_chat_id",
+                format!("\"{}\" is not a chat id", chat_id),
+                Some("Use the numeric id (negative for groups and channels) or a channel's @username"),
+            ));
+        }
+    }
+    for chat in &config.telegram_chats {
+        if !is_chat_id(&chat.chat_id) {
+            diagnostics.push(Diagnostic::error(
+                "telegram_chats",
+                format!("\"{}\" is not a chat id", chat.chat_id),
+                Some("Use the numeric id (negative for groups and channels) or a channel's @username"),
+            ));
+        }
+    }
+    
+    match config.poll_interval_secs {
+        0 => diagnostics.push(Diagnostic::error(
+            "poll_interval_secs",
+            "The poll interval can't be 0",
+            Some("Use at least 300 seconds"),
+        )),
+        secs if secs < MIN_SANE_POLL_SECS => diagnostics.push(Diagnostic::warning(
+            "poll_interval_secs",
+            format!("Polling every {} seconds will use up the daily YouTube API quota", secs),
+            Some("Use at least 300 seconds"),
+        )),
+        secs if secs > MAX_SANE_POLL_SECS => diagnostics.push(Diagnostic::warning(
+            "poll_interval_secs",
+            "Polling less than once a day misses most trends",
+            None,
+        )),
+        _ => {}
+    }
+    
+    let openai_uses: Vec<&str> = [
+        (config.llm_backend == LlmBackend::OpenAi, "scripts"),
+        (config.tts_backend == TtsBackend::OpenAi, "narration"),
+        (config.transcription_backend == TranscriptionBackend::OpenAi, "transcription"),
+    ]
+    .into_iter()
+    .filter_map(|(used, purpose)| used.then_some(purpose))
+    .collect();
+    match config.openai_api_key.as_deref() {
+        None if !openai_uses.is_empty() => diagnostics.push(Diagnostic::error(
+            "openai_api_key",
+            format!("OpenAI is selected for {} but no API key is set", openai_uses.join(", ")),
+            Some("Add a key from platform.openai.com, or pick a local backend"),
+        )),
+        Some(key) if !key.starts_with("sk-") => diagnostics.push(Diagnostic::error(
+            "openai_api_key",
+            "This doesn't look like an OpenAI API key",
+            Some("OpenAI keys start with \"sk-\""),
+        )),
+        _ => {}
+    }
+    
+    let stock_key = match config.stock_provider {
+        StockProvider::Disabled => None,
+        StockProvider::Pexels => Some(("Pexels", "pexels_api_key", &config.pexels_api_key)),
+        StockProvider::Pixabay => Some(("Pixabay", "pixabay_api_key", &config.pixabay_api_key)),
+    };
+    if let Some((provider, field, None)) = stock_key {
+        diagnostics.push(Diagnostic::error(
+            field,
+            format!("{} is selected for stock footage but no API key is set", provider),
+            None,
+        ));
+    }
+    
+    if config.transcription_backend == TranscriptionBackend::WhisperCpp {
+        match config.whisper_model_path.as_deref() {
+            None => diagnostics.push(Diagnostic::error(
+                "whisper_model_path",
+                "whisper.cpp needs a model file",
+                Some("Download a ggml model such as ggml-base.bin"),
+            )),
+            Some(path) if !Path::new(path).is_file() => diagnostics.push(Diagnostic::error(
+                "whisper_model_path",
+                format!("{} does not exist", path),
+                None,
+            )),
+            Some(_) => {}
+        }
+    }
+    if let Some(path) = config.face_model_path.as_deref() {
+        if !Path::new(path).is_file() {
+            diagnostics.push(Diagnostic::error("face_model_path", format!("{} does not exist", path), None));
+        }
+    }
+    
+    for (field, value) in [
+        ("max_concurrent_generations", config.max_concurrent_generations),
+        ("max_concurrent_renders", config.max_concurrent_renders),
+    ] {
+        if value == 0 {
+            diagnostics.push(Diagnostic::error(field, "Nothing will run with a limit of 0", Some("Use at least 1")));
+        }
+    }
+    if config.target_languages.is_empty() {
+        diagnostics.push(Diagnostic::error(
+            "target_languages",
+            "No target languages; jobs produce no shorts",
+            Some("Add a language code such as \"en\""),
+        ));
+    }
+    match config.max_short_secs {
+        0 => diagnostics.push(Diagnostic::error(
+            "max_short_secs",
+            "No short fits in 0 seconds",
+            Some("Use 60, or up to 180 for longer Shorts"),
+        )),
+        secs if secs > MAX_SHORT_SECS => diagnostics.push(Diagnostic::warning(
+            "max_short_secs",
+            format!("YouTube files uploads over {} seconds as regular videos, not Shorts", MAX_SHORT_SECS),
+            Some("Use at most 180 seconds"),
+        )),
+        _ => {}
+    }
+    if !(0.0..=1.0).contains(&config.similarity_threshold) {
+        diagnostics.push(Diagnostic::warning(
+            "similarity_threshold",
+            "The similarity threshold is a share of words in common, from 0 to 1",
+            Some("Use 0 to turn the duplicate check off, or around 0.6"),
+        ));
+    }
+    if !config.render_presets.iter().any(|preset| preset.name == config.default_render_preset) {
+        diagnostics.push(Diagnostic::warning(
+            "default_render_preset",
+            format!("There is no preset called \"{}\"; the built-in 1080p30 is used", config.default_render_preset),
+            None,
+        ));
+    }
+    diagnostics
+}
+
+/// ffmpeg, ffprobe and yt-dlp, through the dependency check
+async fn check_tools(config: &Config) -> Vec<Diagnostic> {
+    deps::check(config)
+        .await
+        .tools
+        .into_iter()
+        .filter(|tool| !tool.ok)
+        .map(|tool| {
+            let field = match tool.name.as_str() {
+                "ffmpeg" => "ffmpeg_path",
+                "ffprobe" => "ffprobe_path",
+                _ => "yt_dlp_path",
+            };
+            let message = match tool.message {
+                Some(message) => format!("{}: {}", tool.name, message),
+                None => format!("{} is not usable", tool.name),
+            };
+            let fix_hint = if tool.found {
+                "Update it, or point the setting at a newer build"
+            } else {
+                "Install it, or set the path to the binary"
+            };
+            Diagnostic::error(field, message, Some(fix_hint))
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct OllamaTags {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Deserialize)]
+struct OllamaModel {
+    name: String,
+}
+
+/// Ollama answers and has the configured model, when it is the LLM backend
+async fn check_ollama(config: &Config) -> Vec<Diagnostic> {
+    if config.llm_backend != LlmBackend::Ollama {
+        return Vec::new();
+    }
+    let url = format!("{}/api/tags", config.ollama_endpoint.trim_end_matches('/'));
+    let response = http::client().get(&url).timeout(http::PROBE_TIMEOUT).send().await;
+    let tags: OllamaTags = match response {
+        Ok(response) if response.status().is_success() => match response.json().await {
+            Ok(tags) => tags,
+            Err(e) => {
+                return vec![Diagnostic::error(
+                    "ollama_endpoint",
+                    format!("{} is not an Ollama server: {}", config.ollama_endpoint, e),
+                    None,
+                )]
+            }
+        },
+        Ok(response) => {
+            return vec![Diagnostic::error(
+                "ollama_endpoint",
+                format!("Ollama returned {}", response.status()),
+                None,
+            )]
+        }
+        Err(e) => {
+            return vec![Diagnostic::error(
+                "ollama_endpoint",
+                format!("Can't reach Ollama at {}: {}", config.ollama_endpoint, e),
+                Some("Start Ollama with `ollama serve`, or fix the endpoint"),
+            )]
+        }
+    };
+    
+    // "llama3" matches the "llama3:latest" Ollama lists
+    let wanted = &config.ollama_model;
+    let installed = tags.models.iter().any(|model| {
+        model.name == *wanted || model.name.strip_suffix(":latest") == Some(wanted.as_str())
+    });
+    if installed {
+        Vec::new()
+    } else {
+        vec![Diagnostic::error(
+            "ollama_model",
+            format!("Ollama has no model called \"{}\"", wanted),
+            Some("Pull it with `ollama pull`, or pick an installed model"),
+        )]
+    }
+}
+
+async fn check_image_backend(config: &Config) -> Vec<Diagnostic> {
+    if config.image_backend == ImageBackend::Disabled {
+        return Vec::new();
+    }
+    match image_gen::test_backend(config.image_backend, &config.image_endpoint).await {
+        Ok(true) => Vec::new(),
+        Ok(false) => vec![Diagnostic::error(
+            "image_endpoint",
+            format!("{} did not answer like an image server of the selected kind", config.image_endpoint),
+            None,
+        )],
+        Err(e) => vec![Diagnostic::error(
+            "image_endpoint",
+            format!("Can't reach {}: {}", config.image_endpoint, e),
+            Some("Start the image server with its API enabled, or fix the endpoint"),
+        )],
+    }
+}
+
+/// Renders, audio and images are all written under the app data dir
+async fn check_writable(app_dir: &Path) -> Vec<Diagnostic> {
+    let probe = app_dir.join(".write-test");
+    let result = tokio::fs::write(&probe, b"").await;
+    let _ = tokio::fs::remove_file(&probe).await;
+    match result {
+        Ok(()) => Vec::new(),
+        Err(e) => vec![Diagnostic::error(
+            "app_dir",
+            format!("Can't write to {}: {}", app_dir.display(), e),
+            Some("Free up disk space or fix the folder's permissions"),
+        )],
+    }
+}
+
+/// "AIza" followed by 35 URL-safe characters
+fn is_google_api_key(key: &str) -> bool {
+    key.len() == 39
+        && key.starts_with("AIza")
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// "<bot id>:<secret>"
+fn is_telegram_token(token: &str) -> bool {
+    match token.split_once(':') {
+        Some((id, secret)) => {
+            !id.is_empty()
+                && id.chars().all(|c| c.is_ascii_digit())
+                && secret.len() >= 30
+                && secret.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Numeric id, negative for groups and channels, or a public @username
+fn is_chat_id(chat_id: &str) -> bool {
+    let chat_id = chat_id.trim();
+    match chat_id.strip_prefix('@') {
+        Some(username) => username.len() >= 5 && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        None => {
+            let digits = chat_id.strip_prefix('-').unwrap_or(chat_id);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+// Settings exported to and imported from a file, for moving a setup between machines
+use crate::db::Config;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Raised when a change to the file layout can't be read by older builds
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Settings holding credentials, as paths into the config. They are left out
+/// of exports unless asked for, and an import without them keeps the
+/// current values.
+const SECRETS: &[&[&str]] = &[
+    &["youtube_api_key"],
+    &["telegram_bot_token"],
+    &["openai_api_key"],
+    &["pexels_api_key"],
+    &["pixabay_api_key"],
+    &["discord_webhook_url"],
+    &["youtube_client_secret"],
+    &["instagram_access_token"],
+    &["drive_refresh_token"],
+    &["media_host", "authorization"],
+    &["artifact_store", "secret_access_key"],
+    &["api_server", "token"],
+];
+
+/// Shown in place of secret values in an import diff
+const MASK: &str = "********";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigFile {
+    version: u32,
+    #[serde(default)]
+    exported_at: Option<String>,
+    #[serde(default)]
+    secrets_included: bool,
+    settings: Map<String, Value>,
+}
+
+/// One setting an import would change
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    pub key: String,
+    /// Current value as JSON, `None` when unset
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// TOML for `.toml` files, JSON otherwise
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// Write `config` to `path`, without credentials unless `include_secrets`
+pub fn export(config: &Config, path: &Path, include_secrets: bool) -> Result<(), String> {
+    let Value::Object(mut settings) = serde_json::to_value(config).map_err(|e| e.to_string())? else {
+        return Err("Config is not an object".to_string());
+    };
+    if !include_secrets {
+        for secret in SECRETS {
+            remove_path(&mut settings, secret);
+        }
+    }
+    let file = ConfigFile {
+        version: FORMAT_VERSION,
+        exported_at: Some(chrono::Utc::now().to_rfc3339()),
+        secrets_included: include_secrets,
+        settings,
+    };
+    let text = if is_toml(path) {
+        // TOML has no null; unset settings are simply left out
+        let mut value = serde_json::to_value(&file).map_err(|e| e.to_string())?;
+        strip_nulls(&mut value);
+        toml::to_string_pretty(&value).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?
+    };
+    std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Read the file at `path` and work out the config it would produce from
+/// `current`, with the settings that would change. Settings missing from
+/// the file keep their current values; unknown ones are an error.
+pub fn plan_import(current: &Config, path: &Path) -> Result<(Config, Vec<ConfigChange>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: ConfigFile = if is_toml(path) {
+        toml::from_str(&text).map_err(|e| format!("Not a settings file: {}", e))?
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("Not a settings file: {}", e))?
+    };
+    if file.version > FORMAT_VERSION {
+        return Err(format!(
+            "The file is from a newer version of ShotAuto (format {}, this build reads up to {})",
+            file.version, FORMAT_VERSION
+        ));
+    }
+    let mut settings = file.settings;
+    // Files exported before the rename still call it "niche"
+    if let Some(voice) = settings.remove("niche") {
+        settings.entry("voice").or_insert(voice);
+    }
+    
+    let Value::Object(before) = serde_json::to_value(current).map_err(|e| e.to_string())? else {
+        return Err("Config is not an object".to_string());
+    };
+    let unknown: Vec<&str> = settings.keys()
+        .filter(|key| !before.contains_key(*key))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!("Unknown settings: {}", unknown.join(", ")));
+    }
+    
+    let mut after = before.clone();
+    for (key, value) in settings {
+        after.insert(key, value);
+    }
+    // A redacted file leaves credentials as they are
+    for secret in SECRETS {
+        if get_path(&after, secret).is_none() {
+            if let Some(value) = get_path(&before, secret) {
+                set_path(&mut after, secret, value.clone());
+            }
+        }
+    }
+    let config: Config = serde_json::from_value(Value::Object(after.clone()))
+        .map_err(|e| format!("Invalid setting: {}", e))?;
+    
+    // Compare what actually gets stored, after defaults are filled in
+    let Value::Object(after) = serde_json::to_value(&config).map_err(|e| e.to_string())? else {
+        return Err("Config is not an object".to_string());
+    };
+    let mut changes: Vec<ConfigChange> = after.iter()
+        .filter(|(key, value)| before.get(*key) != Some(*value))
+        .map(|(key, value)| ConfigChange {
+            key: key.clone(),
+            from: before.get(key).and_then(|value| display(key, value)),
+            to: display(key, value),
+        })
+        .collect();
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok((config, changes))
+}
+
+/// Names of the settings that differ between two configs, sorted
+pub fn changed_keys(before: &Config, after: &Config) -> Vec<String> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) = (serde_json::to_value(before), serde_json::to_value(after)) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<String> = after.into_iter()
+        .filter(|(key, value)| before.get(key) != Some(value))
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Value as shown in a diff; secrets are masked
+fn display(key: &str, value: &Value) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let mut value = value.clone();
+    for secret in SECRETS.iter().filter(|secret| secret[0] == key) {
+        match &secret[1..] {
+            [] => return Some(MASK.to_string()),
+            rest => {
+                if let Value::Object(fields) = &mut value {
+                    if get_path(fields, rest).is_some_and(|v| !v.is_null()) {
+                        set_path(fields, rest, Value::String(MASK.to_string()));
+                    }
+                }
+            }
+        }
+    }
+    Some(value.to_string())
+}
+
+fn get_path<'a>(object: &'a Map<String, Value>, path: &[&str]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut object = object;
+    for key in parents {
+        object = object.get(*key)?.as_object()?;
+    }
+    object.get(*last).filter(|value| !value.is_null())
+}
+
+fn set_path(object: &mut Map<String, Value>, path: &[&str], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut object = object;
+    for key in parents {
+        match object.get_mut(*key).and_then(Value::as_object_mut) {
+            Some(child) => object = child,
+            None => return,
+        }
+    }
+    object.insert(last.to_string(), value);
+}
+
+fn remove_path(object: &mut Map<String, Value>, path: &[&str]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut object = object;
+    for key in parents {
+        match object.get_mut(*key).and_then(Value::as_object_mut) {
+            Some(child) => object = child,
+            None => return,
+        }
+    }
+    object.remove(*last);
+}
+
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|_, value| !value.is_null());
+            fields.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_host::MediaHost;
+    
+    #[test]
+    fn nested_secrets_are_redacted_and_kept_on_import() {
+        let mut config = Config::default();
+        config.media_host = MediaHost::HttpPut {
+            upload_url: "https://files.example.com/upload".into(),
+            public_url: "https://files.example.com".into(),
+            authorization: Some("Bearer host-secret".into()),
+        };
+        config.api_server.token = "api-secret".into();
+        let path = std::env::temp_dir().join(format!("shotauto-config-test-{}.json", std::process::id()));
+        
+        export(&config, &path, false).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("host-secret") && !text.contains("api-secret"));
+        let (imported, changes) = plan_import(&config, &path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(changes.is_empty(), "{:?}", changes);
+        assert_eq!(imported.api_server.token, "api-secret");
+        assert!(matches!(imported.media_host, MediaHost::HttpPut { authorization: Some(a), .. } if a == "Bearer host-secret"));
+        
+        let shown = display("api_server", &serde_json::to_value(&config.api_server).unwrap()).unwrap();
+        assert!(shown.contains(MASK) && !shown.contains("api-secret"));
+    }
+}
@@ -0,0 +1,95 @@
+// Estimated spend on paid APIs, recorded against the job that caused it
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Billable unit of a paid API
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CostKind {
+    /// OpenAI chat completion tokens, prompt and reply together
+    LlmTokens,
+    /// Characters voiced by OpenAI text-to-speech
+    TtsCharacters,
+    /// Pexels or Pixabay search requests
+    StockRequests,
+}
+
+impl CostKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostKind::LlmTokens => "llm_tokens",
+            CostKind::TtsCharacters => "tts_characters",
+            CostKind::StockRequests => "stock_requests",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "llm_tokens" => Some(CostKind::LlmTokens),
+            "tts_characters" => Some(CostKind::TtsCharacters),
+            "stock_requests" => Some(CostKind::StockRequests),
+            _ => None,
+        }
+    }
+}
+
+/// Prices used for the estimates, in the user's currency. Set them from
+/// the provider's price list; the defaults are OpenAI's gpt-4o-mini and
+/// tts-1 list prices in USD, and free stock searches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitPrices {
+    pub llm_per_1k_tokens: f64,
+    pub tts_per_1k_characters: f64,
+    pub stock_per_request: f64,
+}
+
+impl Default for UnitPrices {
+    fn default() -> Self {
+        Self {
+            llm_per_1k_tokens: 0.0006,
+            tts_per_1k_characters: 0.015,
+            stock_per_request: 0.0,
+        }
+    }
+}
+
+impl UnitPrices {
+    /// Estimated cost of `quantity` units of `kind`
+    pub fn cost(&self, kind: CostKind, quantity: f64) -> f64 {
+        match kind {
+            CostKind::LlmTokens => quantity / 1000.0 * self.llm_per_1k_tokens,
+            CostKind::TtsCharacters => quantity / 1000.0 * self.tts_per_1k_characters,
+            CostKind::StockRequests => quantity * self.stock_per_request,
+        }
+    }
+}
+
+/// The job paid API calls are charged to
+struct Scope {
+    db: Database,
+    job_id: i64,
+    prices: UnitPrices,
+}
+
+tokio::task_local! {
+    static SCOPE: Scope;
+}
+
+/// Run `work` with paid API calls made inside it charged to `job_id`
+pub async fn track<F: Future>(db: Database, job_id: i64, prices: UnitPrices, work: F) -> F::Output {
+    SCOPE.scope(Scope { db, job_id, prices }, work).await
+}
+
+/// Record `quantity` units of `kind` against the job being worked on. Calls
+/// made outside a job, e.g. from a manual command, aren't charged to one
+/// and are not recorded.
+pub fn charge(kind: CostKind, quantity: f64) {
+    let _ = SCOPE.try_with(|scope| {
+        let cost = scope.prices.cost(kind, quantity);
+        if let Err(e) = scope.db.record_job_cost(scope.job_id, kind, quantity, cost) {
+            tracing::warn!("Failed to record {} cost for job {}: {}", kind.as_str(), scope.job_id, e);
+        }
+    });
+}
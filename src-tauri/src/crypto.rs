@@ -0,0 +1,39 @@
+// Hashing helpers shared by webhook signing, artifact uploads and downloads
+use sha2::{Digest, Sha256};
+
+/// Lowercase hex encoding
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+    let mut key = if key.len() > BLOCK_SIZE {
+        Sha256::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK_SIZE, 0);
+    
+    let inner = Sha256::new()
+        .chain_update(key.iter().map(|b| b ^ 0x36).collect::<Vec<_>>())
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(key.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>())
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(hex(&mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+}
@@ -1,17 +1,260 @@
 // Database module for ShotAuto
-use rusqlite::{Connection, Result, params};
-use std::path::PathBuf;
+use rusqlite::backup::Backup;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result, Row, Transaction, TransactionBehavior, params};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use crate::api_server::ApiServerSettings;
+use crate::cache::CacheStats;
+use crate::captions::CaptionStyle;
+use crate::costs::{CostKind, UnitPrices};
+use crate::llm::SamplingParams;
+use crate::artifact_store::ArtifactStore;
+use crate::sftp::SftpTarget;
+use crate::media_host::MediaHost;
+use crate::notifications::NotificationSettings;
+use crate::prompt::{ChannelVoice, HookStyle};
+use crate::render::{RenderPreset, TemplateLayout};
+use crate::schedule::{PostingSchedule, RunWindows};
+use crate::timing::SpeechTiming;
+use crate::webhooks::WebhookEvent;
+use crate::youtube_upload::VideoMetadata;
 
 /// Application configuration stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub youtube_api_key: Option<String>,
     pub telegram_bot_token: Option<String>,
+    /// Chat the bot talks to: review copies, progress updates and commands
     pub telegram_chat_id: Option<String>,
     pub ollama_endpoint: String,
+    pub ollama_model: String,
+    pub llm_backend: LlmBackend,
+    pub openai_model: String,
+    /// Poll trending videos in the background every `poll_interval_secs`;
+    /// when off, trends are only fetched on request
+    pub auto_fetch_trends: bool,
     pub poll_interval_secs: u64,
+    /// Words or phrases that send a generated script to manual review
+    pub banned_words: Vec<String>,
+    /// Also ask the LLM whether a script violates the content policy
+    pub llm_moderation_enabled: bool,
+    /// Prompt used for script generation; see `prompt::render` for placeholders
+    pub script_prompt_template: String,
+    pub transcription_backend: TranscriptionBackend,
+    pub openai_api_key: Option<String>,
+    /// yt-dlp binary; see `Config::yt_dlp` for the fallbacks
+    pub yt_dlp_path: Option<String>,
+    /// whisper.cpp CLI binary; falls back to `whisper-cli` on PATH
+    pub whisper_cpp_path: Option<String>,
+    /// ggml model file used by whisper.cpp
+    pub whisper_model_path: Option<String>,
+    /// Language codes (e.g. `en`, `ko`); every job produces one short per language
+    pub target_languages: Vec<String>,
+    /// TTS voice to use for each language code
+    pub language_voices: HashMap<String, String>,
+    /// How many few-shot examples to inject into each script prompt
+    pub examples_per_prompt: u32,
+    pub tts_backend: TtsBackend,
+    /// Default voice when `language_voices` has no entry for the language
+    pub tts_voice: Option<String>,
+    pub openai_tts_model: String,
+    /// edge-tts binary; falls back to `edge-tts` on PATH
+    pub edge_tts_path: Option<String>,
+    /// ffmpeg binary; falls back to `ffmpeg` on PATH
+    pub ffmpeg_path: Option<String>,
+    /// Run a loudness normalization pass on TTS audio
+    pub loudness_normalization: bool,
+    /// Integrated loudness target in LUFS (YouTube normalizes to about -14)
+    pub loudness_target_lufs: f64,
+    /// Maximum true peak in dBTP
+    pub loudness_true_peak_db: f64,
+    /// ffprobe binary; falls back to `ffprobe` on PATH
+    pub ffprobe_path: Option<String>,
+    /// Mix a track from the music library under the narration
+    pub music_enabled: bool,
+    /// Music gain before ducking, in dB relative to the source file
+    pub music_volume_db: f64,
+    pub caption_style: CaptionStyle,
+    /// Render template used for new renders; the built-in layout when unset
+    pub default_render_template: Option<i64>,
+    /// Stock footage provider used as the render background
+    pub stock_provider: StockProvider,
+    pub pexels_api_key: Option<String>,
+    pub pixabay_api_key: Option<String>,
+    /// Generate one background image per scene; takes precedence over stock footage
+    pub image_backend: ImageBackend,
+    pub image_endpoint: String,
+    /// Checkpoint name; A1111 keeps its loaded model when unset
+    pub image_model: Option<String>,
+    pub image_steps: u32,
+    /// Size limit of the media cache (stock clips, images, source videos, narration), in MB
+    #[serde(alias = "source_cache_limit_mb")]
+    pub cache_limit_mb: u64,
+    pub thumbnail_mode: ThumbnailMode,
+    pub video_encoder: VideoEncoder,
+    /// Named output formats; see `RenderPreset::defaults`
+    pub render_presets: Vec<RenderPreset>,
+    /// Preset used by `render_short`
+    pub default_render_preset: String,
+    /// Jobs in the script/narration stage at once; mostly waiting on the LLM and TTS
+    pub max_concurrent_generations: u32,
+    /// Jobs in the CPU-heavy render stage at once
+    pub max_concurrent_renders: u32,
+    /// Time limit for one job's generation stage; 0 for none
+    pub generation_timeout_secs: u64,
+    /// Time limit for one job's render stage; 0 for none
+    pub render_timeout_secs: u64,
+    /// Times a failed stage is retried before the job is given up on
+    pub max_retries: u32,
+    /// Hold rendered shorts for manual approval before they are sent or uploaded
+    pub approval_required: bool,
+    /// Keep a status message per job up to date in the Telegram chat
+    pub telegram_progress_updates: bool,
+    /// Chats and channels rendered shorts are delivered to
+    pub telegram_chats: Vec<TelegramChat>,
+    /// Discord channel webhook rendered shorts are posted to
+    pub discord_webhook_url: Option<String>,
+    /// OAuth client of the user's Google Cloud project, for uploads
+    pub youtube_client_id: Option<String>,
+    pub youtube_client_secret: Option<String>,
+    /// Daily publish slots assigned to approved shorts
+    pub posting_schedule: PostingSchedule,
+    /// Instagram Business account id that Reels are published to
+    pub instagram_user_id: Option<String>,
+    /// Long-lived Graph API token with instagram_content_publish
+    pub instagram_access_token: Option<String>,
+    /// Publish shorts to Instagram unless a short says otherwise
+    pub instagram_default: bool,
+    /// Temporary public hosting for platforms that fetch videos by URL
+    pub media_host: MediaHost,
+    /// Hours between view/like/comment refreshes of uploaded shorts
+    pub stats_refresh_hours: u32,
+    /// How new jobs are spread over the connected YouTube accounts
+    pub account_assignment: AccountAssignment,
+    /// Copy the database to the backups folder once a day
+    pub auto_backup: bool,
+    /// Days daily backups are kept
+    pub backup_retention_days: u32,
+    /// Days fetched trends without a job are kept; 0 keeps them forever
+    pub keep_trends_days: u32,
+    /// Days after which new trends nobody made a job from are archived; 0
+    /// never archives them
+    pub archive_trends_days: u32,
+    /// Days finished jobs, with their shorts and logs, are kept; 0 keeps them forever
+    pub keep_done_jobs_days: u32,
+    /// Days narration, renders and thumbnails stay on disk; 0 keeps them,
+    /// and files no short uses any more, forever
+    pub keep_rendered_files_days: u32,
+    /// Free space below which a `low-disk-space` event is sent; 0 turns the warning off
+    pub low_disk_warning_mb: u32,
+    /// Prices the per-job cost estimates are worked out with
+    pub unit_prices: UnitPrices,
+    /// Pipeline events shown as desktop notifications
+    pub notifications: NotificationSettings,
+    /// Closing the window hides it to the tray and the pipeline keeps running
+    pub close_to_tray: bool,
+    /// Start hidden in the tray when the user logs in
+    pub launch_on_startup: bool,
+    /// How long quitting waits for running jobs to reach a checkpoint before stopping them
+    pub shutdown_grace_secs: u64,
+    /// Local REST API for remote control
+    pub api_server: ApiServerSettings,
+    /// Exercise the pipeline without posting anything: trends come from a
+    /// bundled fixture, renders are 2 s placeholders and nothing is sent out
+    pub dry_run: bool,
+    /// Queue priority of jobs created in a batch; higher runs first
+    pub batch_priority: i32,
+    /// Who the channel is for and how it talks, see `prompt::ChannelVoice`
+    pub voice: ChannelVoice,
+    /// Scripts written per job and language, each with its own hook style.
+    /// Above one they are rendered and published as an A/B variant group.
+    pub script_variants: u32,
+    /// Word overlap, from 0 to 1, at which a trend counts as a near-duplicate
+    /// of a short already made and gets no job; 0 turns the check off
+    pub similarity_threshold: f64,
+    /// Longest a rendered short may be. YouTube files longer uploads as
+    /// regular videos; it takes Shorts of up to 180 seconds.
+    pub max_short_secs: u32,
+    /// What to do with a render over `max_short_secs`
+    pub length_fix: LengthFix,
+    /// Cut silence before and after TTS narration and shorten long pauses
+    pub pacing_cleanup: bool,
+    /// Pauses between sentences longer than this are shortened to it
+    pub max_pause_ms: u32,
+    /// UltraFace ONNX model for the `subject` reframe mode
+    pub face_model_path: Option<String>,
+    /// How long Ollama keeps the model loaded after a request, as a duration
+    /// such as `30m` or `-1` for always; empty leaves Ollama's default
+    pub ollama_keep_alive: String,
+    /// Load the Ollama model when the worker starts, so the first job doesn't wait for it
+    pub ollama_warm_up: bool,
+    /// When trends are fetched and jobs run; any time unless enabled
+    pub run_windows: RunWindows,
+    /// Cap on download speed across yt-dlp and stock footage, in KB/s; 0 for none
+    pub download_limit_kb: u32,
+    /// Cap on YouTube upload speed, in KB/s; 0 for none
+    pub upload_limit_kb: u32,
+    /// Where finished renders are kept
+    pub artifact_store: ArtifactStore,
+    /// Keep the local copy of a render once it is in the bucket
+    pub keep_local_renders: bool,
+    /// Google Drive folder finished shorts are backed up to
+    pub drive_folder_id: Option<String>,
+    /// Set by connecting Google Drive; uses the YouTube OAuth client
+    pub drive_refresh_token: Option<String>,
+    /// Server finished shorts are copied to over SFTP
+    pub sftp: SftpTarget,
+    /// Work on jobs for starred trends before any others
+    pub prioritize_starred_trends: bool,
+}
+
+impl Config {
+    /// ffmpeg binary to invoke
+    pub fn ffmpeg(&self) -> &str {
+        self.ffmpeg_path.as_deref().unwrap_or("ffmpeg")
+    }
+    
+    /// ffprobe binary to invoke
+    pub fn ffprobe(&self) -> &str {
+        self.ffprobe_path.as_deref().unwrap_or("ffprobe")
+    }
+    
+    /// Preset called `name`, or the built-in 1080p30 preset when there is none
+    pub fn render_preset(&self, name: &str) -> RenderPreset {
+        self.render_presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .unwrap_or_default()
+    }
+    
+    /// Shorts made per job and language: `script_variants`, kept between one
+    /// and the number of hook styles
+    pub fn variants_per_language(&self) -> usize {
+        (self.script_variants as usize).clamp(1, HookStyle::ALL.len())
+    }
+    
+    /// yt-dlp binary to invoke: the configured path, else one bundled next to
+    /// the app executable, else `yt-dlp` on PATH
+    pub fn yt_dlp(&self) -> String {
+        if let Some(ref path) = self.yt_dlp_path {
+            return path.clone();
+        }
+        let name = if cfg!(windows) { "yt-dlp.exe" } else { "yt-dlp" };
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+            .filter(|bundled| bundled.is_file())
+            .map(|bundled| bundled.to_string_lossy().to_string())
+            .unwrap_or_else(|| "yt-dlp".to_string())
+    }
 }
 
 impl Default for Config {
@@ -21,11 +264,367 @@ impl Default for Config {
             telegram_bot_token: None,
             telegram_chat_id: None,
             ollama_endpoint: "http://localhost:11434".to_string(),
+            ollama_model: "llama3.2".to_string(),
+            llm_backend: LlmBackend::Ollama,
+            openai_model: "gpt-4o-mini".to_string(),
+            auto_fetch_trends: false,
             poll_interval_secs: 300, // 5 minutes
+            banned_words: Vec::new(),
+            llm_moderation_enabled: false,
+            script_prompt_template: crate::prompt::DEFAULT_SCRIPT_TEMPLATE.to_string(),
+            transcription_backend: TranscriptionBackend::Disabled,
+            openai_api_key: None,
+            yt_dlp_path: None,
+            whisper_cpp_path: None,
+            whisper_model_path: None,
+            target_languages: vec!["en".to_string()],
+            language_voices: HashMap::new(),
+            examples_per_prompt: 2,
+            tts_backend: TtsBackend::Edge,
+            tts_voice: None,
+            openai_tts_model: "tts-1".to_string(),
+            edge_tts_path: None,
+            ffmpeg_path: None,
+            loudness_normalization: true,
+            loudness_target_lufs: -14.0,
+            loudness_true_peak_db: -1.5,
+            ffprobe_path: None,
+            music_enabled: false,
+            music_volume_db: -18.0,
+            caption_style: CaptionStyle::default(),
+            default_render_template: None,
+            stock_provider: StockProvider::Disabled,
+            pexels_api_key: None,
+            pixabay_api_key: None,
+            image_backend: ImageBackend::Disabled,
+            image_endpoint: "http://127.0.0.1:7860".to_string(),
+            image_model: None,
+            image_steps: 25,
+            cache_limit_mb: 4096,
+            thumbnail_mode: ThumbnailMode::Frame,
+            video_encoder: VideoEncoder::Libx264,
+            render_presets: RenderPreset::defaults(),
+            default_render_preset: "1080p30".to_string(),
+            max_concurrent_generations: 3,
+            max_concurrent_renders: 1,
+            generation_timeout_secs: 900,
+            render_timeout_secs: 1800,
+            max_retries: 2,
+            approval_required: false,
+            telegram_progress_updates: false,
+            telegram_chats: Vec::new(),
+            discord_webhook_url: None,
+            youtube_client_id: None,
+            youtube_client_secret: None,
+            posting_schedule: PostingSchedule::default(),
+            instagram_user_id: None,
+            instagram_access_token: None,
+            instagram_default: false,
+            media_host: MediaHost::default(),
+            stats_refresh_hours: 6,
+            account_assignment: AccountAssignment::RoundRobin,
+            auto_backup: false,
+            backup_retention_days: 7,
+            keep_trends_days: 0,
+            archive_trends_days: 7,
+            keep_done_jobs_days: 0,
+            keep_rendered_files_days: 0,
+            low_disk_warning_mb: 2048,
+            unit_prices: UnitPrices::default(),
+            notifications: NotificationSettings::default(),
+            close_to_tray: true,
+            launch_on_startup: false,
+            shutdown_grace_secs: 30,
+            api_server: ApiServerSettings::default(),
+            dry_run: false,
+            batch_priority: 0,
+            voice: ChannelVoice::default(),
+            script_variants: 1,
+            similarity_threshold: 0.6,
+            max_short_secs: 60,
+            length_fix: LengthFix::Auto,
+            pacing_cleanup: true,
+            max_pause_ms: 600,
+            face_model_path: None,
+            ollama_keep_alive: "30m".to_string(),
+            ollama_warm_up: true,
+            run_windows: RunWindows::default(),
+            download_limit_kb: 0,
+            upload_limit_kb: 0,
+            artifact_store: ArtifactStore::default(),
+            keep_local_renders: true,
+            drive_folder_id: None,
+            drive_refresh_token: None,
+            sftp: SftpTarget::default(),
+            prioritize_starred_trends: false,
+        }
+    }
+}
+
+/// Which service generates scripts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmBackend {
+    #[default]
+    Ollama,
+    OpenAi,
+}
+
+impl LlmBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmBackend::Ollama => "ollama",
+            LlmBackend::OpenAi => "open_ai",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "open_ai" => LlmBackend::OpenAi,
+            _ => LlmBackend::Ollama,
+        }
+    }
+}
+
+/// Which text-to-speech service voices the scripts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsBackend {
+    OpenAi,
+    #[default]
+    Edge,
+}
+
+impl TtsBackend {
+    pub const ALL: [TtsBackend; 2] = [TtsBackend::OpenAi, TtsBackend::Edge];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TtsBackend::OpenAi => "open_ai",
+            TtsBackend::Edge => "edge",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "open_ai" => TtsBackend::OpenAi,
+            _ => TtsBackend::Edge,
+        }
+    }
+}
+
+/// Where source-video transcripts come from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionBackend {
+    #[default]
+    Disabled,
+    WhisperCpp,
+    OpenAi,
+}
+
+impl TranscriptionBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranscriptionBackend::Disabled => "disabled",
+            TranscriptionBackend::WhisperCpp => "whisper_cpp",
+            TranscriptionBackend::OpenAi => "open_ai",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "whisper_cpp" => TranscriptionBackend::WhisperCpp,
+            "open_ai" => TranscriptionBackend::OpenAi,
+            _ => TranscriptionBackend::Disabled,
+        }
+    }
+}
+
+/// Where background stock footage comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StockProvider {
+    #[default]
+    Disabled,
+    Pexels,
+    Pixabay,
+}
+
+impl StockProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockProvider::Disabled => "disabled",
+            StockProvider::Pexels => "pexels",
+            StockProvider::Pixabay => "pixabay",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "pexels" => StockProvider::Pexels,
+            "pixabay" => StockProvider::Pixabay,
+            _ => StockProvider::Disabled,
+        }
+    }
+}
+
+/// Stable Diffusion server used for generated backgrounds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageBackend {
+    #[default]
+    Disabled,
+    /// AUTOMATIC1111 web UI (`--api`) or a compatible fork
+    Automatic1111,
+    ComfyUi,
+}
+
+impl ImageBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageBackend::Disabled => "disabled",
+            ImageBackend::Automatic1111 => "automatic1111",
+            ImageBackend::ComfyUi => "comfy_ui",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "automatic1111" => ImageBackend::Automatic1111,
+            "comfy_ui" => ImageBackend::ComfyUi,
+            _ => ImageBackend::Disabled,
+        }
+    }
+}
+
+/// H.264 encoder used for renders; hardware encoders fall back to `Libx264`
+/// when they fail
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum VideoEncoder {
+    #[default]
+    #[serde(rename = "libx264")]
+    Libx264,
+    /// NVIDIA GPUs
+    #[serde(rename = "h264_nvenc")]
+    Nvenc,
+    /// macOS
+    #[serde(rename = "h264_videotoolbox")]
+    VideoToolbox,
+    /// Intel Quick Sync
+    #[serde(rename = "h264_qsv")]
+    Qsv,
+}
+
+impl VideoEncoder {
+    pub const ALL: [VideoEncoder; 4] = [
+        VideoEncoder::Libx264,
+        VideoEncoder::Nvenc,
+        VideoEncoder::VideoToolbox,
+        VideoEncoder::Qsv,
+    ];
+    
+    /// ffmpeg encoder name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoEncoder::Libx264 => "libx264",
+            VideoEncoder::Nvenc => "h264_nvenc",
+            VideoEncoder::VideoToolbox => "h264_videotoolbox",
+            VideoEncoder::Qsv => "h264_qsv",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "h264_nvenc" => VideoEncoder::Nvenc,
+            "h264_videotoolbox" => VideoEncoder::VideoToolbox,
+            "h264_qsv" => VideoEncoder::Qsv,
+            _ => VideoEncoder::Libx264,
+        }
+    }
+}
+
+/// How a short's poster frame is made
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailMode {
+    /// The most representative frame of the render
+    #[default]
+    Frame,
+    /// That frame, darkened, with the trend title written over it
+    Title,
+}
+
+impl ThumbnailMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThumbnailMode::Frame => "frame",
+            ThumbnailMode::Title => "title",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "title" => ThumbnailMode::Title,
+            _ => ThumbnailMode::Frame,
+        }
+    }
+}
+
+/// What happens to a short rendered longer than `Config::max_short_secs`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthFix {
+    /// Cut trailing silence, then speed the short up slightly if that isn't
+    /// enough
+    #[default]
+    Auto,
+    /// Only cut trailing silence
+    TrimSilence,
+    /// Fail the job
+    Fail,
+}
+
+impl LengthFix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LengthFix::Auto => "auto",
+            LengthFix::TrimSilence => "trim_silence",
+            LengthFix::Fail => "fail",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "trim_silence" => LengthFix::TrimSilence,
+            "fail" => LengthFix::Fail,
+            _ => LengthFix::Auto,
         }
     }
 }
 
+/// Telegram chat or channel that rendered shorts are delivered to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramChat {
+    /// Numeric id, or @username for public channels
+    pub chat_id: String,
+    /// Shown in the UI in place of the id
+    #[serde(default)]
+    pub label: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl TelegramChat {
+    pub fn display_name(&self) -> &str {
+        if self.label.trim().is_empty() { &self.chat_id } else { &self.label }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Trend data from YouTube
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trend {
@@ -36,27 +635,186 @@ pub struct Trend {
     pub views: Option<i64>,
     pub category: Option<String>,
     pub fetched_at: DateTime<Utc>,
+    pub transcript: Option<String>,
+    /// The source video's keywords, as set by its uploader
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub source: TrendSource,
+    /// The operator's own notes on the trend
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Marked by hand as worth making; see `prioritize_starred_trends`
+    #[serde(default)]
+    pub starred: bool,
+    #[serde(default)]
+    pub state: TrendState,
+    /// Why the trend was skipped, when it was
+    #[serde(default)]
+    pub skip_reason: Option<String>,
+    /// Made up rather than fetched, by the dry-run fixture or the demo data;
+    /// its jobs are always dry runs
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Where a trend came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendSource {
+    /// A YouTube video, fetched as trending or added by link
+    #[default]
+    YouTube,
+    /// A free-text topic typed in by hand. There is no source video; the
+    /// topic is the title and `video_id` is a `topic:` key.
+    ManualTopic,
+    /// Made up by `demo::seed`; its jobs are never claimed by the pipeline
+    Demo,
+}
+
+impl TrendSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendSource::YouTube => "youtube",
+            TrendSource::ManualTopic => "manual_topic",
+            TrendSource::Demo => "demo",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "manual_topic" => TrendSource::ManualTopic,
+            "demo" => TrendSource::Demo,
+            _ => TrendSource::YouTube,
+        }
+    }
+}
+
+/// Where a trend is in its life, from fetched to made or set aside
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendState {
+    /// Fetched or added, with no job yet
+    #[default]
+    New,
+    /// A job has been made from it
+    Queued,
+    /// A job made from it finished
+    Processed,
+    /// Passed over by hand; see `skip_reason`
+    Skipped,
+    /// Left without a job for `archive_trends_days`
+    Archived,
+}
+
+impl TrendState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendState::New => "new",
+            TrendState::Queued => "queued",
+            TrendState::Processed => "processed",
+            TrendState::Skipped => "skipped",
+            TrendState::Archived => "archived",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "queued" => TrendState::Queued,
+            "processed" => TrendState::Processed,
+            "skipped" => TrendState::Skipped,
+            "archived" => TrendState::Archived,
+            _ => TrendState::New,
+        }
+    }
+}
+
+/// Columns selected for a `Trend`, in the order `trend_from_row` reads them
+const TREND_COLUMNS: &str = "id, video_id, title, channel, views, category, fetched_at, transcript, source, tags_json, notes, starred, state, skip_reason, dry_run";
+
+/// Number of columns in a column list
+fn column_count(columns: &str) -> usize {
+    columns.split(", ").count()
+}
+
+/// Qualify every column in a column list with a table alias
+fn prefixed(alias: &str, columns: &str) -> String {
+    columns
+        .split(", ")
+        .map(|c| format!("{}.{}", alias, c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Build a `Trend` from a row whose trend columns start at `base`
+fn trend_from_row(row: &Row, base: usize) -> Result<Trend> {
+    Ok(Trend {
+        id: Some(row.get(base)?),
+        video_id: row.get(base + 1)?,
+        title: row.get(base + 2)?,
+        channel: row.get(base + 3)?,
+        views: row.get(base + 4)?,
+        category: row.get(base + 5)?,
+        fetched_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(base + 6)?)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+        transcript: row.get(base + 7)?,
+        source: TrendSource::from_str(&row.get::<_, String>(base + 8)?),
+        tags: row.get::<_, Option<String>>(base + 9)?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        notes: row.get(base + 10)?,
+        starred: row.get(base + 11)?,
+        state: TrendState::from_str(&row.get::<_, String>(base + 12)?),
+        skip_reason: row.get(base + 13)?,
+        dry_run: row.get(base + 14)?,
+    })
 }
 
 /// Job status enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Pending,
     Generating,
+    /// Scripts and narration are ready; waiting for a render slot
+    Generated,
     Rendering,
     Done,
     Failed,
+    NeedsReview,
+    /// Failed on every allowed attempt; waits for manual triage
+    Dead,
 }
 
 impl JobStatus {
+    pub const ALL: [JobStatus; 8] = [
+        JobStatus::Pending,
+        JobStatus::Generating,
+        JobStatus::Generated,
+        JobStatus::Rendering,
+        JobStatus::Done,
+        JobStatus::Failed,
+        JobStatus::NeedsReview,
+        JobStatus::Dead,
+    ];
+    
+    /// SQL CHECK clause listing every valid status
+    pub fn check_constraint() -> String {
+        let values: Vec<String> = Self::ALL.iter().map(|s| format!("'{}'", s.as_str())).collect();
+        format!("CHECK(status IN ({}))", values.join(","))
+    }
+    
     pub fn as_str(&self) -> &'static str {
         match self {
             JobStatus::Pending => "pending",
             JobStatus::Generating => "generating",
+            JobStatus::Generated => "generated",
             JobStatus::Rendering => "rendering",
             JobStatus::Done => "done",
             JobStatus::Failed => "failed",
+            JobStatus::NeedsReview => "needs_review",
+            JobStatus::Dead => "dead",
         }
     }
     
@@ -64,14 +822,59 @@ impl JobStatus {
         match s {
             "pending" => JobStatus::Pending,
             "generating" => JobStatus::Generating,
+            "generated" => JobStatus::Generated,
             "rendering" => JobStatus::Rendering,
             "done" => JobStatus::Done,
             "failed" => JobStatus::Failed,
+            "needs_review" => JobStatus::NeedsReview,
+            "dead" => JobStatus::Dead,
             _ => JobStatus::Pending,
         }
     }
 }
 
+/// Step of the pipeline, in the order a job goes through them. A job's
+/// checkpoint is the last step finished for all of its shorts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStep {
+    /// Scripts written and moderated
+    Script,
+    /// Narration and caption timing
+    Narration,
+    /// Upload title, description and hashtags
+    Metadata,
+    /// Rendered video and thumbnail
+    Video,
+}
+
+impl PipelineStep {
+    pub const ALL: [PipelineStep; 4] = [
+        PipelineStep::Script,
+        PipelineStep::Narration,
+        PipelineStep::Metadata,
+        PipelineStep::Video,
+    ];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineStep::Script => "script",
+            PipelineStep::Narration => "narration",
+            PipelineStep::Metadata => "metadata",
+            PipelineStep::Video => "video",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.as_str() == s)
+    }
+    
+    /// The step before this one, `None` for the first
+    pub fn previous(&self) -> Option<PipelineStep> {
+        Self::ALL.into_iter().take_while(|step| step != self).last()
+    }
+}
+
 /// Job in the processing queue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
@@ -84,293 +887,4738 @@ pub struct Job {
     pub created_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
+    /// Worker holding the job while a stage runs
+    pub worker_id: Option<String>,
+    /// The worker's claim lapses at this time unless renewed
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// YouTube account the job's shorts are uploaded to
+    pub target_account_id: Option<i64>,
+    /// Profile that was active when the job was created
+    pub profile_id: Option<i64>,
+    /// Created in dry-run mode: rendered as a placeholder and never posted
+    pub dry_run: bool,
+    /// Last step finished for every short; workers carry on after it
+    pub checkpoint: Option<PipelineStep>,
 }
 
-/// Generated short video
+const JOB_COLUMNS: &str = "id, trend_id, status, priority, retry_count, error_msg, created_at, started_at, finished_at, worker_id, lease_expires_at, target_account_id, profile_id, dry_run, checkpoint";
+
+fn job_from_row(row: &Row) -> Result<Job> {
+    let timestamp = |i: usize| -> Result<Option<DateTime<Utc>>> {
+        Ok(row.get::<_, Option<String>>(i)?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)))
+    };
+    Ok(Job {
+        id: Some(row.get(0)?),
+        trend_id: row.get(1)?,
+        status: JobStatus::from_str(&row.get::<_, String>(2)?),
+        priority: row.get(3)?,
+        retry_count: row.get(4)?,
+        error_msg: row.get(5)?,
+        created_at: timestamp(6)?.unwrap_or_else(Utc::now),
+        started_at: timestamp(7)?,
+        finished_at: timestamp(8)?,
+        worker_id: row.get(9)?,
+        lease_expires_at: timestamp(10)?,
+        target_account_id: row.get(11)?,
+        profile_id: row.get(12)?,
+        dry_run: row.get(13)?,
+        checkpoint: row.get::<_, Option<String>>(14)?.and_then(|s| PipelineStep::from_str(&s)),
+    })
+}
+
+/// One failed attempt at a job stage
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Short {
+pub struct JobError {
     pub id: Option<i64>,
     pub job_id: i64,
-    pub script: Option<String>,
-    pub audio_path: Option<String>,
-    pub video_path: Option<String>,
-    pub duration_sec: Option<f64>,
-    pub telegram_sent: bool,
+    /// 1 for the first attempt
+    pub attempt: i32,
+    pub stage: String,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
 }
 
-/// Database connection wrapper
-pub struct Database {
-    conn: Connection,
+/// Dead job with its trend and every recorded failure, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadJob {
+    pub job: Job,
+    pub trend: Trend,
+    pub errors: Vec<JobError>,
 }
 
-impl Database {
-    /// Create a new database connection
-    pub fn new(path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(&path)?;
-        let db = Self { conn };
-        db.init_schema()?;
-        Ok(db)
-    }
-    
-    /// Initialize database schema
-    fn init_schema(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            -- Configuration table
-            CREATE TABLE IF NOT EXISTS config (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );
-            
-            -- Trends from YouTube
-            CREATE TABLE IF NOT EXISTS trends (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                video_id TEXT UNIQUE NOT NULL,
-                title TEXT NOT NULL,
-                channel TEXT,
-                views INTEGER,
-                category TEXT,
-                fetched_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            );
-            
-            -- Processing jobs queue
-            CREATE TABLE IF NOT EXISTS jobs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                trend_id INTEGER REFERENCES trends(id),
-                status TEXT DEFAULT 'pending' 
-                    CHECK(status IN ('pending','generating','rendering','done','failed')),
-                priority INTEGER DEFAULT 0,
-                retry_count INTEGER DEFAULT 0,
-                error_msg TEXT,
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                started_at TIMESTAMP,
-                finished_at TIMESTAMP
-            );
-            
-            -- Generated shorts
-            CREATE TABLE IF NOT EXISTS shorts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                job_id INTEGER REFERENCES jobs(id),
-                script TEXT,
-                audio_path TEXT,
-                video_path TEXT,
-                duration_sec REAL,
-                telegram_sent BOOLEAN DEFAULT 0
-            );
-            
-            -- Performance metrics
-            CREATE TABLE IF NOT EXISTS metrics (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                job_id INTEGER REFERENCES jobs(id),
-                stage TEXT,
-                duration_ms INTEGER,
-                recorded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            );
-            
-            -- Create indexes
-            CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status);
-            CREATE INDEX IF NOT EXISTS idx_trends_video_id ON trends(video_id);
-            "#
-        )?;
-        Ok(())
+/// Log lines `get_job_details` includes, the most recent ones
+const DETAIL_LOG_LINES: u32 = 50;
+
+/// Everything recorded about one job, for inspecting it in the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobDetails {
+    pub job: Job,
+    pub trend: Trend,
+    /// Jobs that have to be done before this one is started
+    pub depends_on: Vec<i64>,
+    /// One per target language and variant, with script, paths and duration
+    pub shorts: Vec<Short>,
+    /// Failed attempts, oldest first
+    pub errors: Vec<JobError>,
+    pub metrics: Vec<JobMetric>,
+    /// The last `DETAIL_LOG_LINES` log entries, oldest first
+    pub logs: JobLogPage,
+}
+
+/// Severity of a job log entry, least severe first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "debug" => LogLevel::Debug,
+            "warn" => LogLevel::Warn,
+            "error" => LogLevel::Error,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+/// Message logged while a job ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLog {
+    pub id: Option<i64>,
+    pub job_id: i64,
+    pub level: LogLevel,
+    /// Pipeline stage that wrote the entry, if any
+    pub stage: Option<String>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One page of a job's log, oldest entry first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLogPage {
+    pub entries: Vec<JobLog>,
+    /// Entries in the whole log
+    pub total: i64,
+}
+
+/// Who did something recorded in the activity log
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Actor {
+    /// Someone using the app
+    Ui,
+    /// The pipeline, running on its own
+    Autopilot,
+    /// A command sent to the Telegram bot
+    TelegramBot,
+    /// A request to the local REST API
+    Api,
+}
+
+impl Actor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Actor::Ui => "ui",
+            Actor::Autopilot => "autopilot",
+            Actor::TelegramBot => "telegram-bot",
+            Actor::Api => "api",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "autopilot" => Actor::Autopilot,
+            "telegram-bot" => Actor::TelegramBot,
+            "api" => Actor::Api,
+            _ => Actor::Ui,
+        }
+    }
+}
+
+/// Significant action recorded in the activity log
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityAction {
+    ConfigChanged,
+    ProfileSwitched,
+    JobCreated,
+    JobDeleted,
+    JobRestored,
+    /// A step of a job was run again, named in the detail
+    StepRerun,
+    ShortApproved,
+    ShortRejected,
+    ShortRegenerated,
+    ShortUploaded,
+}
+
+impl ActivityAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityAction::ConfigChanged => "config_changed",
+            ActivityAction::ProfileSwitched => "profile_switched",
+            ActivityAction::JobCreated => "job_created",
+            ActivityAction::JobDeleted => "job_deleted",
+            ActivityAction::JobRestored => "job_restored",
+            ActivityAction::StepRerun => "step_rerun",
+            ActivityAction::ShortApproved => "short_approved",
+            ActivityAction::ShortRejected => "short_rejected",
+            ActivityAction::ShortRegenerated => "short_regenerated",
+            ActivityAction::ShortUploaded => "short_uploaded",
+        }
+    }
+}
+
+/// Entry in the activity log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Activity {
+    pub id: i64,
+    pub actor: Actor,
+    /// `ActivityAction` name; kept as text so entries written by newer
+    /// builds still load
+    pub action: String,
+    pub job_id: Option<i64>,
+    pub short_id: Option<i64>,
+    /// What changed, e.g. the settings that were saved
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Rows deleted per transaction when pruning old data
+const PRUNE_BATCH: i64 = 500;
+
+/// Table written by `export_data`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportScope {
+    Trends,
+    /// Jobs with the title, channel and category of their trend
+    Jobs,
+    /// Shorts with their latest view count
+    Shorts,
+    Metrics,
+}
+
+impl ExportScope {
+    /// Query for one page of rows after a given id. The first column is
+    /// the id, which pages are keyed on.
+    fn batch_sql(&self) -> &'static str {
+        match self {
+            ExportScope::Trends => "SELECT id, video_id, title, channel, views, category, fetched_at, transcript, source, tags_json,
+                        notes, starred, state, skip_reason
+                 FROM trends WHERE id > ?1 ORDER BY id LIMIT ?2",
+            ExportScope::Jobs => "SELECT j.id, j.status, j.priority, j.retry_count, j.error_msg,
+                        j.created_at, j.started_at, j.finished_at, j.target_account_id,
+                        j.trend_id, t.video_id AS trend_video_id, t.title AS trend_title,
+                        t.channel AS trend_channel, t.category AS trend_category, t.views AS trend_views
+                 FROM jobs j LEFT JOIN trends t ON t.id = j.trend_id
+                 WHERE j.id > ?1 ORDER BY j.id LIMIT ?2",
+            ExportScope::Shorts => "SELECT s.id, s.job_id, s.language, s.script, s.duration_sec,
+                        s.approval_status, s.review_note, s.reviewed_at, s.video_path, s.publish_at,
+                        s.youtube_video_id, s.uploaded_at,
+                        (SELECT st.views FROM short_stats st WHERE st.short_id = s.id
+                         ORDER BY st.fetched_at DESC LIMIT 1) AS latest_views
+                 FROM shorts s WHERE s.id > ?1 ORDER BY s.id LIMIT ?2",
+            ExportScope::Metrics => "SELECT id, job_id, stage, metric, value, duration_ms, recorded_at
+                 FROM metrics WHERE id > ?1 ORDER BY id LIMIT ?2",
+        }
+    }
+}
+
+/// One page of exported rows
+#[derive(Debug, Clone)]
+pub struct ExportBatch {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Id of the last row, to pass as `after_id` for the next page
+    pub last_id: Option<i64>,
+}
+
+/// Time window for metric queries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsRange {
+    Day,
+    #[default]
+    Week,
+    Month,
+    All,
+}
+
+impl MetricsRange {
+    /// Start of the window, `None` for all time
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        let days = match self {
+            MetricsRange::Day => 1,
+            MetricsRange::Week => 7,
+            MetricsRange::Month => 30,
+            MetricsRange::All => return None,
+        };
+        Some(Utc::now() - Duration::days(days))
+    }
+}
+
+/// Quantity charted by `get_metrics_series`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesMetric {
+    /// Jobs that finished done
+    JobsCompleted,
+    /// Mean generation stage duration, in ms
+    GenerationTime,
+    /// Mean render stage duration, in ms
+    RenderTime,
+    /// Mean time to upload a short to one Telegram chat, in ms
+    TelegramLatency,
+}
+
+impl SeriesMetric {
+    /// Query yielding (bucket, value) rows from `?1` on, or all of them when
+    /// it is empty, bucketed by the strftime format `?2`. Timestamps are
+    /// compared through `datetime()`, since rows hold both RFC 3339 and
+    /// SQLite's own format.
+    fn sql(&self) -> &'static str {
+        match self {
+            SeriesMetric::JobsCompleted => {
+                "SELECT strftime(?2, finished_at), COUNT(*) FROM jobs
+                 WHERE status = 'done' AND deleted_at IS NULL
+                   AND (?1 = '' OR datetime(finished_at) >= datetime(?1))
+                 GROUP BY 1 ORDER BY 1"
+            }
+            SeriesMetric::GenerationTime => {
+                "SELECT strftime(?2, recorded_at), AVG(duration_ms) FROM metrics
+                 WHERE stage = 'generation' AND duration_ms IS NOT NULL
+                   AND (?1 = '' OR datetime(recorded_at) >= datetime(?1))
+                 GROUP BY 1 ORDER BY 1"
+            }
+            SeriesMetric::RenderTime => {
+                "SELECT strftime(?2, recorded_at), AVG(duration_ms) FROM metrics
+                 WHERE stage = 'render' AND duration_ms IS NOT NULL
+                   AND (?1 = '' OR datetime(recorded_at) >= datetime(?1))
+                 GROUP BY 1 ORDER BY 1"
+            }
+            SeriesMetric::TelegramLatency => {
+                "SELECT strftime(?2, recorded_at), AVG(duration_ms) FROM metrics
+                 WHERE stage = 'telegram' AND duration_ms IS NOT NULL
+                   AND (?1 = '' OR datetime(recorded_at) >= datetime(?1))
+                 GROUP BY 1 ORDER BY 1"
+            }
+        }
+    }
+}
+
+/// Width of one point in a metrics series
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesBucket {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl SeriesBucket {
+    /// strftime format of a bucket's start, in UTC
+    fn format(&self) -> &'static str {
+        match self {
+            SeriesBucket::Hour => "%Y-%m-%dT%H:00:00Z",
+            SeriesBucket::Day => "%Y-%m-%d",
+        }
+    }
+}
+
+/// One bucket of a metrics series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesPoint {
+    /// Start of the bucket: an RFC 3339 hour or a YYYY-MM-DD day, UTC
+    pub bucket: String,
+    pub value: f64,
+}
+
+/// Stage duration or named measurement recorded for a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobMetric {
+    pub stage: Option<String>,
+    /// Set for durations
+    pub duration_ms: Option<i64>,
+    /// Name of a measurement such as `input_lufs`, with its value
+    pub metric: Option<String>,
+    pub value: Option<f64>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Duration percentiles of one pipeline stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageMetrics {
+    pub stage: String,
+    /// Successful runs in the window
+    pub count: usize,
+    pub p50_ms: i64,
+    pub p95_ms: i64,
+    pub max_ms: i64,
+}
+
+/// Counts read from YouTube for one video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoStatistics {
+    pub views: i64,
+    /// `None` when the channel hides it
+    pub likes: Option<i64>,
+    pub comments: Option<i64>,
+}
+
+/// View count of a trend at one poll
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendViews {
+    pub views: i64,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// One statistics sample of an uploaded short
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortStats {
+    pub views: i64,
+    pub likes: Option<i64>,
+    pub comments: Option<i64>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// How an uploaded short has done over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortPerformance {
+    pub short_id: i64,
+    pub title: String,
+    pub youtube_video_id: Option<String>,
+    pub latest: Option<ShortStats>,
+    /// Samples, oldest first
+    pub history: Vec<ShortStats>,
+}
+
+/// Latest view count of one short in an A/B variant group
+#[derive(Debug, Clone)]
+pub struct VariantViews {
+    pub group_id: i64,
+    pub hook_style: Option<HookStyle>,
+    /// `None` until statistics have been fetched
+    pub views: Option<i64>,
+}
+
+/// Latest counts of the shorts made from one trend category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicPerformance {
+    pub topic: String,
+    pub shorts: i64,
+    pub total_views: i64,
+    pub avg_views: f64,
+    pub avg_likes: Option<f64>,
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[i64], p: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// A short made elsewhere, with its files already in place, for
+/// `import_short`
+#[derive(Debug, Clone)]
+pub struct ImportedShort {
+    pub language: String,
+    pub script: String,
+    pub video_path: String,
+    pub duration_sec: f64,
+    pub thumbnail_path: Option<String>,
+    pub timing: Option<SpeechTiming>,
+    pub metadata: Option<VideoMetadata>,
+}
+
+/// Generated short video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Short {
+    pub id: Option<i64>,
+    pub job_id: i64,
+    pub script: Option<String>,
+    pub audio_path: Option<String>,
+    pub video_path: Option<String>,
+    pub duration_sec: Option<f64>,
+    pub language: String,
+    /// Background music track mixed under the narration
+    pub music_id: Option<i64>,
+    /// Word/sentence timing of the narration, stored as JSON
+    pub timing: Option<SpeechTiming>,
+    /// JPEG poster frame
+    pub thumbnail_path: Option<String>,
+    /// Review state when approval was required at render time
+    pub approval: Option<ApprovalStatus>,
+    /// Reason given when the short was rejected
+    pub review_note: Option<String>,
+    /// Scheduled YouTube publish time; public on upload when unset
+    pub publish_at: Option<DateTime<Utc>>,
+    pub youtube_video_id: Option<String>,
+    /// Publish to Instagram; `None` follows `Config::instagram_default`
+    pub instagram: Option<bool>,
+    /// Id of the first short in this short's A/B variant group, shared by
+    /// every sibling; `None` when the job wrote one script per language
+    pub variant_group_id: Option<i64>,
+    /// Kind of hook the variant's script was asked to open with
+    pub hook_style: Option<HookStyle>,
+    /// Title, description and tags to upload with, prepared once the script
+    /// is written; built from the upload template at upload time when unset
+    pub upload_metadata: Option<VideoMetadata>,
+}
+
+impl Short {
+    /// Opening sentence of the script, without its sound-effect markers
+    pub fn hook(&self) -> Option<String> {
+        let script = crate::sfx::strip(self.script.as_deref()?);
+        let end = script
+            .char_indices()
+            .find(|(_, c)| matches!(c, '.' | '!' | '?' | '\n'))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(script.len());
+        Some(script[..end].trim().to_string()).filter(|hook| !hook.is_empty())
+    }
+    
+    /// Whether the short may be sent or uploaded: approved, or rendered
+    /// while approval wasn't required
+    pub fn is_deliverable(&self) -> bool {
+        matches!(self.approval, None | Some(ApprovalStatus::Approved))
+    }
+}
+
+/// Review state of a rendered short
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    AwaitingApproval,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::AwaitingApproval => "awaiting_approval",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "approved" => ApprovalStatus::Approved,
+            "rejected" => ApprovalStatus::Rejected,
+            _ => ApprovalStatus::AwaitingApproval,
+        }
+    }
+}
+
+/// Outcome of publishing a short to one destination
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicationStatus {
+    Sent,
+    Failed,
+}
+
+impl PublicationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublicationStatus::Sent => "sent",
+            PublicationStatus::Failed => "failed",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "sent" => PublicationStatus::Sent,
+            _ => PublicationStatus::Failed,
+        }
+    }
+}
+
+/// Latest publication of a short to one destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Publication {
+    pub id: i64,
+    pub short_id: i64,
+    /// Where it went, e.g. `youtube` or `telegram`
+    pub platform: String,
+    /// Where on that platform, e.g. a channel or chat id
+    pub destination: String,
+    pub status: PublicationStatus,
+    /// Platform's id for what was posted, e.g. the YouTube video id
+    pub remote_id: Option<String>,
+    /// Public link to what was posted, when the platform has one
+    pub url: Option<String>,
+    /// When it last went through
+    pub published_at: Option<String>,
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub updated_at: String,
+}
+
+const SHORT_COLUMNS: &str = "id, job_id, script, audio_path, video_path, duration_sec, language, music_id, timing_json, thumbnail_path, approval_status, review_note, publish_at, youtube_video_id, instagram, variant_group_id, hook_style, upload_metadata_json";
+
+fn short_from_row(row: &Row) -> Result<Short> {
+    Ok(Short {
+        id: Some(row.get(0)?),
+        job_id: row.get(1)?,
+        script: row.get(2)?,
+        audio_path: row.get(3)?,
+        video_path: row.get(4)?,
+        duration_sec: row.get(5)?,
+        language: row.get(6)?,
+        music_id: row.get(7)?,
+        timing: row.get::<_, Option<String>>(8)?
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        thumbnail_path: row.get(9)?,
+        approval: row.get::<_, Option<String>>(10)?.map(|s| ApprovalStatus::from_str(&s)),
+        review_note: row.get(11)?,
+        publish_at: row.get::<_, Option<String>>(12)?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+        youtube_video_id: row.get(13)?,
+        instagram: row.get(14)?,
+        variant_group_id: row.get(15)?,
+        hook_style: row.get::<_, Option<String>>(16)?.map(|s| HookStyle::from_str(&s)),
+        upload_metadata: row.get::<_, Option<String>>(17)?
+            .and_then(|s| serde_json::from_str(&s).ok()),
+    })
+}
+
+/// A short that has a script, with the text it can be compared on
+#[derive(Debug, Clone)]
+pub struct ProducedShort {
+    pub short_id: i64,
+    pub job_id: i64,
+    pub trend_id: i64,
+    /// Title of the trend it was made from
+    pub title: String,
+    pub transcript: Option<String>,
+    pub script: String,
+}
+
+/// Track in the local background-music library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MusicTrack {
+    pub id: Option<i64>,
+    pub path: String,
+    pub title: String,
+    /// Lowercase tags matched against the trend category (e.g. `chill`, `gaming`)
+    pub tags: Vec<String>,
+}
+
+fn music_from_row(row: &Row) -> Result<MusicTrack> {
+    Ok(MusicTrack {
+        id: Some(row.get(0)?),
+        path: row.get(1)?,
+        title: row.get(2)?,
+        tags: row.get::<_, String>(3)?
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect(),
+    })
+}
+
+/// Sound effect a script can call for with `[sfx:name]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundEffect {
+    pub id: Option<i64>,
+    /// Name used in markers: lowercase letters, digits, `-` and `_`
+    pub name: String,
+    pub path: String,
+    /// Gain applied when mixing it in, in dB relative to the source file
+    pub volume_db: f64,
+}
+
+fn sound_effect_from_row(row: &Row) -> Result<SoundEffect> {
+    Ok(SoundEffect {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        path: row.get(2)?,
+        volume_db: row.get(3)?,
+    })
+}
+
+/// Tags are stored as `,tag1,tag2,` so a single LIKE can match whole tags
+fn encode_tags(tags: &[String]) -> String {
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().to_lowercase().replace(',', " "))
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tags.is_empty() {
+        String::new()
+    } else {
+        format!(",{},", tags.join(","))
+    }
+}
+
+/// Timed visual segment of a short's narration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub id: Option<i64>,
+    pub short_id: i64,
+    /// 0-based order within the short
+    pub position: i64,
+    /// Narration spoken during the scene
+    pub text: String,
+    /// Offset into the narration, in seconds
+    pub start_sec: f64,
+    /// Target time on screen, in seconds
+    pub duration_sec: f64,
+    /// Prompt for a generated background image
+    pub visual_prompt: String,
+    /// Search term for stock footage
+    pub stock_keyword: String,
+    /// Image or clip shown during the scene, once fetched or generated
+    pub media_path: Option<String>,
+}
+
+const SCENE_COLUMNS: &str =
+    "id, short_id, position, text, start_sec, duration_sec, visual_prompt, stock_keyword, media_path";
+
+fn scene_from_row(row: &Row) -> Result<Scene> {
+    Ok(Scene {
+        id: Some(row.get(0)?),
+        short_id: row.get(1)?,
+        position: row.get(2)?,
+        text: row.get(3)?,
+        start_sec: row.get(4)?,
+        duration_sec: row.get(5)?,
+        visual_prompt: row.get(6)?,
+        stock_keyword: row.get(7)?,
+        media_path: row.get(8)?,
+    })
+}
+
+/// Curated example script used as few-shot context for the LLM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Example {
+    pub id: Option<i64>,
+    /// Niche tag matched against the trend category (e.g. `gaming`, `tech`)
+    pub niche: String,
+    pub title: String,
+    pub script: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+fn example_from_row(row: &Row) -> Result<Example> {
+    Ok(Example {
+        id: Some(row.get(0)?),
+        niche: row.get(1)?,
+        title: row.get(2)?,
+        script: row.get(3)?,
+        created_at: row.get::<_, Option<String>>(4)?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+    })
+}
+
+/// Script prompt template with its own sampling parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Niche this template applies to; empty matches any trend
+    pub niche: String,
+    /// Template text, see `prompt::render` for placeholders
+    pub body: String,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<i64>,
+}
+
+impl PromptTemplate {
+    pub fn sampling(&self) -> SamplingParams {
+        SamplingParams {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            seed: self.seed,
+        }
+    }
+}
+
+/// Title, description and tags for YouTube uploads, see
+/// `youtube_upload::VideoMetadata::build` for placeholders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    /// YouTube channel this template applies to; empty matches any channel
+    pub channel_id: String,
+    pub title: String,
+    pub description: String,
+    /// Video tags; the first few also become `{hashtags}`
+    pub tags: Vec<String>,
+    /// Text for `{affiliate_links}`, one link per line
+    pub affiliate_links: String,
+}
+
+const UPLOAD_TEMPLATE_COLUMNS: &str = "id, name, channel_id, title, description, tags_json, affiliate_links";
+
+fn upload_template_from_row(row: &Row) -> Result<UploadTemplate> {
+    Ok(UploadTemplate {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        channel_id: row.get(2)?,
+        title: row.get(3)?,
+        description: row.get(4)?,
+        tags: row.get::<_, String>(5)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        affiliate_links: row.get(6)?,
+    })
+}
+
+const PROMPT_TEMPLATE_COLUMNS: &str = "id, name, niche, body, temperature, top_p, max_tokens, seed";
+
+fn prompt_template_from_row(row: &Row) -> Result<PromptTemplate> {
+    Ok(PromptTemplate {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        niche: row.get(2)?,
+        body: row.get(3)?,
+        temperature: row.get(4)?,
+        top_p: row.get(5)?,
+        max_tokens: row.get(6)?,
+        seed: row.get(7)?,
+    })
+}
+
+/// User-defined URL that receives signed POSTs for the events it subscribes to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Option<i64>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// HMAC-SHA256 key for the signature header; generated when left empty
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, events_json, secret, enabled";
+
+fn webhook_from_row(row: &Row) -> Result<Webhook> {
+    Ok(Webhook {
+        id: Some(row.get(0)?),
+        url: row.get(1)?,
+        events: row.get::<_, String>(2)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        secret: row.get(3)?,
+        enabled: row.get(4)?,
+    })
+}
+
+/// How the autopilot picks a YouTube account for a new job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountAssignment {
+    /// Take turns between enabled accounts
+    #[default]
+    RoundRobin,
+    /// Accounts whose niche matches the trend's category first, then
+    /// accounts without a niche
+    Niche,
+}
+
+impl AccountAssignment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountAssignment::RoundRobin => "round_robin",
+            AccountAssignment::Niche => "niche",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "niche" => AccountAssignment::Niche,
+            _ => AccountAssignment::RoundRobin,
+        }
+    }
+}
+
+/// Named set of settings. The active profile's settings are the ones in
+/// `config`; the others are kept aside until switched to.
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+const PROFILE_COLUMNS: &str = "id, name, active, created_at";
+
+fn profile_from_row(row: &Row) -> Result<Profile> {
+    Ok(Profile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        active: row.get(2)?,
+        created_at: row.get::<_, String>(3)
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+/// Config rows as a key/value map
+fn config_entries(conn: &Connection) -> Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM config")?;
+    let entries = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<_, _>>>()?;
+    Ok(entries)
+}
+
+/// Connected YouTube account and the channel it uploads to
+#[derive(Debug, Clone, Serialize)]
+pub struct Account {
+    pub id: i64,
+    /// `None` for an account carried over from before there were several,
+    /// until its first upload looks the channel up
+    pub channel_id: Option<String>,
+    pub channel_title: String,
+    #[serde(skip)]
+    pub refresh_token: String,
+    /// Trend category this account takes under niche assignment; empty takes any
+    pub niche: String,
+    /// Playlist uploads are added to
+    pub playlist_id: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+const ACCOUNT_COLUMNS: &str = "id, channel_id, channel_title, refresh_token, niche, playlist_id, enabled, created_at";
+
+fn account_from_row(row: &Row) -> Result<Account> {
+    Ok(Account {
+        id: row.get(0)?,
+        channel_id: row.get(1)?,
+        channel_title: row.get(2)?,
+        refresh_token: row.get(3)?,
+        niche: row.get(4)?,
+        playlist_id: row.get(5)?,
+        enabled: row.get(6)?,
+        created_at: row.get::<_, String>(7)
+            .ok()
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+/// Named video style: background, captions, watermark and intro/outro clips
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RenderTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    pub layout: TemplateLayout,
+}
+
+fn encode_layout(layout: &TemplateLayout) -> String {
+    serde_json::to_string(layout).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn render_template_from_row(row: &Row) -> Result<RenderTemplate> {
+    Ok(RenderTemplate {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        layout: row.get::<_, String>(2)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// Every publish time claimed by a short
+fn publish_times(conn: &Connection) -> Result<Vec<DateTime<Utc>>> {
+    let mut stmt = conn.prepare("SELECT publish_at FROM shorts WHERE publish_at IS NOT NULL AND deleted_at IS NULL")?;
+    let times = stmt.query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter_map(|s| DateTime::parse_from_rfc3339(&s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .collect();
+    Ok(times)
+}
+
+/// Delete a short and everything recorded about it
+fn purge_short(conn: &Connection, short_id: i64) -> Result<()> {
+    for sql in [
+        "DELETE FROM scenes WHERE short_id = ?",
+        "DELETE FROM publications WHERE short_id = ?",
+        "DELETE FROM short_stats WHERE short_id = ?",
+        "DELETE FROM shorts WHERE id = ?",
+    ] {
+        conn.execute(sql, params![short_id])?;
+    }
+    Ok(())
+}
+
+/// Insert or refresh one trend and record its view count; see
+/// `Database::upsert_trend`
+fn upsert_trend_row(conn: &Connection, trend: &Trend) -> Result<i64> {
+    let fetched_at = trend.fetched_at.to_rfc3339();
+    let trend_id: i64 = conn.prepare_cached(
+        "INSERT INTO trends (video_id, title, channel, views, category, fetched_at, source, tags_json, dry_run)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(video_id) DO UPDATE SET
+             title = excluded.title,
+             dry_run = excluded.dry_run,
+             channel = COALESCE(excluded.channel, trends.channel),
+             views = COALESCE(excluded.views, trends.views),
+             category = COALESCE(excluded.category, trends.category),
+             tags_json = COALESCE(excluded.tags_json, trends.tags_json),
+             fetched_at = excluded.fetched_at,
+             state = CASE trends.state WHEN 'archived' THEN 'new' ELSE trends.state END
+         RETURNING id",
+    )?
+    .query_row(
+        params![
+            trend.video_id,
+            trend.title,
+            trend.channel,
+            trend.views,
+            trend.category,
+            fetched_at,
+            trend.source.as_str(),
+            (!trend.tags.is_empty()).then(|| serde_json::to_string(&trend.tags).unwrap_or_default()),
+            trend.dry_run,
+        ],
+        |row| row.get(0),
+    )?;
+    if let Some(views) = trend.views {
+        conn.prepare_cached("INSERT INTO trend_views (trend_id, views, fetched_at) VALUES (?, ?, ?)")?
+            .execute(params![trend_id, views, fetched_at])?;
+    }
+    Ok(trend_id)
+}
+
+/// Delete a job with its shorts, logs, errors and metrics
+fn purge_job(conn: &Connection, job_id: i64) -> Result<()> {
+    let short_ids: Vec<i64> = conn
+        .prepare("SELECT id FROM shorts WHERE job_id = ?")?
+        .query_map(params![job_id], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+    for short_id in short_ids {
+        purge_short(conn, short_id)?;
+    }
+    for sql in [
+        "DELETE FROM job_errors WHERE job_id = ?",
+        "DELETE FROM job_logs WHERE job_id = ?",
+        "DELETE FROM metrics WHERE job_id = ?",
+        // Jobs still waiting on this one were marked dead when it was trashed
+        "DELETE FROM job_dependencies WHERE job_id = ?1 OR depends_on_job_id = ?1",
+        "DELETE FROM jobs WHERE id = ?",
+    ] {
+        conn.execute(sql, params![job_id])?;
+    }
+    Ok(())
+}
+
+/// Mark the jobs waiting on `job_id`, and the ones waiting on those, dead:
+/// they can't start now that it `reason`
+fn kill_dependents(conn: &Connection, job_id: i64, reason: &str) -> Result<()> {
+    let dependents: Vec<i64> = conn
+        .prepare(
+            "SELECT j.id FROM jobs j JOIN job_dependencies dep ON dep.job_id = j.id
+             WHERE dep.depends_on_job_id = ? AND j.status = 'pending' AND j.deleted_at IS NULL",
+        )?
+        .query_map(params![job_id], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+    for dependent in dependents {
+        conn.execute(
+            "UPDATE jobs SET status = 'dead', finished_at = ?, error_msg = ? WHERE id = ?",
+            params![Utc::now().to_rfc3339(), format!("Job {} it depends on {}", job_id, reason), dependent],
+        )?;
+        kill_dependents(conn, dependent, "is dead")?;
+    }
+    Ok(())
+}
+
+/// Stored in `PRAGMA user_version`; bump when a change to the schema can't
+/// be read by older builds
+pub const SCHEMA_VERSION: i32 = 2;
+
+/// Connections kept open between calls; more are opened under load and
+/// closed again when the pool is full
+const POOL_SIZE: usize = 4;
+
+/// SQLite database shared by commands and background tasks. Every call
+/// borrows its own connection from a small pool, so a slow query doesn't
+/// hold up unrelated callers. Clones share the pool.
+#[derive(Clone)]
+pub struct Database {
+    path: Arc<PathBuf>,
+    /// SQLCipher passphrase, set when the database is encrypted
+    key: Arc<Option<String>>,
+    idle: Arc<Mutex<Vec<Connection>>>,
+}
+
+/// Connection borrowed from the pool, handed back when dropped
+struct PooledConnection<'a> {
+    idle: &'a Mutex<Vec<Connection>>,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+    
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut idle)) = (self.conn.take(), self.idle.lock()) {
+            if idle.len() < POOL_SIZE {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+impl Database {
+    /// Open the database, creating and migrating the schema as needed
+    pub fn new(path: PathBuf) -> Result<Self> {
+        Self::with_key(path, None)
+    }
+    
+    /// Open a database encrypted with SQLCipher under `key`. Only builds with
+    /// the `sqlcipher` feature can read one.
+    pub fn with_key(path: PathBuf, key: Option<String>) -> Result<Self> {
+        let db = Self {
+            path: Arc::new(path),
+            key: Arc::new(key),
+            idle: Arc::new(Mutex::new(Vec::new())),
+        };
+        db.migrate()?;
+        db.init_schema()?;
+        db.migrate_publications()?;
+        db.import_legacy_account()?;
+        db.ensure_profile()?;
+        Ok(db)
+    }
+    
+    /// Open a fresh, empty database held in memory, for tests and demos.
+    /// Every call gets its own; it is gone once the last clone is dropped.
+    pub fn new_in_memory() -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        // A named shared-cache database, so every connection in the pool
+        // sees the same data. The pool keeps one open between calls.
+        let uri = format!(
+            "file:shotauto-memory-{}-{}?mode=memory&cache=shared",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        Self::new(PathBuf::from(uri))
+    }
+    
+    /// Database file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+    
+    /// Borrow a connection, opening a new one when all are in use
+    fn conn(&self) -> Result<PooledConnection<'_>> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self.open(&self.path, OpenFlags::default())?,
+        };
+        Ok(PooledConnection { idle: &self.idle, conn: Some(conn) })
+    }
+    
+    /// Open a connection. WAL lets the pipeline write while the UI reads,
+    /// the busy timeout makes writers wait for each other instead of failing
+    /// with "database is locked", and foreign keys are enforced.
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Connection> {
+        let conn = self.open_file(path, flags)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        conn.busy_timeout(std::time::Duration::from_millis(5000))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        Ok(conn)
+    }
+    
+    /// Open `path` unlocked with this database's key, if it has one. The key
+    /// has to be set before anything else reads the file.
+    fn open_file(&self, path: &Path, flags: OpenFlags) -> Result<Connection> {
+        let conn = Connection::open_with_flags(path, flags)?;
+        if let Some(key) = self.key.as_deref() {
+            conn.pragma_update(None, "key", key)?;
+        }
+        Ok(conn)
+    }
+    
+    /// Move the single account connected before there were several into
+    /// `accounts`
+    fn import_legacy_account(&self) -> Result<()> {
+        let conn = self.conn()?;
+        let Some(refresh_token) = self.get_config("youtube_refresh_token")? else {
+            return Ok(());
+        };
+        let channel_id = self.get_config("youtube_channel_id")?.filter(|id| !id.is_empty());
+        conn.execute(
+            "INSERT OR IGNORE INTO accounts (channel_id, channel_title, refresh_token, playlist_id, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                channel_id,
+                "YouTube",
+                refresh_token,
+                self.get_config("target_playlist_id")?,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM config WHERE key IN ('youtube_refresh_token', 'youtube_channel_id', 'target_playlist_id')",
+            [],
+        )?;
+        Ok(())
+    }
+    
+    /// Create the default profile, active, for installs from before profiles
+    fn ensure_profile(&self) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO profiles (name, active, created_at)
+             SELECT 'Default', 1, ? WHERE NOT EXISTS (SELECT 1 FROM profiles)",
+            params![Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Bring tables created by older versions up to date.
+    /// Runs before `init_schema`, so it only touches tables that already exist.
+    fn migrate(&self) -> Result<()> {
+        if self.table_exists("accounts")? {
+            self.allow_unknown_account_channel()?;
+        }
+        if self.table_exists("jobs")? {
+            self.sync_job_status_check()?;
+            self.add_column_if_missing("jobs", "worker_id", "TEXT")?;
+            self.add_column_if_missing("jobs", "lease_expires_at", "TIMESTAMP")?;
+            self.add_column_if_missing("jobs", "telegram_message_id", "INTEGER")?;
+            self.add_column_if_missing("jobs", "target_account_id", "INTEGER REFERENCES accounts(id)")?;
+            self.add_column_if_missing("jobs", "deleted_at", "TIMESTAMP")?;
+            self.add_column_if_missing("jobs", "profile_id", "INTEGER REFERENCES profiles(id)")?;
+            self.add_column_if_missing("jobs", "dry_run", "BOOLEAN NOT NULL DEFAULT 0")?;
+            if !self.column_exists("jobs", "checkpoint")? {
+                self.add_column_if_missing("jobs", "checkpoint", "TEXT")?;
+                // Jobs a stage has finished with are past all of its steps
+                self.conn()?.execute_batch(
+                    "UPDATE jobs SET checkpoint = 'metadata' WHERE status = 'generated';
+                     UPDATE jobs SET checkpoint = 'video' WHERE status = 'done';",
+                )?;
+            }
+        }
+        if self.table_exists("trends")? {
+            self.add_column_if_missing("trends", "transcript", "TEXT")?;
+            self.add_column_if_missing("trends", "source", "TEXT NOT NULL DEFAULT 'youtube'")?;
+            self.add_column_if_missing("trends", "tags_json", "TEXT")?;
+            self.add_column_if_missing("trends", "notes", "TEXT")?;
+            self.add_column_if_missing("trends", "starred", "BOOLEAN NOT NULL DEFAULT 0")?;
+            if !self.column_exists("trends", "state")? {
+                self.add_column_if_missing("trends", "state", "TEXT NOT NULL DEFAULT 'new'")?;
+                // Trends that already have jobs are past `new`
+                self.conn()?.execute_batch(
+                    "UPDATE trends SET state = 'queued' WHERE id IN (SELECT trend_id FROM jobs);
+                     UPDATE trends SET state = 'processed' WHERE id IN (SELECT trend_id FROM jobs WHERE status = 'done');",
+                )?;
+            }
+            self.add_column_if_missing("trends", "skip_reason", "TEXT")?;
+            self.add_column_if_missing("trends", "dry_run", "BOOLEAN NOT NULL DEFAULT 0")?;
+        }
+        if self.table_exists("shorts")? {
+            self.add_column_if_missing("shorts", "language", "TEXT NOT NULL DEFAULT 'en'")?;
+            self.add_column_if_missing("shorts", "music_id", "INTEGER REFERENCES music(id)")?;
+            self.add_column_if_missing("shorts", "timing_json", "TEXT")?;
+            self.add_column_if_missing("shorts", "thumbnail_path", "TEXT")?;
+            self.add_column_if_missing("shorts", "approval_status", "TEXT")?;
+            self.add_column_if_missing("shorts", "review_note", "TEXT")?;
+            self.add_column_if_missing("shorts", "reviewed_at", "TIMESTAMP")?;
+            self.add_column_if_missing("shorts", "telegram_message_id", "INTEGER")?;
+            self.add_column_if_missing("shorts", "publish_at", "TIMESTAMP")?;
+            self.add_column_if_missing("shorts", "youtube_video_id", "TEXT")?;
+            self.add_column_if_missing("shorts", "uploaded_at", "TIMESTAMP")?;
+            self.add_column_if_missing("shorts", "instagram", "BOOLEAN")?;
+            self.add_column_if_missing("shorts", "deleted_at", "TIMESTAMP")?;
+            self.add_column_if_missing("shorts", "variant_group_id", "INTEGER")?;
+            self.add_column_if_missing("shorts", "hook_style", "TEXT")?;
+            self.add_column_if_missing("shorts", "upload_metadata_json", "TEXT")?;
+        }
+        if self.table_exists("deliveries")? {
+            self.add_column_if_missing("deliveries", "url", "TEXT")?;
+        }
+        if self.table_exists("metrics")? {
+            self.add_column_if_missing("metrics", "metric", "TEXT")?;
+            self.add_column_if_missing("metrics", "value", "REAL")?;
+        }
+        Ok(())
+    }
+    
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        if !self.column_exists(table, column)? {
+            let conn = self.conn()?;
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+        }
+        Ok(())
+    }
+    
+    fn table_exists(&self, table: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+            params![table],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+    
+    /// Move what older versions tracked in the deliveries table, the
+    /// telegram_sent flag and bare YouTube video ids into publications.
+    /// Runs after `init_schema` has created that table.
+    fn migrate_publications(&self) -> Result<()> {
+        let has_deliveries = self.table_exists("deliveries")?;
+        let has_telegram_sent = self.column_exists("shorts", "telegram_sent")?;
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        if has_deliveries {
+            tx.execute(
+                "INSERT OR IGNORE INTO publications
+                     (short_id, platform, destination, status, remote_id, url, published_at, error, attempts, updated_at)
+                 SELECT short_id, channel, destination, status, message_id, url,
+                        CASE WHEN status = 'sent' THEN updated_at END, error, attempts, updated_at
+                 FROM deliveries",
+                [],
+            )?;
+            tx.execute_batch("DROP TABLE deliveries")?;
+        }
+        if has_telegram_sent {
+            tx.execute(
+                "INSERT OR IGNORE INTO publications (short_id, platform, destination, status, remote_id, published_at, updated_at)
+                 SELECT id, 'telegram', '', 'sent', CAST(telegram_message_id AS TEXT), ?1, ?1 FROM shorts s
+                 WHERE telegram_sent = 1
+                   AND NOT EXISTS (SELECT 1 FROM publications p WHERE p.short_id = s.id AND p.platform = 'telegram')",
+                params![Utc::now().to_rfc3339()],
+            )?;
+            tx.execute_batch("ALTER TABLE shorts DROP COLUMN telegram_sent")?;
+        }
+        // Uploads from before deliveries were recorded only have the video id
+        tx.execute(
+            "INSERT OR IGNORE INTO publications (short_id, platform, destination, status, remote_id, published_at, updated_at)
+             SELECT id, 'youtube', '', 'sent', youtube_video_id, COALESCE(uploaded_at, ?1), COALESCE(uploaded_at, ?1) FROM shorts s
+             WHERE youtube_video_id IS NOT NULL
+               AND NOT EXISTS (SELECT 1 FROM publications p WHERE p.short_id = s.id AND p.platform = 'youtube')",
+            params![Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()
+    }
+    
+    fn column_exists(&self, table: &str, column: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(columns.iter().any(|name| name == column))
+    }
+    
+    /// Rebuild the jobs table when its status CHECK constraint is missing a status.
+    /// SQLite cannot alter constraints in place, so the table is copied.
+    fn sync_job_status_check(&self) -> Result<()> {
+        let conn = self.conn()?;
+        let sql: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'jobs'",
+            [],
+            |row| row.get(0),
+        )?;
+        let expected = JobStatus::check_constraint();
+        if sql.contains(&expected) {
+            return Ok(());
+        }
+        let Some(start) = sql.find("CHECK(status IN (") else {
+            return Ok(());
+        };
+        let Some(len) = sql[start..].find("))") else {
+            return Ok(());
+        };
+        let new_sql = format!("{}{}{}", &sql[..start], expected, &sql[start + len + 2..])
+            .replacen("CREATE TABLE jobs", "CREATE TABLE jobs_new", 1);
+        
+        // Dropping jobs would trip the foreign keys of its child tables;
+        // the pragma is a no-op inside a transaction, so it wraps it
+        conn.execute_batch(&format!(
+            "PRAGMA foreign_keys = OFF;
+             BEGIN;
+             {};
+             INSERT INTO jobs_new SELECT * FROM jobs;
+             DROP TABLE jobs;
+             ALTER TABLE jobs_new RENAME TO jobs;
+             COMMIT;
+             PRAGMA foreign_keys = ON;",
+            new_sql
+        ))
+    }
+    
+    /// Let an account's `channel_id` be NULL while its channel isn't known;
+    /// older tables stored '' under NOT NULL. SQLite cannot drop NOT NULL in
+    /// place, so the table is copied.
+    fn allow_unknown_account_channel(&self) -> Result<()> {
+        let conn = self.conn()?;
+        let sql: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'accounts'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !sql.contains("channel_id TEXT NOT NULL UNIQUE") {
+            return Ok(());
+        }
+        let new_sql = sql
+            .replacen("channel_id TEXT NOT NULL UNIQUE", "channel_id TEXT UNIQUE", 1)
+            .replacen("CREATE TABLE accounts", "CREATE TABLE accounts_new", 1);
+        
+        // Jobs point at accounts; see `sync_job_status_check`
+        conn.execute_batch(&format!(
+            "PRAGMA foreign_keys = OFF;
+             BEGIN;
+             {};
+             INSERT INTO accounts_new SELECT * FROM accounts;
+             DROP TABLE accounts;
+             ALTER TABLE accounts_new RENAME TO accounts;
+             UPDATE accounts SET channel_id = NULL WHERE channel_id = '';
+             COMMIT;
+             PRAGMA foreign_keys = ON;",
+            new_sql
+        ))
+    }
+    
+    /// Initialize database schema
+    fn init_schema(&self) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute_batch(&format!(
+            r#"
+            -- Configuration table
+            CREATE TABLE IF NOT EXISTS config (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            
+            -- Trends from YouTube
+            CREATE TABLE IF NOT EXISTS trends (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                video_id TEXT UNIQUE NOT NULL,
+                title TEXT NOT NULL,
+                channel TEXT,
+                views INTEGER,
+                category TEXT,
+                fetched_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                transcript TEXT,
+                source TEXT NOT NULL DEFAULT 'youtube',
+                -- JSON array of the source video's tags
+                tags_json TEXT,
+                notes TEXT,
+                starred BOOLEAN NOT NULL DEFAULT 0,
+                state TEXT NOT NULL DEFAULT 'new',
+                skip_reason TEXT,
+                dry_run BOOLEAN NOT NULL DEFAULT 0
+            );
+            
+            -- Processing jobs queue
+            CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trend_id INTEGER REFERENCES trends(id),
+                status TEXT DEFAULT 'pending' 
+                    {status_check},
+                priority INTEGER DEFAULT 0,
+                retry_count INTEGER DEFAULT 0,
+                error_msg TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                started_at TIMESTAMP,
+                finished_at TIMESTAMP,
+                worker_id TEXT,
+                lease_expires_at TIMESTAMP,
+                -- Status message kept up to date in the Telegram chat
+                telegram_message_id INTEGER,
+                target_account_id INTEGER REFERENCES accounts(id),
+                -- Set while the job is in the trash
+                deleted_at TIMESTAMP,
+                profile_id INTEGER REFERENCES profiles(id),
+                -- Created in dry-run mode; nothing of it was really posted
+                dry_run BOOLEAN NOT NULL DEFAULT 0,
+                -- Last pipeline step finished for every short of the job
+                checkpoint TEXT
+            );
+            
+            -- Jobs that have to be done before another is started
+            CREATE TABLE IF NOT EXISTS job_dependencies (
+                job_id INTEGER NOT NULL REFERENCES jobs(id),
+                depends_on_job_id INTEGER NOT NULL REFERENCES jobs(id),
+                PRIMARY KEY (job_id, depends_on_job_id)
+            );
+            
+            -- Failure history, one row per failed stage attempt
+            CREATE TABLE IF NOT EXISTS job_errors (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER NOT NULL REFERENCES jobs(id),
+                attempt INTEGER NOT NULL,
+                stage TEXT NOT NULL,
+                message TEXT NOT NULL,
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- Per-job log messages
+            CREATE TABLE IF NOT EXISTS job_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER NOT NULL REFERENCES jobs(id),
+                level TEXT NOT NULL,
+                stage TEXT,
+                message TEXT NOT NULL,
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- Generated shorts
+            CREATE TABLE IF NOT EXISTS shorts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER REFERENCES jobs(id),
+                script TEXT,
+                audio_path TEXT,
+                video_path TEXT,
+                duration_sec REAL,
+                language TEXT NOT NULL DEFAULT 'en',
+                music_id INTEGER REFERENCES music(id),
+                timing_json TEXT,
+                thumbnail_path TEXT,
+                -- NULL when no approval was required
+                approval_status TEXT,
+                review_note TEXT,
+                reviewed_at TIMESTAMP,
+                -- Latest video message in the Telegram chat, for bot replies
+                telegram_message_id INTEGER,
+                -- When YouTube should make the upload public
+                publish_at TIMESTAMP,
+                youtube_video_id TEXT,
+                uploaded_at TIMESTAMP,
+                -- Publish to Instagram; NULL follows the config default
+                instagram BOOLEAN,
+                -- Set while the short is in the trash
+                deleted_at TIMESTAMP,
+                -- First short of the A/B variant group; NULL for a lone short
+                variant_group_id INTEGER,
+                hook_style TEXT,
+                -- Prepared title, description and tags, as JSON
+                upload_metadata_json TEXT
+            );
+            
+            -- Latest publication of each short to each destination
+            CREATE TABLE IF NOT EXISTS publications (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                short_id INTEGER NOT NULL REFERENCES shorts(id),
+                platform TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                status TEXT NOT NULL,
+                remote_id TEXT,
+                -- Public link to what was posted
+                url TEXT,
+                published_at TIMESTAMP,
+                error TEXT,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                updated_at TIMESTAMP NOT NULL,
+                UNIQUE(short_id, platform, destination)
+            );
+            
+            -- Visual scenes of a short, in narration order
+            CREATE TABLE IF NOT EXISTS scenes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                short_id INTEGER NOT NULL REFERENCES shorts(id),
+                position INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                start_sec REAL NOT NULL,
+                duration_sec REAL NOT NULL,
+                visual_prompt TEXT NOT NULL DEFAULT '',
+                stock_keyword TEXT NOT NULL DEFAULT '',
+                media_path TEXT
+            );
+            
+            -- Background music library
+            CREATE TABLE IF NOT EXISTS music (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                title TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT ''
+            );
+            
+            -- Sound effects scripts call for with [sfx:name]
+            CREATE TABLE IF NOT EXISTS sound_effects (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                path TEXT NOT NULL,
+                volume_db REAL NOT NULL DEFAULT 0
+            );
+            
+            -- Few-shot example scripts for prompts
+            CREATE TABLE IF NOT EXISTS examples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                niche TEXT NOT NULL DEFAULT '',
+                title TEXT NOT NULL,
+                script TEXT NOT NULL,
+                created_at TIMESTAMP
+            );
+            
+            -- Script prompt templates with sampling parameters
+            CREATE TABLE IF NOT EXISTS prompt_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                niche TEXT NOT NULL DEFAULT '',
+                body TEXT NOT NULL,
+                temperature REAL,
+                top_p REAL,
+                max_tokens INTEGER,
+                seed INTEGER
+            );
+            
+            -- Upload metadata templates, per YouTube channel
+            CREATE TABLE IF NOT EXISTS upload_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                channel_id TEXT NOT NULL DEFAULT '',
+                title TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                tags_json TEXT NOT NULL DEFAULT '[]',
+                affiliate_links TEXT NOT NULL DEFAULT ''
+            );
+            
+            -- Video style templates; layout is a JSON `TemplateLayout`
+            CREATE TABLE IF NOT EXISTS render_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                layout_json TEXT NOT NULL DEFAULT '{{}}'
+            );
+            
+            -- Named sets of settings
+            CREATE TABLE IF NOT EXISTS profiles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                -- Exactly one profile is active; its settings are the config table
+                active BOOLEAN NOT NULL DEFAULT 0,
+                -- JSON object of config keys, kept while the profile is inactive
+                settings TEXT NOT NULL DEFAULT '{{}}',
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- YouTube accounts shorts are uploaded to
+            CREATE TABLE IF NOT EXISTS accounts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel_id TEXT UNIQUE,
+                channel_title TEXT NOT NULL,
+                refresh_token TEXT NOT NULL,
+                niche TEXT NOT NULL DEFAULT '',
+                playlist_id TEXT,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- Outgoing webhooks; events is a JSON list of event names
+            CREATE TABLE IF NOT EXISTS webhooks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL,
+                events_json TEXT NOT NULL DEFAULT '[]',
+                secret TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- Performance metrics
+            CREATE TABLE IF NOT EXISTS metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER REFERENCES jobs(id),
+                stage TEXT,
+                duration_ms INTEGER,
+                recorded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                -- Non-duration measurements, e.g. metric = 'input_lufs'
+                metric TEXT,
+                value REAL
+            );
+            
+            -- View/like/comment samples of uploaded shorts
+            CREATE TABLE IF NOT EXISTS short_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                short_id INTEGER NOT NULL REFERENCES shorts(id),
+                views INTEGER NOT NULL,
+                likes INTEGER,
+                comments INTEGER,
+                fetched_at TIMESTAMP NOT NULL
+            );
+            
+            -- View count of each trend at every poll
+            CREATE TABLE IF NOT EXISTS trend_views (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trend_id INTEGER NOT NULL REFERENCES trends(id),
+                views INTEGER NOT NULL,
+                fetched_at TIMESTAMP NOT NULL
+            );
+            
+            -- Significant actions and who took them
+            CREATE TABLE IF NOT EXISTS activity_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                job_id INTEGER,
+                short_id INTEGER,
+                detail TEXT,
+                created_at TIMESTAMP NOT NULL
+            );
+            
+            -- Estimated spend on paid APIs; kept when the job is pruned so
+            -- monthly totals don't shrink
+            CREATE TABLE IF NOT EXISTS job_costs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                quantity REAL NOT NULL,
+                cost REAL NOT NULL,
+                recorded_at TIMESTAMP NOT NULL
+            );
+            
+            -- Cached media by request key; entries with the same content share a file
+            CREATE TABLE IF NOT EXISTS cache_entries (
+                kind TEXT NOT NULL,
+                key TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                meta TEXT,
+                last_used_at TEXT NOT NULL,
+                PRIMARY KEY (kind, key)
+            );
+            
+            -- Create indexes
+            CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status);
+            CREATE INDEX IF NOT EXISTS idx_jobs_finished_at ON jobs(finished_at);
+            CREATE INDEX IF NOT EXISTS idx_metrics_stage ON metrics(stage, recorded_at);
+            CREATE INDEX IF NOT EXISTS idx_trends_video_id ON trends(video_id);
+            CREATE INDEX IF NOT EXISTS idx_trends_state ON trends(state, fetched_at);
+            CREATE INDEX IF NOT EXISTS idx_shorts_job_id ON shorts(job_id);
+            CREATE INDEX IF NOT EXISTS idx_shorts_variant_group_id ON shorts(variant_group_id);
+            CREATE INDEX IF NOT EXISTS idx_examples_niche ON examples(niche);
+            CREATE INDEX IF NOT EXISTS idx_scenes_short_id ON scenes(short_id, position);
+            CREATE INDEX IF NOT EXISTS idx_job_errors_job_id ON job_errors(job_id);
+            CREATE INDEX IF NOT EXISTS idx_job_logs_job_id ON job_logs(job_id);
+            CREATE INDEX IF NOT EXISTS idx_short_stats_short_id ON short_stats(short_id, fetched_at);
+            CREATE INDEX IF NOT EXISTS idx_trend_views_trend_id ON trend_views(trend_id, fetched_at);
+            CREATE INDEX IF NOT EXISTS idx_activity_log_job_id ON activity_log(job_id);
+            CREATE INDEX IF NOT EXISTS idx_job_costs_recorded_at ON job_costs(recorded_at);
+            "#,
+            status_check = JobStatus::check_constraint(),
+        ))?;
+        // Never lower it: a newer build may have opened this file
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        Ok(())
+    }
+    
+    // ==================== Backup ====================
+    
+    /// Copy the database to `dest` with SQLite's online backup, which gives a
+    /// consistent copy while other connections keep writing
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        let conn = self.conn()?;
+        // The copy is encrypted with the same key as the database
+        let mut copy = self.open_file(dest, OpenFlags::default())?;
+        let backup = Backup::new(&conn, &mut copy)?;
+        backup.run_to_completion(256, std::time::Duration::ZERO, None)
+    }
+    
+    /// Replace the whole database with the one at `src`, then bring it up to
+    /// the current schema
+    pub fn restore_from(&self, src: &Path) -> Result<()> {
+        let mut conn = self.conn()?;
+        let source = self.open_file(src, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Backup::new(&source, &mut conn)?.run_to_completion(256, std::time::Duration::ZERO, None)?;
+        // The other pooled connections predate the restore; this one is kept
+        // open so an in-memory database outlives the reset
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.clear();
+        }
+        drop(conn);
+        self.migrate()?;
+        self.init_schema()?;
+        self.migrate_publications()?;
+        self.import_legacy_account()?;
+        self.ensure_profile()
+    }
+    
+    /// Schema version of the database file at `path`, or `None` if it isn't
+    /// a ShotAuto database. An encrypted database only reads files under its
+    /// own key.
+    pub fn schema_version_of(&self, path: &Path) -> Result<Option<i32>> {
+        let conn = self.open_file(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let tables: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('config', 'trends', 'jobs', 'shorts')",
+            [],
+            |row| row.get(0),
+        )?;
+        if tables < 4 {
+            return Ok(None);
+        }
+        conn.pragma_query_value(None, "user_version", |row| row.get(0)).map(Some)
+    }
+    
+    // ==================== Encryption ====================
+    
+    /// Copy the plaintext database at `src` to `dest`, encrypted under `key`.
+    /// The schema version is carried over, and `src`'s write-ahead log is
+    /// checkpointed and emptied first so nothing is left only in it.
+    #[cfg(feature = "sqlcipher")]
+    pub fn export_encrypted(src: &Path, dest: &Path, key: &str) -> Result<()> {
+        let conn = Connection::open(src)?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        conn.execute(
+            "ATTACH DATABASE ? AS encrypted KEY ?",
+            params![dest.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.pragma_update(Some(rusqlite::DatabaseName::Attached("encrypted")), "user_version", version)?;
+        conn.execute("DETACH DATABASE encrypted", [])?;
+        Ok(())
+    }
+    
+    // ==================== Retention ====================
+    
+    /// Delete jobs that finished (done, failed or dead) before `before`,
+    /// with their shorts and everything recorded about them. Works through
+    /// them a batch per transaction so writers aren't held up for long.
+    /// Returns the number of jobs deleted and the bucket object URLs their
+    /// shorts were stored at, which the caller deletes.
+    pub fn prune_finished_jobs(&self, before: DateTime<Utc>) -> Result<(usize, Vec<String>)> {
+        let conn = self.conn()?;
+        let cutoff = before.to_rfc3339();
+        let mut deleted = 0;
+        let mut objects = Vec::new();
+        loop {
+            let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+            let ids: Vec<i64> = tx
+                .prepare(
+                    "SELECT id FROM jobs WHERE status IN (?, ?, ?) AND finished_at < ? ORDER BY id LIMIT ?",
+                )?
+                .query_map(
+                    params![
+                        JobStatus::Done.as_str(),
+                        JobStatus::Failed.as_str(),
+                        JobStatus::Dead.as_str(),
+                        cutoff,
+                        PRUNE_BATCH,
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<Result<_>>()?;
+            if ids.is_empty() {
+                return Ok((deleted, objects));
+            }
+            for id in &ids {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT video_path FROM shorts
+                     WHERE job_id = ? AND (video_path LIKE 'http://%' OR video_path LIKE 'https://%')",
+                )?;
+                objects.extend(stmt.query_map(params![id], |row| row.get::<_, String>(0))?.collect::<Result<Vec<_>>>()?);
+                drop(stmt);
+                purge_job(&tx, *id)?;
+            }
+            tx.commit()?;
+            deleted += ids.len();
+        }
+    }
+    
+    /// Delete trends fetched before `before` that no job was made from.
+    /// Returns the number deleted.
+    pub fn prune_trends(&self, before: DateTime<Utc>) -> Result<usize> {
+        let conn = self.conn()?;
+        let cutoff = before.to_rfc3339();
+        let mut deleted = 0;
+        loop {
+            let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+            let ids: Vec<i64> = tx
+                .prepare(
+                    "SELECT t.id FROM trends t
+                     WHERE t.fetched_at < ? AND NOT EXISTS (SELECT 1 FROM jobs j WHERE j.trend_id = t.id)
+                     LIMIT ?",
+                )?
+                .query_map(params![cutoff, PRUNE_BATCH], |row| row.get(0))?
+                .collect::<Result<_>>()?;
+            for id in &ids {
+                tx.execute("DELETE FROM trend_views WHERE trend_id = ?", params![id])?;
+                tx.execute("DELETE FROM trends WHERE id = ?", params![id])?;
+            }
+            let count = ids.len();
+            tx.commit()?;
+            if count == 0 {
+                return Ok(deleted);
+            }
+            deleted += count;
+        }
+    }
+    
+    /// Every narration, render, thumbnail and scene media path a row still points at
+    pub fn referenced_media_paths(&self) -> Result<HashSet<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT audio_path FROM shorts WHERE audio_path IS NOT NULL
+             UNION SELECT video_path FROM shorts WHERE video_path IS NOT NULL
+             UNION SELECT thumbnail_path FROM shorts WHERE thumbnail_path IS NOT NULL
+             UNION SELECT media_path FROM scenes WHERE media_path IS NOT NULL",
+        )?;
+        let paths = stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<HashSet<String>>>()?;
+        Ok(paths)
+    }
+    
+    /// Narration, render, thumbnail and scene media paths of rejected shorts,
+    /// leaving out media other shorts use too
+    pub fn rejected_short_media_paths(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT audio_path FROM shorts WHERE approval_status = ?1 AND audio_path IS NOT NULL
+             UNION SELECT video_path FROM shorts WHERE approval_status = ?1 AND video_path IS NOT NULL
+             UNION SELECT thumbnail_path FROM shorts WHERE approval_status = ?1 AND thumbnail_path IS NOT NULL
+             UNION SELECT sc.media_path FROM scenes sc JOIN shorts s ON s.id = sc.short_id
+                   WHERE s.approval_status = ?1 AND sc.media_path IS NOT NULL
+                   -- Stock clips are cached by keyword and may be shared with other shorts
+                   AND sc.media_path NOT IN (
+                       SELECT sc2.media_path FROM scenes sc2 JOIN shorts s2 ON s2.id = sc2.short_id
+                       WHERE s2.approval_status IS NOT ?1 AND sc2.media_path IS NOT NULL
+                   )",
+        )?;
+        let paths = stmt.query_map(params![ApprovalStatus::Rejected.as_str()], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        Ok(paths)
+    }
+    
+    /// Unset a deleted file wherever a short or scene points at it
+    pub fn clear_media_path(&self, path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        for sql in [
+            "UPDATE shorts SET audio_path = NULL WHERE audio_path = ?1",
+            "UPDATE shorts SET video_path = NULL WHERE video_path = ?1",
+            "UPDATE shorts SET thumbnail_path = NULL WHERE thumbnail_path = ?1",
+            "UPDATE scenes SET media_path = NULL WHERE media_path = ?1",
+        ] {
+            conn.execute(sql, params![path])?;
+        }
+        Ok(())
+    }
+    
+    // ==================== Export ====================
+    
+    /// Up to `limit` rows of `scope` with ids above `after_id`, in id order
+    pub fn export_batch(&self, scope: ExportScope, after_id: i64, limit: u32) -> Result<ExportBatch> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(scope.batch_sql())?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = Vec::new();
+        let mut last_id = None;
+        let mut cursor = stmt.query(params![after_id, limit])?;
+        while let Some(row) = cursor.next()? {
+            last_id = Some(row.get(0)?);
+            let mut values = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                values.push(match row.get_ref(i)? {
+                    ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+                    ValueRef::Integer(n) => n.into(),
+                    ValueRef::Real(x) => x.into(),
+                    ValueRef::Text(text) => String::from_utf8_lossy(text).into(),
+                });
+            }
+            rows.push(values);
+        }
+        Ok(ExportBatch { columns, rows, last_id })
     }
     
     // ==================== Config CRUD ====================
     
-    /// Get a config value
-    pub fn get_config(&self, key: &str) -> Result<Option<String>> {
-        let mut stmt = self.conn.prepare("SELECT value FROM config WHERE key = ?")?;
-        let mut rows = stmt.query(params![key])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(row.get(0)?))
-        } else {
-            Ok(None)
-        }
+    /// Get a config value
+    pub fn get_config(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT value FROM config WHERE key = ?")?;
+        let mut rows = stmt.query(params![key])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(row.get(0)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Set a config value
+    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+    
+    /// Remove a config value so its default applies
+    pub fn delete_config(&self, key: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM config WHERE key = ?", params![key])?;
+        Ok(())
+    }
+    
+    /// Load full config
+    pub fn load_config(&self) -> Result<Config> {
+        Ok(Config {
+            youtube_api_key: self.get_config("youtube_api_key")?,
+            telegram_bot_token: self.get_config("telegram_bot_token")?,
+            telegram_chat_id: self.get_config("telegram_chat_id")?,
+            ollama_endpoint: self.get_config("ollama_endpoint")?
+                .unwrap_or_else(|| "http://localhost:11434".to_string()),
+            ollama_model: self.get_config("ollama_model")?
+                .unwrap_or_else(|| "llama3.2".to_string()),
+            llm_backend: self.get_config("llm_backend")?
+                .map(|s| LlmBackend::from_str(&s))
+                .unwrap_or_default(),
+            openai_model: self.get_config("openai_model")?
+                .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            auto_fetch_trends: self.get_config("auto_fetch_trends")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            poll_interval_secs: self.get_config("poll_interval_secs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(300),
+            banned_words: self.get_config("banned_words")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            llm_moderation_enabled: self.get_config("llm_moderation_enabled")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            script_prompt_template: self.get_config("script_prompt_template")?
+                .unwrap_or_else(|| crate::prompt::DEFAULT_SCRIPT_TEMPLATE.to_string()),
+            transcription_backend: self.get_config("transcription_backend")?
+                .map(|s| TranscriptionBackend::from_str(&s))
+                .unwrap_or_default(),
+            openai_api_key: self.get_config("openai_api_key")?,
+            yt_dlp_path: self.get_config("yt_dlp_path")?,
+            whisper_cpp_path: self.get_config("whisper_cpp_path")?,
+            whisper_model_path: self.get_config("whisper_model_path")?,
+            target_languages: self.get_config("target_languages")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_else(|| vec!["en".to_string()]),
+            language_voices: self.get_config("language_voices")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            examples_per_prompt: self.get_config("examples_per_prompt")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(2),
+            tts_backend: self.get_config("tts_backend")?
+                .map(|s| TtsBackend::from_str(&s))
+                .unwrap_or_default(),
+            tts_voice: self.get_config("tts_voice")?,
+            openai_tts_model: self.get_config("openai_tts_model")?
+                .unwrap_or_else(|| "tts-1".to_string()),
+            edge_tts_path: self.get_config("edge_tts_path")?,
+            ffmpeg_path: self.get_config("ffmpeg_path")?,
+            loudness_normalization: self.get_config("loudness_normalization")?
+                .map(|s| s == "true")
+                .unwrap_or(true),
+            loudness_target_lufs: self.get_config("loudness_target_lufs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(-14.0),
+            loudness_true_peak_db: self.get_config("loudness_true_peak_db")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(-1.5),
+            ffprobe_path: self.get_config("ffprobe_path")?,
+            music_enabled: self.get_config("music_enabled")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            music_volume_db: self.get_config("music_volume_db")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(-18.0),
+            caption_style: self.get_config("caption_style")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            default_render_template: self.get_config("default_render_template")?
+                .and_then(|s| s.parse().ok()),
+            stock_provider: self.get_config("stock_provider")?
+                .map(|s| StockProvider::from_str(&s))
+                .unwrap_or_default(),
+            pexels_api_key: self.get_config("pexels_api_key")?,
+            pixabay_api_key: self.get_config("pixabay_api_key")?,
+            image_backend: self.get_config("image_backend")?
+                .map(|s| ImageBackend::from_str(&s))
+                .unwrap_or_default(),
+            image_endpoint: self.get_config("image_endpoint")?
+                .unwrap_or_else(|| "http://127.0.0.1:7860".to_string()),
+            image_model: self.get_config("image_model")?,
+            image_steps: self.get_config("image_steps")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(25),
+            cache_limit_mb: self.get_config("cache_limit_mb")?
+                .or(self.get_config("source_cache_limit_mb")?)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(4096),
+            thumbnail_mode: self.get_config("thumbnail_mode")?
+                .map(|s| ThumbnailMode::from_str(&s))
+                .unwrap_or_default(),
+            video_encoder: self.get_config("video_encoder")?
+                .map(|s| VideoEncoder::from_str(&s))
+                .unwrap_or_default(),
+            render_presets: self.get_config("render_presets")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_else(RenderPreset::defaults),
+            default_render_preset: self.get_config("default_render_preset")?
+                .unwrap_or_else(|| "1080p30".to_string()),
+            max_concurrent_generations: self.get_config("max_concurrent_generations")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3),
+            max_concurrent_renders: self.get_config("max_concurrent_renders")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1),
+            generation_timeout_secs: self.get_config("generation_timeout_secs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(900),
+            render_timeout_secs: self.get_config("render_timeout_secs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1800),
+            max_retries: self.get_config("max_retries")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(2),
+            approval_required: self.get_config("approval_required")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            telegram_progress_updates: self.get_config("telegram_progress_updates")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            telegram_chats: match self.get_config("telegram_chats")? {
+                Some(s) => serde_json::from_str(&s).unwrap_or_default(),
+                // Before there was a list, shorts went to the bot's own chat
+                None => self.get_config("telegram_chat_id")?
+                    .map(|chat_id| vec![TelegramChat { chat_id, label: String::new(), enabled: true }])
+                    .unwrap_or_default(),
+            },
+            discord_webhook_url: self.get_config("discord_webhook_url")?,
+            youtube_client_id: self.get_config("youtube_client_id")?,
+            youtube_client_secret: self.get_config("youtube_client_secret")?,
+            posting_schedule: self.get_config("posting_schedule")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            instagram_user_id: self.get_config("instagram_user_id")?,
+            instagram_access_token: self.get_config("instagram_access_token")?,
+            instagram_default: self.get_config("instagram_default")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            media_host: self.get_config("media_host")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            stats_refresh_hours: self.get_config("stats_refresh_hours")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(6),
+            account_assignment: self.get_config("account_assignment")?
+                .map(|s| AccountAssignment::from_str(&s))
+                .unwrap_or_default(),
+            auto_backup: self.get_config("auto_backup")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            backup_retention_days: self.get_config("backup_retention_days")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(7),
+            keep_trends_days: self.get_config("keep_trends_days")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            archive_trends_days: self.get_config("archive_trends_days")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(7),
+            keep_done_jobs_days: self.get_config("keep_done_jobs_days")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            keep_rendered_files_days: self.get_config("keep_rendered_files_days")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            low_disk_warning_mb: self.get_config("low_disk_warning_mb")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(2048),
+            unit_prices: self.get_config("unit_prices")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            notifications: self.get_config("notifications")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            close_to_tray: self.get_config("close_to_tray")?
+                .map(|s| s == "true")
+                .unwrap_or(true),
+            launch_on_startup: self.get_config("launch_on_startup")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            shutdown_grace_secs: self.get_config("shutdown_grace_secs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
+            api_server: self.get_config("api_server")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            dry_run: self.get_config("dry_run")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            batch_priority: self.get_config("batch_priority")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            // Stored as "niche" before it was renamed
+            voice: match self.get_config("voice")? {
+                Some(voice) => Some(voice),
+                None => self.get_config("niche")?,
+            }
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+            script_variants: self.get_config("script_variants")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1),
+            similarity_threshold: self.get_config("similarity_threshold")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0.6),
+            max_short_secs: self.get_config("max_short_secs")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+            length_fix: self.get_config("length_fix")?
+                .map(|s| LengthFix::from_str(&s))
+                .unwrap_or_default(),
+            pacing_cleanup: self.get_config("pacing_cleanup")?
+                .map(|s| s == "true")
+                .unwrap_or(true),
+            max_pause_ms: self.get_config("max_pause_ms")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(600),
+            face_model_path: self.get_config("face_model_path")?,
+            ollama_keep_alive: self.get_config("ollama_keep_alive")?
+                .unwrap_or_else(|| "30m".to_string()),
+            ollama_warm_up: self.get_config("ollama_warm_up")?
+                .map(|s| s == "true")
+                .unwrap_or(true),
+            run_windows: self.get_config("run_windows")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            download_limit_kb: self.get_config("download_limit_kb")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            upload_limit_kb: self.get_config("upload_limit_kb")?
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            artifact_store: self.get_config("artifact_store")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            keep_local_renders: self.get_config("keep_local_renders")?
+                .map(|s| s == "true")
+                .unwrap_or(true),
+            drive_folder_id: self.get_config("drive_folder_id")?,
+            drive_refresh_token: self.get_config("drive_refresh_token")?,
+            sftp: self.get_config("sftp")?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            prioritize_starred_trends: self.get_config("prioritize_starred_trends")?
+                .map(|s| s == "true")
+                .unwrap_or(false),
+        })
+    }
+    
+    /// Save full config in one transaction. Unset optional fields delete
+    /// their keys, so clearing a setting sticks.
+    pub fn save_config(&self, config: &Config) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let set = |key: &str, value: &str| {
+            tx.execute("INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)", params![key, value])
+                .map(|_| ())
+        };
+        let set_opt = |key: &str, value: Option<&str>| match value {
+            Some(value) => set(key, value),
+            None => tx.execute("DELETE FROM config WHERE key = ?", params![key]).map(|_| ()),
+        };
+        set_opt("youtube_api_key", config.youtube_api_key.as_deref())?;
+        set_opt("telegram_bot_token", config.telegram_bot_token.as_deref())?;
+        set_opt("telegram_chat_id", config.telegram_chat_id.as_deref())?;
+        set("ollama_endpoint", &config.ollama_endpoint)?;
+        set("ollama_model", &config.ollama_model)?;
+        set("llm_backend", config.llm_backend.as_str())?;
+        set("openai_model", &config.openai_model)?;
+        set("auto_fetch_trends", &config.auto_fetch_trends.to_string())?;
+        set("poll_interval_secs", &config.poll_interval_secs.to_string())?;
+        set(
+            "banned_words",
+            &serde_json::to_string(&config.banned_words).unwrap_or_else(|_| "[]".to_string()),
+        )?;
+        set("llm_moderation_enabled", &config.llm_moderation_enabled.to_string())?;
+        set("script_prompt_template", &config.script_prompt_template)?;
+        set("transcription_backend", config.transcription_backend.as_str())?;
+        set_opt("openai_api_key", config.openai_api_key.as_deref())?;
+        set_opt("yt_dlp_path", config.yt_dlp_path.as_deref())?;
+        set_opt("whisper_cpp_path", config.whisper_cpp_path.as_deref())?;
+        set_opt("whisper_model_path", config.whisper_model_path.as_deref())?;
+        set(
+            "target_languages",
+            &serde_json::to_string(&config.target_languages).unwrap_or_else(|_| "[\"en\"]".to_string()),
+        )?;
+        set(
+            "language_voices",
+            &serde_json::to_string(&config.language_voices).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set("examples_per_prompt", &config.examples_per_prompt.to_string())?;
+        set("tts_backend", config.tts_backend.as_str())?;
+        set_opt("tts_voice", config.tts_voice.as_deref())?;
+        set("openai_tts_model", &config.openai_tts_model)?;
+        set_opt("edge_tts_path", config.edge_tts_path.as_deref())?;
+        set_opt("ffmpeg_path", config.ffmpeg_path.as_deref())?;
+        set("loudness_normalization", &config.loudness_normalization.to_string())?;
+        set("loudness_target_lufs", &config.loudness_target_lufs.to_string())?;
+        set("loudness_true_peak_db", &config.loudness_true_peak_db.to_string())?;
+        set_opt("ffprobe_path", config.ffprobe_path.as_deref())?;
+        set("music_enabled", &config.music_enabled.to_string())?;
+        set("music_volume_db", &config.music_volume_db.to_string())?;
+        set(
+            "caption_style",
+            &serde_json::to_string(&config.caption_style).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set_opt(
+            "default_render_template",
+            config.default_render_template.map(|template_id| template_id.to_string()).as_deref(),
+        )?;
+        set("stock_provider", config.stock_provider.as_str())?;
+        set_opt("pexels_api_key", config.pexels_api_key.as_deref())?;
+        set_opt("pixabay_api_key", config.pixabay_api_key.as_deref())?;
+        set("image_backend", config.image_backend.as_str())?;
+        set("image_endpoint", &config.image_endpoint)?;
+        set_opt("image_model", config.image_model.as_deref())?;
+        set("image_steps", &config.image_steps.to_string())?;
+        set("cache_limit_mb", &config.cache_limit_mb.to_string())?;
+        set("thumbnail_mode", config.thumbnail_mode.as_str())?;
+        set("video_encoder", config.video_encoder.as_str())?;
+        set(
+            "render_presets",
+            &serde_json::to_string(&config.render_presets).unwrap_or_else(|_| "[]".to_string()),
+        )?;
+        set("default_render_preset", &config.default_render_preset)?;
+        set("max_concurrent_generations", &config.max_concurrent_generations.to_string())?;
+        set("max_concurrent_renders", &config.max_concurrent_renders.to_string())?;
+        set("generation_timeout_secs", &config.generation_timeout_secs.to_string())?;
+        set("render_timeout_secs", &config.render_timeout_secs.to_string())?;
+        set("max_retries", &config.max_retries.to_string())?;
+        set("approval_required", &config.approval_required.to_string())?;
+        set("telegram_progress_updates", &config.telegram_progress_updates.to_string())?;
+        set(
+            "telegram_chats",
+            &serde_json::to_string(&config.telegram_chats).unwrap_or_else(|_| "[]".to_string()),
+        )?;
+        set_opt("discord_webhook_url", config.discord_webhook_url.as_deref())?;
+        set_opt("youtube_client_id", config.youtube_client_id.as_deref())?;
+        set_opt("youtube_client_secret", config.youtube_client_secret.as_deref())?;
+        set(
+            "posting_schedule",
+            &serde_json::to_string(&config.posting_schedule).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set_opt("instagram_user_id", config.instagram_user_id.as_deref())?;
+        set_opt("instagram_access_token", config.instagram_access_token.as_deref())?;
+        set("instagram_default", &config.instagram_default.to_string())?;
+        set(
+            "media_host",
+            &serde_json::to_string(&config.media_host).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set("stats_refresh_hours", &config.stats_refresh_hours.to_string())?;
+        set("account_assignment", config.account_assignment.as_str())?;
+        set("auto_backup", &config.auto_backup.to_string())?;
+        set("backup_retention_days", &config.backup_retention_days.to_string())?;
+        set("keep_trends_days", &config.keep_trends_days.to_string())?;
+        set("archive_trends_days", &config.archive_trends_days.to_string())?;
+        set("keep_done_jobs_days", &config.keep_done_jobs_days.to_string())?;
+        set("keep_rendered_files_days", &config.keep_rendered_files_days.to_string())?;
+        set("low_disk_warning_mb", &config.low_disk_warning_mb.to_string())?;
+        set(
+            "unit_prices",
+            &serde_json::to_string(&config.unit_prices).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set(
+            "notifications",
+            &serde_json::to_string(&config.notifications).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set("close_to_tray", &config.close_to_tray.to_string())?;
+        set("launch_on_startup", &config.launch_on_startup.to_string())?;
+        set("shutdown_grace_secs", &config.shutdown_grace_secs.to_string())?;
+        set("api_server", &serde_json::to_string(&config.api_server).unwrap_or_else(|_| "{}".to_string()))?;
+        set("dry_run", &config.dry_run.to_string())?;
+        set("batch_priority", &config.batch_priority.to_string())?;
+        set("voice", &serde_json::to_string(&config.voice).unwrap_or_else(|_| "{}".to_string()))?;
+        set_opt("niche", None)?;
+        set("script_variants", &config.script_variants.to_string())?;
+        set("similarity_threshold", &config.similarity_threshold.to_string())?;
+        set("max_short_secs", &config.max_short_secs.to_string())?;
+        set("length_fix", config.length_fix.as_str())?;
+        set("pacing_cleanup", &config.pacing_cleanup.to_string())?;
+        set("max_pause_ms", &config.max_pause_ms.to_string())?;
+        set_opt("face_model_path", config.face_model_path.as_deref())?;
+        set("ollama_keep_alive", &config.ollama_keep_alive)?;
+        set("ollama_warm_up", &config.ollama_warm_up.to_string())?;
+        set(
+            "run_windows",
+            &serde_json::to_string(&config.run_windows).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set("download_limit_kb", &config.download_limit_kb.to_string())?;
+        set("upload_limit_kb", &config.upload_limit_kb.to_string())?;
+        set(
+            "artifact_store",
+            &serde_json::to_string(&config.artifact_store).unwrap_or_else(|_| "{}".to_string()),
+        )?;
+        set("keep_local_renders", &config.keep_local_renders.to_string())?;
+        set_opt("drive_folder_id", config.drive_folder_id.as_deref())?;
+        set_opt("drive_refresh_token", config.drive_refresh_token.as_deref())?;
+        set("sftp", &serde_json::to_string(&config.sftp).unwrap_or_else(|_| "{}".to_string()))?;
+        set("prioritize_starred_trends", &config.prioritize_starred_trends.to_string())?;
+        tx.commit()
+    }
+    
+    // ==================== Trends CRUD ====================
+    
+    /// Insert a polled trend, or refresh title, channel, views and category
+    /// of one seen before, and record its view count. Returns the trend id.
+    pub fn upsert_trend(&self, trend: &Trend) -> Result<i64> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let trend_id = upsert_trend_row(&tx, trend)?;
+        tx.commit()?;
+        Ok(trend_id)
+    }
+    
+    /// `upsert_trend` for a whole page of trends in one transaction, which is
+    /// much faster than a transaction per row. Returns the trend ids in the
+    /// order given; a video listed twice gets the same id both times.
+    pub fn insert_trends_batch(&self, trends: &[Trend]) -> Result<Vec<i64>> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let ids = trends.iter()
+            .map(|trend| upsert_trend_row(&tx, trend))
+            .collect::<Result<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(ids)
+    }
+    
+    /// A trend's view count samples, oldest first
+    pub fn get_trend_history(&self, trend_id: i64) -> Result<Vec<TrendViews>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT views, fetched_at FROM trend_views WHERE trend_id = ? ORDER BY fetched_at"
+        )?;
+        let history = stmt.query_map(params![trend_id], |row| {
+            Ok(TrendViews {
+                views: row.get(0)?,
+                fetched_at: row.get::<_, String>(1)
+                    .ok()
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(Utc::now),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(history)
+    }
+    
+    /// Get trend by video_id
+    pub fn get_trend_by_video_id(&self, video_id: &str) -> Result<Option<Trend>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM trends WHERE video_id = ?", TREND_COLUMNS)
+        )?;
+        let mut rows = stmt.query(params![video_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(trend_from_row(row, 0)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Get trend by id
+    pub fn get_trend(&self, trend_id: i64) -> Result<Option<Trend>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM trends WHERE id = ?", TREND_COLUMNS)
+        )?;
+        let mut rows = stmt.query(params![trend_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(trend_from_row(row, 0)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Trends, newest first. With `starred_only`, just the starred ones;
+    /// with `state`, just those in that state.
+    pub fn list_trends(&self, starred_only: bool, state: Option<TrendState>, limit: u32) -> Result<Vec<Trend>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM trends WHERE (starred = 1 OR NOT ?1) AND (?2 IS NULL OR state = ?2)
+             ORDER BY fetched_at DESC, id DESC LIMIT ?3",
+            TREND_COLUMNS
+        ))?;
+        let trends = stmt.query_map(params![starred_only, state.map(|s| s.as_str()), limit], |row| trend_from_row(row, 0))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(trends)
+    }
+    
+    /// Set a trend aside with the reason why. Returns false when there is
+    /// no such trend.
+    pub fn skip_trend(&self, trend_id: i64, reason: Option<&str>) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE trends SET state = 'skipped', skip_reason = ? WHERE id = ?",
+            params![reason, trend_id],
+        )?;
+        Ok(updated > 0)
+    }
+    
+    /// Archive new, unstarred trends fetched before `before`. Returns the
+    /// number archived.
+    pub fn archive_stale_trends(&self, before: DateTime<Utc>) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE trends SET state = 'archived'
+             WHERE state = 'new' AND starred = 0 AND fetched_at < ?
+                 AND NOT EXISTS (SELECT 1 FROM jobs j WHERE j.trend_id = trends.id)",
+            params![before.to_rfc3339()],
+        )
+    }
+    
+    /// Replace a trend's notes; `None` clears them. Returns false when there
+    /// is no such trend.
+    pub fn set_trend_note(&self, trend_id: i64, notes: Option<&str>) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute("UPDATE trends SET notes = ? WHERE id = ?", params![notes, trend_id])?;
+        Ok(updated > 0)
+    }
+    
+    /// Flip a trend's star, returning the new state, or `None` when there is
+    /// no such trend
+    pub fn toggle_trend_star(&self, trend_id: i64) -> Result<Option<bool>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "UPDATE trends SET starred = NOT starred WHERE id = ? RETURNING starred",
+            params![trend_id],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+    
+    /// Store the source-video transcript for a trend
+    pub fn set_trend_transcript(&self, trend_id: i64, transcript: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE trends SET transcript = ? WHERE id = ?",
+            params![transcript, trend_id],
+        )?;
+        Ok(())
+    }
+    
+    // ==================== Jobs CRUD ====================
+    
+    /// Create a new job for a trend, assigned to the next YouTube account.
+    /// The trend moves to `queued` unless it was already processed.
+    pub fn create_job(&self, trend_id: i64, priority: i32) -> Result<i64> {
+        self.insert_job(trend_id, priority, &[], false)
+    }
+    
+    /// Create a job that isn't started until every job in `depends_on` is done
+    pub fn create_dependent_job(&self, trend_id: i64, depends_on: &[i64], priority: i32) -> Result<i64> {
+        self.insert_job(trend_id, priority, depends_on, false)
+    }
+    
+    /// Create a job for demo data, in dry-run mode whatever the config says
+    pub fn create_demo_job(&self, trend_id: i64) -> Result<i64> {
+        self.insert_job(trend_id, 0, &[], true)
+    }
+    
+    fn insert_job(&self, trend_id: i64, priority: i32, depends_on: &[i64], dry_run: bool) -> Result<i64> {
+        let conn = self.conn()?;
+        let trend = self.get_trend(trend_id)?;
+        let dry_run = dry_run || trend.as_ref().is_some_and(|t| t.dry_run);
+        let category = trend.and_then(|t| t.category);
+        let config = self.load_config()?;
+        let account_id = self.next_account(category.as_deref(), config.account_assignment)?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO jobs (trend_id, status, priority, target_account_id, profile_id, dry_run)
+             VALUES (?, 'pending', ?, ?, (SELECT id FROM profiles WHERE active = 1), ?)",
+            params![trend_id, priority, account_id, dry_run || config.dry_run],
+        )?;
+        let job_id = tx.last_insert_rowid();
+        for depends_on_job_id in depends_on {
+            tx.execute(
+                "INSERT OR IGNORE INTO job_dependencies (job_id, depends_on_job_id) VALUES (?, ?)",
+                params![job_id, depends_on_job_id],
+            )?;
+        }
+        tx.execute(
+            "UPDATE trends SET state = 'queued', skip_reason = NULL WHERE id = ? AND state != 'processed'",
+            params![trend_id],
+        )?;
+        tx.commit()?;
+        Ok(job_id)
+    }
+    
+    /// Whether a job was created in dry-run mode
+    pub fn job_is_dry_run(&self, job_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT dry_run FROM jobs WHERE id = ?", params![job_id], |row| row.get(0))
+    }
+    
+    /// A job's status and whether it is in the trash
+    pub fn job_status(&self, job_id: i64) -> Result<Option<(JobStatus, bool)>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT status, deleted_at IS NOT NULL FROM jobs WHERE id = ?",
+            params![job_id],
+            |row| Ok((JobStatus::from_str(&row.get::<_, String>(0)?), row.get(1)?)),
+        )
+        .optional()
+    }
+    
+    /// Jobs that have to be done before `job_id` is started
+    pub fn job_dependencies(&self, job_id: i64) -> Result<Vec<i64>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT depends_on_job_id FROM job_dependencies WHERE job_id = ? ORDER BY depends_on_job_id")?;
+        let ids = stmt.query_map(params![job_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ids)
+    }
+    
+    /// Point a job's uploads at another account, or at none
+    pub fn set_job_account(&self, job_id: i64, account_id: Option<i64>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET target_account_id = ? WHERE id = ?",
+            params![account_id, job_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Claim the next pending job for `worker_id`
+    pub fn get_next_pending_job(&self, worker_id: &str, lease: Duration) -> Result<Option<(Job, Trend)>> {
+        self.claim_next_job(JobStatus::Pending, JobStatus::Generating, worker_id, lease, false)
+    }
+    
+    /// Atomically move the highest-priority, oldest job in `from` to `to` and
+    /// lease it to `worker_id`. Jobs with a dependency that isn't done yet
+    /// are left waiting. With `starred_first`, jobs for starred trends go ahead of all
+    /// others. A single UPDATE both picks and claims the row, so concurrent
+    /// workers never get the same job.
+    pub fn claim_next_job(
+        &self,
+        from: JobStatus,
+        to: JobStatus,
+        worker_id: &str,
+        lease: Duration,
+        starred_first: bool,
+    ) -> Result<Option<(Job, Trend)>> {
+        let conn = self.conn()?;
+        let now = Utc::now();
+        let expires = now + lease;
+        let claimed: Option<i64> = conn.query_row(
+            r#"
+            UPDATE jobs SET status = ?, started_at = ?, worker_id = ?, lease_expires_at = ?
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE status = ? AND deleted_at IS NULL
+                    AND (profile_id IS NULL OR profile_id = (SELECT id FROM profiles WHERE active = 1))
+                    AND NOT EXISTS (
+                        SELECT 1 FROM job_dependencies dep JOIN jobs d ON d.id = dep.depends_on_job_id
+                        WHERE dep.job_id = jobs.id AND d.status != 'done'
+                    )
+                    AND NOT EXISTS (SELECT 1 FROM trends t WHERE t.id = jobs.trend_id AND t.source = 'demo')
+                ORDER BY CASE WHEN ?6 THEN (SELECT starred FROM trends t WHERE t.id = jobs.trend_id) ELSE 0 END DESC,
+                    priority DESC, created_at ASC
+                LIMIT 1
+            )
+            RETURNING id
+            "#,
+            params![to.as_str(), now.to_rfc3339(), worker_id, expires.to_rfc3339(), from.as_str(), starred_first],
+            |row| row.get(0),
+        ).optional()?;
+        match claimed {
+            Some(job_id) => self.get_job_with_trend(job_id),
+            None => Ok(None),
+        }
+    }
+    
+    /// Get a job together with its trend
+    pub fn get_job_with_trend(&self, job_id: i64) -> Result<Option<(Job, Trend)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM jobs j JOIN trends t ON j.trend_id = t.id WHERE j.id = ?",
+            prefixed("j", JOB_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        let mut rows = stmt.query(params![job_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some((job_from_row(row)?, trend_from_row(row, column_count(JOB_COLUMNS))?)))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Extend a worker's lease on a job. Returns false when the worker no
+    /// longer holds it.
+    pub fn renew_job_lease(&self, job_id: i64, worker_id: &str, lease: Duration) -> Result<bool> {
+        let conn = self.conn()?;
+        let expires = (Utc::now() + lease).to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE jobs SET lease_expires_at = ? WHERE id = ? AND worker_id = ?",
+            params![expires, job_id, worker_id],
+        )?;
+        Ok(updated > 0)
+    }
+    
+    /// Update job status
+    pub fn update_job_status(&self, job_id: i64, status: JobStatus, error_msg: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+        let now = Utc::now().to_rfc3339();
+        match status {
+            JobStatus::Generating | JobStatus::Rendering => {
+                conn.execute(
+                    "UPDATE jobs SET status = ?, started_at = ? WHERE id = ?",
+                    params![status.as_str(), now, job_id],
+                )?;
+            }
+            JobStatus::Done | JobStatus::Failed | JobStatus::Dead => {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute(
+                    "UPDATE jobs SET status = ?, finished_at = ?, error_msg = ?, worker_id = NULL, lease_expires_at = NULL WHERE id = ?",
+                    params![status.as_str(), now, error_msg, job_id],
+                )?;
+                match status {
+                    JobStatus::Done => {
+                        tx.execute(
+                            "UPDATE trends SET state = 'processed' WHERE id = (SELECT trend_id FROM jobs WHERE id = ?)",
+                            params![job_id],
+                        )?;
+                    }
+                    JobStatus::Dead => kill_dependents(&tx, job_id, "is dead")?,
+                    _ => {}
+                }
+                tx.commit()?;
+            }
+            JobStatus::NeedsReview => {
+                conn.execute(
+                    "UPDATE jobs SET status = ?, error_msg = ?, worker_id = NULL, lease_expires_at = NULL WHERE id = ?",
+                    params![status.as_str(), error_msg, job_id],
+                )?;
+            }
+            _ => {
+                conn.execute(
+                    "UPDATE jobs SET status = ?, worker_id = NULL, lease_expires_at = NULL WHERE id = ?",
+                    params![status.as_str(), job_id],
+                )?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Send a failed job back to `queued` for another attempt
+    pub fn retry_job(&self, job_id: i64, queued: JobStatus, error_msg: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET status = ?, retry_count = retry_count + 1, error_msg = ?,
+             worker_id = NULL, lease_expires_at = NULL WHERE id = ?",
+            params![queued.as_str(), error_msg, job_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Hand a job `worker_id` stopped early back to `queued` without using
+    /// up an attempt; the stage picks up from the work already stored. A
+    /// job whose lease has passed to another worker is left to that worker.
+    pub fn release_job(&self, job_id: i64, worker_id: &str, queued: JobStatus) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET status = ?, worker_id = NULL, lease_expires_at = NULL WHERE id = ? AND worker_id = ?",
+            params![queued.as_str(), job_id, worker_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Move a job's timestamps `age` into the past, keeping how long it
+    /// took. Demo data uses it so the charts have a history to show.
+    pub fn backdate_job(&self, job_id: i64, age: Duration) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET
+                 created_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at, ?1),
+                 started_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', started_at, ?1),
+                 finished_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', finished_at, ?1)
+             WHERE id = ?2",
+            params![format!("-{} seconds", age.num_seconds()), job_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Delete the demo trends with their jobs, shorts and costs. Returns how
+    /// many jobs went.
+    pub fn delete_demo_data(&self) -> Result<usize> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let job_ids: Vec<i64> = tx
+            .prepare("SELECT id FROM jobs WHERE trend_id IN (SELECT id FROM trends WHERE source = 'demo')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        for &job_id in &job_ids {
+            tx.execute("DELETE FROM job_costs WHERE job_id = ?", params![job_id])?;
+            purge_job(&tx, job_id)?;
+        }
+        tx.execute("DELETE FROM trend_views WHERE trend_id IN (SELECT id FROM trends WHERE source = 'demo')", [])?;
+        tx.execute("DELETE FROM trends WHERE source = 'demo'", [])?;
+        tx.commit()?;
+        Ok(job_ids.len())
+    }
+    
+    /// Run a trivial query, to see that the database answers
+    pub fn ping(&self) -> Result<()> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT 1", [], |_| Ok(()))
+    }
+    
+    /// Number of jobs in each status, trashed jobs left out
+    pub fn count_jobs_by_status(&self) -> Result<HashMap<String, i64>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT status, COUNT(*) FROM jobs WHERE deleted_at IS NULL GROUP BY status"
+        )?;
+        let counts = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(counts)
+    }
+    
+    /// Put jobs whose worker stopped renewing its lease (crashed, or the app
+    /// was closed mid-stage) back in `queued`. Returns how many were requeued.
+    pub fn requeue_expired_jobs(&self, running: JobStatus, queued: JobStatus) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET status = ?, worker_id = NULL, lease_expires_at = NULL
+             WHERE status = ? AND (lease_expires_at IS NULL OR lease_expires_at < ?)
+                 AND trend_id NOT IN (SELECT id FROM trends WHERE source = 'demo')",
+            params![queued.as_str(), running.as_str(), Utc::now().to_rfc3339()],
+        )
+    }
+    
+    /// Record a failed attempt at a job stage
+    pub fn record_job_error(&self, job_id: i64, attempt: i32, stage: &str, message: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO job_errors (job_id, attempt, stage, message, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![job_id, attempt, stage, message, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Failure history of a job, oldest first
+    pub fn get_job_errors(&self, job_id: i64) -> Result<Vec<JobError>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, job_id, attempt, stage, message, created_at FROM job_errors WHERE job_id = ? ORDER BY id"
+        )?;
+        let errors = stmt.query_map(params![job_id], |row| {
+            Ok(JobError {
+                id: Some(row.get(0)?),
+                job_id: row.get(1)?,
+                attempt: row.get(2)?,
+                stage: row.get(3)?,
+                message: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(errors)
+    }
+    
+    /// Dead jobs with their failure history, most recently failed first
+    pub fn list_dead_jobs(&self) -> Result<Vec<DeadJob>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM jobs j JOIN trends t ON j.trend_id = t.id
+             WHERE j.status = 'dead' ORDER BY j.finished_at DESC",
+            prefixed("j", JOB_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        let jobs = stmt.query_map([], |row| {
+            Ok((job_from_row(row)?, trend_from_row(row, column_count(JOB_COLUMNS))?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        jobs.into_iter()
+            .map(|(job, trend)| {
+                let errors = self.get_job_errors(job.id.unwrap_or_default())?;
+                Ok(DeadJob { job, trend, errors })
+            })
+            .collect()
+    }
+    
+    /// A job with its trend, shorts, failed attempts, metrics and latest
+    /// log entries, or `None` when there is no such job
+    pub fn get_job_details(&self, job_id: i64) -> Result<Option<JobDetails>> {
+        let Some((job, trend)) = self.get_job_with_trend(job_id)? else {
+            return Ok(None);
+        };
+        let total = self.get_job_logs(job_id, 0, 0)?.total;
+        let offset = u32::try_from(total).unwrap_or(u32::MAX).saturating_sub(DETAIL_LOG_LINES);
+        Ok(Some(JobDetails {
+            job,
+            trend,
+            depends_on: self.job_dependencies(job_id)?,
+            shorts: self.get_shorts_for_job(job_id)?,
+            errors: self.get_job_errors(job_id)?,
+            metrics: self.get_job_metrics(job_id)?,
+            logs: self.get_job_logs(job_id, offset, DETAIL_LOG_LINES)?,
+        }))
+    }
+    
+    /// Give a dead job a fresh set of attempts from `queued`. Its error
+    /// history is kept. Returns false when the job isn't dead.
+    pub fn requeue_dead_job(&self, job_id: i64, queued: JobStatus) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE jobs SET status = ?, retry_count = 0, error_msg = NULL, finished_at = NULL
+             WHERE id = ? AND status = 'dead'",
+            params![queued.as_str(), job_id],
+        )?;
+        Ok(updated > 0)
+    }
+    
+    /// Telegram status message for a job, if one has been posted
+    pub fn get_job_telegram_message(&self, job_id: i64) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT telegram_message_id FROM jobs WHERE id = ?",
+            params![job_id],
+            |row| row.get(0),
+        )
+    }
+    
+    pub fn set_job_telegram_message(&self, job_id: i64, message_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET telegram_message_id = ? WHERE id = ?",
+            params![message_id, job_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Record that `step` is finished for every short of a job
+    pub fn set_job_checkpoint(&self, job_id: i64, step: PipelineStep) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE jobs SET checkpoint = ? WHERE id = ?",
+            params![step.as_str(), job_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Discard what `step` made for a job's shorts and whatever was made
+    /// from that, wind the checkpoint back to before `step` and queue the
+    /// job in `queued`. Earlier work and publications are kept, but a new
+    /// video needs a fresh review, as after `regenerate_short`. Returns false
+    /// when the job is running, in the trash or hasn't got past `step` yet.
+    pub fn rerun_job_step(&self, job_id: i64, step: PipelineStep, queued: JobStatus) -> Result<bool> {
+        let approval_required = self.load_config()?.approval_required;
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let job: Option<(String, Option<String>)> = tx.query_row(
+            "SELECT status, checkpoint FROM jobs WHERE id = ? AND deleted_at IS NULL",
+            params![job_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        let Some((status, checkpoint)) = job else {
+            return Ok(false);
+        };
+        let running = matches!(JobStatus::from_str(&status), JobStatus::Generating | JobStatus::Rendering);
+        let reached = checkpoint.as_deref().and_then(PipelineStep::from_str).is_some_and(|done| done >= step);
+        if running || !reached {
+            return Ok(false);
+        }
+        tx.execute(
+            "UPDATE jobs SET status = ?, checkpoint = ?, retry_count = 0, error_msg = NULL, finished_at = NULL WHERE id = ?",
+            params![queued.as_str(), step.previous().map(|s| s.as_str()), job_id],
+        )?;
+        let cleared = match step {
+            PipelineStep::Script => "script = NULL, audio_path = NULL, timing_json = NULL, upload_metadata_json = NULL,
+                 video_path = NULL, duration_sec = NULL, thumbnail_path = NULL",
+            PipelineStep::Narration => "audio_path = NULL, timing_json = NULL, video_path = NULL, duration_sec = NULL,
+                 thumbnail_path = NULL",
+            PipelineStep::Metadata => "upload_metadata_json = NULL",
+            PipelineStep::Video => "video_path = NULL, duration_sec = NULL, thumbnail_path = NULL",
+        };
+        tx.execute(
+            &format!("UPDATE shorts SET {} WHERE job_id = ? AND deleted_at IS NULL", cleared),
+            params![job_id],
+        )?;
+        if step != PipelineStep::Metadata {
+            let approval = approval_required.then_some(ApprovalStatus::AwaitingApproval.as_str());
+            tx.execute(
+                "UPDATE shorts SET approval_status = ?, review_note = NULL, reviewed_at = NULL, telegram_message_id = NULL
+                 WHERE job_id = ? AND deleted_at IS NULL",
+                params![approval, job_id],
+            )?;
+        }
+        // Scenes are timed against the narration
+        if step <= PipelineStep::Narration {
+            tx.execute(
+                "DELETE FROM scenes WHERE short_id IN (SELECT id FROM shorts WHERE job_id = ?)",
+                params![job_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+    
+    // ==================== Job Logs ====================
+    
+    /// Append a message to a job's log
+    pub fn insert_job_log(&self, job_id: i64, level: LogLevel, stage: Option<&str>, message: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO job_logs (job_id, level, stage, message, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![job_id, level.as_str(), stage, message, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Up to `limit` entries of a job's log starting at `offset`, oldest first
+    pub fn get_job_logs(&self, job_id: i64, offset: u32, limit: u32) -> Result<JobLogPage> {
+        let conn = self.conn()?;
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM job_logs WHERE job_id = ?",
+            params![job_id],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(
+            "SELECT id, job_id, level, stage, message, created_at FROM job_logs
+             WHERE job_id = ? ORDER BY id LIMIT ? OFFSET ?"
+        )?;
+        let entries = stmt.query_map(params![job_id, limit, offset], |row| {
+            Ok(JobLog {
+                id: Some(row.get(0)?),
+                job_id: row.get(1)?,
+                level: LogLevel::from_str(&row.get::<_, String>(2)?),
+                stage: row.get(3)?,
+                message: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(JobLogPage { entries, total })
+    }
+    
+    // ==================== Activity Log ====================
+    
+    /// Record an action in the activity log
+    pub fn log_activity(
+        &self,
+        actor: Actor,
+        action: ActivityAction,
+        job_id: Option<i64>,
+        short_id: Option<i64>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO activity_log (actor, action, job_id, short_id, detail, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![actor.as_str(), action.as_str(), job_id, short_id, detail, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Up to `limit` activity entries older than `before_id`, newest first.
+    /// Pass the last id of a page as `before_id` to get the next one.
+    pub fn get_activity(&self, limit: u32, before_id: Option<i64>) -> Result<Vec<Activity>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, actor, action, job_id, short_id, detail, created_at FROM activity_log
+             WHERE id < ? ORDER BY id DESC LIMIT ?"
+        )?;
+        let entries = stmt.query_map(params![before_id.unwrap_or(i64::MAX), limit], |row| {
+            Ok(Activity {
+                id: row.get(0)?,
+                actor: Actor::from_str(&row.get::<_, String>(1)?),
+                action: row.get(2)?,
+                job_id: row.get(3)?,
+                short_id: row.get(4)?,
+                detail: row.get(5)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+    
+    // ==================== Shorts CRUD ====================
+    
+    /// Store a short made elsewhere, such as one imported from a bundle, as
+    /// a finished job under `trend`, all in one transaction. Returns the job
+    /// and short ids.
+    pub fn import_short(&self, trend: &Trend, short: &ImportedShort) -> Result<(i64, i64)> {
+        let config = self.load_config()?;
+        let account_id = self.next_account(trend.category.as_deref(), config.account_assignment)?;
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let trend_id = upsert_trend_row(&tx, trend)?;
+        let now = Utc::now().to_rfc3339();
+        tx.execute(
+            "INSERT INTO jobs (trend_id, status, target_account_id, profile_id, started_at, finished_at)
+             VALUES (?, 'done', ?, (SELECT id FROM profiles WHERE active = 1), ?, ?)",
+            params![trend_id, account_id, now, now],
+        )?;
+        let job_id = tx.last_insert_rowid();
+        let timing = short.timing.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let metadata = short.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_default());
+        tx.execute(
+            "INSERT INTO shorts (job_id, language, script, video_path, duration_sec, thumbnail_path, timing_json, upload_metadata_json)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                job_id,
+                short.language,
+                short.script,
+                short.video_path,
+                short.duration_sec,
+                short.thumbnail_path,
+                timing,
+                metadata,
+            ],
+        )?;
+        let short_id = tx.last_insert_rowid();
+        tx.execute(
+            "UPDATE trends SET state = 'processed', skip_reason = NULL WHERE id = ?",
+            params![trend_id],
+        )?;
+        tx.commit()?;
+        Ok((job_id, short_id))
+    }
+    
+    /// Create `variants` empty shorts per target language for a job. With
+    /// more than one, each language's shorts form a variant group and are
+    /// given different hook styles.
+    pub fn create_shorts_for_languages(&self, job_id: i64, languages: &[String], variants: usize) -> Result<Vec<i64>> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let mut ids = Vec::with_capacity(languages.len() * variants.max(1));
+        for language in languages {
+            if variants <= 1 {
+                tx.execute(
+                    "INSERT INTO shorts (job_id, language) VALUES (?, ?)",
+                    params![job_id, language],
+                )?;
+                ids.push(tx.last_insert_rowid());
+                continue;
+            }
+            let mut group_id = None;
+            for hook_style in HookStyle::ALL.iter().cycle().take(variants) {
+                tx.execute(
+                    "INSERT INTO shorts (job_id, language, variant_group_id, hook_style) VALUES (?, ?, ?, ?)",
+                    params![job_id, language, group_id, hook_style.as_str()],
+                )?;
+                let id = tx.last_insert_rowid();
+                if group_id.is_none() {
+                    tx.execute("UPDATE shorts SET variant_group_id = ? WHERE id = ?", params![id, id])?;
+                    group_id = Some(id);
+                }
+                ids.push(id);
+            }
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+    
+    /// Shorts of a variant group, first variant first. Shorts in the trash
+    /// are left out.
+    pub fn list_variants(&self, group_id: i64) -> Result<Vec<Short>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM shorts WHERE variant_group_id = ? AND deleted_at IS NULL ORDER BY id",
+            SHORT_COLUMNS
+        ))?;
+        let shorts = stmt.query_map(params![group_id], short_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(shorts)
+    }
+    
+    /// Shorts with a script, with the trend each was made from, for
+    /// duplicate checks. Shorts and jobs in the trash are left out.
+    pub fn list_produced_shorts(&self) -> Result<Vec<ProducedShort>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.job_id, j.trend_id, t.title, t.transcript, s.script
+             FROM shorts s
+             JOIN jobs j ON s.job_id = j.id
+             JOIN trends t ON j.trend_id = t.id
+             WHERE s.script IS NOT NULL AND s.deleted_at IS NULL AND j.deleted_at IS NULL
+             ORDER BY s.id"
+        )?;
+        let shorts = stmt.query_map([], |row| {
+            Ok(ProducedShort {
+                short_id: row.get(0)?,
+                job_id: row.get(1)?,
+                trend_id: row.get(2)?,
+                title: row.get(3)?,
+                transcript: row.get(4)?,
+                script: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(shorts)
+    }
+    
+    /// Get all shorts produced by a job (one per language and variant), except those in the trash
+    pub fn get_shorts_for_job(&self, job_id: i64) -> Result<Vec<Short>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM shorts WHERE job_id = ? AND deleted_at IS NULL ORDER BY id", SHORT_COLUMNS)
+        )?;
+        let shorts = stmt.query_map(params![job_id], short_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(shorts)
+    }
+    
+    /// Get a short together with the trend it was made from. Shorts in the
+    /// trash aren't found.
+    pub fn get_short_with_trend(&self, short_id: i64) -> Result<Option<(Short, Trend)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM shorts s JOIN jobs j ON s.job_id = j.id JOIN trends t ON j.trend_id = t.id
+             WHERE s.id = ? AND s.deleted_at IS NULL",
+            prefixed("s", SHORT_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        let mut rows = stmt.query(params![short_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some((short_from_row(row)?, trend_from_row(row, column_count(SHORT_COLUMNS))?)))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Store the generated script for a short
+    pub fn set_short_script(&self, short_id: i64, script: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET script = ? WHERE id = ?",
+            params![script, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Store the title, description and tags a short will be uploaded with
+    pub fn set_short_upload_metadata(&self, short_id: i64, metadata: &VideoMetadata) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET upload_metadata_json = ? WHERE id = ?",
+            params![serde_json::to_string(metadata).unwrap_or_default(), short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Store the path of a short's narration audio
+    pub fn set_short_audio(&self, short_id: i64, audio_path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET audio_path = ? WHERE id = ?",
+            params![audio_path, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Store the narration timing for a short
+    pub fn set_short_timing(&self, short_id: i64, timing: &SpeechTiming) -> Result<()> {
+        let conn = self.conn()?;
+        let json = serde_json::to_string(timing)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "UPDATE shorts SET timing_json = ? WHERE id = ?",
+            params![json, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Remember which music track a short uses, so re-renders stay consistent
+    pub fn set_short_music(&self, short_id: i64, music_id: Option<i64>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET music_id = ? WHERE id = ?",
+            params![music_id, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Store the poster frame for a short
+    pub fn set_short_thumbnail(&self, short_id: i64, thumbnail_path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET thumbnail_path = ? WHERE id = ?",
+            params![thumbnail_path, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Store the rendered video for a short
+    pub fn set_short_video(&self, short_id: i64, video_path: &str, duration_sec: f64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET video_path = ?, duration_sec = ? WHERE id = ?",
+            params![video_path, duration_sec, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Set a short's review state. Approving or rejecting stamps the review time.
+    pub fn set_short_approval(&self, short_id: i64, status: ApprovalStatus, note: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+        let reviewed_at = (status != ApprovalStatus::AwaitingApproval).then(|| Utc::now().to_rfc3339());
+        conn.execute(
+            "UPDATE shorts SET approval_status = ?, review_note = ?, reviewed_at = ? WHERE id = ?",
+            params![status.as_str(), note, reviewed_at, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Shorts waiting for review, oldest first, with their trends
+    pub fn list_shorts_awaiting_approval(&self, starred_only: bool) -> Result<Vec<(Short, Trend)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM shorts s JOIN jobs j ON s.job_id = j.id JOIN trends t ON j.trend_id = t.id
+             WHERE s.approval_status = 'awaiting_approval' AND s.deleted_at IS NULL AND (t.starred = 1 OR NOT ?1)
+             ORDER BY s.id",
+            prefixed("s", SHORT_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        let shorts = stmt.query_map(params![starred_only], |row| {
+            Ok((short_from_row(row)?, trend_from_row(row, column_count(SHORT_COLUMNS))?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(shorts)
+    }
+    
+    /// Choose whether a short goes to Instagram; `None` follows the config default
+    pub fn set_short_instagram(&self, short_id: i64, enabled: Option<bool>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET instagram = ? WHERE id = ?",
+            params![enabled, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Schedule a short for publishing, or clear its slot
+    pub fn set_short_publish_at(&self, short_id: i64, publish_at: Option<DateTime<Utc>>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET publish_at = ? WHERE id = ?",
+            params![publish_at.map(|t| t.to_rfc3339()), short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Record a finished YouTube upload
+    pub fn set_short_uploaded(&self, short_id: i64, video_id: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET youtube_video_id = ?, uploaded_at = ? WHERE id = ?",
+            params![video_id, Utc::now().to_rfc3339(), short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Shorts scheduled between `from` and `until`, soonest first, with their trends
+    pub fn list_scheduled_shorts(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<(Short, Trend)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM shorts s JOIN jobs j ON s.job_id = j.id JOIN trends t ON j.trend_id = t.id
+             WHERE s.publish_at IS NOT NULL AND s.deleted_at IS NULL",
+            prefixed("s", SHORT_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        // RFC 3339 strings with different offsets don't sort, so filter here
+        let mut shorts = stmt.query_map([], |row| {
+            Ok((short_from_row(row)?, trend_from_row(row, column_count(SHORT_COLUMNS))?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        shorts.retain(|(short, _)| short.publish_at.is_some_and(|t| t >= from && t <= until));
+        shorts.sort_by_key(|(short, _)| short.publish_at);
+        Ok(shorts)
+    }
+    
+    /// Publish times already taken by other shorts
+    pub fn scheduled_publish_times(&self) -> Result<Vec<DateTime<Utc>>> {
+        publish_times(&*self.conn()?)
+    }
+    
+    /// Give a short the next free slot of `schedule` unless it already has a
+    /// publish time. Runs as one write transaction so parallel uploads can't
+    /// claim the same slot.
+    pub fn claim_publish_slot(&self, short_id: i64, schedule: &PostingSchedule) -> Result<Option<DateTime<Utc>>> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let existing: Option<String> = tx.query_row(
+            "SELECT publish_at FROM shorts WHERE id = ?",
+            params![short_id],
+            |row| row.get(0),
+        )?;
+        if let Some(existing) = existing.and_then(|s| DateTime::parse_from_rfc3339(&s).ok()) {
+            return Ok(Some(existing.with_timezone(&Utc)));
+        }
+        let slot = schedule.next_free_slot(&publish_times(&tx)?);
+        tx.execute(
+            "UPDATE shorts SET publish_at = ? WHERE id = ?",
+            params![slot.map(|t| t.to_rfc3339()), short_id],
+        )?;
+        tx.commit()?;
+        Ok(slot)
+    }
+    
+    /// Remember the Telegram message carrying a short's video so replies to
+    /// it can be traced back
+    pub fn set_short_telegram_message(&self, short_id: i64, message_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE shorts SET telegram_message_id = ? WHERE id = ?",
+            params![message_id, short_id],
+        )?;
+        Ok(())
+    }
+    
+    /// Short whose video was sent as Telegram message `message_id`
+    pub fn find_short_by_telegram_message(&self, message_id: i64) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        conn
+            .query_row(
+                "SELECT id FROM shorts WHERE telegram_message_id = ? AND deleted_at IS NULL",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+    
+    /// Throw away a short's script and everything made from it, and send
+    /// its job back to `queued` with a fresh set of attempts so the pipeline
+    /// produces it again. The language and music pick are kept. Returns
+    /// false, changing nothing, when the job is being worked on.
+    pub fn regenerate_short(&self, short_id: i64, queued: JobStatus) -> Result<bool> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        // The job's other shorts skip the steps they already went through
+        let requeued = tx.execute(
+            "UPDATE jobs SET status = ?, retry_count = 0, error_msg = NULL, finished_at = NULL, checkpoint = NULL
+             WHERE id = (SELECT job_id FROM shorts WHERE id = ?) AND status NOT IN ('generating', 'rendering')",
+            params![queued.as_str(), short_id],
+        )?;
+        if requeued == 0 {
+            return Ok(false);
+        }
+        tx.execute("DELETE FROM scenes WHERE short_id = ?", params![short_id])?;
+        tx.execute("DELETE FROM publications WHERE short_id = ?", params![short_id])?;
+        tx.execute("DELETE FROM short_stats WHERE short_id = ?", params![short_id])?;
+        tx.execute(
+            "UPDATE shorts SET script = NULL, audio_path = NULL, video_path = NULL, duration_sec = NULL,
+                 timing_json = NULL, thumbnail_path = NULL, approval_status = NULL,
+                 review_note = NULL, reviewed_at = NULL, telegram_message_id = NULL, publish_at = NULL,
+                 youtube_video_id = NULL, uploaded_at = NULL, upload_metadata_json = NULL
+             WHERE id = ?",
+            params![short_id],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+    
+    // ==================== Trash ====================
+    
+    /// Move a short to the trash. Returns false when there is no such short
+    /// outside the trash.
+    pub fn trash_short(&self, short_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE shorts SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), short_id],
+        )?;
+        Ok(updated > 0)
+    }
+    
+    /// Take a short out of the trash, along with its job if that was
+    /// trashed too. Returns false when the short isn't in the trash.
+    pub fn restore_short(&self, short_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let updated = tx.execute(
+            "UPDATE shorts SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+            params![short_id],
+        )?;
+        tx.execute(
+            "UPDATE jobs SET deleted_at = NULL WHERE id = (SELECT job_id FROM shorts WHERE id = ?)",
+            params![short_id],
+        )?;
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+    
+    /// Move a job and its shorts to the trash; the pipeline stops picking it
+    /// up, and jobs waiting on it are marked dead unless it was done.
+    /// Returns false when there is no such job outside the trash.
+    pub fn trash_job(&self, job_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let now = Utc::now().to_rfc3339();
+        let updated = tx.execute(
+            "UPDATE jobs SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+            params![now, job_id],
+        )?;
+        let done: bool = tx.query_row("SELECT status = 'done' FROM jobs WHERE id = ?", params![job_id], |row| row.get(0))
+            .optional()?
+            .unwrap_or(true);
+        if updated > 0 && !done {
+            kill_dependents(&tx, job_id, "is in the trash")?;
+        }
+        tx.execute(
+            "UPDATE shorts SET deleted_at = ? WHERE job_id = ? AND deleted_at IS NULL",
+            params![now, job_id],
+        )?;
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+    
+    /// Take a job out of the trash with the shorts that were trashed along
+    /// with it. Returns false when the job isn't in the trash.
+    pub fn restore_job(&self, job_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE shorts SET deleted_at = NULL
+             WHERE job_id = ?1 AND deleted_at = (SELECT deleted_at FROM jobs WHERE id = ?1)",
+            params![job_id],
+        )?;
+        let updated = tx.execute(
+            "UPDATE jobs SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+            params![job_id],
+        )?;
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+    
+    /// Shorts in the trash, most recently deleted first, with their trends
+    pub fn list_trashed_shorts(&self) -> Result<Vec<(Short, Trend)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM shorts s JOIN jobs j ON s.job_id = j.id JOIN trends t ON j.trend_id = t.id
+             WHERE s.deleted_at IS NOT NULL ORDER BY s.deleted_at DESC",
+            prefixed("s", SHORT_COLUMNS),
+            prefixed("t", TREND_COLUMNS),
+        ))?;
+        let shorts = stmt.query_map([], |row| {
+            Ok((short_from_row(row)?, trend_from_row(row, column_count(SHORT_COLUMNS))?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(shorts)
+    }
+    
+    /// Permanently delete trashed jobs and shorts. Returns how many shorts
+    /// went and the media paths they used, for the caller to remove.
+    pub fn empty_trash(&self) -> Result<(usize, Vec<String>)> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let paths: Vec<String> = tx
+            .prepare(
+                "SELECT audio_path FROM shorts WHERE deleted_at IS NOT NULL AND audio_path IS NOT NULL
+                 UNION SELECT video_path FROM shorts WHERE deleted_at IS NOT NULL AND video_path IS NOT NULL
+                 UNION SELECT thumbnail_path FROM shorts WHERE deleted_at IS NOT NULL AND thumbnail_path IS NOT NULL
+                 UNION SELECT sc.media_path FROM scenes sc JOIN shorts s ON s.id = sc.short_id
+                       WHERE s.deleted_at IS NOT NULL AND sc.media_path IS NOT NULL",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        let short_ids: Vec<i64> = tx
+            .prepare("SELECT id FROM shorts WHERE deleted_at IS NOT NULL")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        let job_ids: Vec<i64> = tx
+            .prepare("SELECT id FROM jobs WHERE deleted_at IS NOT NULL")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        for short_id in &short_ids {
+            purge_short(&tx, *short_id)?;
+        }
+        for job_id in job_ids {
+            purge_job(&tx, job_id)?;
+        }
+        tx.commit()?;
+        Ok((short_ids.len(), paths))
+    }
+    
+    // ==================== Deliveries ====================
+    
+    /// Record the outcome of publishing a short to one destination,
+    /// replacing the previous outcome there. `Ok` carries the platform's id
+    /// for what was posted.
+    pub fn record_publication(
+        &self,
+        short_id: i64,
+        platform: &str,
+        destination: &str,
+        result: std::result::Result<&str, &str>,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        let now = Utc::now().to_rfc3339();
+        let (status, remote_id, published_at, error) = match result {
+            Ok(remote_id) => (PublicationStatus::Sent, Some(remote_id), Some(now.as_str()), None),
+            Err(error) => (PublicationStatus::Failed, None, None, Some(error)),
+        };
+        conn.execute(
+            "INSERT INTO publications (short_id, platform, destination, status, remote_id, published_at, error, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(short_id, platform, destination) DO UPDATE SET
+                 status = excluded.status, remote_id = excluded.remote_id, published_at = excluded.published_at,
+                 error = excluded.error, attempts = attempts + 1, updated_at = excluded.updated_at, url = NULL",
+            params![short_id, platform, destination, status.as_str(), remote_id, published_at, error, now],
+        )?;
+        Ok(())
+    }
+    
+    /// Attach the public link to a successful publication
+    pub fn set_publication_url(&self, short_id: i64, platform: &str, destination: &str, url: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE publications SET url = ? WHERE short_id = ? AND platform = ? AND destination = ?",
+            params![url, short_id, platform, destination],
+        )?;
+        Ok(())
+    }
+    
+    /// Every destination a short has been published to, or failed to reach
+    pub fn list_publications(&self, short_id: i64) -> Result<Vec<Publication>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, short_id, platform, destination, status, remote_id, url, published_at, error, attempts, updated_at
+             FROM publications WHERE short_id = ? ORDER BY platform, id"
+        )?;
+        let publications = stmt.query_map(params![short_id], |row| {
+            Ok(Publication {
+                id: row.get(0)?,
+                short_id: row.get(1)?,
+                platform: row.get(2)?,
+                destination: row.get(3)?,
+                status: PublicationStatus::from_str(&row.get::<_, String>(4)?),
+                remote_id: row.get(5)?,
+                url: row.get(6)?,
+                published_at: row.get(7)?,
+                error: row.get(8)?,
+                attempts: row.get(9)?,
+                updated_at: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(publications)
+    }
+    
+    // ==================== Scenes CRUD ====================
+    
+    /// Replace a short's scenes with a new segmentation
+    pub fn replace_scenes(&self, short_id: i64, scenes: &[Scene]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM scenes WHERE short_id = ?", params![short_id])?;
+        for scene in scenes {
+            tx.execute(
+                "INSERT INTO scenes (short_id, position, text, start_sec, duration_sec, visual_prompt, stock_keyword, media_path)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    short_id,
+                    scene.position,
+                    scene.text,
+                    scene.start_sec,
+                    scene.duration_sec,
+                    scene.visual_prompt,
+                    scene.stock_keyword,
+                    scene.media_path,
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+    
+    /// Get a short's scenes in order
+    pub fn get_scenes(&self, short_id: i64) -> Result<Vec<Scene>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM scenes WHERE short_id = ? ORDER BY position", SCENE_COLUMNS)
+        )?;
+        let scenes = stmt.query_map(params![short_id], scene_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(scenes)
+    }
+    
+    /// Remember the image or clip fetched for a scene
+    pub fn set_scene_media(&self, scene_id: i64, media_path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE scenes SET media_path = ? WHERE id = ?",
+            params![media_path, scene_id],
+        )?;
+        Ok(())
+    }
+    
+    // ==================== Examples CRUD ====================
+    
+    /// Add a curated example script
+    pub fn insert_example(&self, example: &Example) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO examples (niche, title, script, created_at) VALUES (?, ?, ?, ?)",
+            params![
+                example.niche.trim().to_lowercase(),
+                example.title,
+                example.script,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Update an existing example
+    pub fn update_example(&self, example: &Example) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE examples SET niche = ?, title = ?, script = ? WHERE id = ?",
+            params![example.niche.trim().to_lowercase(), example.title, example.script, example.id],
+        )?;
+        Ok(())
+    }
+    
+    /// Delete an example
+    pub fn delete_example(&self, example_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM examples WHERE id = ?", params![example_id])?;
+        Ok(())
+    }
+    
+    /// List examples, optionally restricted to one niche
+    pub fn list_examples(&self, niche: Option<&str>) -> Result<Vec<Example>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, niche, title, script, created_at FROM examples
+             WHERE ?1 IS NULL OR niche = ?1
+             ORDER BY niche, id DESC"
+        )?;
+        let examples = stmt.query_map(params![niche.map(|n| n.trim().to_lowercase())], example_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(examples)
+    }
+    
+    /// Pick up to `limit` examples for a niche: exact niche matches first,
+    /// then untagged general examples, newest first within each group
+    pub fn top_examples(&self, niche: Option<&str>, limit: u32) -> Result<Vec<Example>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, niche, title, script, created_at FROM examples
+             WHERE niche = ?1 OR niche = ''
+             ORDER BY (niche = ?1) DESC, id DESC
+             LIMIT ?2"
+        )?;
+        let niche = niche.map(|n| n.trim().to_lowercase()).unwrap_or_default();
+        let examples = stmt.query_map(params![niche, limit], example_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(examples)
+    }
+    
+    // ==================== Prompt Templates CRUD ====================
+    
+    /// Add a prompt template
+    pub fn insert_prompt_template(&self, template: &PromptTemplate) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO prompt_templates (name, niche, body, temperature, top_p, max_tokens, seed)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                template.name,
+                template.niche.trim().to_lowercase(),
+                template.body,
+                template.temperature,
+                template.top_p,
+                template.max_tokens,
+                template.seed,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Update a prompt template
+    pub fn update_prompt_template(&self, template: &PromptTemplate) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE prompt_templates SET name = ?, niche = ?, body = ?, temperature = ?, top_p = ?,
+             max_tokens = ?, seed = ? WHERE id = ?",
+            params![
+                template.name,
+                template.niche.trim().to_lowercase(),
+                template.body,
+                template.temperature,
+                template.top_p,
+                template.max_tokens,
+                template.seed,
+                template.id,
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// Delete a prompt template
+    pub fn delete_prompt_template(&self, template_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM prompt_templates WHERE id = ?", params![template_id])?;
+        Ok(())
+    }
+    
+    /// List all prompt templates
+    pub fn list_prompt_templates(&self) -> Result<Vec<PromptTemplate>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM prompt_templates ORDER BY niche, name", PROMPT_TEMPLATE_COLUMNS)
+        )?;
+        let templates = stmt.query_map([], prompt_template_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(templates)
+    }
+    
+    /// Find the template for a niche, falling back to a catch-all template
+    pub fn prompt_template_for_niche(&self, niche: Option<&str>) -> Result<Option<PromptTemplate>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM prompt_templates WHERE niche = ?1 OR niche = ''
+             ORDER BY (niche = ?1) DESC, id LIMIT 1",
+            PROMPT_TEMPLATE_COLUMNS
+        ))?;
+        let niche = niche.map(|n| n.trim().to_lowercase()).unwrap_or_default();
+        let mut rows = stmt.query(params![niche])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(prompt_template_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    // ==================== Upload Templates CRUD ====================
+    
+    /// Add an upload template
+    pub fn insert_upload_template(&self, template: &UploadTemplate) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO upload_templates (name, channel_id, title, description, tags_json, affiliate_links)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                template.name,
+                template.channel_id.trim(),
+                template.title,
+                template.description,
+                serde_json::to_string(&template.tags).unwrap_or_else(|_| "[]".to_string()),
+                template.affiliate_links,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Update an upload template
+    pub fn update_upload_template(&self, template: &UploadTemplate) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE upload_templates SET name = ?, channel_id = ?, title = ?, description = ?, tags_json = ?,
+             affiliate_links = ? WHERE id = ?",
+            params![
+                template.name,
+                template.channel_id.trim(),
+                template.title,
+                template.description,
+                serde_json::to_string(&template.tags).unwrap_or_else(|_| "[]".to_string()),
+                template.affiliate_links,
+                template.id,
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// Delete an upload template
+    pub fn delete_upload_template(&self, template_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM upload_templates WHERE id = ?", params![template_id])?;
+        Ok(())
+    }
+    
+    /// List all upload templates
+    pub fn list_upload_templates(&self) -> Result<Vec<UploadTemplate>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM upload_templates ORDER BY channel_id, name", UPLOAD_TEMPLATE_COLUMNS)
+        )?;
+        let templates = stmt.query_map([], upload_template_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(templates)
+    }
+    
+    /// Find the template for a channel, falling back to a catch-all template
+    pub fn upload_template_for_channel(&self, channel_id: Option<&str>) -> Result<Option<UploadTemplate>> {
+        let conn = self.conn()?;
+        conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM upload_templates WHERE channel_id = ?1 OR channel_id = ''
+                     ORDER BY (channel_id = ?1) DESC, id LIMIT 1",
+                    UPLOAD_TEMPLATE_COLUMNS
+                ),
+                params![channel_id.unwrap_or_default()],
+                upload_template_from_row,
+            )
+            .optional()
+    }
+    
+    // ==================== Music Library CRUD ====================
+    
+    /// Add a track to the music library
+    pub fn insert_music(&self, track: &MusicTrack) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO music (path, title, tags) VALUES (?, ?, ?)",
+            params![track.path, track.title, encode_tags(&track.tags)],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Update a library track
+    pub fn update_music(&self, track: &MusicTrack) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE music SET path = ?, title = ?, tags = ? WHERE id = ?",
+            params![track.path, track.title, encode_tags(&track.tags), track.id],
+        )?;
+        Ok(())
+    }
+    
+    /// Remove a track from the library (the file itself is left alone)
+    pub fn delete_music(&self, music_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("UPDATE shorts SET music_id = NULL WHERE music_id = ?", params![music_id])?;
+        conn.execute("DELETE FROM music WHERE id = ?", params![music_id])?;
+        Ok(())
+    }
+    
+    /// List the music library
+    pub fn list_music(&self) -> Result<Vec<MusicTrack>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id, path, title, tags FROM music ORDER BY title")?;
+        let tracks = stmt.query_map([], music_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tracks)
+    }
+    
+    /// Get a single library track
+    pub fn get_music(&self, music_id: i64) -> Result<Option<MusicTrack>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id, path, title, tags FROM music WHERE id = ?")?;
+        let mut rows = stmt.query(params![music_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(music_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Pick a random track carrying `tag`, or any random track when none match
+    pub fn pick_music(&self, tag: Option<&str>) -> Result<Option<MusicTrack>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, path, title, tags FROM music
+             ORDER BY (tags LIKE '%,' || ?1 || ',%') DESC, RANDOM()
+             LIMIT 1"
+        )?;
+        let tag = tag.map(|t| t.trim().to_lowercase()).unwrap_or_default();
+        let mut rows = stmt.query(params![tag])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(music_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    // ==================== Sound Effects ====================
+    
+    /// Add a sound effect, replacing the file and gain of one with the same name
+    pub fn upsert_sound_effect(&self, effect: &SoundEffect) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "INSERT INTO sound_effects (name, path, volume_db) VALUES (?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET path = excluded.path, volume_db = excluded.volume_db
+             RETURNING id",
+            params![effect.name, effect.path, effect.volume_db],
+            |row| row.get(0),
+        )
+    }
+    
+    /// List the sound effect library by name
+    pub fn list_sound_effects(&self) -> Result<Vec<SoundEffect>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id, name, path, volume_db FROM sound_effects ORDER BY name")?;
+        let effects = stmt.query_map([], sound_effect_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(effects)
+    }
+    
+    // ==================== Media Cache ====================
+    
+    /// Path and stored metadata of a cache entry
+    pub fn get_cache_entry(&self, kind: &str, key: &str) -> Result<Option<(String, Option<String>)>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT path, meta FROM cache_entries WHERE kind = ? AND key = ?",
+            params![kind, key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+    }
+    
+    /// Add or replace a cache entry, marking it used now
+    pub fn put_cache_entry(
+        &self,
+        kind: &str,
+        key: &str,
+        content_hash: &str,
+        path: &str,
+        size_bytes: i64,
+        meta: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO cache_entries (kind, key, content_hash, path, size_bytes, meta, last_used_at)
+             VALUES (?, ?, ?, ?, ?, ?, strftime('%Y-%m-%d %H:%M:%f', 'now'))",
+            params![kind, key, content_hash, path, size_bytes, meta],
+        )?;
+        Ok(())
+    }
+    
+    /// Mark a cache entry as just used
+    pub fn touch_cache_entry(&self, kind: &str, key: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE cache_entries SET last_used_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE kind = ? AND key = ?",
+            params![kind, key],
+        )?;
+        Ok(())
     }
     
-    /// Set a config value
-    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)",
-            params![key, value],
+    pub fn delete_cache_entry(&self, kind: &str, key: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM cache_entries WHERE kind = ? AND key = ?", params![kind, key])?;
+        Ok(())
+    }
+    
+    /// Every cached file with its size, least recently used first
+    pub fn list_cache_files(&self) -> Result<Vec<(String, i64)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT path, MAX(size_bytes) FROM cache_entries
+             GROUP BY path
+             ORDER BY MAX(last_used_at), MAX(rowid)",
         )?;
+        let files = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(files)
+    }
+    
+    /// Drop every entry pointing at a deleted file
+    pub fn delete_cache_file(&self, path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM cache_entries WHERE path = ?", params![path])?;
         Ok(())
     }
     
-    /// Load full config
-    pub fn load_config(&self) -> Result<Config> {
-        Ok(Config {
-            youtube_api_key: self.get_config("youtube_api_key")?,
-            telegram_bot_token: self.get_config("telegram_bot_token")?,
-            telegram_chat_id: self.get_config("telegram_chat_id")?,
-            ollama_endpoint: self.get_config("ollama_endpoint")?
-                .unwrap_or_else(|| "http://localhost:11434".to_string()),
-            poll_interval_secs: self.get_config("poll_interval_secs")?
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(300),
-        })
+    /// Entries, files and bytes per kind of cached media
+    pub fn cache_stats(&self) -> Result<Vec<CacheStats>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT kind, SUM(entries), COUNT(*), SUM(size_bytes), SUM((entries - 1) * size_bytes)
+             FROM (
+                 SELECT kind, path, MAX(size_bytes) AS size_bytes, COUNT(*) AS entries
+                 FROM cache_entries GROUP BY kind, path
+             )
+             GROUP BY kind
+             ORDER BY kind",
+        )?;
+        let stats = stmt.query_map([], |row| {
+            Ok(CacheStats {
+                kind: row.get(0)?,
+                entries: row.get(1)?,
+                files: row.get(2)?,
+                bytes: row.get(3)?,
+                saved_bytes: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(stats)
     }
     
-    /// Save full config
-    pub fn save_config(&self, config: &Config) -> Result<()> {
-        if let Some(ref key) = config.youtube_api_key {
-            self.set_config("youtube_api_key", key)?;
+    // ==================== Render Templates CRUD ====================
+    
+    /// Add a render template
+    pub fn insert_render_template(&self, template: &RenderTemplate) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO render_templates (name, layout_json) VALUES (?, ?)",
+            params![template.name, encode_layout(&template.layout)],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Update a render template
+    pub fn update_render_template(&self, template: &RenderTemplate) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE render_templates SET name = ?, layout_json = ? WHERE id = ?",
+            params![template.name, encode_layout(&template.layout), template.id],
+        )?;
+        Ok(())
+    }
+    
+    /// Delete a render template
+    pub fn delete_render_template(&self, template_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM render_templates WHERE id = ?", params![template_id])?;
+        Ok(())
+    }
+    
+    /// List render templates
+    pub fn list_render_templates(&self) -> Result<Vec<RenderTemplate>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id, name, layout_json FROM render_templates ORDER BY name")?;
+        let templates = stmt.query_map([], render_template_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(templates)
+    }
+    
+    /// Template to render with: `template_id` if it exists, else the built-in default
+    pub fn render_template_or_default(&self, template_id: Option<i64>) -> Result<RenderTemplate> {
+        let conn = self.conn()?;
+        let Some(template_id) = template_id else {
+            return Ok(RenderTemplate::default());
+        };
+        let mut stmt = conn.prepare("SELECT id, name, layout_json FROM render_templates WHERE id = ?")?;
+        let mut rows = stmt.query(params![template_id])?;
+        if let Some(row) = rows.next()? {
+            render_template_from_row(row)
+        } else {
+            Ok(RenderTemplate::default())
         }
-        if let Some(ref token) = config.telegram_bot_token {
-            self.set_config("telegram_bot_token", token)?;
+    }
+    
+    // ==================== Profiles ====================
+    
+    /// List profiles, oldest first
+    pub fn list_profiles(&self) -> Result<Vec<Profile>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY id", PROFILE_COLUMNS))?;
+        let profiles = stmt.query_map([], profile_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(profiles)
+    }
+    
+    pub fn get_profile(&self, profile_id: i64) -> Result<Option<Profile>> {
+        let conn = self.conn()?;
+        conn
+            .query_row(
+                &format!("SELECT {} FROM profiles WHERE id = ?", PROFILE_COLUMNS),
+                params![profile_id],
+                profile_from_row,
+            )
+            .optional()
+    }
+    
+    /// Add an inactive profile. It starts from the default settings, or from
+    /// a copy of `copy_from`'s.
+    pub fn create_profile(&self, name: &str, copy_from: Option<i64>) -> Result<i64> {
+        let conn = self.conn()?;
+        let settings = match copy_from {
+            Some(source_id) => {
+                let (active, settings): (bool, String) = conn.query_row(
+                    "SELECT active, settings FROM profiles WHERE id = ?",
+                    params![source_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                if active {
+                    serde_json::to_string(&config_entries(&conn)?).unwrap_or_else(|_| "{}".to_string())
+                } else {
+                    settings
+                }
+            }
+            None => "{}".to_string(),
+        };
+        conn.execute(
+            "INSERT INTO profiles (name, active, settings, created_at) VALUES (?, 0, ?, ?)",
+            params![name, settings, Utc::now().to_rfc3339()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// Make a profile active: the current settings are put aside under the
+    /// active profile and the chosen profile's take their place. Workers
+    /// only pick up jobs created under the active profile.
+    pub fn switch_profile(&self, profile_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let (active, settings): (bool, String) = tx.query_row(
+            "SELECT active, settings FROM profiles WHERE id = ?",
+            params![profile_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if active {
+            return Ok(());
         }
-        if let Some(ref chat_id) = config.telegram_chat_id {
-            self.set_config("telegram_chat_id", chat_id)?;
+        let current = serde_json::to_string(&config_entries(&tx)?).unwrap_or_else(|_| "{}".to_string());
+        tx.execute("UPDATE profiles SET settings = ?, active = 0 WHERE active = 1", params![current])?;
+        tx.execute("DELETE FROM config", [])?;
+        let entries: HashMap<String, String> = serde_json::from_str(&settings).unwrap_or_default();
+        for (key, value) in &entries {
+            tx.execute("INSERT INTO config (key, value) VALUES (?, ?)", params![key, value])?;
         }
-        self.set_config("ollama_endpoint", &config.ollama_endpoint)?;
-        self.set_config("poll_interval_secs", &config.poll_interval_secs.to_string())?;
+        tx.execute("UPDATE profiles SET settings = '{}', active = 1 WHERE id = ?", params![profile_id])?;
+        tx.commit()
+    }
+    
+    /// Delete an inactive profile. Its jobs are kept and no longer tied to a
+    /// profile, so any worker may run them.
+    pub fn delete_profile(&self, profile_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("UPDATE jobs SET profile_id = NULL WHERE profile_id = ?", params![profile_id])?;
+        tx.execute("DELETE FROM profiles WHERE id = ? AND active = 0", params![profile_id])?;
+        tx.commit()
+    }
+    
+    // ==================== Accounts CRUD ====================
+    
+    /// Add a connected account, or refresh the token of one already known
+    pub fn upsert_account(&self, channel_id: &str, channel_title: &str, refresh_token: &str) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "INSERT INTO accounts (channel_id, channel_title, refresh_token, created_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(channel_id) DO UPDATE SET channel_title = excluded.channel_title,
+                 refresh_token = excluded.refresh_token
+             RETURNING id",
+            params![channel_id, channel_title, refresh_token, Utc::now().to_rfc3339()],
+            |row| row.get(0),
+        )
+    }
+    
+    /// Record the channel of an account that didn't know it yet
+    pub fn set_account_channel(&self, account_id: i64, channel_id: &str, channel_title: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE accounts SET channel_id = ?, channel_title = ? WHERE id = ?",
+            params![channel_id, channel_title, account_id],
+        )?;
         Ok(())
     }
     
-    // ==================== Trends CRUD ====================
+    /// Update the settings of an account
+    pub fn update_account(&self, account_id: i64, niche: &str, playlist_id: Option<&str>, enabled: bool) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE accounts SET niche = ?, playlist_id = ?, enabled = ? WHERE id = ?",
+            params![
+                niche.trim().to_lowercase(),
+                playlist_id.map(str::trim).filter(|p| !p.is_empty()),
+                enabled,
+                account_id,
+            ],
+        )?;
+        Ok(())
+    }
     
-    /// Insert a new trend (ignores duplicates)
-    pub fn insert_trend(&self, trend: &Trend) -> Result<i64> {
-        self.conn.execute(
-            "INSERT OR IGNORE INTO trends (video_id, title, channel, views, category, fetched_at) 
-             VALUES (?, ?, ?, ?, ?, ?)",
+    /// Delete an account; its jobs are reassigned when they upload
+    pub fn delete_account(&self, account_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("UPDATE jobs SET target_account_id = NULL WHERE target_account_id = ?", params![account_id])?;
+        conn.execute("DELETE FROM accounts WHERE id = ?", params![account_id])?;
+        Ok(())
+    }
+    
+    /// List accounts, oldest first
+    pub fn list_accounts(&self) -> Result<Vec<Account>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM accounts ORDER BY id", ACCOUNT_COLUMNS))?;
+        let accounts = stmt.query_map([], account_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(accounts)
+    }
+    
+    pub fn get_account(&self, account_id: i64) -> Result<Option<Account>> {
+        let conn = self.conn()?;
+        conn
+            .query_row(
+                &format!("SELECT {} FROM accounts WHERE id = ?", ACCOUNT_COLUMNS),
+                params![account_id],
+                account_from_row,
+            )
+            .optional()
+    }
+    
+    /// Enabled account a new job should go to. Round robin takes the account
+    /// assigned least recently; niche assignment prefers accounts whose niche
+    /// is `category` and otherwise takes turns between accounts without one.
+    pub fn next_account(&self, category: Option<&str>, assignment: AccountAssignment) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        let niche = match assignment {
+            AccountAssignment::RoundRobin => None,
+            AccountAssignment::Niche => Some(category.map(|c| c.trim().to_lowercase()).unwrap_or_default()),
+        };
+        conn
+            .query_row(
+                "SELECT a.id FROM accounts a
+                 LEFT JOIN (SELECT target_account_id, MAX(id) AS last_job FROM jobs GROUP BY target_account_id) j
+                     ON j.target_account_id = a.id
+                 WHERE a.enabled = 1 AND (?1 IS NULL OR a.niche = ?1 OR a.niche = '')
+                 ORDER BY (a.niche = ?1) DESC, j.last_job IS NOT NULL, j.last_job, a.id
+                 LIMIT 1",
+                params![niche],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+    
+    /// Account a job uploads to, assigning one first if it has none
+    pub fn job_account(&self, job_id: i64) -> Result<Option<Account>> {
+        let Some((job, trend)) = self.get_job_with_trend(job_id)? else {
+            return Ok(None);
+        };
+        let account_id = match job.target_account_id {
+            Some(account_id) => Some(account_id),
+            None => {
+                let assignment = self.load_config()?.account_assignment;
+                let account_id = self.next_account(trend.category.as_deref(), assignment)?;
+                if account_id.is_some() {
+                    self.set_job_account(job_id, account_id)?;
+                }
+                account_id
+            }
+        };
+        match account_id {
+            Some(account_id) => self.get_account(account_id),
+            None => Ok(None),
+        }
+    }
+    
+    /// Whether any account can take uploads
+    pub fn has_enabled_account(&self) -> Result<bool> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT EXISTS(SELECT 1 FROM accounts WHERE enabled = 1)", [], |row| row.get(0))
+    }
+    
+    // ==================== Webhooks CRUD ====================
+    
+    /// Add a webhook
+    pub fn insert_webhook(&self, webhook: &Webhook) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO webhooks (url, events_json, secret, enabled, created_at) VALUES (?, ?, ?, ?, ?)",
             params![
-                trend.video_id,
-                trend.title,
-                trend.channel,
-                trend.views,
-                trend.category,
-                trend.fetched_at.to_rfc3339(),
+                webhook.url.trim(),
+                serde_json::to_string(&webhook.events).unwrap_or_else(|_| "[]".to_string()),
+                webhook.secret,
+                webhook.enabled,
+                Utc::now().to_rfc3339(),
             ],
         )?;
-        Ok(self.conn.last_insert_rowid())
+        Ok(conn.last_insert_rowid())
     }
     
-    /// Get trend by video_id
-    pub fn get_trend_by_video_id(&self, video_id: &str) -> Result<Option<Trend>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, video_id, title, channel, views, category, fetched_at FROM trends WHERE video_id = ?"
+    /// Delete a webhook
+    pub fn delete_webhook(&self, webhook_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM webhooks WHERE id = ?", params![webhook_id])?;
+        Ok(())
+    }
+    
+    /// List webhooks, oldest first
+    pub fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM webhooks ORDER BY id", WEBHOOK_COLUMNS))?;
+        let webhooks = stmt.query_map([], webhook_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(webhooks)
+    }
+    
+    pub fn get_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>> {
+        let conn = self.conn()?;
+        conn
+            .query_row(
+                &format!("SELECT {} FROM webhooks WHERE id = ?", WEBHOOK_COLUMNS),
+                params![webhook_id],
+                webhook_from_row,
+            )
+            .optional()
+    }
+    
+    /// Enabled webhooks subscribed to `event`
+    pub fn webhooks_for_event(&self, event: WebhookEvent) -> Result<Vec<Webhook>> {
+        Ok(self.list_webhooks()?
+            .into_iter()
+            .filter(|w| w.enabled && w.events.contains(&event))
+            .collect())
+    }
+    
+    // ==================== Metrics ====================
+    
+    /// Record how long a pipeline stage took for a job
+    pub fn record_metric(&self, job_id: i64, stage: &str, duration_ms: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO metrics (job_id, stage, duration_ms, recorded_at) VALUES (?, ?, ?, ?)",
+            params![job_id, stage, duration_ms, Utc::now().to_rfc3339()],
         )?;
-        let mut rows = stmt.query(params![video_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Trend {
-                id: Some(row.get(0)?),
-                video_id: row.get(1)?,
-                title: row.get(2)?,
-                channel: row.get(3)?,
-                views: row.get(4)?,
-                category: row.get(5)?,
-                fetched_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+        Ok(())
+    }
+    
+    /// p50/p95 durations per stage over `range`, slowest stage first
+    pub fn get_stage_metrics(&self, range: MetricsRange) -> Result<Vec<StageMetrics>> {
+        let conn = self.conn()?;
+        let since = range.since().map(|t| t.to_rfc3339()).unwrap_or_default();
+        let mut stmt = conn.prepare(
+            "SELECT stage, duration_ms FROM metrics
+             WHERE duration_ms IS NOT NULL AND stage IS NOT NULL
+               AND (?1 = '' OR datetime(recorded_at) >= datetime(?1))
+             ORDER BY stage, duration_ms"
+        )?;
+        let rows = stmt.query_map(params![since], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        
+        let mut by_stage: Vec<(String, Vec<i64>)> = Vec::new();
+        for (stage, duration) in rows {
+            match by_stage.last_mut() {
+                Some((current, durations)) if *current == stage => durations.push(duration),
+                _ => by_stage.push((stage, vec![duration])),
+            }
+        }
+        let mut metrics: Vec<StageMetrics> = by_stage.into_iter()
+            .map(|(stage, durations)| StageMetrics {
+                stage,
+                count: durations.len(),
+                p50_ms: percentile(&durations, 50.0),
+                p95_ms: percentile(&durations, 95.0),
+                max_ms: durations.last().copied().unwrap_or(0),
+            })
+            .collect();
+        metrics.sort_by_key(|m| std::cmp::Reverse(m.p50_ms));
+        Ok(metrics)
+    }
+    
+    /// `metric` per `bucket` over `range`, oldest first. Buckets without data
+    /// are left out.
+    pub fn get_metrics_series(&self, metric: SeriesMetric, bucket: SeriesBucket, range: MetricsRange) -> Result<Vec<SeriesPoint>> {
+        let conn = self.conn()?;
+        let since = range.since().map(|t| t.to_rfc3339()).unwrap_or_default();
+        let mut stmt = conn.prepare(metric.sql())?;
+        let points = stmt.query_map(params![since, bucket.format()], |row| {
+            Ok(SeriesPoint { bucket: row.get(0)?, value: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(points)
+    }
+    
+    /// Every duration and measurement recorded for a job, oldest first
+    pub fn get_job_metrics(&self, job_id: i64) -> Result<Vec<JobMetric>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT stage, duration_ms, metric, value, recorded_at FROM metrics WHERE job_id = ? ORDER BY id"
+        )?;
+        let metrics = stmt.query_map(params![job_id], |row| {
+            Ok(JobMetric {
+                stage: row.get(0)?,
+                duration_ms: row.get(1)?,
+                metric: row.get(2)?,
+                value: row.get(3)?,
+                recorded_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
-            }))
-        } else {
-            Ok(None)
-        }
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(metrics)
     }
     
-    // ==================== Jobs CRUD ====================
+    /// Record a named measurement for a pipeline stage (e.g. loudness in LUFS)
+    pub fn record_metric_value(&self, job_id: i64, stage: &str, metric: &str, value: f64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO metrics (job_id, stage, metric, value, recorded_at) VALUES (?, ?, ?, ?, ?)",
+            params![job_id, stage, metric, value, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
     
-    /// Create a new job for a trend
-    pub fn create_job(&self, trend_id: i64, priority: i32) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO jobs (trend_id, status, priority) VALUES (?, 'pending', ?)",
-            params![trend_id, priority],
+    // ==================== Short Stats ====================
+    
+    /// Shorts published to `platform` since `since`, as (short id, remote id)
+    pub fn list_published(&self, platform: &str, since: DateTime<Utc>) -> Result<Vec<(i64, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT p.short_id, p.remote_id FROM publications p JOIN shorts s ON s.id = p.short_id
+             WHERE p.platform = ? AND p.status = 'sent' AND p.remote_id IS NOT NULL AND p.published_at >= ?
+               AND s.deleted_at IS NULL
+             ORDER BY p.short_id"
         )?;
-        Ok(self.conn.last_insert_rowid())
+        let videos = stmt.query_map(params![platform, since.to_rfc3339()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(videos)
     }
     
-    /// Get next pending job
-    pub fn get_next_pending_job(&self) -> Result<Option<(Job, Trend)>> {
-        let mut stmt = self.conn.prepare(
-            r#"
-            SELECT j.id, j.trend_id, j.status, j.priority, j.retry_count, j.error_msg, 
-                   j.created_at, j.started_at, j.finished_at,
-                   t.id, t.video_id, t.title, t.channel, t.views, t.category, t.fetched_at
-            FROM jobs j
-            JOIN trends t ON j.trend_id = t.id
-            WHERE j.status = 'pending'
-            ORDER BY j.priority DESC, j.created_at ASC
-            LIMIT 1
-            "#
-        )?;
-        let mut rows = stmt.query([])?;
-        if let Some(row) = rows.next()? {
-            let job = Job {
-                id: Some(row.get(0)?),
-                trend_id: row.get(1)?,
-                status: JobStatus::from_str(&row.get::<_, String>(2)?),
-                priority: row.get(3)?,
-                retry_count: row.get(4)?,
-                error_msg: row.get(5)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                started_at: row.get::<_, Option<String>>(7)?
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&Utc)),
-                finished_at: row.get::<_, Option<String>>(8)?
+    /// Store a statistics sample for a short
+    pub fn insert_short_stats(&self, short_id: i64, stats: &VideoStatistics) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO short_stats (short_id, views, likes, comments, fetched_at) VALUES (?, ?, ?, ?, ?)",
+            params![short_id, stats.views, stats.likes, stats.comments, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// A short's statistics samples, oldest first
+    pub fn get_short_stats(&self, short_id: i64) -> Result<Vec<ShortStats>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT views, likes, comments, fetched_at FROM short_stats WHERE short_id = ? ORDER BY fetched_at"
+        )?;
+        let stats = stmt.query_map(params![short_id], |row| {
+            Ok(ShortStats {
+                views: row.get(0)?,
+                likes: row.get(1)?,
+                comments: row.get(2)?,
+                fetched_at: row.get::<_, String>(3)
+                    .ok()
                     .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&Utc)),
-            };
-            let trend = Trend {
-                id: Some(row.get(9)?),
-                video_id: row.get(10)?,
-                title: row.get(11)?,
-                channel: row.get(12)?,
-                views: row.get(13)?,
-                category: row.get(14)?,
-                fetched_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(15)?)
                     .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-            };
-            Ok(Some((job, trend)))
-        } else {
-            Ok(None)
-        }
+                    .unwrap_or_else(Utc::now),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(stats)
     }
     
-    /// Update job status
-    pub fn update_job_status(&self, job_id: i64, status: JobStatus, error_msg: Option<&str>) -> Result<()> {
-        let now = Utc::now().to_rfc3339();
-        match status {
-            JobStatus::Generating | JobStatus::Rendering => {
-                self.conn.execute(
-                    "UPDATE jobs SET status = ?, started_at = ? WHERE id = ?",
-                    params![status.as_str(), now, job_id],
-                )?;
-            }
-            JobStatus::Done | JobStatus::Failed => {
-                self.conn.execute(
-                    "UPDATE jobs SET status = ?, finished_at = ?, error_msg = ? WHERE id = ?",
-                    params![status.as_str(), now, error_msg, job_id],
-                )?;
+    /// Latest view count of every short in a variant group, by group
+    pub fn list_variant_views(&self) -> Result<Vec<VariantViews>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT s.variant_group_id, s.hook_style, st.views
+             FROM shorts s
+             LEFT JOIN (SELECT short_id, MAX(id) AS id FROM short_stats GROUP BY short_id) latest ON latest.short_id = s.id
+             LEFT JOIN short_stats st ON st.id = latest.id
+             WHERE s.variant_group_id IS NOT NULL AND s.deleted_at IS NULL
+             ORDER BY s.variant_group_id, s.id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(VariantViews {
+                group_id: row.get(0)?,
+                hook_style: row.get::<_, Option<String>>(1)?.map(|s| HookStyle::from_str(&s)),
+                views: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+    
+    /// Trend categories ranked by the average latest view count of their
+    /// shorts, best first
+    pub fn get_topic_performance(&self, limit: u32) -> Result<Vec<TopicPerformance>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(NULLIF(t.category, ''), 'uncategorized'), COUNT(*), SUM(st.views), AVG(st.views), AVG(st.likes)
+             FROM short_stats st
+             JOIN (SELECT short_id, MAX(id) AS id FROM short_stats GROUP BY short_id) latest ON st.id = latest.id
+             JOIN shorts s ON s.id = st.short_id
+             JOIN jobs j ON s.job_id = j.id
+             JOIN trends t ON j.trend_id = t.id
+             GROUP BY 1 ORDER BY AVG(st.views) DESC LIMIT ?"
+        )?;
+        let topics = stmt.query_map(params![limit], |row| {
+            Ok(TopicPerformance {
+                topic: row.get(0)?,
+                shorts: row.get(1)?,
+                total_views: row.get(2)?,
+                avg_views: row.get(3)?,
+                avg_likes: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        Ok(topics)
+    }
+    
+    // ==================== Costs ====================
+    
+    /// Record the estimated cost of paid API usage by a job
+    pub fn record_job_cost(&self, job_id: i64, kind: CostKind, quantity: f64, cost: f64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO job_costs (job_id, kind, quantity, cost, recorded_at) VALUES (?, ?, ?, ?, ?)",
+            params![job_id, kind.as_str(), quantity, cost, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Estimated spend in each of the last `months` calendar months (UTC),
+    /// the current one included, newest first. Months without spend are left out.
+    pub fn get_cost_report(&self, months: u32) -> Result<Vec<MonthlyCost>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT strftime('%Y-%m', recorded_at) AS month, kind, SUM(quantity), SUM(cost), COUNT(DISTINCT job_id)
+             FROM job_costs
+             WHERE recorded_at >= date('now', 'start of month', ?)
+             GROUP BY month, kind
+             ORDER BY month DESC, kind"
+        )?;
+        let rows = stmt.query_map(params![format!("-{} months", months.saturating_sub(1))], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, f64>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?;
+        let mut report: Vec<MonthlyCost> = Vec::new();
+        for row in rows {
+            let (month, kind, quantity, cost, jobs) = row?;
+            // Kinds a newer build recorded
+            let Some(kind) = CostKind::from_str(&kind) else { continue };
+            if report.last().map(|last| &last.month) != Some(&month) {
+                report.push(MonthlyCost { month, total: 0.0, items: Vec::new() });
             }
-            _ => {
-                self.conn.execute(
-                    "UPDATE jobs SET status = ? WHERE id = ?",
-                    params![status.as_str(), job_id],
-                )?;
+            if let Some(entry) = report.last_mut() {
+                entry.total += cost;
+                entry.items.push(CostItem { kind, quantity, cost, jobs });
             }
         }
-        Ok(())
+        Ok(report)
     }
     
     // ==================== Stats ====================
     
     /// Get dashboard statistics
     pub fn get_stats(&self) -> Result<DashboardStats> {
-        let trends_count: i64 = self.conn.query_row(
+        let conn = self.conn()?;
+        let trends_count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM trends", [], |row| row.get(0)
         )?;
-        let pending_count: i64 = self.conn.query_row(
+        let pending_count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM jobs WHERE status = 'pending'", [], |row| row.get(0)
         )?;
-        let done_count: i64 = self.conn.query_row(
+        let done_count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM jobs WHERE status = 'done'", [], |row| row.get(0)
         )?;
-        let failed_count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM jobs WHERE status = 'failed'", [], |row| row.get(0)
+        let failed_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM jobs WHERE status IN ('failed', 'dead')", [], |row| row.get(0)
+        )?;
+        let mut stmt = conn.prepare(
+            "SELECT platform, SUM(status = 'sent'), SUM(status = 'failed') FROM publications
+             GROUP BY platform ORDER BY platform"
+        )?;
+        let publications = stmt.query_map([], |row| {
+            Ok(PlatformPublications { platform: row.get(0)?, sent: row.get(1)?, failed: row.get(2)? })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        
+        // created_at is SQLite's own timestamp format and finished_at RFC 3339;
+        // julianday() and date() read both
+        let since = (Utc::now() - Duration::days(STATS_WINDOW_DAYS)).to_rfc3339();
+        let mut stmt = conn.prepare(
+            "SELECT date(j.finished_at), COUNT(s.id) FROM jobs j
+             JOIN shorts s ON s.job_id = j.id AND s.deleted_at IS NULL
+             WHERE j.status = 'done' AND j.finished_at >= ?1 AND j.deleted_at IS NULL
+             GROUP BY date(j.finished_at) ORDER BY 1"
+        )?;
+        let shorts_per_day = stmt.query_map(params![since], |row| {
+            Ok(DailyCount { date: row.get(0)?, count: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        
+        let (avg_pipeline_secs, success_rate): (Option<f64>, Option<f64>) = conn.query_row(
+            "SELECT AVG(CASE WHEN status = 'done' THEN (julianday(finished_at) - julianday(created_at)) * 86400 END),
+                    AVG(status = 'done')
+             FROM jobs
+             WHERE status IN ('done', 'failed', 'dead') AND finished_at >= ?1 AND deleted_at IS NULL",
+            params![since],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        // Jobs created but not finished at each hour mark
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE marks(at) AS (
+                 SELECT julianday('now') - ?1 / 24.0
+                 UNION ALL SELECT at + 1 / 24.0 FROM marks WHERE at + 1 / 24.0 <= julianday('now') + 1e-9
+             )
+             SELECT strftime('%Y-%m-%dT%H:%M:%SZ', m.at),
+                    (SELECT COUNT(*) FROM jobs j
+                     WHERE julianday(j.created_at) <= m.at
+                       AND (j.finished_at IS NULL OR julianday(j.finished_at) > m.at)
+                       AND j.deleted_at IS NULL)
+             FROM marks m ORDER BY m.at"
+        )?;
+        let queue_depth = stmt.query_map(params![QUEUE_DEPTH_HOURS], |row| {
+            Ok(QueueDepth { at: row.get(0)?, depth: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+        
+        let published_secs: f64 = conn.query_row(
+            "SELECT COALESCE(SUM(duration_sec), 0) FROM shorts s
+             WHERE s.deleted_at IS NULL
+               AND EXISTS (SELECT 1 FROM publications p WHERE p.short_id = s.id AND p.status = 'sent')",
+            [],
+            |row| row.get(0),
         )?;
         
         Ok(DashboardStats {
@@ -378,10 +5626,22 @@ impl Database {
             pending_jobs: pending_count,
             completed_jobs: done_count,
             failed_jobs: failed_count,
+            publications,
+            shorts_per_day,
+            avg_pipeline_secs,
+            success_rate,
+            queue_depth,
+            published_minutes: published_secs / 60.0,
         })
     }
 }
 
+/// Days covered by the per-day and rate figures of `get_stats`
+const STATS_WINDOW_DAYS: i64 = 30;
+
+/// Hours of queue depth history in `get_stats`, one point per hour
+const QUEUE_DEPTH_HOURS: i64 = 24;
+
 /// Dashboard statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DashboardStats {
@@ -389,4 +5649,394 @@ pub struct DashboardStats {
     pub pending_jobs: i64,
     pub completed_jobs: i64,
     pub failed_jobs: i64,
+    /// Publication outcomes per platform (youtube, telegram, ...)
+    pub publications: Vec<PlatformPublications>,
+    /// Shorts of jobs finished on each of the last 30 days; days without any are left out
+    pub shorts_per_day: Vec<DailyCount>,
+    /// Mean time from job creation to done over the last 30 days
+    pub avg_pipeline_secs: Option<f64>,
+    /// Share of jobs finished in the last 30 days that finished done, 0 to 1
+    pub success_rate: Option<f64>,
+    /// Unfinished jobs at each hour of the last day, oldest first
+    pub queue_depth: Vec<QueueDepth>,
+    /// Length of every short uploaded to YouTube or delivered to a chat
+    pub published_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCount {
+    /// YYYY-MM-DD, UTC
+    pub date: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueDepth {
+    /// RFC 3339, UTC
+    pub at: String,
+    pub depth: i64,
+}
+
+/// Shorts published and failing on one platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformPublications {
+    pub platform: String,
+    pub sent: i64,
+    pub failed: i64,
+}
+
+/// Estimated spend in one calendar month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyCost {
+    /// YYYY-MM, UTC
+    pub month: String,
+    pub total: f64,
+    pub items: Vec<CostItem>,
+}
+
+/// Usage and estimated cost of one kind of paid API in a month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostItem {
+    pub kind: CostKind,
+    pub quantity: f64,
+    pub cost: f64,
+    /// Jobs that used it
+    pub jobs: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trends;
+    
+    /// A freshly fetched YouTube trend, titled after its id
+    fn trend(video_id: &str) -> Trend {
+        Trend {
+            id: None,
+            video_id: video_id.into(),
+            title: video_id.into(),
+            channel: None,
+            views: None,
+            category: None,
+            fetched_at: Utc::now(),
+            transcript: None,
+            tags: Vec::new(),
+            source: TrendSource::YouTube,
+            notes: None,
+            starred: false,
+            state: TrendState::New,
+            skip_reason: None,
+            dry_run: false,
+        }
+    }
+    
+    #[test]
+    fn in_memory_databases_are_separate() {
+        let a = Database::new_in_memory().unwrap();
+        let b = Database::new_in_memory().unwrap();
+        a.set_config("poll_interval_secs", "120").unwrap();
+        assert_eq!(a.get_config("poll_interval_secs").unwrap().as_deref(), Some("120"));
+        assert_eq!(b.get_config("poll_interval_secs").unwrap(), None);
+    }
+    
+    #[test]
+    fn in_memory_database_is_shared_by_its_connections() {
+        let db = Database::new_in_memory().unwrap();
+        // Hold one connection so the next call has to open another
+        let held = db.conn().unwrap();
+        db.set_config("poll_interval_secs", "120").unwrap();
+        let value: String = held.query_row("SELECT value FROM config WHERE key = 'poll_interval_secs'", [], |row| row.get(0)).unwrap();
+        assert_eq!(value, "120");
+    }
+    
+    #[test]
+    fn hooks_leave_out_sound_effect_markers() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = db.upsert_trend(&trend("abc")).unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let short_id = db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap()[0];
+        db.set_short_script(short_id, "[sfx:whoosh] Cats can't [SFX:pop] taste sugar! Here's why.").unwrap();
+        let (short, _) = db.get_short_with_trend(short_id).unwrap().unwrap();
+        assert_eq!(short.hook().as_deref(), Some("Cats can't taste sugar!"));
+    }
+    
+    #[test]
+    fn regenerating_a_short_of_a_running_job_changes_nothing() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = db.upsert_trend(&trend("abc")).unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let short_id = db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap()[0];
+        db.set_short_script(short_id, "A script.").unwrap();
+        db.update_job_status(job_id, JobStatus::Rendering, None).unwrap();
+        
+        assert!(!db.regenerate_short(short_id, JobStatus::Pending).unwrap());
+        assert_eq!(db.get_short_with_trend(short_id).unwrap().unwrap().0.script.as_deref(), Some("A script."));
+        
+        db.update_job_status(job_id, JobStatus::Failed, Some("boom")).unwrap();
+        assert!(db.regenerate_short(short_id, JobStatus::Pending).unwrap());
+        assert_eq!(db.get_short_with_trend(short_id).unwrap().unwrap().0.script, None);
+        assert_eq!(db.job_status(job_id).unwrap(), Some((JobStatus::Pending, false)));
+    }
+    
+    #[test]
+    fn metric_ranges_compare_timestamps_of_either_format() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = db.upsert_trend(&trend("abc")).unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        db.record_metric(job_id, "render", 1000).unwrap();
+        // Written by SQLite's CURRENT_TIMESTAMP, which sorts below RFC 3339 on the same day
+        let conn = db.conn().unwrap();
+        let earlier = (Utc::now() - Duration::hours(2)).format("%Y-%m-%d %H:%M:%S").to_string();
+        let older = (Utc::now() - Duration::days(3)).format("%Y-%m-%d %H:%M:%S").to_string();
+        for (duration, at) in [(2000, &earlier), (3000, &older)] {
+            conn.execute(
+                "INSERT INTO metrics (job_id, stage, duration_ms, recorded_at) VALUES (?, 'render', ?, ?)",
+                params![job_id, duration, at],
+            )
+            .unwrap();
+        }
+        drop(conn);
+        
+        let day = db.get_stage_metrics(MetricsRange::Day).unwrap();
+        assert_eq!((day[0].count, day[0].max_ms), (2, 2000));
+        assert_eq!(db.get_stage_metrics(MetricsRange::All).unwrap()[0].count, 3);
+        let series = db.get_metrics_series(SeriesMetric::RenderTime, SeriesBucket::Day, MetricsRange::Week).unwrap();
+        assert!(series.iter().any(|p| p.bucket == older[..10] && p.value == 3000.0));
+    }
+    
+    #[test]
+    fn pruning_jobs_reports_their_stored_objects() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = db.upsert_trend(&trend("abc")).unwrap();
+        let mut short_ids = Vec::new();
+        for _ in 0..2 {
+            let job_id = db.create_job(trend_id, 0).unwrap();
+            short_ids.extend(db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap());
+            db.update_job_status(job_id, JobStatus::Done, None).unwrap();
+        }
+        db.set_short_video(short_ids[0], "https://s3.example.com/bucket/renders/short_1.mp4", 30.0).unwrap();
+        db.set_short_video(short_ids[1], "/data/renders/short_2.mp4", 30.0).unwrap();
+        
+        let (deleted, objects) = db.prune_finished_jobs(Utc::now() + Duration::minutes(1)).unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(objects, vec!["https://s3.example.com/bucket/renders/short_1.mp4".to_string()]);
+    }
+    
+    #[test]
+    fn jobs_for_fixture_trends_stay_dry_runs() {
+        let db = Database::new_in_memory().unwrap();
+        let fixture = db.upsert_trend(&Trend { dry_run: true, ..trend("fixture1") }).unwrap();
+        let real = db.upsert_trend(&trend("real1")).unwrap();
+        assert!(db.get_trend(fixture).unwrap().unwrap().dry_run);
+        
+        let job = db.create_job(fixture, 0).unwrap();
+        assert!(db.job_is_dry_run(job).unwrap());
+        let job = db.create_job(real, 0).unwrap();
+        assert!(!db.job_is_dry_run(job).unwrap());
+    }
+    
+    #[test]
+    fn older_delivery_records_become_publications() {
+        let db = Database::new_in_memory().unwrap();
+        let conn = db.conn().unwrap();
+        conn.execute_batch(
+            "ALTER TABLE shorts ADD COLUMN telegram_sent BOOLEAN DEFAULT 0;
+             CREATE TABLE deliveries (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, short_id INTEGER NOT NULL, channel TEXT NOT NULL,
+                 destination TEXT NOT NULL, status TEXT NOT NULL, message_id TEXT, error TEXT,
+                 attempts INTEGER NOT NULL DEFAULT 1, updated_at TIMESTAMP NOT NULL, url TEXT,
+                 UNIQUE(short_id, channel, destination));
+             INSERT INTO shorts (id, language, telegram_sent, telegram_message_id) VALUES (1, 'en', 1, 77);
+             INSERT INTO shorts (id, language, youtube_video_id, uploaded_at) VALUES (2, 'en', 'abc123', '2026-01-02T00:00:00+00:00');
+             INSERT INTO deliveries (short_id, channel, destination, status, message_id, updated_at)
+                 VALUES (2, 'discord', 'hooks', 'sent', '9', '2026-01-03T00:00:00+00:00');",
+        )
+        .unwrap();
+        drop(conn);
+        db.migrate_publications().unwrap();
+        
+        assert!(!db.table_exists("deliveries").unwrap());
+        assert!(!db.column_exists("shorts", "telegram_sent").unwrap());
+        let telegram = db.list_publications(1).unwrap();
+        assert_eq!((telegram[0].platform.as_str(), telegram[0].remote_id.as_deref()), ("telegram", Some("77")));
+        let platforms: Vec<_> = db.list_publications(2).unwrap().into_iter().map(|p| p.platform).collect();
+        assert_eq!(platforms, ["discord", "youtube"]);
+        let since = DateTime::parse_from_rfc3339("2026-01-01T00:00:00+00:00").unwrap().with_timezone(&Utc);
+        assert_eq!(db.list_published("youtube", since).unwrap(), [(2, "abc123".to_string())]);
+    }
+    
+    #[test]
+    fn refetching_a_trend_keeps_its_star_and_notes() {
+        let db = Database::new_in_memory().unwrap();
+        let mut trend = Trend { views: Some(1_000), ..trend("abc123") };
+        let trend_id = db.upsert_trend(&trend).unwrap();
+        assert_eq!(db.toggle_trend_star(trend_id).unwrap(), Some(true));
+        assert!(db.set_trend_note(trend_id, Some("film this week")).unwrap());
+        trend.views = Some(2_000);
+        db.upsert_trend(&trend).unwrap();
+        
+        let starred = db.list_trends(true, None, 10).unwrap();
+        assert_eq!(starred.len(), 1);
+        assert!(starred[0].starred);
+        assert_eq!(starred[0].notes.as_deref(), Some("film this week"));
+        assert_eq!(db.toggle_trend_star(trend_id).unwrap(), Some(false));
+        assert!(db.list_trends(true, None, 10).unwrap().is_empty());
+        assert_eq!(db.toggle_trend_star(trend_id + 1).unwrap(), None);
+    }
+    
+    #[test]
+    fn batch_insert_returns_the_stored_ids() {
+        let db = Database::new_in_memory().unwrap();
+        let viewed = |video_id: &str, views| Trend { views: Some(views), ..trend(video_id) };
+        let existing = db.upsert_trend(&viewed("b", 10)).unwrap();
+        let ids = db.insert_trends_batch(&[viewed("a", 1), viewed("b", 20), viewed("a", 2)]).unwrap();
+        assert_eq!(ids[1], existing);
+        assert_eq!(ids[0], ids[2]);
+        assert_ne!(ids[0], existing);
+        assert_eq!(db.get_trend(existing).unwrap().unwrap().views, Some(20));
+        assert_eq!(db.get_trend_history(ids[0]).unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn job_details_hold_the_latest_log_lines() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "home espresso").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap();
+        for i in 0..DETAIL_LOG_LINES + 5 {
+            db.insert_job_log(job_id, LogLevel::Info, Some("generate"), &format!("line {}", i)).unwrap();
+        }
+        db.record_job_error(job_id, 1, "generate", "timed out").unwrap();
+        db.record_metric(job_id, "generate", 1_200).unwrap();
+        
+        let details = db.get_job_details(job_id).unwrap().unwrap();
+        assert_eq!(details.trend.id, Some(trend_id));
+        assert_eq!(details.shorts.len(), 1);
+        assert_eq!(details.errors[0].message, "timed out");
+        assert_eq!(details.metrics[0].duration_ms, Some(1_200));
+        assert_eq!(details.logs.total, i64::from(DETAIL_LOG_LINES + 5));
+        assert_eq!(details.logs.entries.len(), DETAIL_LOG_LINES as usize);
+        assert_eq!(details.logs.entries.last().unwrap().message, format!("line {}", DETAIL_LOG_LINES + 4));
+        assert!(db.get_job_details(job_id + 1).unwrap().is_none());
+    }
+    
+    #[test]
+    fn rerunning_a_step_keeps_the_work_before_it() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "home espresso").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        let short_id = db.create_shorts_for_languages(job_id, &["en".to_string()], 1).unwrap()[0];
+        db.set_short_script(short_id, "Script").unwrap();
+        db.set_short_audio(short_id, "short.mp3").unwrap();
+        db.set_job_checkpoint(job_id, PipelineStep::Metadata).unwrap();
+        
+        // Not rendered yet
+        assert!(!db.rerun_job_step(job_id, PipelineStep::Video, JobStatus::Generated).unwrap());
+        db.set_short_video(short_id, "short.mp4", 42.0).unwrap();
+        db.set_job_checkpoint(job_id, PipelineStep::Video).unwrap();
+        db.update_job_status(job_id, JobStatus::Done, None).unwrap();
+        db.set_config("approval_required", "true").unwrap();
+        db.set_short_approval(short_id, ApprovalStatus::Approved, Some("looks good")).unwrap();
+        
+        assert!(db.rerun_job_step(job_id, PipelineStep::Narration, JobStatus::Pending).unwrap());
+        let (job, _) = db.get_job_with_trend(job_id).unwrap().unwrap();
+        assert_eq!((job.status, job.checkpoint), (JobStatus::Pending, Some(PipelineStep::Script)));
+        let short = &db.get_shorts_for_job(job_id).unwrap()[0];
+        assert_eq!(short.script.as_deref(), Some("Script"));
+        assert!(short.audio_path.is_none() && short.video_path.is_none());
+        // The new narration hasn't been reviewed
+        assert_eq!(short.approval, Some(ApprovalStatus::AwaitingApproval));
+        assert!(short.review_note.is_none() && !short.is_deliverable());
+    }
+    
+    #[test]
+    fn dependent_jobs_wait_for_their_dependency() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "home espresso").unwrap();
+        let first = db.create_job(trend_id, 0).unwrap();
+        let second = db.create_job(trend_id, 0).unwrap();
+        let compilation = db.create_dependent_job(trend_id, &[first, second], 10).unwrap();
+        let claim = || db.claim_next_job(JobStatus::Pending, JobStatus::Generating, "worker", Duration::minutes(5), false)
+            .unwrap()
+            .and_then(|(job, _)| job.id);
+        
+        // The higher-priority job has to wait for both
+        assert_eq!(claim(), Some(first));
+        assert_eq!(claim(), Some(second));
+        assert_eq!(claim(), None);
+        db.update_job_status(first, JobStatus::Done, None).unwrap();
+        assert_eq!(claim(), None);
+        db.update_job_status(second, JobStatus::Done, None).unwrap();
+        assert_eq!(claim(), Some(compilation));
+        assert_eq!(db.get_job_details(compilation).unwrap().unwrap().depends_on, [first, second]);
+    }
+    
+    #[test]
+    fn a_legacy_account_leaves_its_unknown_channel_null() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_config("youtube_refresh_token", "token").unwrap();
+        db.import_legacy_account().unwrap();
+        let account = db.list_accounts().unwrap().remove(0);
+        assert_eq!(account.channel_id, None);
+        
+        db.set_account_channel(account.id, "UC123", "Cooking").unwrap();
+        let account = db.get_account(account.id).unwrap().unwrap();
+        assert_eq!(account.channel_id.as_deref(), Some("UC123"));
+        assert_eq!(account.channel_title, "Cooking");
+    }
+    
+    #[test]
+    fn only_the_lease_holder_releases_a_job() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "home espresso").unwrap();
+        let job_id = db.create_job(trend_id, 0).unwrap();
+        db.claim_next_job(JobStatus::Pending, JobStatus::Generating, "second", Duration::minutes(5), false).unwrap();
+        
+        db.release_job(job_id, "first", JobStatus::Pending).unwrap();
+        assert_eq!(db.job_status(job_id).unwrap(), Some((JobStatus::Generating, false)));
+        db.release_job(job_id, "second", JobStatus::Pending).unwrap();
+        assert_eq!(db.job_status(job_id).unwrap(), Some((JobStatus::Pending, false)));
+    }
+    
+    #[test]
+    fn jobs_waiting_on_a_lost_dependency_are_dead() {
+        let db = Database::new_in_memory().unwrap();
+        let trend_id = trends::add_topic(&db, "home espresso").unwrap();
+        let trashed = db.create_job(trend_id, 0).unwrap();
+        let dying = db.create_job(trend_id, 0).unwrap();
+        let after_trashed = db.create_dependent_job(trend_id, &[trashed], 0).unwrap();
+        let after_dying = db.create_dependent_job(trend_id, &[dying], 0).unwrap();
+        let chained = db.create_dependent_job(trend_id, &[after_dying], 0).unwrap();
+        
+        assert!(db.trash_job(trashed).unwrap());
+        db.update_job_status(dying, JobStatus::Dead, Some("out of retries")).unwrap();
+        for job_id in [after_trashed, after_dying, chained] {
+            assert_eq!(db.job_status(job_id).unwrap(), Some((JobStatus::Dead, false)));
+        }
+        db.empty_trash().unwrap();
+        assert_eq!(db.job_status(trashed).unwrap(), None);
+    }
+    
+    #[test]
+    fn trends_move_through_their_states() {
+        let db = Database::new_in_memory().unwrap();
+        let fetched_at = Utc::now() - Duration::days(10);
+        let stale = db.upsert_trend(&Trend { fetched_at, ..trend("stale") }).unwrap();
+        let made = db.upsert_trend(&Trend { fetched_at, ..trend("made") }).unwrap();
+        let skipped = db.upsert_trend(&Trend { fetched_at, ..trend("skipped") }).unwrap();
+        let state = |id| db.get_trend(id).unwrap().unwrap().state;
+        
+        let job_id = db.create_job(made, 0).unwrap();
+        assert_eq!(state(made), TrendState::Queued);
+        db.update_job_status(job_id, JobStatus::Done, None).unwrap();
+        assert_eq!(state(made), TrendState::Processed);
+        assert!(db.skip_trend(skipped, Some("off-topic")).unwrap());
+        assert_eq!(db.get_trend(skipped).unwrap().unwrap().skip_reason.as_deref(), Some("off-topic"));
+        
+        assert_eq!(db.archive_stale_trends(Utc::now() - Duration::days(7)).unwrap(), 1);
+        assert_eq!(state(stale), TrendState::Archived);
+        // Trending again brings it back
+        db.upsert_trend(&trend("stale")).unwrap();
+        assert_eq!(state(stale), TrendState::New);
+    }
 }
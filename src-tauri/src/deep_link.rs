@@ -0,0 +1,31 @@
+// shotauto:// links, opened from a browser, a bookmarklet or an extension
+use reqwest::Url;
+
+pub const SCHEME: &str = "shotauto";
+
+/// What a link asks the app to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    /// Add a video as a trend: `shotauto://add?video=<id>` or
+    /// `shotauto://add?url=<YouTube link>`
+    AddTrend(String),
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.starts_with(&format!("{}://", SCHEME))
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, String> {
+    let url = Url::parse(link).map_err(|e| format!("Invalid link {}: {}", link, e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {}:// link: {}", SCHEME, link));
+    }
+    let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+    match url.host_str() {
+        Some("add") => param("video")
+            .or_else(|| param("url"))
+            .map(DeepLink::AddTrend)
+            .ok_or_else(|| format!("Link has no video or url: {}", link)),
+        _ => Err(format!("Unknown link: {}", link)),
+    }
+}
@@ -0,0 +1,185 @@
+// Demo data for showing the dashboard without API keys or a real run
+use crate::costs::{CostKind, UnitPrices};
+use crate::db::{ApprovalStatus, Database, JobStatus, PipelineStep, Trend, TrendSource, TrendState, VideoStatistics};
+use crate::dry_run;
+use chrono::{Duration, Utc};
+use rusqlite::Result;
+use serde::Serialize;
+
+/// Videos the demo trends stand for: title, channel, category id, views
+const TRENDS: &[(&str, &str, &str, i64)] = &[
+    ("I Tried Living Without a Phone for 30 Days", "Everyday Experiments", "22", 2_481_903),
+    ("Why Octopuses Are Smarter Than You Think", "Deep Sea Notes", "28", 1_320_455),
+    ("The 5-Minute Pasta Trick Restaurants Don't Tell You", "Kitchen Shortcuts", "26", 987_112),
+    ("Building a Tiny House in 48 Hours", "Weekend Builds", "26", 3_102_778),
+    ("What Happens If You Skip Breakfast for a Year", "Body Explained", "27", 1_845_020),
+    ("The Fastest Way to Learn Any Language", "Polyglot Lab", "27", 764_391),
+    ("Retro Console Restoration: Yellowed to New", "Pixel Revival", "20", 532_884),
+    ("This Bridge Was Built Without a Single Nail", "Engineering Oddities", "28", 2_209_617),
+];
+
+const SCRIPT: &str = "You won't believe what happened next. In just sixty seconds, here is \
+    everything you need to know, and the one detail everybody misses. Follow for part two.";
+
+/// Finished jobs added on top of one in every status, one per past day
+const HISTORY_DAYS: usize = 14;
+
+/// What `seed` added
+#[derive(Debug, Clone, Serialize)]
+pub struct Seeded {
+    pub trends: usize,
+    pub jobs: usize,
+    pub shorts: usize,
+}
+
+/// Add demo trends, a job in every status, a finished job for each of the
+/// past `HISTORY_DAYS` days, and their shorts, stage timings, costs,
+/// deliveries and view counts. The jobs are dry runs the pipeline never
+/// claims, and uploaded shorts get dry-run video ids, so the statistics
+/// poller leaves them alone.
+pub fn seed(db: &Database) -> Result<Seeded> {
+    let now = Utc::now();
+    let mut trend_ids = Vec::with_capacity(TRENDS.len());
+    for (i, (title, channel, category, views)) in TRENDS.iter().enumerate() {
+        let trend = Trend {
+            id: None,
+            video_id: format!("demo{:07}", i + 1),
+            title: title.to_string(),
+            channel: Some(channel.to_string()),
+            views: Some(*views),
+            category: Some(category.to_string()),
+            fetched_at: now - Duration::hours(i as i64),
+            transcript: None,
+            tags: Vec::new(),
+            source: TrendSource::Demo,
+            notes: None,
+            starred: false,
+            state: TrendState::New,
+            skip_reason: None,
+            dry_run: true,
+        };
+        trend_ids.push(db.upsert_trend(&trend)?);
+    }
+    
+    let statuses = JobStatus::ALL.into_iter()
+        .chain(std::iter::repeat_n(JobStatus::Done, HISTORY_DAYS));
+    let mut seeded = Seeded { trends: trend_ids.len(), jobs: 0, shorts: 0 };
+    for (i, status) in statuses.enumerate() {
+        let job_id = db.create_demo_job(trend_ids[i % trend_ids.len()])?;
+        let short_ids = db.create_shorts_for_languages(job_id, &["en".to_string()], 1)?;
+        for &short_id in &short_ids {
+            fill(db, job_id, short_id, status.clone(), i)?;
+        }
+        // The first of each status is recent; the history goes back a day per job
+        let age = match i.checked_sub(JobStatus::ALL.len()) {
+            None => Duration::minutes(i as i64 * 7),
+            Some(day) => Duration::days(day as i64 + 1) + Duration::minutes((i * 37 % 600) as i64),
+        };
+        db.backdate_job(job_id, age)?;
+        seeded.jobs += 1;
+        seeded.shorts += short_ids.len();
+    }
+    Ok(seeded)
+}
+
+/// Bring a job and its short to `status`, with the work a real run leaves
+/// behind. `n` varies the numbers from job to job.
+fn fill(db: &Database, job_id: i64, short_id: i64, status: JobStatus, n: usize) -> Result<()> {
+    let n = n as i64;
+    let prices = UnitPrices::default();
+    match status {
+        JobStatus::Pending => return Ok(()),
+        JobStatus::Failed => {
+            let error = "Ollama returned 503 Service Unavailable";
+            db.record_job_error(job_id, 1, "generation", error)?;
+            return db.update_job_status(job_id, status, Some(error));
+        }
+        JobStatus::Dead => {
+            let error = "ffmpeg exited with exit status: 1: Conversion failed!";
+            for attempt in 1..=3 {
+                db.record_job_error(job_id, attempt, "render", error)?;
+            }
+            return db.update_job_status(job_id, status, Some(error));
+        }
+        _ => {}
+    }
+    
+    db.set_short_script(short_id, SCRIPT)?;
+    db.record_metric(job_id, "script", 4_000 + n * 977 % 3_000)?;
+    let tokens = 1_200.0 + (n * 131 % 900) as f64;
+    db.record_job_cost(job_id, CostKind::LlmTokens, tokens, prices.cost(CostKind::LlmTokens, tokens))?;
+    db.set_job_checkpoint(job_id, PipelineStep::Script)?;
+    match status {
+        JobStatus::Generating => return db.update_job_status(job_id, status, None),
+        JobStatus::NeedsReview => return db.update_job_status(job_id, status, Some("en script: Banned word: guaranteed")),
+        _ => {}
+    }
+    
+    db.record_metric(job_id, "narration", 2_500 + n * 613 % 2_000)?;
+    let characters = SCRIPT.chars().count() as f64;
+    db.record_job_cost(job_id, CostKind::TtsCharacters, characters, prices.cost(CostKind::TtsCharacters, characters))?;
+    db.set_job_checkpoint(job_id, PipelineStep::Metadata)?;
+    if status != JobStatus::Done {
+        return db.update_job_status(job_id, status, None);
+    }
+    
+    db.record_metric(job_id, "video", 20_000 + n * 4_111 % 25_000)?;
+    db.set_job_checkpoint(job_id, PipelineStep::Video)?;
+    db.update_job_status(job_id, status, None)?;
+    // The recent finished short waits for review so the approval queue isn't empty
+    if n < JobStatus::ALL.len() as i64 {
+        return db.set_short_approval(short_id, ApprovalStatus::AwaitingApproval, None);
+    }
+    db.set_short_approval(short_id, ApprovalStatus::Approved, None)?;
+    db.record_metric(job_id, "telegram", 1_500 + n * 211 % 1_000)?;
+    db.record_publication(short_id, "telegram", "@shotauto_demo", Ok(&(1_000 + n).to_string()))?;
+    let video_id = dry_run::fake_video_id(short_id);
+    db.record_publication(short_id, "youtube", dry_run::CHANNEL, Ok(&video_id))?;
+    db.set_short_uploaded(short_id, &video_id)?;
+    let views = 800 + n * n * 347 % 25_000;
+    db.insert_short_stats(short_id, &VideoStatistics {
+        views,
+        likes: Some(views / 22),
+        comments: Some(views / 310),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn seeds_every_job_status() {
+        let db = Database::new_in_memory().unwrap();
+        let seeded = seed(&db).unwrap();
+        assert_eq!(seeded.trends, TRENDS.len());
+        assert_eq!(seeded.jobs, JobStatus::ALL.len() + HISTORY_DAYS);
+        let counts = db.count_jobs_by_status().unwrap();
+        for status in JobStatus::ALL {
+            assert!(counts.get(status.as_str()).is_some_and(|&count| count > 0), "no {} job", status.as_str());
+        }
+        assert_eq!(db.list_shorts_awaiting_approval(false).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn fills_the_dashboard() {
+        let db = Database::new_in_memory().unwrap();
+        seed(&db).unwrap();
+        let stats = db.get_stats().unwrap();
+        assert_eq!(stats.completed_jobs, HISTORY_DAYS as i64 + 1);
+        assert!(stats.shorts_per_day.len() > 1, "{:?}", stats.shorts_per_day);
+        assert!(!db.list_published("youtube", Utc::now() - Duration::days(30)).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn demo_jobs_are_never_claimed_and_can_be_removed() {
+        let db = Database::new_in_memory().unwrap();
+        let seeded = seed(&db).unwrap();
+        let claimed = db.claim_next_job(JobStatus::Pending, JobStatus::Generating, "worker", Duration::minutes(5), false).unwrap();
+        assert!(claimed.is_none());
+        
+        assert_eq!(db.delete_demo_data().unwrap(), seeded.jobs);
+        assert!(db.count_jobs_by_status().unwrap().is_empty());
+        assert!(db.list_trends(false, None, 100).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+// External tool checks (ffmpeg, ffprobe, yt-dlp) and static ffmpeg installs
+use crate::crypto::hex;
+use crate::db::Config;
+use crate::http;
+use crate::process::run;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Oldest ffmpeg/ffprobe we render with; the tpad, zoompan and ass filters
+/// behave as expected from this release on
+const MIN_FFMPEG: &str = "4.4";
+
+/// Oldest yt-dlp we accept; YouTube changes break old releases quickly
+const MIN_YT_DLP: &str = "2024.04.09";
+
+/// Presence and version of one external tool
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolStatus {
+    pub name: String,
+    /// Binary that was invoked
+    pub path: String,
+    pub found: bool,
+    pub version: Option<String>,
+    pub min_version: String,
+    /// Found and at least `min_version`
+    pub ok: bool,
+    /// What is wrong, or a caveat about the version check
+    pub message: Option<String>,
+}
+
+/// Result of `check`
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub tools: Vec<ToolStatus>,
+    /// Every tool is usable
+    pub ok: bool,
+    /// A static ffmpeg build can be installed on this platform
+    pub ffmpeg_installable: bool,
+}
+
+/// Check ffmpeg, ffprobe and yt-dlp as the app would invoke them
+pub async fn check(config: &Config) -> DependencyReport {
+    let tools = vec![
+        check_tool("ffmpeg", config.ffmpeg(), "-version", MIN_FFMPEG).await,
+        check_tool("ffprobe", config.ffprobe(), "-version", MIN_FFMPEG).await,
+        check_tool("yt-dlp", &config.yt_dlp(), "--version", MIN_YT_DLP).await,
+    ];
+    DependencyReport {
+        ok: tools.iter().all(|t| t.ok),
+        tools,
+        ffmpeg_installable: ffmpeg_archives().is_some(),
+    }
+}
+
+async fn check_tool(name: &str, path: &str, version_flag: &str, min_version: &str) -> ToolStatus {
+    let mut status = ToolStatus {
+        name: name.to_string(),
+        path: path.to_string(),
+        found: false,
+        version: None,
+        min_version: min_version.to_string(),
+        ok: false,
+        message: None,
+    };
+    let output = match run(Command::new(path).arg(version_flag)).await {
+        Ok(output) => output,
+        Err(e) => {
+            status.message = Some(e);
+            return status;
+        }
+    };
+    status.found = true;
+    
+    let text = String::from_utf8_lossy(&output.stdout);
+    let version = parse_version(text.lines().next().unwrap_or(""));
+    match version.as_deref().and_then(numeric) {
+        Some(found) => {
+            status.ok = found >= numeric(min_version).unwrap_or_default();
+            if !status.ok {
+                status.message = Some(format!("{} {} or newer is required", name, min_version));
+            }
+        }
+        None => {
+            // Git snapshots ("N-113000-g...") carry no release number
+            status.ok = true;
+            status.message = Some("Unrecognised version; assuming a recent build".to_string());
+        }
+    }
+    status.version = version;
+    status
+}
+
+/// Version from the first line of `-version`/`--version` output:
+/// "ffmpeg version 6.1.1-static ..." or a bare "2024.08.06"
+fn parse_version(line: &str) -> Option<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let word = match words.iter().position(|w| *w == "version") {
+        Some(i) => words.get(i + 1)?,
+        None => words.first()?,
+    };
+    Some(word.to_string())
+}
+
+/// Leading dotted number of a version ("n6.1.1-static" -> [6, 1, 1])
+fn numeric(version: &str) -> Option<Vec<u32>> {
+    let digits: String = version
+        .trim_start_matches('n')
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let parts: Vec<u32> = digits.split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    (!parts.is_empty()).then_some(parts)
+}
+
+/// A static ffmpeg build and the SHA-256 checksum file its mirror
+/// publishes next to it
+struct Archive {
+    url: &'static str,
+    checksums_url: &'static str,
+}
+
+impl Archive {
+    /// Last path segment of the URL, as listed in checksum files
+    fn file_name(&self) -> &'static str {
+        self.url.rsplit('/').next().unwrap_or(self.url)
+    }
+}
+
+/// Static ffmpeg archives for this platform; ffmpeg and ffprobe are
+/// anywhere inside them. Only mirrors that publish SHA-256 checksums are
+/// used, since the binaries are run once installed.
+fn ffmpeg_archives() -> Option<&'static [Archive]> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Some(&[Archive {
+            url: "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-linux64-gpl.tar.xz",
+            checksums_url: "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/checksums.sha256",
+        }]),
+        ("linux", "aarch64") => Some(&[Archive {
+            url: "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-linuxarm64-gpl.tar.xz",
+            checksums_url: "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/checksums.sha256",
+        }]),
+        ("windows", "x86_64") => Some(&[Archive {
+            url: "https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip",
+            checksums_url: "https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip.sha256",
+        }]),
+        _ => None,
+    }
+}
+
+/// Download a static ffmpeg build into `app_dir/bin`, returning the ffmpeg
+/// and ffprobe paths. Each archive is checked against its mirror's SHA-256
+/// before it is unpacked, with the system `tar`, which reads zip as well on
+/// Windows 10+.
+pub async fn install_ffmpeg(app_dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let archives = ffmpeg_archives().ok_or_else(|| {
+        format!(
+            "No verified static ffmpeg build for {}/{}; install ffmpeg with your package manager (e.g. brew install ffmpeg)",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    })?;
+    let bin_dir = app_dir.join("bin");
+    let work_dir = bin_dir.join("download");
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    tokio::fs::create_dir_all(&work_dir).await.map_err(|e| e.to_string())?;
+    
+    let result = async {
+        for (i, archive) in archives.iter().enumerate() {
+            let expected = published_sha256(archive).await?;
+            let path = work_dir.join(format!("archive_{}", i));
+            let actual = download(archive.url, &path).await?;
+            if !actual.eq_ignore_ascii_case(&expected) {
+                return Err(format!(
+                    "Checksum mismatch for {}: expected {}, got {}; not installing it",
+                    archive.url, expected, actual
+                ));
+            }
+            run(Command::new("tar").arg("-xf").arg(&path).arg("-C").arg(&work_dir)).await?;
+        }
+        let ffmpeg = install_binary(&work_dir, &bin_dir, "ffmpeg").await?;
+        let ffprobe = install_binary(&work_dir, &bin_dir, "ffprobe").await?;
+        Ok((ffmpeg, ffprobe))
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+/// The SHA-256 the mirror publishes for `archive`
+async fn published_sha256(archive: &Archive) -> Result<String, String> {
+    let response = http::send(http::client().get(archive.checksums_url)).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Download of {} failed ({})", archive.checksums_url, response.status()));
+    }
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    find_sha256(&text, archive.file_name())
+        .ok_or_else(|| format!("No SHA-256 for {} in {}", archive.file_name(), archive.checksums_url))
+}
+
+/// The checksum for `file_name` in a `sha256sum`-style listing
+/// ("<hash>  <name>" per line), or the only hash in a file holding just one
+fn find_sha256(text: &str, file_name: &str) -> Option<String> {
+    let is_hash = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    let lines: Vec<Vec<&str>> = text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|words| words.first().is_some_and(|w| is_hash(w)))
+        .collect();
+    if let [words] = lines.as_slice() {
+        if words.len() == 1 {
+            return Some(words[0].to_string());
+        }
+    }
+    lines.iter()
+        .find(|words| words.get(1).is_some_and(|name| name.trim_start_matches('*').rsplit('/').next() == Some(file_name)))
+        .map(|words| words[0].to_string())
+}
+
+/// Stream `url` to `path`, returning the SHA-256 of what was written
+async fn download(url: &str, path: &Path) -> Result<String, String> {
+    let mut response = http::send(http::client().get(url)).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Download of {} failed ({})", url, response.status()));
+    }
+    let mut file = tokio::fs::File::create(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Find the binary `name` under `search_dir` and copy it into `bin_dir`
+async fn install_binary(search_dir: &Path, bin_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let file_name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    let found = find_file(search_dir, &file_name)
+        .await?
+        .ok_or_else(|| format!("{} not found in the downloaded archive", file_name))?;
+    let target = bin_dir.join(&file_name);
+    tokio::fs::copy(&found, &target).await.map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(target)
+}
+
+/// Depth-first search for a file called `file_name`
+async fn find_file(dir: &Path, file_name: &str) -> Result<Option<PathBuf>, String> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await.map_err(|e| e.to_string())?;
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+            let path = entry.path();
+            let file_type = entry.file_type().await.map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if entry.file_name() == file_name {
+                return Ok(Some(path));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn finds_the_checksum_of_an_archive() {
+        let a = "a".repeat(64);
+        let b = "b".repeat(64);
+        let listing = format!("{}  ffmpeg-master-latest-linux64-gpl.tar.xz\n{} *ffmpeg-master-latest-win64-gpl.zip\n", a, b);
+        assert_eq!(find_sha256(&listing, "ffmpeg-master-latest-win64-gpl.zip"), Some(b.clone()));
+        assert_eq!(find_sha256(&listing, "ffmpeg-master-latest-linux64-gpl.tar.xz"), Some(a.clone()));
+        assert_eq!(find_sha256(&listing, "ffmpeg-release-essentials.zip"), None);
+        assert_eq!(find_sha256(&format!("{}\n", a), "ffmpeg-release-essentials.zip"), Some(a));
+        assert_eq!(find_sha256("not a checksum", "ffmpeg-release-essentials.zip"), None);
+    }
+}
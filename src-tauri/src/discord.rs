@@ -0,0 +1,124 @@
+// Discord delivery for ShotAuto
+use crate::http;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Largest attachment a webhook can post to a server without boosts
+pub const MAX_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Embed accent colour (ShotAuto red)
+const EMBED_COLOR: u32 = 0xE5_3935;
+
+/// Text shown alongside a posted short
+pub struct ShortEmbed<'a> {
+    pub title: &'a str,
+    pub language: &'a str,
+    pub duration_sec: Option<f64>,
+    /// Opening line of the script
+    pub hook: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct SentMessage {
+    id: String,
+}
+
+/// Post a rendered video with an embed through a channel webhook, returning
+/// the message id. Videos over `MAX_UPLOAD_BYTES` are refused up front; use
+/// `send_link` for those.
+pub async fn send_video(webhook_url: &str, video_path: &Path, embed: &ShortEmbed<'_>) -> Result<String, String> {
+    let size = tokio::fs::metadata(video_path).await.map_err(|e| e.to_string())?.len();
+    if size > MAX_UPLOAD_BYTES {
+        return Err(format!(
+            "Video is {:.1} MB, over Discord's {} MB attachment limit",
+            size as f64 / 1_048_576.0,
+            MAX_UPLOAD_BYTES / 1_048_576
+        ));
+    }
+    let bytes = tokio::fs::read(video_path).await.map_err(|e| e.to_string())?;
+    let file_name = video_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "short.mp4".to_string());
+    let payload = serde_json::json!({ "embeds": [embed_json(embed)] });
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str("video/mp4")
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::multipart::Form::new()
+        .text("payload_json", payload.to_string())
+        .part("files[0]", part);
+    
+    // wait=true makes Discord return the created message
+    let response = http::client()
+        .post(webhook_url)
+        .query(&[("wait", "true")])
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Discord webhook failed ({}): {}", status, body));
+    }
+    let message: SentMessage = response.json().await.map_err(|e| e.to_string())?;
+    Ok(message.id)
+}
+
+/// Post a link to a video hosted elsewhere, for renders too large to attach
+pub async fn send_link(webhook_url: &str, link: &str, embed: &ShortEmbed<'_>) -> Result<String, String> {
+    let mut embed = embed_json(embed);
+    embed["url"] = serde_json::Value::String(link.to_string());
+    let response = http::client()
+        .post(webhook_url)
+        .query(&[("wait", "true")])
+        .json(&serde_json::json!({ "content": link, "embeds": [embed] }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Discord webhook failed ({}): {}", status, body));
+    }
+    let message: SentMessage = response.json().await.map_err(|e| e.to_string())?;
+    Ok(message.id)
+}
+
+fn embed_json(embed: &ShortEmbed<'_>) -> serde_json::Value {
+    let mut fields = vec![serde_json::json!({
+        "name": "Language",
+        "value": embed.language.to_uppercase(),
+        "inline": true,
+    })];
+    if let Some(duration) = embed.duration_sec {
+        fields.push(serde_json::json!({
+            "name": "Duration",
+            "value": format!("{:.0} s", duration),
+            "inline": true,
+        }));
+    }
+    let mut json = serde_json::json!({
+        "title": embed.title,
+        "color": EMBED_COLOR,
+        "fields": fields,
+    });
+    if let Some(hook) = embed.hook {
+        json["description"] = serde_json::Value::String(hook.to_string());
+    }
+    json
+}
+
+/// Webhook URL without its token, safe to show and store:
+/// `.../api/webhooks/123/abc` -> `webhook 123`
+pub fn destination_name(webhook_url: &str) -> String {
+    let mut parts = webhook_url.trim_end_matches('/').rsplit('/');
+    let _token = parts.next();
+    match parts.next() {
+        Some(id) if id.chars().all(|c| c.is_ascii_digit()) => format!("webhook {}", id),
+        _ => "webhook".to_string(),
+    }
+}
@@ -0,0 +1,135 @@
+// Source video downloads (yt-dlp) into the media cache
+use crate::cache::{self, CacheKind};
+use crate::db::{Config, Database};
+use crate::transfers::{self, Direction};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+/// Marks yt-dlp progress lines among its other output
+const PROGRESS_PREFIX: &str = "shotauto-progress:";
+
+/// Download progress reported while yt-dlp runs
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub video_id: String,
+    /// 0-100
+    pub percent: f64,
+    pub downloaded_bytes: u64,
+    /// `None` when yt-dlp doesn't know the size yet
+    pub total_bytes: Option<u64>,
+}
+
+/// Download a YouTube video as MP4 into the media cache, calling
+/// `on_progress` as yt-dlp reports it. Cached downloads are reused.
+pub async fn download_source<F>(
+    config: &Config,
+    db: &Database,
+    app_dir: &Path,
+    video_id: &str,
+    on_progress: F,
+) -> Result<PathBuf, String>
+where
+    F: Fn(DownloadProgress),
+{
+    let key = cache::key(&["youtube", video_id]);
+    if let Some(cached) = cache::lookup(db, CacheKind::Source, &key)? {
+        return Ok(cached.path);
+    }
+    let path = cache::scratch_path(app_dir, CacheKind::Source, "mp4").await?;
+    
+    let url = format!("https://www.youtube.com/watch?v={}", video_id);
+    let template = format!(
+        "download:{}%(progress._percent_str)s|%(progress.downloaded_bytes)s|%(progress.total_bytes)s|%(progress.total_bytes_estimate)s",
+        PROGRESS_PREFIX
+    );
+    // yt-dlp writes to `.part` files and renames them when complete, so an
+    // interrupted download never leaves a truncated file at `path`
+    let mut command = Command::new(config.yt_dlp());
+    command
+        .args(["-f", "bv*[height<=1080][ext=mp4]+ba[ext=m4a]/b[ext=mp4]/b"])
+        .args(["--merge-output-format", "mp4", "--no-playlist", "--newline"])
+        .args(["--progress-template", &template])
+        .arg("-o")
+        .arg(path.with_extension("%(ext)s"));
+    if let Some(ref ffmpeg) = config.ffmpeg_path {
+        command.args(["--ffmpeg-location", ffmpeg]);
+    }
+    // yt-dlp paces itself; each download gets the whole cap
+    if config.download_limit_kb > 0 {
+        command.args(["--limit-rate", &format!("{}K", config.download_limit_kb)]);
+    }
+    let mut child = command
+        .arg(&url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start yt-dlp: {}", e))?;
+    
+    let stdout = child.stdout.take().ok_or("yt-dlp stdout unavailable")?;
+    let mut stderr = child.stderr.take().ok_or("yt-dlp stderr unavailable")?;
+    // Drain stderr alongside stdout so a chatty yt-dlp can't block on a full pipe
+    let stderr_task = tokio::spawn(async move {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text).await;
+        text
+    });
+    
+    let transfer = transfers::start(Direction::Download, format!("youtube {}", video_id), None);
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+        if let Some(progress) = parse_progress(video_id, &line) {
+            transfer.progress(progress.downloaded_bytes, progress.total_bytes);
+            on_progress(progress);
+        }
+    }
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    let stderr = stderr_task.await.unwrap_or_default();
+    if !status.success() {
+        remove_leftovers(&path).await;
+        let tail: Vec<&str> = stderr.lines().collect();
+        return Err(format!("yt-dlp exited with {}: {}", status, tail[tail.len().saturating_sub(5)..].join("\n")));
+    }
+    if !path.is_file() {
+        remove_leftovers(&path).await;
+        return Err("yt-dlp finished but produced no MP4 file".to_string());
+    }
+    
+    cache::store(db, config, app_dir, CacheKind::Source, &key, &path, None).await
+}
+
+/// Delete what a failed download left next to `path`: its `.part` files
+/// and the separate video and audio streams of an unfinished merge
+async fn remove_leftovers(path: &Path) {
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+        return;
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    let prefix = format!("{}.", stem);
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+/// Parse one line written with our `--progress-template`
+fn parse_progress(video_id: &str, line: &str) -> Option<DownloadProgress> {
+    let fields: Vec<&str> = line.trim().strip_prefix(PROGRESS_PREFIX)?.split('|').collect();
+    let [percent, downloaded, total, estimate] = fields[..] else {
+        return None;
+    };
+    // Missing values come through as "NA"
+    let number = |s: &str| s.trim().parse::<f64>().ok();
+    Some(DownloadProgress {
+        video_id: video_id.to_string(),
+        percent: number(percent.trim_end_matches('%'))?,
+        downloaded_bytes: number(downloaded).unwrap_or(0.0) as u64,
+        total_bytes: number(total).or_else(|| number(estimate)).map(|b| b as u64),
+    })
+}
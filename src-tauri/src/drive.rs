@@ -0,0 +1,125 @@
+// Backups of finished shorts to a Google Drive folder
+use crate::db::Config;
+use crate::http;
+use crate::resilience::{self, Service};
+use crate::youtube_upload;
+use serde::Deserialize;
+use std::path::Path;
+
+const UPLOAD_URL: &str = "https://www.googleapis.com/upload/drive/v3/files";
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+
+/// Only files the app created itself are visible to it, so the backup
+/// folder has to be made with `create_folder` too
+pub const SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+/// Make a folder named `name` at the top of the user's Drive
+pub async fn create_folder(access_token: &str, name: &str) -> Result<DriveFile, String> {
+    let request = http::client()
+        .post(FILES_URL)
+        .query(&[("fields", "id,webViewLink")])
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({
+            "name": name,
+            "mimeType": "application/vnd.google-apps.folder",
+        }));
+    let response = resilience::send(Service::Drive, request).await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Creating the Drive folder failed ({}): {}", status, body));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+/// A file uploaded to Drive
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveFile {
+    pub id: String,
+    pub web_view_link: Option<String>,
+}
+
+/// Upload `path` into `folder_id` as `name`
+pub async fn upload(
+    config: &Config,
+    access_token: &str,
+    folder_id: &str,
+    path: &Path,
+    name: &str,
+) -> Result<DriveFile, String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if bytes.is_empty() {
+        return Err(format!("{} is empty", path.display()));
+    }
+    let mime = mime_type(path);
+    let request = http::client()
+        .post(UPLOAD_URL)
+        .query(&[("uploadType", "resumable"), ("fields", "id,webViewLink")])
+        .bearer_auth(access_token)
+        .header("X-Upload-Content-Type", mime)
+        .header("X-Upload-Content-Length", bytes.len().to_string())
+        .json(&file_resource(name, folder_id));
+    let session = resilience::send(Service::Drive, request).await?;
+    if session.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err("The app can't see this Drive folder; create the backup folder from the app instead".to_string());
+    }
+    if !session.status().is_success() {
+        let status = session.status();
+        let body = session.text().await.unwrap_or_default();
+        return Err(format!("Drive upload session failed ({}): {}", status, body));
+    }
+    let location = session
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or("Drive did not return an upload URL")?
+        .to_string();
+    
+    let response = youtube_upload::put_resumable(config, Service::Drive, access_token, &location, path, &bytes, mime).await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Drive upload of {} failed ({}): {}", name, status, body));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+fn file_resource(name: &str, folder_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "parents": [folder_id],
+    })
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mp4") => "video/mp4",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("txt") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Folder id from either a bare id or a folder URL pasted from the browser,
+/// e.g. `https://drive.google.com/drive/folders/<id>?usp=sharing`
+pub fn folder_id(input: &str) -> &str {
+    let input = input.trim();
+    match input.split_once("/folders/") {
+        Some((_, rest)) => rest.split(['?', '/', '#']).next().unwrap_or(rest),
+        None => input,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn accepts_folder_urls() {
+        assert_eq!(folder_id("1AbC-d_E"), "1AbC-d_E");
+        assert_eq!(folder_id(" https://drive.google.com/drive/folders/1AbC-d_E?usp=sharing "), "1AbC-d_E");
+        assert_eq!(folder_id("https://drive.google.com/drive/u/0/folders/1AbC-d_E"), "1AbC-d_E");
+        assert_eq!(file_resource("short_3.mp4", "1AbC")["parents"][0], "1AbC");
+    }
+}
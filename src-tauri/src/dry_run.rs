@@ -0,0 +1,22 @@
+// Dry-run mode: the whole pipeline runs, but nothing leaves the machine
+
+/// A recorded `videos.list?chart=mostPopular` response, used instead of the
+/// YouTube API
+pub const TRENDING_FIXTURE: &str = include_str!("../fixtures/trending.json");
+
+/// Length of the placeholder clip rendered instead of a short
+pub const PLACEHOLDER_SECS: f64 = 2.0;
+
+/// Destination recorded for uploads that didn't happen
+pub const CHANNEL: &str = "dry-run";
+
+const VIDEO_ID_PREFIX: &str = "dry-run-";
+
+/// Video id recorded for a short "uploaded" in dry-run mode
+pub fn fake_video_id(short_id: i64) -> String {
+    format!("{}{}", VIDEO_ID_PREFIX, short_id)
+}
+
+pub fn is_fake_video_id(video_id: &str) -> bool {
+    video_id.starts_with(VIDEO_ID_PREFIX)
+}
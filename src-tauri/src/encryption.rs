@@ -0,0 +1,99 @@
+// SQLCipher database encryption with the passphrase kept in the OS keychain
+use crate::db::Database;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Keychain entry holding the database passphrase
+const KEYCHAIN_SERVICE: &str = "shotauto";
+const KEYCHAIN_USER: &str = "database";
+
+/// Shortest passphrase `encrypt_database` accepts
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+
+/// `path` with `suffix` appended to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Marker left by `encrypt_database`: the next start encrypts the database
+/// before anything opens it, so no write can land in the old file mid-copy
+pub fn pending_marker(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".encrypt-pending")
+}
+
+pub fn is_pending(db_path: &Path) -> bool {
+    pending_marker(db_path).exists()
+}
+
+/// Passphrase stored in the keychain, if any
+pub fn stored_key() -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER).map_err(|e| e.to_string())?;
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read the database passphrase from the keychain: {}", e)),
+    }
+}
+
+/// Keep `key` in the keychain and mark the database for encryption on the
+/// next start
+pub fn schedule(db_path: &Path, key: &str) -> Result<(), String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+        .and_then(|entry| entry.set_password(key))
+        .map_err(|e| format!("Failed to save the database passphrase to the keychain: {}", e))?;
+    std::fs::write(pending_marker(db_path), b"").map_err(|e| e.to_string())
+}
+
+/// Open the database at startup: finish a pending encryption, then unlock
+/// it with the keychain passphrase when there is one
+pub fn open(db_path: PathBuf) -> Result<Database, String> {
+    let key = stored_key()?;
+    if is_pending(&db_path) {
+        let key = key.as_deref().ok_or("Database encryption is pending but the keychain has no passphrase")?;
+        encrypt_in_place(&db_path, key)?;
+        std::fs::remove_file(pending_marker(&db_path)).map_err(|e| e.to_string())?;
+        tracing::info!("Encrypted the database at {}", db_path.display());
+    }
+    // A passphrase is only used once the file is encrypted; before that the
+    // keychain may hold one for a database that was never converted
+    let key = key.filter(|_| is_encrypted(&db_path));
+    Database::with_key(db_path, key).map_err(|e| e.to_string())
+}
+
+/// Whether the file at `path` is not readable as plain SQLite
+fn is_encrypted(path: &Path) -> bool {
+    path.exists()
+        && rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0)))
+            .is_err()
+}
+
+/// Replace the plaintext database with an encrypted copy. The copy is
+/// written to a temporary file and renamed over the original, so a crash
+/// leaves one or the other whole. The old write-ahead log, emptied by the
+/// export, belongs to the old file and goes once the new one is in place.
+fn encrypt_in_place(db_path: &Path, key: &str) -> Result<(), String> {
+    // An earlier start got as far as the rename but not the marker
+    if is_encrypted(db_path) {
+        return Ok(());
+    }
+    let encrypted = with_suffix(db_path, ".encrypting");
+    let _ = std::fs::remove_file(&encrypted);
+    let result = Database::export_encrypted(db_path, &encrypted, key)
+        .map_err(|e| format!("Failed to encrypt the database: {}", e))
+        .and_then(|_| std::fs::File::open(&encrypted).and_then(|f| f.sync_all()).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&encrypted, db_path).map_err(|e| format!("Failed to replace the database: {}", e)));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&encrypted);
+        return Err(e);
+    }
+    for suffix in ["-wal", "-shm"] {
+        match std::fs::remove_file(with_suffix(db_path, suffix)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.to_string()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+// Error type returned by Tauri commands
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Failure of a command, sent to the frontend as `{ code, message, retryable }`
+/// so it can explain what to fix and offer a retry where one might help
+#[derive(Debug)]
+pub enum AppError {
+    /// SQLite error
+    Db(rusqlite::Error),
+    /// Request to an external service (YouTube, Telegram, Discord...) failed
+    Http(String),
+    /// ffmpeg, ffprobe or yt-dlp failed
+    Ffmpeg(String),
+    /// Script generation or moderation by the LLM failed
+    Llm(String),
+    /// Arguments or state don't allow the operation
+    Validation(String),
+    /// Row the command refers to doesn't exist
+    NotFound(String),
+    /// A setting the command needs is missing
+    NotConfigured(String),
+    /// Anything else
+    Internal(String),
+}
+
+impl AppError {
+    pub fn not_found(what: &str, id: i64) -> Self {
+        AppError::NotFound(format!("{} {} not found", what, id))
+    }
+    
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Db(_) => "db",
+            AppError::Http(_) => "http",
+            AppError::Ffmpeg(_) => "ffmpeg",
+            AppError::Llm(_) => "llm",
+            AppError::Validation(_) => "validation",
+            AppError::NotFound(_) => "not_found",
+            AppError::NotConfigured(_) => "not_configured",
+            AppError::Internal(_) => "internal",
+        }
+    }
+    
+    /// Whether trying again unchanged may succeed
+    pub fn retryable(&self) -> bool {
+        match self {
+            AppError::Db(rusqlite::Error::SqliteFailure(e, _)) => matches!(
+                e.code,
+                rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+            ),
+            AppError::Http(_) | AppError::Llm(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => write!(f, "Database error: {}", e),
+            AppError::Http(message)
+            | AppError::Ffmpeg(message)
+            | AppError::Llm(message)
+            | AppError::Validation(message)
+            | AppError::NotFound(message)
+            | AppError::NotConfigured(message)
+            | AppError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("retryable", &self.retryable())?;
+        error.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Db(e)
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::Http(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+/// Module helpers report errors as text; unclassified ones end up here
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
+
+/// The pipeline records stage errors as text
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.to_string()
+    }
+}
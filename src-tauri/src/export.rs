@@ -0,0 +1,103 @@
+// Export of pipeline data to CSV or JSON Lines for analysis in spreadsheets
+use crate::db::{Database, ExportScope};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Rows read from the database at a time
+const BATCH_SIZE: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// Write every row of `scope` to `path`, a page at a time so large tables
+/// never sit in memory whole. Returns the number of rows written.
+pub fn export(db: &Database, scope: ExportScope, format: ExportFormat, path: &Path) -> Result<u64, String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let mut written = 0u64;
+    let mut after_id = 0;
+    let mut header_done = false;
+    
+    loop {
+        let batch = db.export_batch(scope, after_id, BATCH_SIZE).map_err(|e| e.to_string())?;
+        if format == ExportFormat::Csv && !header_done {
+            let header: Vec<String> = batch.columns.iter().map(|c| csv_field(c)).collect();
+            writeln!(out, "{}", header.join(",")).map_err(|e| e.to_string())?;
+            header_done = true;
+        }
+        for row in &batch.rows {
+            match format {
+                ExportFormat::Csv => {
+                    let fields: Vec<String> = row.iter().map(csv_value).collect();
+                    writeln!(out, "{}", fields.join(","))
+                }
+                ExportFormat::Jsonl => {
+                    let object: serde_json::Map<String, serde_json::Value> =
+                        batch.columns.iter().cloned().zip(row.iter().cloned()).collect();
+                    writeln!(out, "{}", serde_json::Value::Object(object))
+                }
+            }
+            .map_err(|e| e.to_string())?;
+        }
+        written += batch.rows.len() as u64;
+        
+        match batch.last_id {
+            Some(id) if batch.rows.len() == BATCH_SIZE as usize => after_id = id,
+            _ => break,
+        }
+    }
+    
+    out.flush().map_err(|e| e.to_string())?;
+    Ok(written)
+}
+
+fn csv_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => csv_field(text),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a text field when it holds a separator, quote, line break or tab.
+/// Text that a spreadsheet would run as a formula (a trend title starting
+/// with `=`, say) gets a leading quote.
+fn csv_field(text: &str) -> String {
+    let text = if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    };
+    if text.contains([',', '"', '\n', '\r', '\t']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn fields_with_special_characters_are_quoted() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("one\rtwo"), "\"one\rtwo\"");
+        assert_eq!(csv_field("one\ttwo"), "\"one\ttwo\"");
+    }
+    
+    #[test]
+    fn formulas_are_defused() {
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+        assert_eq!(csv_field("\t=1+1"), "\"'\t=1+1\"");
+    }
+}
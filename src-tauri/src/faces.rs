@@ -0,0 +1,76 @@
+// Face detection with an UltraFace ONNX model (version-RFB-320), used to
+// keep the subject in view when reframing footage
+use crate::db::Config;
+use crate::process::run;
+use crate::reframe::Area;
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Model input size
+const INPUT_WIDTH: usize = 320;
+const INPUT_HEIGHT: usize = 240;
+
+/// Frames sampled from a clip
+const FRAMES: u32 = 8;
+
+/// Detections less confident than this are ignored
+const MIN_SCORE: f32 = 0.7;
+
+/// Up to `FRAMES` frames spread over the first `seconds` of `video`, cropped
+/// to `area` and scaled to the model input, as packed RGB
+pub async fn sample_frames(config: &Config, video: &Path, area: Option<Area>, seconds: u32) -> Result<Vec<Vec<u8>>, String> {
+    let crop = area
+        .map(|a| format!("crop={}:{}:{}:{},", a.width, a.height, a.x, a.y))
+        .unwrap_or_default();
+    let output = run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-t", &seconds.to_string(), "-i"])
+        .arg(video)
+        .args(["-vf", &format!("fps={FRAMES}/{seconds},{crop}scale={INPUT_WIDTH}:{INPUT_HEIGHT}")])
+        .args(["-frames:v", &FRAMES.to_string(), "-f", "rawvideo", "-pix_fmt", "rgb24", "-"]))
+    .await?;
+    Ok(output.stdout
+        .chunks_exact(INPUT_WIDTH * INPUT_HEIGHT * 3)
+        .map(|frame| frame.to_vec())
+        .collect())
+}
+
+/// Horizontal center of the most confident face in each frame, averaged by
+/// confidence, as a fraction of the width. `None` when no frame has a face.
+pub fn focus_x(model: &str, frames: &[Vec<u8>]) -> Result<Option<f64>, String> {
+    let mut session = Session::builder()
+        .and_then(|builder| builder.commit_from_file(model))
+        .map_err(|e| format!("Failed to load face model {}: {}", model, e))?;
+    let (mut sum, mut weight) = (0.0, 0.0);
+    for frame in frames {
+        let tensor = Tensor::from_array(([1usize, 3, INPUT_HEIGHT, INPUT_WIDTH], to_planar(frame)))
+            .map_err(|e| e.to_string())?;
+        let outputs = session.run(ort::inputs![tensor]).map_err(|e| e.to_string())?;
+        let (_, scores) = outputs["scores"].try_extract_tensor::<f32>().map_err(|e| e.to_string())?;
+        let (_, boxes) = outputs["boxes"].try_extract_tensor::<f32>().map_err(|e| e.to_string())?;
+        // Scores are (background, face) pairs; boxes are normalized x1, y1, x2, y2
+        let best = scores.chunks_exact(2)
+            .zip(boxes.chunks_exact(4))
+            .map(|(score, bounds)| (score[1], (bounds[0] + bounds[2]) / 2.0))
+            .filter(|&(score, _)| score >= MIN_SCORE)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((score, x)) = best {
+            sum += (score * x) as f64;
+            weight += score as f64;
+        }
+    }
+    Ok((weight > 0.0).then(|| (sum / weight).clamp(0.0, 1.0)))
+}
+
+/// Packed RGB to the planar layout the model takes, scaled to about -1..1
+fn to_planar(frame: &[u8]) -> Vec<f32> {
+    let pixels = INPUT_WIDTH * INPUT_HEIGHT;
+    let mut data = vec![0.0; pixels * 3];
+    for (i, rgb) in frame.chunks_exact(3).enumerate() {
+        for (channel, &value) in rgb.iter().enumerate() {
+            data[channel * pixels + i] = (value as f32 - 127.0) / 128.0;
+        }
+    }
+    data
+}
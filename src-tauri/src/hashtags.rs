@@ -0,0 +1,99 @@
+// Hashtag suggestions for uploads, from the trend's tags and category
+use crate::db::Trend;
+use std::collections::HashSet;
+
+/// Every short's description carries this so YouTube files it as a Short
+pub const SHORTS_TAG: &str = "#Shorts";
+
+/// Suggestions beyond this many aren't returned; YouTube ignores every
+/// hashtag of a video that has more than 60
+const MAX_SUGGESTIONS: usize = 15;
+
+/// Hashtag for a YouTube video category id
+fn category_hashtag(category_id: &str) -> Option<&'static str> {
+    Some(match category_id {
+        "1" => "#Film",
+        "2" => "#Cars",
+        "10" => "#Music",
+        "15" => "#Animals",
+        "17" => "#Sports",
+        "19" => "#Travel",
+        "20" => "#Gaming",
+        "22" => "#Vlog",
+        "23" => "#Comedy",
+        "24" => "#Entertainment",
+        "25" => "#News",
+        "26" => "#HowTo",
+        "27" => "#Education",
+        "28" => "#Science",
+        "29" => "#Nonprofit",
+        _ => return None,
+    })
+}
+
+/// A tag as a hashtag: words capitalized and run together, everything but
+/// letters and digits dropped. `None` when nothing is left.
+pub fn to_hashtag(tag: &str) -> Option<String> {
+    let word: String = tag
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            let first = chars.next().map(|c| c.to_uppercase().collect::<String>()).unwrap_or_default();
+            first + chars.as_str()
+        })
+        .collect();
+    (!word.is_empty()).then(|| format!("#{}", word))
+}
+
+/// Hashtags for a short made from `trend`: `#Shorts` first, then the
+/// template's tags, the trend's category and the source video's tags.
+/// Duplicates are dropped regardless of case.
+pub fn suggest(trend: &Trend, template_tags: &[String]) -> Vec<String> {
+    let category = trend.category.as_deref().and_then(category_hashtag).map(str::to_string);
+    let candidates = std::iter::once(SHORTS_TAG.to_string())
+        .chain(template_tags.iter().filter_map(|t| to_hashtag(t)))
+        .chain(category)
+        .chain(trend.tags.iter().filter_map(|t| to_hashtag(t)));
+    let mut seen = HashSet::new();
+    candidates
+        .filter(|tag| seen.insert(tag.to_lowercase()))
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    
+    #[test]
+    fn turns_tags_into_hashtags() {
+        assert_eq!(to_hashtag("quick pasta recipes").as_deref(), Some("#QuickPastaRecipes"));
+        assert_eq!(to_hashtag("#DIY!").as_deref(), Some("#DIY"));
+        assert_eq!(to_hashtag(" -- "), None);
+    }
+    
+    #[test]
+    fn suggests_shorts_then_template_category_and_trend_tags() {
+        let trend = Trend {
+            id: None,
+            video_id: "abc".into(),
+            title: "Pasta".into(),
+            channel: None,
+            views: None,
+            category: Some("26".into()),
+            fetched_at: Utc::now(),
+            transcript: None,
+            tags: vec!["pasta".into(), "howto".into(), "shorts".into()],
+            source: Default::default(),
+            notes: None,
+            starred: false,
+            state: Default::default(),
+            skip_reason: None,
+            dry_run: false,
+        };
+        let suggested = suggest(&trend, &["Pasta".to_string()]);
+        assert_eq!(suggested, ["#Shorts", "#Pasta", "#HowTo"]);
+    }
+}
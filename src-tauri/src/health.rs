@@ -0,0 +1,242 @@
+// Health of the app's dependencies, checked on demand and in the background
+use crate::db::{Config, LlmBackend};
+use crate::tts::TtsEngine;
+use crate::{deps, pipeline, AppState};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the monitor re-checks everything
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Below this much free space a render can't be finished
+const MIN_FREE_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but needs attention soon
+    Warning,
+    Down,
+    /// Not configured or not in use
+    Skipped,
+}
+
+/// Result of checking one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// The pipeline can't run without it; the queue is paused while it is down
+    pub critical: bool,
+    pub detail: String,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// No critical dependency is down
+    pub ok: bool,
+    pub checks: Vec<Check>,
+    /// RFC 3339
+    pub checked_at: String,
+}
+
+impl HealthReport {
+    fn critical_down(&self) -> Vec<&'static str> {
+        self.checks.iter()
+            .filter(|c| c.critical && c.status == CheckStatus::Down)
+            .map(|c| c.name)
+            .collect()
+    }
+    
+    /// Whether any check changed status since `other`
+    fn changed_from(&self, other: &HealthReport) -> bool {
+        self.checks.len() != other.checks.len()
+            || self.checks.iter().zip(&other.checks).any(|(a, b)| a.name != b.name || a.status != b.status)
+    }
+}
+
+/// Check the database, ffmpeg, Ollama, the YouTube API, Telegram, the TTS
+/// backend and disk space, all at once
+pub async fn check(state: &AppState) -> HealthReport {
+    let started = Instant::now();
+    let config = match state.db.load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            let database = Check {
+                name: "database",
+                status: CheckStatus::Down,
+                critical: true,
+                detail: e.to_string(),
+                latency_ms: started.elapsed().as_millis() as u64,
+            };
+            return HealthReport { ok: false, checks: vec![database], checked_at: Utc::now().to_rfc3339() };
+        }
+    };
+    let (database, ffmpeg, ollama, youtube, telegram, tts, disk) = tokio::join!(
+        check_database(state),
+        check_ffmpeg(&config),
+        check_ollama(&config),
+        check_youtube(&config),
+        check_telegram(&config),
+        check_tts(state, &config),
+        check_disk(state, &config),
+    );
+    let checks = vec![database, ffmpeg, ollama, youtube, telegram, tts, disk];
+    let mut report = HealthReport { ok: true, checks, checked_at: Utc::now().to_rfc3339() };
+    report.ok = report.critical_down().is_empty();
+    report
+}
+
+fn to_check(name: &'static str, critical: bool, started: Instant, outcome: Result<String, String>) -> Check {
+    let (status, detail) = match outcome {
+        Ok(detail) => (CheckStatus::Ok, detail),
+        Err(detail) => (CheckStatus::Down, detail),
+    };
+    Check { name, status, critical, detail, latency_ms: started.elapsed().as_millis() as u64 }
+}
+
+fn skipped(name: &'static str, detail: &str) -> Check {
+    Check { name, status: CheckStatus::Skipped, critical: false, detail: detail.to_string(), latency_ms: 0 }
+}
+
+async fn check_database(state: &AppState) -> Check {
+    let started = Instant::now();
+    let db = state.db.clone();
+    let outcome = tokio::task::spawn_blocking(move || db.ping())
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()))
+        .map(|()| "Reachable".to_string());
+    to_check("database", true, started, outcome)
+}
+
+async fn check_ffmpeg(config: &Config) -> Check {
+    let started = Instant::now();
+    let report = deps::check(config).await;
+    let problems: Vec<String> = report.tools.iter()
+        .filter(|t| !t.ok && (t.name == "ffmpeg" || t.name == "ffprobe"))
+        .map(|t| format!("{}: {}", t.name, t.message.as_deref().unwrap_or("not usable")))
+        .collect();
+    let outcome = if problems.is_empty() {
+        let version = report.tools.first().and_then(|t| t.version.clone()).unwrap_or_default();
+        Ok(format!("ffmpeg {}", version))
+    } else {
+        Err(problems.join("; "))
+    };
+    to_check("ffmpeg", true, started, outcome)
+}
+
+async fn check_ollama(config: &Config) -> Check {
+    if config.llm_backend != LlmBackend::Ollama {
+        return skipped("ollama", "Not the LLM backend");
+    }
+    let started = Instant::now();
+    let outcome = match crate::test_ollama(config.ollama_endpoint.clone()).await {
+        Ok(test) if test.ok => Ok(test.detail),
+        Ok(test) => Err(test.detail),
+        Err(e) => Err(e.to_string()),
+    };
+    to_check("ollama", true, started, outcome)
+}
+
+async fn check_youtube(config: &Config) -> Check {
+    let Some(api_key) = config.youtube_api_key.clone() else {
+        return skipped("youtube", "No API key");
+    };
+    let started = Instant::now();
+    let outcome = match crate::test_youtube_api(api_key).await {
+        Ok(test) if test.ok => Ok(test.detail),
+        Ok(test) => Err(test.detail),
+        Err(e) => Err(e.to_string()),
+    };
+    to_check("youtube", false, started, outcome)
+}
+
+async fn check_telegram(config: &Config) -> Check {
+    let Some(token) = config.telegram_bot_token.clone() else {
+        return skipped("telegram", "No bot token");
+    };
+    let started = Instant::now();
+    let outcome = match crate::test_telegram_bot(token, None).await {
+        Ok(test) if test.ok => Ok(test.detail),
+        Ok(test) => Err(test.detail),
+        Err(e) => Err(e.to_string()),
+    };
+    to_check("telegram", false, started, outcome)
+}
+
+async fn check_tts(state: &AppState, config: &Config) -> Check {
+    let started = Instant::now();
+    let outcome = match state.clients.tts(config, config.tts_backend) {
+        Ok(engine) => engine.list_voices().await
+            .map(|voices| format!("{}: {} voices", config.tts_backend.as_str(), voices.len())),
+        Err(e) => Err(e),
+    };
+    to_check("tts", true, started, outcome)
+}
+
+async fn check_disk(state: &AppState, config: &Config) -> Check {
+    let started = Instant::now();
+    let free_bytes = match fs2::available_space(&state.app_dir) {
+        Ok(free_bytes) => free_bytes,
+        Err(e) => {
+            let mut check = to_check("disk", false, started, Err(e.to_string()));
+            check.status = CheckStatus::Warning;
+            return check;
+        }
+    };
+    let detail = format!("{} MB free", free_bytes / 1024 / 1024);
+    let mut check = to_check("disk", true, started, if free_bytes < MIN_FREE_BYTES { Err(detail) } else { Ok(detail) });
+    if check.status == CheckStatus::Ok && free_bytes < config.low_disk_warning_mb as u64 * 1024 * 1024 {
+        check.status = CheckStatus::Warning;
+    }
+    check
+}
+
+/// Set while the monitor holds the queue paused
+static AUTO_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Re-check every `CHECK_INTERVAL` for as long as the app runs, emitting
+/// `health:changed` whenever a status changes. The queue is paused while a
+/// critical dependency is down, and resumed once it is back unless someone
+/// resumed or paused it by hand in between.
+pub async fn run(app: AppHandle) {
+    let mut last: Option<HealthReport> = None;
+    loop {
+        let state = app.state::<AppState>();
+        let report = check(&state).await;
+        if last.as_ref().is_none_or(|last| report.changed_from(last)) {
+            let _ = app.emit("health:changed", &report);
+        }
+        
+        let down = report.critical_down();
+        if !down.is_empty() && !pipeline::is_paused() {
+            tracing::warn!("Pausing the queue, down: {}", down.join(", "));
+            pipeline::set_paused(true);
+            AUTO_PAUSED.store(true, Ordering::SeqCst);
+            emit_queue_status(&app);
+        } else if down.is_empty() && AUTO_PAUSED.swap(false, Ordering::SeqCst) && pipeline::is_paused() {
+            tracing::info!("Dependencies are back, resuming the queue");
+            pipeline::set_paused(false);
+            emit_queue_status(&app);
+        }
+        last = Some(report);
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Forget an automatic pause once the queue is paused or resumed by hand
+pub fn queue_toggled() {
+    AUTO_PAUSED.store(false, Ordering::SeqCst);
+}
+
+fn emit_queue_status(app: &AppHandle) {
+    if let Ok(status) = pipeline::status(&app.state::<AppState>().db) {
+        let _ = app.emit("queue:status", status);
+    }
+}
@@ -0,0 +1,79 @@
+// Shared HTTP client with timeouts, a User-Agent and retries for GETs
+use reqwest::{Client, Method, RequestBuilder, Response};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const USER_AGENT: &str = concat!("ShotAuto/", env!("CARGO_PKG_VERSION"));
+
+/// A host that doesn't accept the connection in this long is treated as down
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest silence while waiting for or reading a response. Generous: Ollama
+/// only answers once the whole script is written. Requests that must finish
+/// sooner set their own `timeout`.
+const READ_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Time limit for connection tests and health checks
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts of a GET before its error is returned
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubled for each one after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// The app's HTTP client. Cheap to call; every call shares one connection pool.
+pub fn client() -> Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            Client::builder()
+                .user_agent(USER_AGENT)
+                .connect_timeout(CONNECT_TIMEOUT)
+                .read_timeout(READ_TIMEOUT)
+                .build()
+                .expect("Failed to build the HTTP client")
+        })
+        .clone()
+}
+
+/// Send `request`. GET and HEAD requests are retried with jittered backoff
+/// after connection errors, timeouts, 429 and 5xx responses; the last
+/// attempt's result is returned. Other methods are sent once.
+pub async fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let idempotent = matches!(*request.method(), Method::GET | Method::HEAD);
+    if !idempotent {
+        return client.execute(request).await;
+    }
+    
+    let mut attempt = 1;
+    loop {
+        // Bodies are empty for GET, so the clone always succeeds
+        let Some(retry) = request.try_clone().filter(|_| attempt < MAX_ATTEMPTS) else {
+            return client.execute(request).await;
+        };
+        match client.execute(retry).await {
+            Ok(response) if !is_transient_status(&response) => return Ok(response),
+            Err(e) if !(e.is_connect() || e.is_timeout()) => return Err(e),
+            Ok(response) => tracing::debug!("{} returned {}; retrying", request.url(), response.status()),
+            Err(e) => tracing::debug!("{} failed: {}; retrying", request.url(), e),
+        }
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+fn is_transient_status(response: &Response) -> bool {
+    let status = response.status();
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Exponential delay before retry `attempt`, plus up to half again at random
+/// so clients that failed together don't retry together
+fn backoff(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    delay + delay.mul_f64((nanos % 1000) as f64 / 2000.0)
+}
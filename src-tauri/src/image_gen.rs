@@ -0,0 +1,176 @@
+// AI-generated background images (Stable Diffusion via A1111 or ComfyUI)
+use crate::cache;
+use crate::db::{Config, ImageBackend};
+use crate::http;
+use base64::Engine as _;
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::Duration;
+
+/// Generated frame size; 9:16 at a resolution SD 1.5 models handle well
+const IMAGE_WIDTH: u32 = 576;
+const IMAGE_HEIGHT: u32 = 1024;
+
+/// How long to wait for ComfyUI to finish one image
+const COMFYUI_TIMEOUT: Duration = Duration::from_secs(300);
+
+const NEGATIVE_PROMPT: &str = "text, watermark, logo, caption, letters, signature, blurry, lowres, deformed";
+
+/// Media cache key of the image `generate` makes for `prompt`: everything
+/// sent to the backend that changes the picture
+pub fn cache_key(config: &Config, prompt: &str) -> String {
+    cache::key(&[
+        config.image_backend.as_str(),
+        config.image_endpoint.trim_end_matches('/'),
+        config.image_model.as_deref().unwrap_or_default(),
+        &config.image_steps.to_string(),
+        &format!("{}x{}", IMAGE_WIDTH, IMAGE_HEIGHT),
+        NEGATIVE_PROMPT,
+        prompt,
+    ])
+}
+
+/// Generate an image for `prompt` and save it as a PNG at `output`
+pub async fn generate(config: &Config, prompt: &str, output: &Path) -> Result<(), String> {
+    let bytes = match config.image_backend {
+        ImageBackend::Disabled => return Err("Image generation is disabled".to_string()),
+        ImageBackend::Automatic1111 => generate_a1111(config, prompt).await?,
+        ImageBackend::ComfyUi => generate_comfyui(config, prompt).await?,
+    };
+    tokio::fs::write(output, &bytes).await.map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+struct Txt2ImgResponse {
+    images: Vec<String>,
+}
+
+/// AUTOMATIC1111 `/sdapi/v1/txt2img`
+async fn generate_a1111(config: &Config, prompt: &str) -> Result<Vec<u8>, String> {
+    let mut request = serde_json::json!({
+        "prompt": prompt,
+        "negative_prompt": NEGATIVE_PROMPT,
+        "steps": config.image_steps,
+        "width": IMAGE_WIDTH,
+        "height": IMAGE_HEIGHT,
+    });
+    if let Some(ref model) = config.image_model {
+        request["override_settings"] = serde_json::json!({ "sd_model_checkpoint": model });
+    }
+    
+    let url = format!("{}/sdapi/v1/txt2img", config.image_endpoint.trim_end_matches('/'));
+    let response = http::client()
+        .post(&url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("txt2img failed ({}): {}", status, body));
+    }
+    let result: Txt2ImgResponse = response.json().await.map_err(|e| e.to_string())?;
+    let image = result.images.into_iter().next().ok_or("txt2img returned no image")?;
+    base64::engine::general_purpose::STANDARD
+        .decode(image)
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+struct QueuedPrompt {
+    prompt_id: String,
+}
+
+#[derive(Deserialize)]
+struct ComfyImage {
+    filename: String,
+    subfolder: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// ComfyUI: queue a basic txt2img workflow, wait for it, then fetch the output
+async fn generate_comfyui(config: &Config, prompt: &str) -> Result<Vec<u8>, String> {
+    let model = config.image_model.as_deref().ok_or("image_model is required for ComfyUI")?;
+    let endpoint = config.image_endpoint.trim_end_matches('/');
+    let client = http::client();
+    
+    // Seed from the prompt so re-rendering a short reproduces its images
+    let mut hasher = DefaultHasher::new();
+    prompt.hash(&mut hasher);
+    let seed = hasher.finish() >> 1;
+    let workflow = serde_json::json!({
+        "1": { "class_type": "CheckpointLoaderSimple", "inputs": { "ckpt_name": model } },
+        "2": { "class_type": "CLIPTextEncode", "inputs": { "text": prompt, "clip": ["1", 1] } },
+        "3": { "class_type": "CLIPTextEncode", "inputs": { "text": NEGATIVE_PROMPT, "clip": ["1", 1] } },
+        "4": { "class_type": "EmptyLatentImage", "inputs": { "width": IMAGE_WIDTH, "height": IMAGE_HEIGHT, "batch_size": 1 } },
+        "5": { "class_type": "KSampler", "inputs": {
+            "model": ["1", 0], "positive": ["2", 0], "negative": ["3", 0], "latent_image": ["4", 0],
+            "seed": seed, "steps": config.image_steps, "cfg": 7.0,
+            "sampler_name": "euler", "scheduler": "normal", "denoise": 1.0,
+        } },
+        "6": { "class_type": "VAEDecode", "inputs": { "samples": ["5", 0], "vae": ["1", 2] } },
+        "7": { "class_type": "SaveImage", "inputs": { "images": ["6", 0], "filename_prefix": "shotauto" } },
+    });
+    
+    let response = client
+        .post(format!("{}/prompt", endpoint))
+        .json(&serde_json::json!({ "prompt": workflow }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("ComfyUI rejected the workflow ({}): {}", status, body));
+    }
+    let queued: QueuedPrompt = response.json().await.map_err(|e| e.to_string())?;
+    
+    // History stays empty until the prompt has finished
+    let deadline = tokio::time::Instant::now() + COMFYUI_TIMEOUT;
+    let image = loop {
+        if tokio::time::Instant::now() > deadline {
+            return Err("Timed out waiting for ComfyUI".to_string());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let history: serde_json::Value = client
+            .get(format!("{}/history/{}", endpoint, queued.prompt_id))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        let images = &history[&queued.prompt_id]["outputs"]["7"]["images"][0];
+        if !images.is_null() {
+            break serde_json::from_value::<ComfyImage>(images.clone()).map_err(|e| e.to_string())?;
+        }
+    };
+    
+    let response = client
+        .get(format!("{}/view", endpoint))
+        .query(&[("filename", &image.filename), ("subfolder", &image.subfolder), ("type", &image.kind)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("ComfyUI image download failed ({})", response.status()));
+    }
+    Ok(response.bytes().await.map_err(|e| e.to_string())?.to_vec())
+}
+
+/// Check that an image backend answers at `endpoint`
+pub async fn test_backend(backend: ImageBackend, endpoint: &str) -> Result<bool, String> {
+    let path = match backend {
+        ImageBackend::Disabled => return Ok(false),
+        ImageBackend::Automatic1111 => "sdapi/v1/sd-models",
+        ImageBackend::ComfyUi => "system_stats",
+    };
+    let url = format!("{}/{}", endpoint.trim_end_matches('/'), path);
+    let response = http::client().get(&url).timeout(http::PROBE_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+    Ok(response.status().is_success())
+}
+
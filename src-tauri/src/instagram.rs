@@ -0,0 +1,115 @@
+// Instagram Reels publishing through the Graph API (Business accounts)
+use crate::http;
+use serde::Deserialize;
+use std::time::Duration;
+
+const GRAPH_URL: &str = "https://graph.facebook.com/v21.0";
+
+/// Instagram processes the video before it can be published
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_STATUS_POLLS: u32 = 60;
+
+/// Instagram's caption limit, in characters
+const MAX_CAPTION_CHARS: usize = 2200;
+
+/// A published Reel
+pub struct Reel {
+    pub media_id: String,
+    pub permalink: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Created {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ContainerStatus {
+    status_code: String,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Media {
+    permalink: Option<String>,
+}
+
+/// Publish the video at `video_url` as a Reel: create a container, wait for
+/// Instagram to fetch and process it, then publish. The URL must stay
+/// reachable until this returns.
+pub async fn publish_reel(access_token: &str, user_id: &str, video_url: &str, caption: &str) -> Result<Reel, String> {
+    let client = http::client();
+    let caption: String = caption.chars().take(MAX_CAPTION_CHARS).collect();
+    let response = client
+        .post(format!("{}/{}/media", GRAPH_URL, user_id))
+        .form(&[
+            ("media_type", "REELS"),
+            ("video_url", video_url),
+            ("caption", &caption),
+            ("share_to_feed", "true"),
+            ("access_token", access_token),
+        ])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let container: Created = parse(response, "container creation").await?;
+    
+    let mut polls = 0;
+    loop {
+        let request = client
+            .get(format!("{}/{}", GRAPH_URL, container.id))
+            .query(&[("fields", "status_code,status"), ("access_token", access_token)]);
+        let response = http::send(request)
+            .await
+            .map_err(|e| e.to_string())?;
+        let status: ContainerStatus = parse(response, "container status").await?;
+        match status.status_code.as_str() {
+            "FINISHED" => break,
+            "ERROR" | "EXPIRED" => {
+                return Err(format!(
+                    "Instagram could not process the video: {}",
+                    status.status.unwrap_or(status.status_code)
+                ));
+            }
+            _ => {}
+        }
+        polls += 1;
+        if polls >= MAX_STATUS_POLLS {
+            return Err("Timed out waiting for Instagram to process the video".to_string());
+        }
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+    }
+    
+    let response = client
+        .post(format!("{}/{}/media_publish", GRAPH_URL, user_id))
+        .form(&[("creation_id", container.id.as_str()), ("access_token", access_token)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let published: Created = parse(response, "publish").await?;
+    
+    // The link is a nicety; the Reel is live either way
+    let permalink = async {
+        let request = client
+            .get(format!("{}/{}", GRAPH_URL, published.id))
+            .query(&[("fields", "permalink"), ("access_token", access_token)]);
+        let response = http::send(request)
+            .await
+            .map_err(|e| e.to_string())?;
+        parse::<Media>(response, "media lookup").await
+    }
+    .await
+    .map_err(|e| tracing::warn!("Instagram permalink lookup failed: {}", e))
+    .ok()
+    .and_then(|m| m.permalink);
+    Ok(Reel { media_id: published.id, permalink })
+}
+
+async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response, what: &str) -> Result<T, String> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Instagram {} failed ({}): {}", what, status, body));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
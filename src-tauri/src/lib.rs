@@ -1,64 +1,2968 @@
 // ShotAuto - YouTube Shorts Automation Desktop App
 
+mod analytics;
+mod api_server;
+mod artifact_store;
+mod audio;
+mod autostart;
+mod backups;
+mod bundle;
+mod cache;
+mod captions;
+mod clients;
+mod config_check;
+mod config_file;
+mod costs;
+mod crypto;
 mod db;
+mod deep_link;
+mod demo;
+mod deps;
+mod discord;
+mod dry_run;
+mod downloader;
+mod drive;
+#[cfg(feature = "sqlcipher")]
+mod encryption;
+#[cfg(feature = "face-detection")]
+mod faces;
+mod error;
+mod export;
+mod hashtags;
+mod health;
+mod http;
+mod image_gen;
+mod instagram;
+mod llm;
+mod logging;
+mod maintenance;
+mod media_host;
+#[cfg(test)]
+mod mock_server;
+mod moderation;
+mod notifications;
+mod music;
+mod pipeline;
+mod preview;
+mod process;
+mod prompt;
+mod reframe;
+mod render;
+mod resilience;
+mod schedule;
+mod scenes;
+mod sfx;
+mod sftp;
+mod similarity;
+mod stock;
+mod storage;
+mod telegram;
+mod thumbnails;
+mod timing;
+mod transcribe;
+mod transfers;
+mod trends;
+mod tray;
+mod tts;
+mod webhooks;
+mod youtube_api;
+mod youtube_upload;
 
-use db::{Config, Database, DashboardStats};
-use std::sync::Mutex;
-use tauri::State;
+use db::{Account, Activity, ActivityAction, Actor, ApprovalStatus, Config, Database, DashboardStats, DeadJob, Example, ExportScope, ImageBackend, ImportedShort, JobDetails, JobLogPage, JobStatus, LogLevel, MetricsRange, MonthlyCost, MusicTrack, PipelineStep, Profile, PromptTemplate, Publication, PublicationStatus, RenderTemplate, Scene, SeriesBucket, SeriesMetric, SeriesPoint, Short, ShortPerformance, SoundEffect, StageMetrics, TopicPerformance, Trend, TrendSource, TrendState, TrendViews, TtsBackend, UploadTemplate, VideoEncoder, Webhook};
+use cache::{CacheKind, CacheStats};
+use clients::Clients;
+use error::AppError;
+use pipeline::JobLogger;
+use llm::LlmClient;
+use prompt::HookStyle;
+use telegram::TelegramClient;
+use tts::TtsEngine;
+use webhooks::WebhookEvent;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+#[cfg(any(target_os = "macos", target_os = "linux", all(debug_assertions, windows)))]
+use tauri_plugin_deep_link::DeepLinkExt;
 
 /// Application state managed by Tauri
 pub struct AppState {
-    pub db: Mutex<Database>,
+    pub db: Database,
+    /// Root of the app data directory (database, audio, renders)
+    pub app_dir: PathBuf,
+    /// YouTube, Telegram, LLM and TTS API clients
+    pub clients: Clients,
+}
+
+/// Run a database call on the blocking thread pool. Used by commands whose
+/// queries scan whole tables, so they don't tie up an async worker.
+async fn with_db<T: Send + 'static>(
+    state: &AppState,
+    work: impl FnOnce(&Database) -> rusqlite::Result<T> + Send + 'static,
+) -> Result<T, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || work(&db))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::from)
 }
 
 // ==================== Tauri Commands ====================
 
-/// Get current configuration
+/// Get current configuration
+#[tauri::command]
+fn get_config(state: State<AppState>) -> Result<Config, AppError> {
+    state.db.load_config().map_err(AppError::from)
+}
+
+/// Save configuration; returns the config as stored, so the settings
+/// screen shows what will actually be used
+#[tauri::command]
+fn save_config(app: AppHandle, state: State<AppState>, config: Config) -> Result<Config, AppError> {
+    config.run_windows.validate().map_err(AppError::Validation)?;
+    let db = &state.db;
+    let before = db.load_config()?;
+    db.save_config(&config)?;
+    let saved = db.load_config()?;
+    let changed = config_file::changed_keys(&before, &saved);
+    if !changed.is_empty() {
+        record_activity(db, Actor::Ui, ActivityAction::ConfigChanged, None, None, Some(&changed.join(", ")));
+    }
+    if saved.launch_on_startup != before.launch_on_startup {
+        autostart::apply(&app, saved.launch_on_startup).map_err(AppError::Internal)?;
+    }
+    Ok(saved)
+}
+
+/// Remove one setting so its default applies; returns the resulting config
+#[tauri::command]
+fn delete_config(state: State<AppState>, key: String) -> Result<Config, AppError> {
+    let known = serde_json::to_value(Config::default())
+        .ok()
+        .and_then(|config| config.as_object().map(|fields| fields.contains_key(&key)))
+        .unwrap_or(false);
+    if !known {
+        return Err(AppError::Validation(format!("Unknown setting '{}'", key)));
+    }
+    let db = &state.db;
+    db.delete_config(&key)?;
+    record_activity(db, Actor::Ui, ActivityAction::ConfigChanged, None, None, Some(&key));
+    db.load_config().map_err(AppError::from)
+}
+
+/// Check settings before they are used. Checks `config` when given, e.g.
+/// the unsaved form, otherwise the saved settings.
+#[tauri::command]
+async fn validate_config(state: State<'_, AppState>, config: Option<Config>) -> Result<Vec<config_check::Diagnostic>, AppError> {
+    let config = match config {
+        Some(config) => config,
+        None => state.db.load_config()?,
+    };
+    Ok(config_check::validate(&config, &state.app_dir).await)
+}
+
+/// Write the settings to a TOML or JSON file (by extension). API keys and
+/// tokens are left out unless `include_secrets` is set.
+#[tauri::command]
+fn export_config(state: State<AppState>, path: String, include_secrets: bool) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    config_file::export(&config, Path::new(&path), include_secrets).map_err(AppError::from)
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigImport {
+    changes: Vec<config_file::ConfigChange>,
+    /// False for a dry run, or when the file changes nothing
+    applied: bool,
+}
+
+/// Load settings from a file written by `export_config`. With `dry_run` only
+/// the changes are returned, so they can be reviewed before applying.
+#[tauri::command]
+fn import_config(state: State<AppState>, path: String, dry_run: bool) -> Result<ConfigImport, AppError> {
+    let db = &state.db;
+    let current = db.load_config()?;
+    let (config, changes) = config_file::plan_import(&current, Path::new(&path)).map_err(AppError::Validation)?;
+    config.run_windows.validate().map_err(AppError::Validation)?;
+    let applied = !dry_run && !changes.is_empty();
+    if applied {
+        db.save_config(&config)?;
+        let keys: Vec<&str> = changes.iter().map(|change| change.key.as_str()).collect();
+        record_activity(db, Actor::Ui, ActivityAction::ConfigChanged, None, None, Some(&format!("Imported {}", keys.join(", "))));
+    }
+    Ok(ConfigImport { changes, applied })
+}
+
+/// Copy the database to `dest_path`; safe while the pipeline is running
+#[tauri::command]
+async fn backup_database(state: State<'_, AppState>, dest_path: String) -> Result<(), AppError> {
+    with_db(&state, move |db| db.backup_to(std::path::Path::new(&dest_path))).await
+}
+
+/// Replace the database with a backup. The current database is copied to
+/// the backups folder first; returns that copy's path. The queue is paused
+/// and the running jobs have to finish first, so none writes into the
+/// restored database.
+#[tauri::command]
+async fn restore_database(state: State<'_, AppState>, src_path: String) -> Result<String, AppError> {
+    let src = PathBuf::from(&src_path);
+    if !src.is_file() {
+        return Err(AppError::Validation(format!("Backup file not found: {}", src_path)));
+    }
+    let version = state.db.schema_version_of(&src)
+        .map_err(|e| AppError::Validation(format!("Not a readable SQLite database: {}", e)))?
+        .ok_or_else(|| AppError::Validation(format!("{} is not a ShotAuto database", src_path)))?;
+    if version > db::SCHEMA_VERSION {
+        return Err(AppError::Validation(format!(
+            "The backup is from a newer version of ShotAuto (schema {}, this build reads up to {})",
+            version,
+            db::SCHEMA_VERSION
+        )));
+    }
+    
+    let safety_copy = backups::safety_copy_path(&state.app_dir);
+    if let Some(dir) = safety_copy.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let dest = safety_copy.clone();
+    
+    let was_paused = pipeline::is_paused();
+    pipeline::set_paused(true);
+    let grace = Duration::from_secs(state.db.load_config()?.shutdown_grace_secs);
+    let restored = if pipeline::wait_idle(grace).await {
+        with_db(&state, move |db| {
+            db.backup_to(&dest)?;
+            db.restore_from(&src)
+        })
+        .await
+    } else {
+        Err(AppError::Validation("Jobs are still running; restore once they have finished".into()))
+    };
+    if !was_paused {
+        pipeline::set_paused(false);
+    }
+    restored?;
+    tracing::info!("Restored the database from {}; previous copy at {}", src_path, safety_copy.display());
+    Ok(safety_copy.to_string_lossy().to_string())
+}
+
+/// Whether this build can encrypt the database, whether it is encrypted,
+/// and whether encryption waits for a restart
+#[derive(serde::Serialize)]
+struct EncryptionStatus {
+    available: bool,
+    encrypted: bool,
+    pending_restart: bool,
+}
+
+#[tauri::command]
+fn get_encryption_status(state: State<AppState>) -> EncryptionStatus {
+    EncryptionStatus {
+        available: cfg!(feature = "sqlcipher"),
+        encrypted: state.db.is_encrypted(),
+        #[cfg(feature = "sqlcipher")]
+        pending_restart: encryption::is_pending(state.db.path()),
+        #[cfg(not(feature = "sqlcipher"))]
+        pending_restart: false,
+    }
+}
+
+/// Encrypt an existing database with SQLCipher. The passphrase goes to the
+/// OS keychain and the database is converted the next time the app starts.
+#[tauri::command]
+fn encrypt_database(state: State<AppState>, passphrase: String) -> Result<(), AppError> {
+    #[cfg(feature = "sqlcipher")]
+    {
+        if state.db.is_encrypted() {
+            return Err(AppError::Validation("The database is already encrypted".into()));
+        }
+        if passphrase.chars().count() < encryption::MIN_PASSPHRASE_LEN {
+            return Err(AppError::Validation(format!(
+                "The passphrase needs at least {} characters",
+                encryption::MIN_PASSPHRASE_LEN
+            )));
+        }
+        encryption::schedule(state.db.path(), &passphrase)?;
+        tracing::info!("Database encryption scheduled for the next start");
+        Ok(())
+    }
+    #[cfg(not(feature = "sqlcipher"))]
+    {
+        let _ = (state, passphrase);
+        Err(AppError::NotConfigured("This build has no database encryption; build with the sqlcipher feature".into()))
+    }
+}
+
+/// Zip a rendered short with its thumbnail, script, upload metadata and
+/// captions to `dest`, for publishing by hand or editing elsewhere
+#[tauri::command]
+async fn export_short_bundle(state: State<'_, AppState>, short_id: i64, dest: String) -> Result<(), AppError> {
+    let (mut short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let video = local_video(&state, &state.db.load_config()?, &short).await?;
+    short.video_path = Some(video.to_string_lossy().to_string());
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let metadata = match short.upload_metadata {
+            Some(ref metadata) => metadata.clone(),
+            None => build_upload_metadata(&db, &short, &trend)?,
+        };
+        bundle::export(&short, &trend, &metadata, Path::new(&dest))?;
+        Ok::<_, AppError>(())
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Add the short in a bundle made by `export_short_bundle` as a finished
+/// job under the bundle's topic. Returns the new short's id.
+#[tauri::command]
+async fn import_short_bundle(state: State<'_, AppState>, path: String) -> Result<i64, AppError> {
+    // The files are extracted and probed before anything is stored, under
+    // a name of their own since the short has no id yet
+    let stem = format!("import_{}", Utc::now().format("%Y%m%d%H%M%S%3f"));
+    let video = state.app_dir.join("renders").join(format!("{}.mp4", stem));
+    let thumbnail = state.app_dir.join("thumbnails").join(format!("{}.jpg", stem));
+    let remove_media = || {
+        let _ = std::fs::remove_file(&video);
+        let _ = std::fs::remove_file(&thumbnail);
+    };
+    let contents = {
+        let (video, thumbnail) = (video.clone(), thumbnail.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            let path = Path::new(&path);
+            let contents = bundle::read(path)?;
+            for dir in [video.parent(), thumbnail.parent()].into_iter().flatten() {
+                std::fs::create_dir_all(dir)?;
+            }
+            bundle::extract_media(path, &video, &thumbnail)?;
+            Ok::<_, AppError>(contents)
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+    };
+    let contents = contents.inspect_err(|_| remove_media())?;
+    
+    let duration = match contents.manifest.duration_sec {
+        Some(duration) => duration,
+        None => {
+            let config = state.db.load_config()?;
+            render::probe_duration(&config, &video).await
+                .map_err(AppError::Ffmpeg)
+                .inspect_err(|_| remove_media())?
+        }
+    };
+    let trend = trends::topic_trend(&contents.manifest.topic);
+    let short = ImportedShort {
+        language: contents.manifest.language,
+        script: contents.script,
+        video_path: video.to_string_lossy().to_string(),
+        duration_sec: duration,
+        thumbnail_path: contents.has_thumbnail.then(|| thumbnail.to_string_lossy().to_string()),
+        timing: contents.timing,
+        metadata: contents.metadata,
+    };
+    let imported = with_db(&state, move |db| {
+        let (job_id, short_id) = db.import_short(&trend, &short)?;
+        record_activity(db, Actor::Ui, ActivityAction::JobCreated, Some(job_id), Some(short_id), Some("bundle import"));
+        Ok(short_id)
+    })
+    .await;
+    imported.inspect_err(|_| remove_media())
+}
+
+/// Write trends, jobs, shorts or metrics to `path` as CSV or JSON Lines;
+/// returns the number of rows written
+#[tauri::command]
+async fn export_data(
+    state: State<'_, AppState>,
+    scope: ExportScope,
+    format: export::ExportFormat,
+    path: String,
+) -> Result<u64, AppError> {
+    let db = state.db.clone();
+    let rows = tauri::async_runtime::spawn_blocking(move || export::export(&db, scope, format, Path::new(&path)))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+    Ok(rows)
+}
+
+/// Bytes used by renders, audio, caches, backups and the database
+#[tauri::command]
+async fn get_storage_stats(state: State<'_, AppState>) -> Result<storage::StorageStats, AppError> {
+    let app_dir = state.app_dir.clone();
+    let db_path = state.db.path().to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || storage::stats(&app_dir, &db_path))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// Delete draft renders, cached source clips and media of rejected shorts
+#[tauri::command]
+async fn cleanup_storage(
+    state: State<'_, AppState>,
+    options: storage::CleanupOptions,
+) -> Result<storage::CleanupReport, AppError> {
+    let db = state.db.clone();
+    let app_dir = state.app_dir.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || storage::cleanup(&db, &app_dir, &options))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+    tracing::info!("Cleanup deleted {} files ({} bytes)", report.files_deleted, report.bytes_freed);
+    Ok(report)
+}
+
+/// Entries and disk use of the media cache, per kind
+#[tauri::command]
+async fn get_cache_stats(state: State<'_, AppState>) -> Result<Vec<CacheStats>, AppError> {
+    with_db(&state, |db| db.cache_stats()).await
+}
+
+/// Empty the media cache, or only one kind of it; fetched again when needed
+#[tauri::command]
+async fn clear_cache(state: State<'_, AppState>, kind: Option<CacheKind>) -> Result<storage::CleanupReport, AppError> {
+    let db = state.db.clone();
+    let app_dir = state.app_dir.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || cache::clear(&db, &app_dir, kind))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+    tracing::info!(
+        "Cleared the {} cache: {} files ({} bytes)",
+        kind.map(|k| k.as_str()).unwrap_or("media"),
+        report.files_deleted,
+        report.bytes_freed
+    );
+    Ok(report)
+}
+
+/// Get dashboard statistics
+#[tauri::command]
+async fn get_stats(state: State<'_, AppState>) -> Result<DashboardStats, AppError> {
+    with_db(&state, |db| db.get_stats()).await
+}
+
+/// What the job queue is doing
+#[tauri::command]
+async fn get_queue_status(state: State<'_, AppState>) -> Result<pipeline::QueueStatus, AppError> {
+    with_db(&state, pipeline::status).await
+}
+
+/// Stop or resume claiming new jobs; jobs already running finish
+#[tauri::command]
+fn set_queue_paused(state: State<AppState>, paused: bool) -> Result<pipeline::QueueStatus, AppError> {
+    pipeline::set_paused(paused);
+    health::queue_toggled();
+    pipeline::status(&state.db).map_err(AppError::from)
+}
+
+/// Fetch trends and run jobs for the next `minutes` (default 120) even
+/// outside the configured run windows
+#[tauri::command]
+fn run_now(state: State<AppState>, minutes: Option<u32>) -> Result<pipeline::QueueStatus, AppError> {
+    let minutes = minutes.unwrap_or(120);
+    if minutes == 0 {
+        return Err(AppError::Validation("minutes must be at least 1".into()));
+    }
+    pipeline::run_now(chrono::Duration::minutes(minutes.into()));
+    trends::fetch_now();
+    pipeline::status(&state.db).map_err(AppError::from)
+}
+
+/// Downloads and uploads in progress, with their rates and caps
+#[tauri::command]
+fn get_transfer_status(state: State<AppState>) -> Result<transfers::TransferStatus, AppError> {
+    let config = state.db.load_config()?;
+    Ok(transfers::status(&config))
+}
+
+/// Fetch trending videos now. Returns how many were fetched.
+#[tauri::command]
+async fn fetch_trends(state: State<'_, AppState>) -> Result<usize, AppError> {
+    trends::refresh(&state).await
+        .map_err(AppError::Http)?
+        .ok_or_else(|| AppError::NotConfigured("YouTube API key is not configured".into()))
+}
+
+/// Store the YouTube video a link points to as a trend; returns its id
+#[tauri::command]
+async fn add_trend_from_url(state: State<'_, AppState>, url: String) -> Result<i64, AppError> {
+    if trends::video_id_from_url(&url).is_none() {
+        return Err(AppError::Validation(format!("Not a YouTube video link: {}", url)));
+    }
+    trends::add_from_url(&state, &url).await.map_err(AppError::Http)
+}
+
+/// Add the video links among a launch's arguments as trends. Arguments come
+/// from the first launch or are forwarded from later ones, which is also how
+/// `shotauto://` links arrive on Windows and Linux.
+fn open_launch_args(app: &AppHandle, args: Vec<String>) {
+    open_links(app, args.into_iter().skip(1).collect());
+}
+
+/// Add YouTube video links and `shotauto://add` links as trends; anything
+/// else is ignored
+fn open_links(app: &AppHandle, links: Vec<String>) {
+    let sources: Vec<String> = links.into_iter()
+        .filter_map(|link| {
+            if deep_link::is_deep_link(&link) {
+                match deep_link::parse(&link) {
+                    Ok(deep_link::DeepLink::AddTrend(source)) => Some(source),
+                    Err(e) => {
+                        tracing::warn!("{}", e);
+                        None
+                    }
+                }
+            } else if link.starts_with("http://") || link.starts_with("https://") {
+                Some(link)
+            } else {
+                None
+            }
+        })
+        .collect();
+    if sources.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        for source in sources {
+            match trends::add_from_url(&state, &source).await {
+                Ok(trend_id) => {
+                    let _ = app.emit("trend:added", serde_json::json!({ "trend_id": trend_id }));
+                }
+                Err(e) => tracing::warn!("Failed to add {} as a trend: {}", source, e),
+            }
+        }
+    });
+}
+
+/// Run the moderation checks on a script; returns the flag reason, if any
+#[tauri::command]
+async fn check_script(state: State<'_, AppState>, script: String) -> Result<Option<String>, AppError> {
+    let config = state.db.load_config()?;
+    match moderation::moderate_script(&state.clients, &config, &script).await.map_err(AppError::Llm)? {
+        moderation::Verdict::Approved => Ok(None),
+        moderation::Verdict::Flagged(reason) => Ok(Some(reason)),
+    }
+}
+
+/// Transcribe a trend's source video and store the transcript on the trend
+#[tauri::command]
+async fn transcribe_trend(state: State<'_, AppState>, trend_id: i64) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let trend = state.db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    if trend.source != TrendSource::YouTube {
+        return Err(AppError::Validation("This trend has no source video to transcribe".into()));
+    }
+    
+    let transcript = transcribe::transcribe_video(&config, &trend.video_id).await?;
+    
+    let db = &state.db;
+    db.set_trend_transcript(trend_id, &transcript)?;
+    Ok(transcript)
+}
+
+/// Download a trend's source video into the cache, emitting
+/// `download-progress` events while yt-dlp runs
+#[tauri::command]
+async fn download_source(app: AppHandle, state: State<'_, AppState>, trend_id: i64) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let trend = state.db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    if trend.source != TrendSource::YouTube {
+        return Err(AppError::Validation("This trend has no source video to download".into()));
+    }
+    
+    let path = downloader::download_source(&config, &state.db, &state.app_dir, &trend.video_id, |progress| {
+        let _ = app.emit("download-progress", progress);
+    })
+    .await
+    .map_err(AppError::Ffmpeg)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Stored trends, newest first. With `starred_only`, just the starred ones;
+/// with `trend_state`, just those in that state.
+#[tauri::command]
+fn list_trends(
+    state: State<AppState>,
+    starred_only: Option<bool>,
+    trend_state: Option<TrendState>,
+    limit: Option<u32>,
+) -> Result<Vec<Trend>, AppError> {
+    state.db.list_trends(starred_only.unwrap_or(false), trend_state, limit.unwrap_or(100)).map_err(AppError::from)
+}
+
+/// Set a trend aside so it isn't made into a short, noting why
+#[tauri::command]
+fn skip_trend(state: State<AppState>, trend_id: i64, reason: Option<String>) -> Result<(), AppError> {
+    let reason = reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if !state.db.skip_trend(trend_id, reason)? {
+        return Err(AppError::not_found("Trend", trend_id));
+    }
+    Ok(())
+}
+
+/// Replace a trend's notes; an empty note clears them
+#[tauri::command]
+fn set_trend_note(state: State<AppState>, trend_id: i64, note: Option<String>) -> Result<(), AppError> {
+    let note = note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if !state.db.set_trend_note(trend_id, note)? {
+        return Err(AppError::not_found("Trend", trend_id));
+    }
+    Ok(())
+}
+
+/// Star or unstar a trend, returning whether it is now starred
+#[tauri::command]
+fn toggle_trend_star(state: State<AppState>, trend_id: i64) -> Result<bool, AppError> {
+    state.db.toggle_trend_star(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))
+}
+
+/// A trend's view count at every poll, oldest first
+#[tauri::command]
+fn get_trend_history(state: State<AppState>, trend_id: i64) -> Result<Vec<TrendViews>, AppError> {
+    let db = &state.db;
+    db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    db.get_trend_history(trend_id).map_err(AppError::from)
+}
+
+/// Render the script prompt for a trend, as it would be sent to the LLM
+#[tauri::command]
+fn preview_prompt(state: State<AppState>, trend_id: i64, language: Option<String>) -> Result<String, AppError> {
+    let db = &state.db;
+    let config = db.load_config()?;
+    let trend = db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    let language = language.as_deref().unwrap_or("en");
+    let (prompt, _) = prompt::build_script_prompt(db, &config, &trend, language, None)?;
+    Ok(prompt)
+}
+
+/// Generate a script for a trend without creating a job, using the
+/// matching template's sampling parameters
+#[tauri::command]
+async fn generate_script(state: State<'_, AppState>, trend_id: i64, language: Option<String>) -> Result<String, AppError> {
+    write_script(&state, trend_id, language.as_deref().unwrap_or("en"), None, &JobLogger::none(&state.db)).await
+}
+
+/// Queue a job that turns a trend into shorts in every target language.
+/// A trend too similar to a short already made is refused unless `force`
+/// is set.
+#[tauri::command]
+fn create_job(state: State<AppState>, trend_id: i64, priority: Option<i32>, force: Option<bool>) -> Result<i64, AppError> {
+    let db = &state.db;
+    let trend = db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    if !force.unwrap_or(false) {
+        check_not_duplicate(db, &db.load_config()?, &trend)?;
+    }
+    let job_id = db.create_job(trend_id, priority.unwrap_or(0))?;
+    record_activity(db, Actor::Ui, ActivityAction::JobCreated, Some(job_id), None, None);
+    Ok(job_id)
+}
+
+/// Queue a job like `create_job` that isn't started until every job in
+/// `depends_on_job_ids` is done, such as a compilation of other shorts.
+/// If one of those dies or is trashed first, this job is marked dead.
+#[tauri::command]
+fn create_dependent_job(
+    state: State<AppState>,
+    trend_id: i64,
+    depends_on_job_ids: Vec<i64>,
+    priority: Option<i32>,
+    force: Option<bool>,
+) -> Result<i64, AppError> {
+    let db = &state.db;
+    let trend = db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    if depends_on_job_ids.is_empty() {
+        return Err(AppError::Validation("Pick at least one job to wait for".into()));
+    }
+    for &depends_on_job_id in &depends_on_job_ids {
+        match db.job_status(depends_on_job_id)? {
+            None => return Err(AppError::not_found("Job", depends_on_job_id)),
+            Some((_, true)) => return Err(AppError::Validation(format!("Job {} is in the trash", depends_on_job_id))),
+            Some((JobStatus::Dead, _)) => {
+                return Err(AppError::Validation(format!("Job {} is dead; requeue it first", depends_on_job_id)));
+            }
+            Some(_) => {}
+        }
+    }
+    if !force.unwrap_or(false) {
+        check_not_duplicate(db, &db.load_config()?, &trend)?;
+    }
+    let job_id = db.create_dependent_job(trend_id, &depends_on_job_ids, priority.unwrap_or(0))?;
+    let ids: Vec<String> = depends_on_job_ids.iter().map(i64::to_string).collect();
+    let detail = format!("after job {}", ids.join(", "));
+    record_activity(db, Actor::Ui, ActivityAction::JobCreated, Some(job_id), None, Some(&detail));
+    Ok(job_id)
+}
+
+/// Error out when `trend` is a near-duplicate of a short already made,
+/// according to `similarity_threshold`
+fn check_not_duplicate(db: &Database, config: &Config, trend: &Trend) -> Result<(), AppError> {
+    if config.similarity_threshold <= 0.0 {
+        return Ok(());
+    }
+    let matches = similarity::find_similar(db, trend, config.similarity_threshold)?;
+    match matches.first() {
+        Some(m) => Err(AppError::Validation(format!(
+            "\"{}\" is {:.0}% similar to short #{} (\"{}\")",
+            trend.title,
+            m.score * 100.0,
+            m.short_id,
+            m.title
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Shorts already made that cover much the same ground as a trend, most
+/// similar first. `threshold` defaults to `similarity_threshold`.
+#[tauri::command]
+async fn find_similar(
+    state: State<'_, AppState>,
+    trend_id: i64,
+    threshold: Option<f64>,
+) -> Result<Vec<similarity::SimilarShort>, AppError> {
+    with_db(&state, move |db| {
+        let Some(trend) = db.get_trend(trend_id)? else {
+            return Ok(None);
+        };
+        let threshold = match threshold {
+            Some(threshold) => threshold,
+            None => db.load_config()?.similarity_threshold,
+        };
+        similarity::find_similar(db, &trend, threshold).map(Some)
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Trend", trend_id))
+}
+
+/// What became of one input to `create_jobs_batch`
+#[derive(Debug, Serialize)]
+struct BatchItem {
+    input: String,
+    trend_id: Option<i64>,
+    job_id: Option<i64>,
+    error: Option<String>,
+}
+
+/// Queue a job for each YouTube link or free-text topic, at
+/// `batch_priority` unless `priority` is given. Links are looked up through
+/// the API; topics become trends of their own. Near-duplicates of shorts
+/// already made are skipped. An input that fails doesn't stop the rest.
+#[tauri::command]
+async fn create_jobs_batch(
+    state: State<'_, AppState>,
+    inputs: Vec<String>,
+    priority: Option<i32>,
+) -> Result<Vec<BatchItem>, AppError> {
+    let config = state.db.load_config()?;
+    let priority = priority.unwrap_or(config.batch_priority);
+    let mut items = Vec::new();
+    for input in inputs.iter().map(|i| i.trim()).filter(|i| !i.is_empty()) {
+        let trend = match trends::as_link(input) {
+            Some(url) => trends::add_from_url(&state, &url).await,
+            None => trends::add_topic(&state.db, input).map_err(|e| e.to_string()),
+        };
+        let trend_id = match trend {
+            Ok(trend_id) => trend_id,
+            Err(e) => {
+                items.push(BatchItem { input: input.to_string(), trend_id: None, job_id: None, error: Some(e) });
+                continue;
+            }
+        };
+        let created = create_batch_job(&state.db, &config, trend_id, priority);
+        items.push(BatchItem {
+            input: input.to_string(),
+            trend_id: Some(trend_id),
+            job_id: created.as_ref().ok().copied(),
+            error: created.err().map(|e| e.to_string()),
+        });
+    }
+    let created = items.iter().filter(|i| i.job_id.is_some()).count();
+    tracing::info!("Batch queued {} of {} jobs", created, items.len());
+    Ok(items)
+}
+
+/// One job of `create_jobs_batch`, unless the trend is a near-duplicate
+fn create_batch_job(db: &Database, config: &Config, trend_id: i64, priority: i32) -> Result<i64, AppError> {
+    let trend = db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    check_not_duplicate(db, config, &trend)?;
+    let job_id = db.create_job(trend_id, priority)?;
+    record_activity(db, Actor::Ui, ActivityAction::JobCreated, Some(job_id), None, Some("batch"));
+    Ok(job_id)
+}
+
+/// Jobs that failed every attempt, with their failure history
+#[tauri::command]
+async fn list_dead_jobs(state: State<'_, AppState>) -> Result<Vec<DeadJob>, AppError> {
+    with_db(&state, |db| db.list_dead_jobs()).await
+}
+
+/// A job with its trend, shorts, failed attempts, stage metrics and latest
+/// log entries
+#[tauri::command]
+async fn get_job_details(state: State<'_, AppState>, job_id: i64) -> Result<JobDetails, AppError> {
+    with_db(&state, move |db| db.get_job_details(job_id)).await?
+        .ok_or_else(|| AppError::not_found("Job", job_id))
+}
+
+/// A page of a job's log, oldest entry first
+#[tauri::command]
+async fn get_job_logs(
+    state: State<'_, AppState>,
+    job_id: i64,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<JobLogPage, AppError> {
+    with_db(&state, move |db| db.get_job_logs(job_id, offset.unwrap_or(0), limit.unwrap_or(200))).await
+}
+
+/// A page of the activity log, newest first. Pass the last id of a page as
+/// `before_id` to get the one after it.
+#[tauri::command]
+async fn get_activity(state: State<'_, AppState>, limit: Option<u32>, before_id: Option<i64>) -> Result<Vec<Activity>, AppError> {
+    with_db(&state, move |db| db.get_activity(limit.unwrap_or(100), before_id)).await
+}
+
+/// Record an action in the activity log. The action has already happened,
+/// so a failed write is only logged.
+fn record_activity(
+    db: &Database,
+    actor: Actor,
+    action: ActivityAction,
+    job_id: Option<i64>,
+    short_id: Option<i64>,
+    detail: Option<&str>,
+) {
+    if let Err(e) = db.log_activity(actor, action, job_id, short_id, detail) {
+        tracing::warn!("Recording {} in the activity log failed: {}", action.as_str(), e);
+    }
+}
+
+/// Estimated spend on paid APIs per month, newest first, over the last
+/// `months` months (default 12)
+#[tauri::command]
+async fn get_cost_report(state: State<'_, AppState>, months: Option<u32>) -> Result<Vec<MonthlyCost>, AppError> {
+    with_db(&state, move |db| db.get_cost_report(months.unwrap_or(12))).await
+}
+
+/// The newest `limit` app log messages at `level` or above (default info)
+/// containing `filter`, oldest first
+#[tauri::command]
+async fn get_logs(
+    state: State<'_, AppState>,
+    level: Option<LogLevel>,
+    limit: Option<u32>,
+    filter: Option<String>,
+) -> Result<Vec<logging::LogLine>, AppError> {
+    let app_dir = state.app_dir.clone();
+    let lines = tauri::async_runtime::spawn_blocking(move || {
+        logging::read(&app_dir, level.unwrap_or_default(), limit.unwrap_or(500) as usize, filter.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(lines)
+}
+
+/// Show the folder holding the app log files
+#[tauri::command]
+fn open_log_folder(state: State<AppState>) -> Result<(), AppError> {
+    let dir = logging::log_dir(&state.app_dir);
+    std::fs::create_dir_all(&dir)?;
+    tauri_plugin_opener::open_path(&dir, None::<&str>).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// p50/p95 duration of each pipeline stage over a time window
+#[tauri::command]
+async fn get_stage_metrics(state: State<'_, AppState>, range: Option<MetricsRange>) -> Result<Vec<StageMetrics>, AppError> {
+    with_db(&state, move |db| db.get_stage_metrics(range.unwrap_or_default())).await
+}
+
+/// Bucketed time series of one metric, for dashboard charts
+#[tauri::command]
+async fn get_metrics_series(
+    state: State<'_, AppState>,
+    metric: SeriesMetric,
+    bucket: Option<SeriesBucket>,
+    range: Option<MetricsRange>,
+) -> Result<Vec<SeriesPoint>, AppError> {
+    with_db(&state, move |db| db.get_metrics_series(metric, bucket.unwrap_or_default(), range.unwrap_or_default())).await
+}
+
+/// Retry a dead job from the stage it last failed in, with a fresh set of attempts
+#[tauri::command]
+fn requeue_dead_job(state: State<AppState>, job_id: i64) -> Result<(), AppError> {
+    let db = &state.db;
+    let stage = db.get_job_errors(job_id)?
+        .last()
+        .and_then(|error| pipeline::Stage::from_str(&error.stage))
+        .unwrap_or(pipeline::Stage::Generation);
+    if !db.requeue_dead_job(job_id, stage.queued_status())? {
+        return Err(AppError::Validation(format!("Job {} is not dead", job_id)));
+    }
+    Ok(())
+}
+
+/// Script for a trend in `language`, straight from the LLM
+async fn write_script(
+    state: &AppState,
+    trend_id: i64,
+    language: &str,
+    hook_style: Option<HookStyle>,
+    log: &JobLogger<'_>,
+) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let trend = state.db.get_trend(trend_id)?
+        .ok_or_else(|| AppError::not_found("Trend", trend_id))?;
+    let (prompt, params) = prompt::build_script_prompt(&state.db, &config, &trend, language, hook_style)?;
+    log.debug(format!("{} script prompt: {} chars", language, prompt.chars().count()));
+    let script = state.clients.llm(&config)
+        .map_err(AppError::Llm)?
+        .complete(&prompt, &params)
+        .await
+        .map_err(AppError::Llm)?;
+    log.info(format!(
+        "{} script from {}: {} chars",
+        language,
+        config.llm_backend.as_str(),
+        script.trim().chars().count()
+    ));
+    Ok(script.trim().to_string())
+}
+
+/// Add a prompt template
+#[tauri::command]
+fn add_prompt_template(state: State<AppState>, template: PromptTemplate) -> Result<i64, AppError> {
+    state.db.insert_prompt_template(&template).map_err(AppError::from)
+}
+
+/// List prompt templates
+#[tauri::command]
+fn list_prompt_templates(state: State<AppState>) -> Result<Vec<PromptTemplate>, AppError> {
+    state.db.list_prompt_templates().map_err(AppError::from)
+}
+
+/// Update a prompt template
+#[tauri::command]
+fn update_prompt_template(state: State<AppState>, template: PromptTemplate) -> Result<(), AppError> {
+    state.db.update_prompt_template(&template).map_err(AppError::from)
+}
+
+/// Delete a prompt template
+#[tauri::command]
+fn delete_prompt_template(state: State<AppState>, template_id: i64) -> Result<(), AppError> {
+    state.db.delete_prompt_template(template_id).map_err(AppError::from)
+}
+
+/// Add a few-shot example script
+#[tauri::command]
+fn add_example(state: State<AppState>, example: Example) -> Result<i64, AppError> {
+    state.db.insert_example(&example).map_err(AppError::from)
+}
+
+/// List few-shot examples, optionally for one niche
+#[tauri::command]
+fn list_examples(state: State<AppState>, niche: Option<String>) -> Result<Vec<Example>, AppError> {
+    state.db.list_examples(niche.as_deref()).map_err(AppError::from)
+}
+
+/// Update a few-shot example script
+#[tauri::command]
+fn update_example(state: State<AppState>, example: Example) -> Result<(), AppError> {
+    state.db.update_example(&example).map_err(AppError::from)
+}
+
+/// Delete a few-shot example script
+#[tauri::command]
+fn delete_example(state: State<AppState>, example_id: i64) -> Result<(), AppError> {
+    state.db.delete_example(example_id).map_err(AppError::from)
+}
+
+/// Send a rendered short to every enabled Telegram chat
+#[tauri::command]
+async fn send_short_to_telegram(state: State<'_, AppState>, short_id: i64) -> Result<(), AppError> {
+    send_to_telegram(&state, short_id, false).await
+}
+
+/// Send a short to the enabled Telegram chats, recording the outcome for
+/// each in the publications table. With `only_pending`, chats that already
+/// have it are skipped. Fails if any chat couldn't be reached.
+async fn send_to_telegram(state: &AppState, short_id: i64, only_pending: bool) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let delivered: Vec<String> = state.db.list_publications(short_id)?
+        .into_iter()
+        .filter(|d| d.platform == "telegram" && d.status == PublicationStatus::Sent)
+        .map(|d| d.destination)
+        .collect();
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    ensure_not_dry_run(&state.db, short_id, short.job_id)?;
+    
+    let token = config.telegram_bot_token.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Telegram bot token is not configured".into()))?;
+    let video_path = local_video(state, &config, &short).await?;
+    let chats: Vec<_> = config.telegram_chats.iter().filter(|c| c.enabled).collect();
+    if chats.is_empty() {
+        return Err(AppError::NotConfigured("No Telegram chat is enabled".into()));
+    }
+    let caption = telegram::build_caption(&trend.title, &short.language);
+    let thumb = telegram_thumb(&config, &short).await;
+    let bot = state.clients.telegram(token);
+    
+    let mut failures = Vec::new();
+    for chat in chats.iter().filter(|c| !only_pending || !delivered.contains(&c.chat_id)) {
+        let started = Instant::now();
+        let result = bot.send_video(
+            &chat.chat_id,
+            &video_path,
+            &caption,
+            thumb.as_deref(),
+        )
+        .await;
+        let db = &state.db;
+        match result {
+            Ok(message_id) => {
+                db.record_publication(short_id, "telegram", &chat.chat_id, Ok(&message_id.to_string()))?;
+                db.record_metric(short.job_id, "telegram", started.elapsed().as_millis() as i64)?;
+                // Replies in the bot's chat can then be traced to the short
+                if config.telegram_chat_id.as_deref() == Some(chat.chat_id.as_str()) {
+                    db.set_short_telegram_message(short_id, message_id)?;
+                }
+            }
+            Err(e) => {
+                db.record_publication(short_id, "telegram", &chat.chat_id, Err(&e))?;
+                failures.push(format!("{}: {}", chat.display_name(), e));
+            }
+        }
+    }
+    if let Some(thumb) = thumb {
+        let _ = tokio::fs::remove_file(thumb).await;
+    }
+    if !failures.is_empty() {
+        return Err(AppError::Http(format!("{} of {} chats failed: {}", failures.len(), chats.len(), failures.join("; "))));
+    }
+    Ok(())
+}
+
+/// Send a short awaiting approval to the Telegram chat so it can be reviewed
+/// from the bot. Skipped when Telegram isn't configured.
+async fn send_for_review(state: &AppState, short_id: i64, log: &JobLogger<'_>) {
+    let result = async {
+        let config = state.db.load_config()?;
+        let (short, trend) = state.db.get_short_with_trend(short_id)?
+            .ok_or_else(|| AppError::not_found("Short", short_id))?;
+        let (Some(token), Some(chat_id)) = (config.telegram_bot_token.as_deref(), config.telegram_chat_id.as_deref()) else {
+            return Ok(false);
+        };
+        if state.db.job_is_dry_run(short.job_id)? {
+            log.info(format!("Dry run: not sending short {} to Telegram for review", short_id));
+            return Ok(false);
+        }
+        let video_path = local_video(state, &config, &short).await?;
+        let caption = telegram::build_review_caption(&trend.title, &short.language);
+        let thumb = telegram_thumb(&config, &short).await;
+        let result = state.clients.telegram(token)
+            .send_video(chat_id, &video_path, &caption, thumb.as_deref())
+            .await;
+        if let Some(thumb) = thumb {
+            let _ = tokio::fs::remove_file(thumb).await;
+        }
+        let message_id = result?;
+        let db = &state.db;
+        db.set_short_telegram_message(short_id, message_id)?;
+        Ok::<_, AppError>(true)
+    }
+    .await;
+    match result {
+        Ok(true) => log.info(format!("Sent short {} to Telegram for review", short_id)),
+        Ok(false) => {}
+        Err(e) => log.warn(format!("Telegram review copy of short {} failed: {}", short_id, e)),
+    }
+}
+
+/// Telegram-sized copy of a short's thumbnail in the temp dir. The thumb is a
+/// nicety; videos are sent without it if it can't be made.
+async fn telegram_thumb(config: &Config, short: &db::Short) -> Option<PathBuf> {
+    let thumbnail = short.thumbnail_path.as_deref()?;
+    let short_id = short.id.unwrap_or_default();
+    let thumb = std::env::temp_dir().join(format!("shotauto-thumb-{}.jpg", short_id));
+    thumbnails::telegram_thumb(config, std::path::Path::new(thumbnail), &thumb)
+        .await
+        .map(|_| thumb)
+        .map_err(|e| tracing::warn!("Telegram thumbnail failed for short {}: {}", short_id, e))
+        .ok()
+}
+
+/// Post a rendered short to the configured Discord webhook
+#[tauri::command]
+async fn send_short_to_discord(state: State<'_, AppState>, short_id: i64) -> Result<(), AppError> {
+    send_to_discord(&state, short_id, false).await
+}
+
+/// Post a short to the Discord webhook and record the outcome in the
+/// publications table. With `only_pending`, nothing is sent if it already went.
+async fn send_to_discord(state: &AppState, short_id: i64, only_pending: bool) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let delivered = state.db.list_publications(short_id)?
+        .iter()
+        .any(|d| d.platform == "discord" && d.status == PublicationStatus::Sent);
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    ensure_not_dry_run(&state.db, short_id, short.job_id)?;
+    if only_pending && delivered {
+        return Ok(());
+    }
+    let webhook_url = config.discord_webhook_url.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Discord webhook URL is not configured".into()))?;
+    let video_path = local_video(state, &config, &short).await?;
+    let hook = short.hook();
+    let embed = discord::ShortEmbed {
+        title: &trend.title,
+        language: &short.language,
+        duration_sec: short.duration_sec,
+        hook: hook.as_deref(),
+    };
+    let too_big = tokio::fs::metadata(&video_path).await.is_ok_and(|m| m.len() > discord::MAX_UPLOAD_BYTES);
+    let result = match short.youtube_video_id.as_deref() {
+        Some(video_id) if too_big => {
+            discord::send_link(webhook_url, &youtube_upload::short_url(video_id), &embed).await
+        }
+        _ => discord::send_video(webhook_url, &video_path, &embed).await,
+    };
+    
+    let db = &state.db;
+    let destination = discord::destination_name(webhook_url);
+    db.record_publication(short_id, "discord", &destination, result.as_deref().map_err(String::as_str))?;
+    result.map(|_| ()).map_err(AppError::Http)
+}
+
+/// Connect a YouTube account to upload shorts to. Opens Google's consent
+/// page in the browser; connecting a channel again refreshes its token.
+#[tauri::command]
+async fn connect_youtube(state: State<'_, AppState>) -> Result<Account, AppError> {
+    let config = state.db.load_config()?;
+    let refresh_token = youtube_upload::authorize(&config, youtube_upload::SCOPE, |url| {
+        tauri_plugin_opener::open_url(url, None::<&str>).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(AppError::Http)?;
+    let access_token = youtube_upload::access_token(&config, &refresh_token).await.map_err(AppError::Http)?;
+    let channel = youtube_upload::my_channel(&access_token).await.map_err(AppError::Http)?;
+    
+    let db = &state.db;
+    let account_id = db.upsert_account(&channel.id, &channel.title, &refresh_token)?;
+    db.get_account(account_id)?
+        .ok_or_else(|| AppError::not_found("Account", account_id))
+}
+
+/// Connect the Google account whose Drive shorts are backed up to. Uses the
+/// same OAuth client as YouTube uploads. Without a backup folder yet, one
+/// named "ShotAuto" is made.
+#[tauri::command]
+async fn connect_drive(state: State<'_, AppState>) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    let refresh_token = youtube_upload::authorize(&config, drive::SCOPE, |url| {
+        tauri_plugin_opener::open_url(url, None::<&str>).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(AppError::Http)?;
+    state.db.set_config("drive_refresh_token", &refresh_token)?;
+    if config.drive_folder_id.is_none() {
+        create_drive_folder(state, None).await?;
+    }
+    Ok(())
+}
+
+/// Make a new Drive folder, "ShotAuto" unless `name` is given, and back
+/// shorts up to it from now on. The app can only upload into folders it
+/// made itself. Returns the folder id.
+#[tauri::command]
+async fn create_drive_folder(state: State<'_, AppState>, name: Option<String>) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let refresh_token = config.drive_refresh_token.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Google Drive is not connected".into()))?;
+    let name = name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or("ShotAuto");
+    let access_token = youtube_upload::access_token(&config, refresh_token).await.map_err(AppError::Http)?;
+    let folder = drive::create_folder(&access_token, name).await.map_err(AppError::Http)?;
+    state.db.set_config("drive_folder_id", &folder.id)?;
+    Ok(folder.id)
+}
+
+/// Upload a rendered short's video, thumbnail and script to the Drive folder
+#[tauri::command]
+async fn back_up_to_drive(state: State<'_, AppState>, short_id: i64) -> Result<(), AppError> {
+    copy_to_drive(&state, short_id).await
+}
+
+/// Upload a short's files to the Drive folder. Each file is its own
+/// publication: the video to the folder, the thumbnail and script to
+/// `<folder>/thumbnail` and `<folder>/script`. Files an earlier backup got
+/// through are skipped, and an empty script isn't uploaded.
+async fn copy_to_drive(state: &AppState, short_id: i64) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let sent: Vec<String> = state.db.list_publications(short_id)?
+        .into_iter()
+        .filter(|d| d.platform == "drive" && d.status == PublicationStatus::Sent)
+        .map(|d| d.destination)
+        .collect();
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    ensure_not_dry_run(&state.db, short_id, short.job_id)?;
+    let folder_id = config.drive_folder_id.as_deref()
+        .map(drive::folder_id)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| AppError::NotConfigured("Google Drive folder is not configured".into()))?;
+    let refresh_token = config.drive_refresh_token.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Google Drive is not connected".into()))?;
+    
+    let video_dest = folder_id.to_string();
+    let thumbnail_dest = format!("{}/thumbnail", folder_id);
+    let script_dest = format!("{}/script", folder_id);
+    let pending = |destination: &str| !sent.iter().any(|d| d == destination);
+    let thumbnail = short.thumbnail_path.as_deref()
+        .map(PathBuf::from)
+        .filter(|p| p.is_file() && pending(&thumbnail_dest));
+    let script = short.script.as_deref()
+        .map(sfx::strip)
+        .filter(|s| !s.trim().is_empty() && pending(&script_dest));
+    if !pending(&video_dest) && thumbnail.is_none() && script.is_none() {
+        return Ok(());
+    }
+    
+    let access_token = youtube_upload::access_token(&config, refresh_token).await.map_err(AppError::Http)?;
+    // Drive allows any characters; the id keeps names of shorts on the same topic apart
+    let name = format!("{} ({})", trend.title, short_id);
+    let record = |destination: &str, result: &Result<drive::DriveFile, String>| {
+        state.db.record_publication(short_id, "drive", destination, result.as_ref().map(|f| f.id.as_str()).map_err(String::as_str))
+    };
+    if pending(&video_dest) {
+        let video_path = local_video(state, &config, &short).await?;
+        let video = drive::upload(&config, &access_token, folder_id, &video_path, &format!("{}.mp4", name)).await;
+        record(&video_dest, &video)?;
+        // The thumbnail and script are no use without the video
+        let video = video.map_err(AppError::Http)?;
+        if let Some(ref link) = video.web_view_link {
+            state.db.set_publication_url(short_id, "drive", &video_dest, link)?;
+        }
+    }
+    let mut failures = Vec::new();
+    if let Some(thumbnail) = thumbnail {
+        let uploaded = drive::upload(&config, &access_token, folder_id, &thumbnail, &format!("{}.jpg", name)).await;
+        record(&thumbnail_dest, &uploaded)?;
+        if let Err(e) = uploaded {
+            failures.push(format!("thumbnail: {}", e));
+        }
+    }
+    if let Some(script) = script {
+        let dir = storage::scratch_dir("drive-script");
+        tokio::fs::create_dir_all(&dir).await?;
+        let script_path = dir.join(format!("short_{}.txt", short_id));
+        tokio::fs::write(&script_path, script).await?;
+        let uploaded = drive::upload(&config, &access_token, folder_id, &script_path, &format!("{}.txt", name)).await;
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        record(&script_dest, &uploaded)?;
+        if let Err(e) = uploaded {
+            failures.push(format!("script: {}", e));
+        }
+    }
+    if !failures.is_empty() {
+        return Err(AppError::Http(failures.join("; ")));
+    }
+    Ok(())
+}
+
+/// Log in to the SFTP server. The first successful test pins the server's
+/// host key; deliveries refuse a server whose key differs.
+#[tauri::command]
+async fn test_sftp(state: State<'_, AppState>) -> Result<sftp::SftpTest, AppError> {
+    let target = state.db.load_config()?.sftp;
+    let pinned = target.host_key.is_some();
+    let result = tauri::async_runtime::spawn_blocking(move || sftp::test(&target))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::Http)?;
+    if !pinned {
+        let mut config = state.db.load_config()?;
+        config.sftp.host_key = Some(result.host_key.clone());
+        state.db.save_config(&config)?;
+    }
+    Ok(result)
+}
+
+/// Drop the pinned SFTP host key, after the server's key was changed on
+/// purpose. The next `test_sftp` trusts whatever key the server has.
+#[tauri::command]
+fn forget_sftp_host_key(state: State<AppState>) -> Result<(), AppError> {
+    let mut config = state.db.load_config()?;
+    config.sftp.host_key = None;
+    state.db.save_config(&config)?;
+    Ok(())
+}
+
+/// Keep the SFTP password, or the private key's passphrase, in the OS
+/// keychain. `None` removes it.
+#[tauri::command]
+fn set_sftp_secret(state: State<AppState>, secret: Option<String>) -> Result<(), AppError> {
+    let target = state.db.load_config()?.sftp;
+    if target.host.trim().is_empty() || target.username.trim().is_empty() {
+        return Err(AppError::NotConfigured("SFTP host and user are not configured".into()));
+    }
+    sftp::set_secret(&target, secret.as_deref().filter(|s| !s.is_empty()))?;
+    Ok(())
+}
+
+/// Copy a rendered short's video, thumbnail and script to the SFTP server
+#[tauri::command]
+async fn send_short_to_sftp(state: State<'_, AppState>, short_id: i64) -> Result<(), AppError> {
+    copy_to_sftp(&state, short_id, false).await
+}
+
+/// Copy a short's files to the SFTP server and record the remote directory
+/// in the publications table. With `only_pending`, nothing is sent if it
+/// already went.
+async fn copy_to_sftp(state: &AppState, short_id: i64, only_pending: bool) -> Result<(), AppError> {
+    let config = state.db.load_config()?;
+    let (short, _) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let delivered = state.db.list_publications(short_id)?
+        .iter()
+        .any(|d| d.platform == "sftp" && d.status == PublicationStatus::Sent);
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    ensure_not_dry_run(&state.db, short_id, short.job_id)?;
+    if only_pending && delivered {
+        return Ok(());
+    }
+    let target = config.sftp.clone();
+    if !target.is_configured() {
+        return Err(AppError::NotConfigured("SFTP delivery is not configured".into()));
+    }
+    if target.host_key.is_none() {
+        return Err(AppError::NotConfigured("Test the SFTP connection first to trust the server's host key".into()));
+    }
+    let dir = target.remote_dir(Local::now().date_naive(), short_id).map_err(AppError::Validation)?;
+    let video_path = local_video(state, &config, &short).await?;
+    
+    let mut files = vec![(video_path, format!("short_{}.mp4", short_id))];
+    if let Some(thumbnail) = short.thumbnail_path.as_deref().map(PathBuf::from).filter(|p| p.is_file()) {
+        files.push((thumbnail, format!("short_{}.jpg", short_id)));
+    }
+    let scratch = storage::scratch_dir("sftp-script");
+    if let Some(ref script) = short.script {
+        tokio::fs::create_dir_all(&scratch).await?;
+        let script_path = scratch.join(format!("short_{}.txt", short_id));
+        tokio::fs::write(&script_path, sfx::strip(script)).await?;
+        files.push((script_path, format!("short_{}.txt", short_id)));
+    }
+    let runtime = tokio::runtime::Handle::current();
+    let limit = transfers::Direction::Upload.limit(&config);
+    let remote_dir = dir.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        sftp::connect(&target)?.upload(&runtime, limit, &remote_dir, &files)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    
+    let db = &state.db;
+    db.record_publication(short_id, "sftp", &config.sftp.destination(), result.as_ref().map(|_| dir.as_str()).map_err(String::as_str))?;
+    result.map_err(AppError::Http)
+}
+
+/// Configuration profiles, oldest first
+#[tauri::command]
+fn list_profiles(state: State<AppState>) -> Result<Vec<Profile>, AppError> {
+    state.db.list_profiles().map_err(AppError::from)
+}
+
+/// Add a profile with the default settings
+#[tauri::command]
+fn create_profile(state: State<AppState>, name: String) -> Result<Profile, AppError> {
+    add_profile(&state, &name, None)
+}
+
+/// Add a profile with a copy of another profile's settings
+#[tauri::command]
+fn duplicate_profile(state: State<AppState>, profile_id: i64, name: String) -> Result<Profile, AppError> {
+    add_profile(&state, &name, Some(profile_id))
+}
+
+fn add_profile(state: &AppState, name: &str, copy_from: Option<i64>) -> Result<Profile, AppError> {
+    let db = &state.db;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("Profile name is empty".into()));
+    }
+    if db.list_profiles()?.iter().any(|p| p.name == name) {
+        return Err(AppError::Validation(format!("A profile named '{}' already exists", name)));
+    }
+    if let Some(source_id) = copy_from {
+        db.get_profile(source_id)?
+            .ok_or_else(|| AppError::not_found("Profile", source_id))?;
+    }
+    let profile_id = db.create_profile(name, copy_from)?;
+    db.get_profile(profile_id)?
+        .ok_or_else(|| AppError::not_found("Profile", profile_id))
+}
+
+/// Make a profile active; returns its settings. Jobs created under other
+/// profiles wait until their profile is active again.
+#[tauri::command]
+fn switch_profile(state: State<AppState>, profile_id: i64) -> Result<Config, AppError> {
+    let db = &state.db;
+    let profile = db.get_profile(profile_id)?
+        .ok_or_else(|| AppError::not_found("Profile", profile_id))?;
+    db.switch_profile(profile_id)?;
+    tracing::info!("Switched to profile {}", profile_id);
+    record_activity(db, Actor::Ui, ActivityAction::ProfileSwitched, None, None, Some(&profile.name));
+    db.load_config().map_err(AppError::from)
+}
+
+/// Delete a profile other than the active one
+#[tauri::command]
+fn delete_profile(state: State<AppState>, profile_id: i64) -> Result<(), AppError> {
+    let db = &state.db;
+    let profile = db.get_profile(profile_id)?
+        .ok_or_else(|| AppError::not_found("Profile", profile_id))?;
+    if profile.active {
+        return Err(AppError::Validation("Switch to another profile before deleting this one".into()));
+    }
+    db.delete_profile(profile_id).map_err(AppError::from)
+}
+
+/// Connected YouTube accounts
+#[tauri::command]
+fn list_accounts(state: State<AppState>) -> Result<Vec<Account>, AppError> {
+    state.db.list_accounts().map_err(AppError::from)
+}
+
+/// Change the niche, playlist or enabled state of a YouTube account
+#[tauri::command]
+fn update_account(
+    state: State<AppState>,
+    account_id: i64,
+    niche: String,
+    playlist_id: Option<String>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state.db.update_account(account_id, &niche, playlist_id.as_deref(), enabled).map_err(AppError::from)
+}
+
+/// Disconnect a YouTube account
 #[tauri::command]
-fn get_config(state: State<AppState>) -> Result<Config, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.load_config().map_err(|e| e.to_string())
+fn delete_account(state: State<AppState>, account_id: i64) -> Result<(), AppError> {
+    state.db.delete_account(account_id).map_err(AppError::from)
 }
 
-/// Save configuration
+/// Upload a job's shorts to another account
 #[tauri::command]
-fn save_config(state: State<AppState>, config: Config) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.save_config(&config).map_err(|e| e.to_string())
+fn set_job_account(state: State<AppState>, job_id: i64, account_id: Option<i64>) -> Result<(), AppError> {
+    let db = &state.db;
+    if let Some(account_id) = account_id {
+        db.get_account(account_id)?
+            .ok_or_else(|| AppError::not_found("Account", account_id))?;
+    }
+    db.set_job_account(job_id, account_id).map_err(AppError::from)
 }
 
-/// Get dashboard statistics
+/// Upload a short to YouTube now, at its scheduled time if it has one
+#[tauri::command]
+async fn upload_short(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    upload_to_youtube(&state, short_id, false, Actor::Ui).await
+}
+
+/// Upload a short to YouTube and record the outcome in the publications table.
+/// Shorts without a publish time get the next free posting slot when the
+/// schedule is on. With `only_pending`, a short that was already uploaded is
+/// left alone. Returns the video id.
+async fn upload_to_youtube(state: &AppState, short_id: i64, only_pending: bool, actor: Actor) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    if let (true, Some(video_id)) = (only_pending, &short.youtube_video_id) {
+        return Ok(video_id.clone());
+    }
+    if state.db.job_is_dry_run(short.job_id)? {
+        let video_id = dry_run::fake_video_id(short_id);
+        state.db.record_publication(short_id, "youtube", dry_run::CHANNEL, Ok(&video_id))?;
+        state.db.set_short_uploaded(short_id, &video_id)?;
+        record_activity(&state.db, actor, ActivityAction::ShortUploaded, Some(short.job_id), Some(short_id), Some("dry run"));
+        return Ok(video_id);
+    }
+    let account = state.db.job_account(short.job_id)?
+        .ok_or_else(|| AppError::NotConfigured("No enabled YouTube account takes this short".into()))?;
+    if !account.enabled {
+        return Err(AppError::Validation(format!("YouTube account {} is disabled", account.channel_title)));
+    }
+    let channel_id = match account.channel_id.clone() {
+        Some(channel_id) => channel_id,
+        // Carried over from the single account of older versions
+        None => {
+            let access_token = youtube_upload::access_token(&config, &account.refresh_token).await.map_err(AppError::Http)?;
+            let channel = youtube_upload::my_channel(&access_token).await.map_err(AppError::Http)?;
+            state.db.set_account_channel(account.id, &channel.id, &channel.title)?;
+            channel.id
+        }
+    };
+    let template = state.db.upload_template_for_channel(Some(&channel_id))?;
+    let mut publish_at = short.publish_at;
+    if publish_at.is_none() && config.posting_schedule.enabled {
+        publish_at = state.db.claim_publish_slot(short_id, &config.posting_schedule)?;
+    }
+    let video_path = local_video(state, &config, &short).await?;
+    // A slot that has passed means "now"; YouTube rejects past publish times
+    let publish_at = publish_at.filter(|t| *t > Utc::now());
+    
+    let metadata = short.upload_metadata.clone()
+        .unwrap_or_else(|| youtube_upload::VideoMetadata::build(template.as_ref(), &short, &trend, &config.voice));
+    let result = async {
+        let access_token = youtube_upload::access_token(&config, &account.refresh_token).await?;
+        let video_id = youtube_upload::upload_video(&config, &access_token, &video_path, &metadata, publish_at).await?;
+        // The video is up either way; a missing thumbnail or playlist entry
+        // is added by hand
+        if let Some(thumbnail) = short.thumbnail_path.as_deref().map(Path::new).filter(|p| p.is_file()) {
+            if let Err(e) = youtube_upload::set_thumbnail(&access_token, &video_id, thumbnail).await {
+                tracing::warn!("Setting the thumbnail of short {} failed: {}", short_id, e);
+            }
+        }
+        if let Some(ref playlist_id) = account.playlist_id {
+            if let Err(e) = youtube_upload::add_to_playlist(&access_token, playlist_id, &video_id).await {
+                tracing::warn!("Adding short {} to playlist {} failed: {}", short_id, playlist_id, e);
+            }
+        }
+        Ok::<_, String>(video_id)
+    }
+    .await;
+    state.db.record_publication(short_id, "youtube", &channel_id, result.as_deref().map_err(String::as_str))?;
+    if let Ok(ref video_id) = result {
+        state.db.set_short_uploaded(short_id, video_id)?;
+        state.db.set_publication_url(short_id, "youtube", &channel_id, &youtube_upload::short_url(video_id))?;
+        record_activity(&state.db, actor, ActivityAction::ShortUploaded, Some(short.job_id), Some(short_id), Some(video_id));
+    }
+    if let Err(ref e) = result {
+        if youtube_upload::is_quota_error(e) {
+            notifications::notify(
+                &config,
+                notifications::Event::QuotaExhausted,
+                Some(short.job_id),
+                &format!("Short {} can't be uploaded until the quota resets", short_id),
+            );
+        }
+    }
+    let video_id = result.map_err(AppError::Http)?;
+    webhooks::dispatch(state, WebhookEvent::ShortUploaded, serde_json::json!({
+        "short_id": short_id,
+        "job_id": short.job_id,
+        "title": metadata.title,
+        "language": short.language,
+        "video_id": video_id,
+        "channel_id": channel_id,
+        "url": youtube_upload::short_url(&video_id),
+        "publish_at": publish_at,
+    }));
+    Ok(video_id)
+}
+
+/// Playlists of an account's channel, for picking its upload playlist
+#[tauri::command]
+async fn list_my_playlists(state: State<'_, AppState>, account_id: i64) -> Result<Vec<youtube_upload::Playlist>, AppError> {
+    let config = state.db.load_config()?;
+    let account = state.db.get_account(account_id)?
+        .ok_or_else(|| AppError::not_found("Account", account_id))?;
+    let access_token = youtube_upload::access_token(&config, &account.refresh_token).await.map_err(AppError::Http)?;
+    youtube_upload::my_playlists(&access_token).await.map_err(AppError::Http)
+}
+
+/// Title, description and tags a short would be uploaded with
+#[tauri::command]
+fn preview_upload_metadata(state: State<AppState>, short_id: i64) -> Result<youtube_upload::VideoMetadata, AppError> {
+    let db = &state.db;
+    let (short, trend) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    match short.upload_metadata {
+        Some(metadata) => Ok(metadata),
+        None => build_upload_metadata(db, &short, &trend),
+    }
+}
+
+/// Fill the upload template of the channel a short's job goes to
+fn build_upload_metadata(db: &Database, short: &Short, trend: &Trend) -> Result<youtube_upload::VideoMetadata, AppError> {
+    let account = db.job_account(short.job_id)?;
+    let template = db.upload_template_for_channel(account.as_ref().and_then(|a| a.channel_id.as_deref()))?;
+    let config = db.load_config()?;
+    Ok(youtube_upload::VideoMetadata::build(template.as_ref(), short, trend, &config.voice))
+}
+
+/// Build and store the metadata a short will be uploaded with, once its
+/// script is written
+fn prepare_upload_metadata(db: &Database, short_id: i64) -> Result<youtube_upload::VideoMetadata, AppError> {
+    let (short, trend) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let metadata = build_upload_metadata(db, &short, &trend)?;
+    db.set_short_upload_metadata(short_id, &metadata)?;
+    Ok(metadata)
+}
+
+/// Hashtags that fit a short, `#Shorts` first, to pick from when editing
+/// its upload metadata
+#[tauri::command]
+fn suggest_hashtags(state: State<AppState>, short_id: i64) -> Result<Vec<String>, AppError> {
+    let db = &state.db;
+    let (short, trend) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let account = db.job_account(short.job_id)?;
+    let template = db.upload_template_for_channel(account.as_ref().and_then(|a| a.channel_id.as_deref()))?;
+    Ok(hashtags::suggest(&trend, template.map(|t| t.tags).as_deref().unwrap_or_default()))
+}
+
+/// Replace the title, description and tags a short will be uploaded with.
+/// They get the same checks as generated metadata; returns what was stored.
+#[tauri::command]
+fn set_upload_metadata(
+    state: State<AppState>,
+    short_id: i64,
+    metadata: youtube_upload::VideoMetadata,
+) -> Result<youtube_upload::VideoMetadata, AppError> {
+    let db = &state.db;
+    let (short, _) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    if short.youtube_video_id.is_some() {
+        return Err(AppError::Validation(format!("Short {} is already on YouTube", short_id)));
+    }
+    if metadata.title.trim().is_empty() {
+        return Err(AppError::Validation("The title can't be empty".into()));
+    }
+    let metadata = metadata.optimized();
+    db.set_short_upload_metadata(short_id, &metadata)?;
+    Ok(metadata)
+}
+
+/// Add an upload metadata template
+#[tauri::command]
+fn add_upload_template(state: State<AppState>, template: UploadTemplate) -> Result<i64, AppError> {
+    state.db.insert_upload_template(&template).map_err(AppError::from)
+}
+
+/// List upload metadata templates
+#[tauri::command]
+fn list_upload_templates(state: State<AppState>) -> Result<Vec<UploadTemplate>, AppError> {
+    state.db.list_upload_templates().map_err(AppError::from)
+}
+
+/// Update an upload metadata template
+#[tauri::command]
+fn update_upload_template(state: State<AppState>, template: UploadTemplate) -> Result<(), AppError> {
+    state.db.update_upload_template(&template).map_err(AppError::from)
+}
+
+/// Delete an upload metadata template
+#[tauri::command]
+fn delete_upload_template(state: State<AppState>, template_id: i64) -> Result<(), AppError> {
+    state.db.delete_upload_template(template_id).map_err(AppError::from)
+}
+
+/// Set or clear the time a short is published on YouTube. Only possible
+/// before it is uploaded.
+#[tauri::command]
+fn schedule_short(state: State<AppState>, short_id: i64, publish_at: Option<DateTime<Utc>>) -> Result<(), AppError> {
+    let db = &state.db;
+    let (short, _) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    if short.youtube_video_id.is_some() {
+        return Err(AppError::Validation("Short has already been uploaded; reschedule it in YouTube Studio".into()));
+    }
+    if publish_at.is_some_and(|t| t <= Utc::now()) {
+        return Err(AppError::Validation("Publish time must be in the future".into()));
+    }
+    db.set_short_publish_at(short_id, publish_at).map_err(AppError::from)
+}
+
+/// Scheduled shorts and free posting slots in a time range
+#[derive(Debug, Clone, Serialize)]
+struct PublishCalendar {
+    entries: Vec<CalendarEntry>,
+    /// Slots of the posting schedule that no short has taken yet
+    free_slots: Vec<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CalendarEntry {
+    short_id: i64,
+    title: String,
+    language: String,
+    publish_at: DateTime<Utc>,
+    /// Set once the short is on YouTube, waiting to go public
+    youtube_video_id: Option<String>,
+}
+
+/// What gets published when, between `from` and `until`
+#[tauri::command]
+fn get_publish_calendar(state: State<AppState>, from: DateTime<Utc>, until: DateTime<Utc>) -> Result<PublishCalendar, AppError> {
+    let db = &state.db;
+    let config = db.load_config()?;
+    let entries: Vec<CalendarEntry> = db.list_scheduled_shorts(from, until)?
+        .into_iter()
+        .filter_map(|(short, trend)| {
+            Some(CalendarEntry {
+                short_id: short.id?,
+                title: trend.title,
+                language: short.language,
+                publish_at: short.publish_at?,
+                youtube_video_id: short.youtube_video_id,
+            })
+        })
+        .collect();
+    let free_slots = if config.posting_schedule.enabled {
+        config.posting_schedule
+            .slots_between(from.max(Utc::now()), until)
+            .into_iter()
+            .filter(|slot| !entries.iter().any(|e| e.publish_at == *slot))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(PublishCalendar { entries, free_slots })
+}
+
+/// Choose whether a short is published to Instagram; `None` goes back to
+/// the default from the settings
+#[tauri::command]
+fn set_short_instagram(state: State<AppState>, short_id: i64, enabled: Option<bool>) -> Result<(), AppError> {
+    state.db.set_short_instagram(short_id, enabled).map_err(AppError::from)
+}
+
+/// Publish a short to Instagram as a Reel now
+#[tauri::command]
+async fn publish_to_instagram(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    post_to_instagram(&state, short_id, false).await
+}
+
+/// Publish a short as a Reel and record the outcome in the publications table.
+/// Instagram fetches the video by URL, so it is put on the media host for
+/// the duration. With `only_pending`, an earlier success is left alone.
+/// Returns the media id.
+async fn post_to_instagram(state: &AppState, short_id: i64, only_pending: bool) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let published = state.db.list_publications(short_id)?
+        .into_iter()
+        .find(|d| d.platform == "instagram" && d.status == PublicationStatus::Sent)
+        .and_then(|d| d.remote_id);
+    if !short.is_deliverable() {
+        return Err(AppError::Validation(format!("Short {} has not been approved", short_id)));
+    }
+    ensure_not_dry_run(&state.db, short_id, short.job_id)?;
+    if let (true, Some(media_id)) = (only_pending, published) {
+        return Ok(media_id);
+    }
+    let access_token = config.instagram_access_token.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Instagram access token is not configured".into()))?;
+    let user_id = config.instagram_user_id.as_deref()
+        .ok_or_else(|| AppError::NotConfigured("Instagram account id is not configured".into()))?;
+    let video_path = local_video(state, &config, &short).await?;
+    let caption = match short.script.as_deref() {
+        Some(script) => format!("{}\n\n{}", trend.title, sfx::strip(script)),
+        None => trend.title.clone(),
+    };
+    
+    let result = async {
+        let name = format!("shotauto-{}-{}.mp4", short_id, Utc::now().timestamp());
+        let hosted = media_host::put(&config.media_host, &video_path, &name).await?;
+        let result = instagram::publish_reel(access_token, user_id, &hosted.url, &caption).await;
+        if let Err(e) = media_host::remove(&config.media_host, hosted).await {
+            tracing::warn!("Failed to remove {} from the media host: {}", name, e);
+        }
+        result
+    }
+    .await;
+    
+    let db = &state.db;
+    let media_id = result.as_ref().map(|reel| reel.media_id.as_str()).map_err(String::as_str);
+    db.record_publication(short_id, "instagram", user_id, media_id)?;
+    let reel = result.map_err(AppError::Http)?;
+    if let Some(ref permalink) = reel.permalink {
+        db.set_publication_url(short_id, "instagram", user_id, permalink)?;
+    }
+    Ok(reel.media_id)
+}
+
+/// View/like/comment history of an uploaded short
+#[tauri::command]
+fn get_short_performance(state: State<AppState>, short_id: i64) -> Result<ShortPerformance, AppError> {
+    let db = &state.db;
+    let (short, trend) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let history = db.get_short_stats(short_id)?;
+    Ok(ShortPerformance {
+        short_id,
+        title: trend.title,
+        youtube_video_id: short.youtube_video_id,
+        latest: history.last().cloned(),
+        history,
+    })
+}
+
+/// Trend categories whose shorts get the most views, best first
+#[tauri::command]
+async fn get_top_topics(state: State<'_, AppState>, limit: Option<u32>) -> Result<Vec<TopicPerformance>, AppError> {
+    with_db(&state, move |db| db.get_topic_performance(limit.unwrap_or(10))).await
+}
+
+/// The shorts of an A/B variant group with their latest statistics
+#[tauri::command]
+async fn list_variants(state: State<'_, AppState>, group_id: i64) -> Result<Vec<analytics::Variant>, AppError> {
+    let variants = with_db(&state, move |db| analytics::variants(db, group_id)).await?;
+    if variants.is_empty() {
+        return Err(AppError::not_found("Variant group", group_id));
+    }
+    Ok(variants)
+}
+
+/// Hook styles ranked by how often their variant won its A/B test
+#[tauri::command]
+async fn get_hook_style_report(state: State<'_, AppState>) -> Result<Vec<analytics::HookStyleResult>, AppError> {
+    with_db(&state, analytics::compare_hook_styles).await
+}
+
+/// Fetch fresh statistics for uploaded shorts now
+#[tauri::command]
+async fn refresh_short_stats(state: State<'_, AppState>) -> Result<(), AppError> {
+    analytics::refresh(&state).await?
+        .map(|_| ())
+        .ok_or_else(|| AppError::NotConfigured("YouTube API key is not configured".into()))
+}
+
+/// Where a short has been published, and where publishing failed
+#[tauri::command]
+fn list_publications(state: State<AppState>, short_id: i64) -> Result<Vec<Publication>, AppError> {
+    state.db.list_publications(short_id).map_err(AppError::from)
+}
+
+/// Send a rendered short to every configured destination. Destinations that
+/// aren't set up are skipped; failures are logged and don't stop the others.
+async fn deliver_short(state: &AppState, short_id: i64, actor: Actor, log: &JobLogger<'_>) {
+    let config = match state.db.load_config().map_err(|e| e.to_string()) {
+        Ok(config) => config,
+        Err(e) => {
+            log.warn(format!("Delivery of short {} skipped: {}", short_id, e));
+            return;
+        }
+    };
+    let dry_run = state.db.get_short_with_trend(short_id).map_err(|e| e.to_string())
+        .ok()
+        .flatten()
+        .map(|(short, _)| state.db.job_is_dry_run(short.job_id).unwrap_or(false))
+        .unwrap_or(false);
+    if dry_run {
+        match upload_to_youtube(state, short_id, true, actor).await {
+            Ok(video_id) => log.info(format!("Dry run: recorded short {} as uploaded ({}); nothing was posted", short_id, video_id)),
+            Err(e) => log.warn(format!("Dry run delivery of short {} failed: {}", short_id, e)),
+        }
+        return;
+    }
+    // Upload first so the other destinations can link to the video
+    let youtube = state.db.has_enabled_account().map_err(|e| e.to_string())
+        .unwrap_or(false);
+    if youtube {
+        match upload_to_youtube(state, short_id, true, actor).await {
+            Ok(video_id) => log.info(format!("Uploaded short {} to YouTube as {}", short_id, video_id)),
+            Err(e) => log.warn(format!("YouTube upload of short {} failed: {}", short_id, e)),
+        }
+    }
+    if config.telegram_bot_token.is_some() && config.telegram_chats.iter().any(|c| c.enabled) {
+        match send_to_telegram(state, short_id, true).await {
+            Ok(()) => log.info(format!("Sent short {} to Telegram", short_id)),
+            Err(e) => log.warn(format!("Telegram delivery of short {} failed: {}", short_id, e)),
+        }
+    }
+    if config.discord_webhook_url.is_some() {
+        match send_to_discord(state, short_id, true).await {
+            Ok(()) => log.info(format!("Posted short {} to Discord", short_id)),
+            Err(e) => log.warn(format!("Discord delivery of short {} failed: {}", short_id, e)),
+        }
+    }
+    if config.drive_refresh_token.is_some() && config.drive_folder_id.is_some() {
+        match copy_to_drive(state, short_id).await {
+            Ok(()) => log.info(format!("Backed up short {} to Google Drive", short_id)),
+            Err(e) => log.warn(format!("Drive backup of short {} failed: {}", short_id, e)),
+        }
+    }
+    if config.sftp.is_configured() {
+        match copy_to_sftp(state, short_id, true).await {
+            Ok(()) => log.info(format!("Copied short {} to {}", short_id, config.sftp.host)),
+            Err(e) => log.warn(format!("SFTP delivery of short {} failed: {}", short_id, e)),
+        }
+    }
+    let instagram = state.db.get_short_with_trend(short_id).map_err(|e| e.to_string())
+        .ok()
+        .flatten()
+        .and_then(|(short, _)| short.instagram)
+        .unwrap_or(config.instagram_default);
+    if instagram && config.instagram_access_token.is_some() {
+        match post_to_instagram(state, short_id, true).await {
+            Ok(media_id) => log.info(format!("Published short {} to Instagram as {}", short_id, media_id)),
+            Err(e) => log.warn(format!("Instagram publishing of short {} failed: {}", short_id, e)),
+        }
+    }
+}
+
+/// Rendered shorts waiting for review, with their trends. With
+/// `starred_only`, just those made from starred trends.
+#[tauri::command]
+fn list_awaiting_approval(state: State<AppState>, starred_only: Option<bool>) -> Result<Vec<(db::Short, db::Trend)>, AppError> {
+    state.db.list_shorts_awaiting_approval(starred_only.unwrap_or(false)).map_err(AppError::from)
+}
+
+/// Approve a short held for review and deliver it
+#[tauri::command]
+async fn approve_short(state: State<'_, AppState>, short_id: i64) -> Result<(), AppError> {
+    approve(&state, short_id, Actor::Ui).await
+}
+
+async fn approve(state: &AppState, short_id: i64, actor: Actor) -> Result<(), AppError> {
+    let (short, _) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    if short.video_path.is_none() {
+        return Err(AppError::Validation("Short has not been rendered yet".into()));
+    }
+    state.db.set_short_approval(short_id, ApprovalStatus::Approved, None)?;
+    record_activity(&state.db, actor, ActivityAction::ShortApproved, Some(short.job_id), Some(short_id), None);
+    deliver_short(state, short_id, actor, &JobLogger::none(&state.db)).await;
+    Ok(())
+}
+
+/// Reject a short held for review; it won't be sent or uploaded
+#[tauri::command]
+fn reject_short(state: State<AppState>, short_id: i64, reason: String) -> Result<(), AppError> {
+    reject(&state, short_id, Some(&reason), Actor::Ui)
+}
+
+fn reject(state: &AppState, short_id: i64, reason: Option<&str>, actor: Actor) -> Result<(), AppError> {
+    let db = &state.db;
+    let (short, _) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+    db.set_short_approval(short_id, ApprovalStatus::Rejected, reason)?;
+    record_activity(db, actor, ActivityAction::ShortRejected, Some(short.job_id), Some(short_id), reason);
+    Ok(())
+}
+
+/// Move a short to the trash; it isn't delivered or uploaded, and its files
+/// stay on disk until the trash is emptied
+#[tauri::command]
+fn delete_short(state: State<AppState>, short_id: i64) -> Result<(), AppError> {
+    if !state.db.trash_short(short_id)? {
+        return Err(AppError::not_found("Short", short_id));
+    }
+    Ok(())
+}
+
+/// Take a short out of the trash
+#[tauri::command]
+fn restore_short(state: State<AppState>, short_id: i64) -> Result<(), AppError> {
+    if !state.db.restore_short(short_id)? {
+        return Err(AppError::Validation(format!("Short {} is not in the trash", short_id)));
+    }
+    Ok(())
+}
+
+/// Move a job and its shorts to the trash; the pipeline stops working on it
+#[tauri::command]
+fn delete_job(state: State<AppState>, job_id: i64) -> Result<(), AppError> {
+    if !state.db.trash_job(job_id)? {
+        return Err(AppError::not_found("Job", job_id));
+    }
+    record_activity(&state.db, Actor::Ui, ActivityAction::JobDeleted, Some(job_id), None, None);
+    Ok(())
+}
+
+/// Take a job out of the trash with the shorts deleted along with it
+#[tauri::command]
+fn restore_job(state: State<AppState>, job_id: i64) -> Result<(), AppError> {
+    if !state.db.restore_job(job_id)? {
+        return Err(AppError::Validation(format!("Job {} is not in the trash", job_id)));
+    }
+    record_activity(&state.db, Actor::Ui, ActivityAction::JobRestored, Some(job_id), None, None);
+    Ok(())
+}
+
+/// Shorts in the trash, most recently deleted first, with their trends
+#[tauri::command]
+fn list_trash(state: State<AppState>) -> Result<Vec<(db::Short, db::Trend)>, AppError> {
+    state.db.list_trashed_shorts().map_err(AppError::from)
+}
+
+/// Permanently delete everything in the trash, files included. Returns the
+/// number of shorts deleted.
+#[tauri::command]
+async fn empty_trash(state: State<'_, AppState>) -> Result<usize, AppError> {
+    let app_dir = state.app_dir.clone();
+    let (count, report, objects) = with_db(&state, move |db| {
+        let (count, paths) = db.empty_trash()?;
+        // Cached stock clips and images may still be used elsewhere
+        let mut referenced = db.referenced_media_paths()?;
+        artifact_store::add_local_copies(&app_dir, &mut referenced);
+        let mut report = storage::CleanupReport::default();
+        let (objects, files): (Vec<String>, Vec<String>) = paths.into_iter()
+            .filter(|path| !referenced.contains(path) && !cache::contains(&app_dir, std::path::Path::new(path)))
+            .partition(|path| artifact_store::is_remote(path));
+        for path in files {
+            storage::remove_file(std::path::Path::new(&path), &mut report);
+        }
+        Ok((count, report, objects))
+    })
+    .await?;
+    let store = state.db.load_config()?.artifact_store;
+    for url in &objects {
+        if let Err(e) = artifact_store::delete(&store, &state.app_dir, url).await {
+            tracing::warn!("Failed to delete {}: {}", url, e);
+        }
+    }
+    tracing::info!("Emptied the trash: {} shorts, {} files, {} stored objects", count, report.files_deleted, objects.len());
+    Ok(count)
+}
+
+/// Discard a short's script, narration and video and queue its job so the
+/// pipeline makes it again
+#[tauri::command]
+fn regenerate_short(state: State<AppState>, short_id: i64) -> Result<(), AppError> {
+    regenerate(&state, short_id, Actor::Ui)
+}
+
+fn regenerate(state: &AppState, short_id: i64, actor: Actor) -> Result<(), AppError> {
+    let db = &state.db;
+    let (short, _) = db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let queued = pipeline::Stage::Generation.queued_status();
+    if !db.regenerate_short(short_id, queued)? {
+        return Err(AppError::Validation(format!("Job {} is still running; try again when it finishes", short.job_id)));
+    }
+    record_activity(db, actor, ActivityAction::ShortRegenerated, Some(short.job_id), Some(short_id), None);
+    Ok(())
+}
+
+/// Run one step of a job again, and the steps after it, keeping the work
+/// before it: re-narrate or re-render without a new script, for instance
+#[tauri::command]
+fn rerun_stage(state: State<AppState>, job_id: i64, stage: PipelineStep) -> Result<(), AppError> {
+    let db = &state.db;
+    let (job, _) = db.get_job_with_trend(job_id)?
+        .ok_or_else(|| AppError::not_found("Job", job_id))?;
+    if job.checkpoint < Some(stage) {
+        return Err(AppError::Validation(format!("Job {} hasn't finished its {} step yet", job_id, stage.as_str())));
+    }
+    let queued = pipeline::Stage::of(stage).queued_status();
+    if !db.rerun_job_step(job_id, stage, queued)? {
+        return Err(AppError::Validation(format!("Job {} is running or in the trash", job_id)));
+    }
+    record_activity(db, Actor::Ui, ActivityAction::StepRerun, Some(job_id), None, Some(stage.as_str()));
+    Ok(())
+}
+
+/// List TTS voices for one backend, or for every usable backend when none is given
+#[tauri::command]
+async fn list_voices(state: State<'_, AppState>, backend: Option<TtsBackend>) -> Result<Vec<tts::Voice>, AppError> {
+    let config = state.db.load_config()?;
+    match backend {
+        Some(backend) => Ok(state.clients.tts(&config, backend)?.list_voices().await.map_err(AppError::Http)?),
+        None => Ok(tts::list_all_voices(&state.clients, &config).await),
+    }
+}
+
+/// Voice a short's script with the configured TTS backend and store the audio path
+#[tauri::command]
+async fn synthesize_short(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    synthesize(&state, short_id, &JobLogger::none(&state.db)).await
+}
+
+async fn synthesize(state: &AppState, short_id: i64, log: &JobLogger<'_>) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let (short, _) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    
+    let script = short.script.ok_or_else(|| AppError::Validation("Short has no script yet".into()))?;
+    let script = sfx::strip(&script);
+    let voice = tts::voice_for_language(&config, &short.language)
+        .ok_or_else(|| format!("No TTS voice configured for language '{}'", short.language))?;
+    
+    let audio_dir = state.app_dir.join("audio");
+    tokio::fs::create_dir_all(&audio_dir).await?;
+    let audio_path = audio_dir.join(format!("short_{}.mp3", short_id));
+    
+    let engine_timing = narrate(state, &config, &script, &voice, &audio_path).await?;
+    log.info(format!("Narrated short {} with {} voice {}", short_id, config.tts_backend.as_str(), voice));
+    
+    // Some engines pad both ends and pause for seconds between sentences,
+    // which loses viewers; tighten that before anything measures the audio
+    let cuts = if config.pacing_cleanup {
+        let duration = render::probe_duration(&config, &audio_path).await.map_err(AppError::Ffmpeg)?;
+        let max_pause = config.max_pause_ms as f64 / 1000.0;
+        let cuts = audio::tighten_pacing(config.ffmpeg(), &audio_path, duration, max_pause)
+            .await
+            .map_err(AppError::Ffmpeg)?;
+        let removed: f64 = cuts.iter().map(|(start, end)| end - start).sum();
+        log.debug(format!("Cut {:.1} s of silence from {:.1} s of narration", removed, duration));
+        cuts
+    } else {
+        Vec::new()
+    };
+    
+    // TTS engines differ wildly in level; bring every narration to the same loudness
+    let loudness = if config.loudness_normalization {
+        let normalized = audio_dir.join(format!("short_{}.norm.mp3", short_id));
+        let report = audio::normalize_loudness(
+            config.ffmpeg(),
+            &audio_path,
+            &normalized,
+            config.loudness_target_lufs,
+            config.loudness_true_peak_db,
+        )
+        .await
+        .map_err(AppError::Ffmpeg)?;
+        tokio::fs::rename(&normalized, &audio_path).await?;
+        log.debug(format!("Loudness {:.1} LUFS -> {:.1} LUFS", report.input_lufs, report.output_lufs));
+        Some(report)
+    } else {
+        None
+    };
+    
+    // Captions need word timing: take the engine's, else align with whisper.cpp
+    // when it is set up, else estimate from the audio length
+    let speech_timing = match engine_timing {
+        Some(mut t) => {
+            t.remove_spans(&cuts);
+            t
+        }
+        None => {
+            let aligned = if config.whisper_model_path.is_some() {
+                timing::align_with_whisper(&config, &audio_path, &script).await
+                    .map_err(|e| log.warn(format!("Forced alignment failed, estimating timing: {}", e)))
+                    .ok()
+            } else {
+                None
+            };
+            match aligned {
+                Some(t) => t,
+                None => timing::estimate(&script, render::probe_duration(&config, &audio_path).await.map_err(AppError::Ffmpeg)?),
+            }
+        }
+    };
+    log.debug(format!("Caption timing source: {:?}", speech_timing.source));
+    
+    let audio_path = audio_path.to_string_lossy().to_string();
+    let db = &state.db;
+    db.set_short_audio(short_id, &audio_path)?;
+    db.set_short_timing(short_id, &speech_timing)?;
+    if let Some(report) = loudness {
+        db.record_metric_value(short.job_id, "loudnorm", "input_lufs", report.input_lufs)?;
+        db.record_metric_value(short.job_id, "loudnorm", "input_true_peak", report.input_true_peak)?;
+        db.record_metric_value(short.job_id, "loudnorm", "output_lufs", report.output_lufs)?;
+        db.record_metric_value(short.job_id, "loudnorm", "output_true_peak", report.output_true_peak)?;
+    }
+    Ok(audio_path)
+}
+
+/// Raw TTS narration of `script`, copied to `output` for post-processing.
+/// Text voiced the same way before comes from the media cache, engine
+/// timing included.
+async fn narrate(
+    state: &AppState,
+    config: &Config,
+    script: &str,
+    voice: &str,
+    output: &Path,
+) -> Result<Option<timing::SpeechTiming>, AppError> {
+    let backend = config.tts_backend;
+    let model = match backend {
+        TtsBackend::OpenAi => config.openai_tts_model.as_str(),
+        TtsBackend::Edge => "",
+    };
+    let key = cache::key(&[backend.as_str(), model, voice, script]);
+    let (path, engine_timing) = match cache::lookup(&state.db, CacheKind::Tts, &key)? {
+        Some(cached) => (cached.path, cached.meta.and_then(|meta| serde_json::from_str(&meta).ok())),
+        None => {
+            let scratch = cache::scratch_path(&state.app_dir, CacheKind::Tts, "mp3").await?;
+            let engine_timing = state.clients.tts(config, backend)?
+                .synthesize(script, voice, &scratch)
+                .await?;
+            let meta = engine_timing.as_ref().and_then(|t| serde_json::to_string(t).ok());
+            let path = cache::store(&state.db, config, &state.app_dir, CacheKind::Tts, &key, &scratch, meta.as_deref()).await?;
+            (path, engine_timing)
+        }
+    };
+    tokio::fs::copy(&path, output).await?;
+    Ok(engine_timing)
+}
+
+/// Synthesize a short voice sample and return its file path; the UI loads it
+/// through the asset protocol (`convertFileSrc`)
+#[tauri::command]
+async fn preview_voice(
+    state: State<'_, AppState>,
+    backend: TtsBackend,
+    voice_id: String,
+    sample_text: Option<String>,
+) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let path = tts::preview(&state.clients, &config, backend, &voice_id, sample_text.as_deref().unwrap_or("")).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Asset protocol URL the UI can play one of a short's files from. Only
+/// files inside the app data directory are let through, one at a time.
+#[tauri::command]
+async fn get_short_media_url(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    short_id: i64,
+    kind: preview::MediaKind,
+) -> Result<String, AppError> {
+    let (short, _) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let path = kind.path(&short)
+        .ok_or_else(|| AppError::NotFound(format!("Short {} has no {} yet", short_id, kind.as_str())))?;
+    // Videos kept in a bucket are played from a local copy
+    let path = if artifact_store::is_remote(path) {
+        local_video(&state, &state.db.load_config()?, &short).await?
+    } else {
+        PathBuf::from(path)
+    };
+    let path = preview::resolve(&state.app_dir, &path.to_string_lossy()).map_err(AppError::Validation)?;
+    app.asset_protocol_scope()
+        .allow_file(&path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(preview::asset_url(&path))
+}
+
+/// Render a short's narration into a vertical video, mixing in background music when enabled
+#[tauri::command]
+async fn render_short(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    render_video(&state, short_id, RenderMode::Final(None), &JobLogger::none(&state.db)).await
+}
+
+/// Render a short again with a named preset, replacing its current video
+#[tauri::command]
+async fn rerender_short(state: State<'_, AppState>, short_id: i64, preset: String) -> Result<String, AppError> {
+    render_video(&state, short_id, RenderMode::Final(Some(&preset)), &JobLogger::none(&state.db)).await
+}
+
+/// Quick 360p render without music for checking pacing and captions. The
+/// file goes to the temp dir and is never attached to the short for delivery.
+#[tauri::command]
+async fn draft_render(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    render_video(&state, short_id, RenderMode::Draft, &JobLogger::none(&state.db)).await
+}
+
+/// What a render is for
+enum RenderMode<'a> {
+    /// Deliverable video with the named preset, or the default preset when `None`
+    Final(Option<&'a str>),
+    /// Throwaway low-res preview
+    Draft,
+}
+
+async fn render_video(state: &AppState, short_id: i64, mode: RenderMode<'_>, log: &JobLogger<'_>) -> Result<String, AppError> {
+    let draft = matches!(mode, RenderMode::Draft);
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let track = if config.music_enabled && !draft {
+        music::select_for_short(&state.db, &short, &trend)?
+    } else {
+        None
+    };
+    let template = state.db.render_template_or_default(config.default_render_template)?;
+    let dry_run = state.db.job_is_dry_run(short.job_id)?;
+    let effects = match (&short.script, &short.timing) {
+        (Some(script), Some(timing)) => {
+            let (cues, unknown) = sfx::place(script, timing, &state.db.list_sound_effects()?);
+            for name in unknown {
+                log.warn(format!("Short {} calls for sound effect '{}', which is not in the library", short_id, name));
+            }
+            cues
+        }
+        _ => Vec::new(),
+    };
+    let preset = match mode {
+        RenderMode::Draft => render::RenderPreset::draft(),
+        RenderMode::Final(Some(name)) => config.render_presets.iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown render preset '{}'", name))?,
+        RenderMode::Final(None) => config.render_preset(&config.default_render_preset),
+    };
+    
+    let narration = short.audio_path.as_deref().ok_or_else(|| AppError::Validation("Short has no narration audio yet".into()))?;
+    let render_dir = if draft {
+        storage::drafts_dir()
+    } else {
+        state.app_dir.join("renders")
+    };
+    tokio::fs::create_dir_all(&render_dir).await?;
+    let video_path = render_dir.join(format!("short_{}.mp4", short_id));
+    
+    let duration = if dry_run && !draft {
+        log.info(format!("Dry run: rendering a placeholder for short {}", short_id));
+        render::render_placeholder(&config, &preset, dry_run::PLACEHOLDER_SECS, &video_path)
+            .await
+            .map_err(AppError::Ffmpeg)?
+    } else {
+        let caption_style = template.layout.caption_style(&config);
+        let subtitles = match (&short.timing, caption_style.enabled) {
+            (Some(timing), true) => {
+                let path = render_dir.join(format!("short_{}.ass", short_id));
+                tokio::fs::write(&path, captions::build_ass(timing, caption_style))
+                    .await?;
+                Some(path)
+            }
+            _ => None,
+        };
+        
+        // Per-scene visuals; scenes are segmented on first render if that stage
+        // hasn't run. Drafts only use media that is already on disk.
+        let slides = if scenes::visuals_enabled(&config) {
+            scene_slides(state, &config, &short, !draft, log).await?
+        } else {
+            Vec::new()
+        };
+        
+        let input = render::RenderInput {
+            narration: std::path::Path::new(&narration),
+            music: track.as_ref().map(|t| std::path::Path::new(&t.path)),
+            effects: &effects,
+            subtitles: subtitles.as_deref(),
+            slides: &slides,
+        };
+        log.info(format!(
+            "Rendering short {} with preset {} ({}x{}, {} fps, {} slides)",
+            short_id, preset.name, preset.width, preset.height, preset.fps, slides.len()
+        ));
+        render::render_short(&config, &template, &preset, &input, &video_path)
+            .await
+            .map_err(AppError::Ffmpeg)?
+    };
+    log.info(format!("Rendered short {}: {:.1} s", short_id, duration));
+    // Over the limit, YouTube quietly files an upload as a regular video
+    let duration = if draft || dry_run {
+        duration
+    } else {
+        match render::fit_length(&config, &preset, &video_path, duration).await.map_err(AppError::Ffmpeg)? {
+            render::LengthCheck::Fits => duration,
+            render::LengthCheck::Trimmed(fitted) => {
+                log.info(format!("Cut the trailing silence of short {}: {:.1} s to {:.1} s", short_id, duration, fitted));
+                fitted
+            }
+            render::LengthCheck::SpedUp { factor, duration: fitted } => {
+                log.warn(format!(
+                    "Sped short {} up by {:.0}% to fit: {:.1} s to {:.1} s",
+                    short_id,
+                    (factor - 1.0) * 100.0,
+                    duration,
+                    fitted
+                ));
+                fitted
+            }
+            render::LengthCheck::TooLong => {
+                return Err(AppError::Validation(format!(
+                    "Short {} is {:.1} s long, over the {} s limit for Shorts; shorten the script or raise the limit",
+                    short_id, duration, config.max_short_secs
+                )));
+            }
+        }
+    };
+    let video_path_str = video_path.to_string_lossy().to_string();
+    if draft {
+        return Ok(video_path_str);
+    }
+    
+    let thumbnail = make_thumbnail(&state.app_dir, &config, short_id, &video_path, &trend.title)
+        .await
+        .map_err(|e| log.warn(format!("Thumbnail failed for short {}: {}", short_id, e)))
+        .ok();
+    // With a bucket configured the short points at the object instead. A
+    // failed upload leaves it on the local copy.
+    let video_path_str = if dry_run {
+        video_path_str
+    } else {
+        let key = format!("renders/short_{}.mp4", short_id);
+        match artifact_store::put(&config.artifact_store, &video_path, &key).await {
+            Ok(Some(url)) => {
+                log.info(format!("Stored short {} at {}", short_id, url));
+                if !config.keep_local_renders {
+                    if let Err(e) = tokio::fs::remove_file(&video_path).await {
+                        log.warn(format!("Failed to delete the local copy of short {}: {}", short_id, e));
+                    }
+                }
+                url
+            }
+            Ok(None) => video_path_str,
+            Err(e) => {
+                log.warn(format!("Keeping short {} on local disk: {}", short_id, e));
+                video_path_str
+            }
+        }
+    };
+    
+    state.db.set_short_video(short_id, &video_path_str, duration)?;
+    if let Some(thumbnail) = thumbnail {
+        state.db.set_short_thumbnail(short_id, &thumbnail)?;
+    }
+    // A new video needs a fresh review, even if an earlier render was approved
+    if config.approval_required {
+        state.db.set_short_approval(short_id, ApprovalStatus::AwaitingApproval, None)?;
+    }
+    if dry_run {
+        return Ok(video_path_str);
+    }
+    webhooks::dispatch(state, WebhookEvent::ShortRendered, serde_json::json!({
+        "short_id": short_id,
+        "job_id": short.job_id,
+        "title": trend.title,
+        "language": short.language,
+        "video_path": video_path_str,
+        "duration_sec": duration,
+        "awaiting_approval": config.approval_required,
+    }));
+    Ok(video_path_str)
+}
+
+/// Refuse to post a short of a dry-run job by hand: its video is only a
+/// placeholder
+fn ensure_not_dry_run(db: &Database, short_id: i64, job_id: i64) -> Result<(), AppError> {
+    if db.job_is_dry_run(job_id)? {
+        return Err(AppError::Validation(format!("Short {} is from a dry run and is never posted", short_id)));
+    }
+    Ok(())
+}
+
+/// Local file of a short's video, fetched back from the artifact store when
+/// only the bucket has it
+async fn local_video(state: &AppState, config: &Config, short: &Short) -> Result<PathBuf, AppError> {
+    let stored = short.video_path.as_deref()
+        .ok_or_else(|| AppError::Validation("Short has not been rendered yet".into()))?;
+    artifact_store::local_path(&config.artifact_store, &state.app_dir, stored)
+        .await
+        .map_err(AppError::Http)
+}
+
+/// Write a short's poster frame to `thumbnails/short_{id}.jpg`
+async fn make_thumbnail(
+    app_dir: &std::path::Path,
+    config: &Config,
+    short_id: i64,
+    video: &std::path::Path,
+    title: &str,
+) -> Result<String, AppError> {
+    let dir = app_dir.join("thumbnails");
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(format!("short_{}.jpg", short_id));
+    thumbnails::generate(config, video, title, &path).await.map_err(AppError::Ffmpeg)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Regenerate a rendered short's poster frame
+#[tauri::command]
+async fn generate_thumbnail(state: State<'_, AppState>, short_id: i64) -> Result<String, AppError> {
+    let config = state.db.load_config()?;
+    let (short, trend) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let video_path = local_video(&state, &config, &short).await?;
+    let thumbnail = make_thumbnail(&state.app_dir, &config, short_id, &video_path, &trend.title)
+        .await?;
+    
+    let db = &state.db;
+    db.set_short_thumbnail(short_id, &thumbnail)?;
+    Ok(thumbnail)
+}
+
+/// Fetch or generate media for each of a short's scenes and turn them into slides.
+/// A scene whose media can't be fetched reuses the previous scene's visual.
+/// Without `fetch`, only scenes and media that already exist are used.
+async fn scene_slides(
+    state: &AppState,
+    config: &Config,
+    short: &db::Short,
+    fetch: bool,
+    log: &JobLogger<'_>,
+) -> Result<Vec<render::Slide>, AppError> {
+    let short_id = short.id.ok_or("Short has no id")?;
+    let mut scenes = state.db.get_scenes(short_id)?;
+    if scenes.is_empty() && fetch {
+        let script = short.script.as_deref().ok_or_else(|| AppError::Validation("Short has no script yet".into()))?;
+        let segmented = scenes::segment(&state.clients, config, short_id, &sfx::strip(script), short.timing.as_ref()).await;
+        let db = &state.db;
+        db.replace_scenes(short_id, &segmented)?;
+        scenes = db.get_scenes(short_id)?;
+    }
+    
+    for scene in scenes.iter_mut() {
+        if scene.media_path.as_deref().is_some_and(|p| std::path::Path::new(p).is_file()) {
+            continue;
+        }
+        scene.media_path = None;
+        if !fetch {
+            continue;
+        }
+        match scenes::fetch_media(config, &state.db, &state.app_dir, scene).await {
+            Ok(Some(path)) => {
+                let path = path.to_string_lossy().to_string();
+                if let Some(scene_id) = scene.id {
+                    let db = &state.db;
+                    db.set_scene_media(scene_id, &path)?;
+                }
+                scene.media_path = Some(path);
+            }
+            Ok(None) => {}
+            Err(e) => log.warn(format!("No visual for scene {} of short {}: {}", scene.position + 1, short_id, e)),
+        }
+    }
+    Ok(scenes::slides(&scenes))
+}
+
+/// Split a short's script into timed visual scenes, replacing any earlier split
+#[tauri::command]
+async fn segment_short(state: State<'_, AppState>, short_id: i64) -> Result<Vec<Scene>, AppError> {
+    let config = state.db.load_config()?;
+    let (short, _) = state.db.get_short_with_trend(short_id)?
+        .ok_or_else(|| AppError::not_found("Short", short_id))?;
+    let script = short.script.as_deref().ok_or_else(|| AppError::Validation("Short has no script yet".into()))?;
+    let scenes = scenes::segment(&state.clients, &config, short_id, &sfx::strip(script), short.timing.as_ref()).await;
+    
+    let db = &state.db;
+    db.replace_scenes(short_id, &scenes)?;
+    db.get_scenes(short_id).map_err(AppError::from)
+}
+
+/// List a short's scenes in order
+#[tauri::command]
+fn list_scenes(state: State<AppState>, short_id: i64) -> Result<Vec<Scene>, AppError> {
+    state.db.get_scenes(short_id).map_err(AppError::from)
+}
+
+/// Hardware and software H.264 encoders that work on this machine
+#[tauri::command]
+async fn detect_encoders(state: State<'_, AppState>) -> Result<Vec<VideoEncoder>, AppError> {
+    let config = state.db.load_config()?;
+    render::detect_encoders(&config).await.map_err(AppError::Ffmpeg)
+}
+
+/// Check that ffmpeg, ffprobe and yt-dlp are installed and recent enough
+#[tauri::command]
+async fn check_dependencies(state: State<'_, AppState>) -> Result<deps::DependencyReport, AppError> {
+    let config = state.db.load_config()?;
+    Ok(deps::check(&config).await)
+}
+
+/// Check every dependency the app talks to and report each one's status
+#[tauri::command]
+async fn health_check(state: State<'_, AppState>) -> Result<health::HealthReport, AppError> {
+    Ok(health::check(&state).await)
+}
+
+/// Fill the database with demo trends, jobs, shorts and metrics, for trying
+/// the dashboard without API keys. The demo jobs are dry runs the worker
+/// never picks up; `clear_demo_data` removes them.
+#[tauri::command]
+async fn seed_demo_data(app: AppHandle, state: State<'_, AppState>) -> Result<demo::Seeded, AppError> {
+    let seeded = with_db(&state, demo::seed).await?;
+    tracing::info!("Seeded {} demo jobs", seeded.jobs);
+    if let Ok(status) = pipeline::status(&state.db) {
+        let _ = app.emit("queue:status", status);
+    }
+    Ok(seeded)
+}
+
+/// Delete the demo trends and everything made from them. Returns the number
+/// of jobs deleted.
+#[tauri::command]
+async fn clear_demo_data(state: State<'_, AppState>) -> Result<usize, AppError> {
+    let jobs = with_db(&state, |db| db.delete_demo_data()).await?;
+    tracing::info!("Deleted {} demo jobs", jobs);
+    Ok(jobs)
+}
+
+/// Download a static ffmpeg build into the app data dir and point config at it
 #[tauri::command]
-fn get_stats(state: State<AppState>) -> Result<DashboardStats, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.get_stats().map_err(|e| e.to_string())
+async fn install_ffmpeg(state: State<'_, AppState>) -> Result<deps::DependencyReport, AppError> {
+    let (ffmpeg, ffprobe) = deps::install_ffmpeg(&state.app_dir).await.map_err(AppError::Http)?;
+    let mut config = state.db.load_config()?;
+    config.ffmpeg_path = Some(ffmpeg.to_string_lossy().to_string());
+    config.ffprobe_path = Some(ffprobe.to_string_lossy().to_string());
+    state.db.save_config(&config)?;
+    Ok(deps::check(&config).await)
+}
+
+/// Add a video style template
+#[tauri::command]
+fn add_render_template(state: State<AppState>, template: RenderTemplate) -> Result<i64, AppError> {
+    state.db.insert_render_template(&template).map_err(AppError::from)
+}
+
+/// List video style templates
+#[tauri::command]
+fn list_render_templates(state: State<AppState>) -> Result<Vec<RenderTemplate>, AppError> {
+    state.db.list_render_templates().map_err(AppError::from)
+}
+
+/// Update a video style template
+#[tauri::command]
+fn update_render_template(state: State<AppState>, template: RenderTemplate) -> Result<(), AppError> {
+    state.db.update_render_template(&template).map_err(AppError::from)
+}
+
+/// Delete a video style template
+#[tauri::command]
+fn delete_render_template(state: State<AppState>, template_id: i64) -> Result<(), AppError> {
+    state.db.delete_render_template(template_id).map_err(AppError::from)
+}
+
+/// Copy a logo into app data and use it as the watermark of the default
+/// render template, creating that template if only the built-in one exists
+#[tauri::command]
+fn set_watermark(state: State<AppState>, path: String) -> Result<String, AppError> {
+    let source = std::path::Path::new(&path);
+    if !source.is_file() {
+        return Err(AppError::Validation(format!("Watermark file not found: {}", path)));
+    }
+    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let dir = state.app_dir.join("watermarks");
+    std::fs::create_dir_all(&dir)?;
+    let stored = dir.join(format!("watermark.{}", extension.to_lowercase()));
+    std::fs::copy(source, &stored)?;
+    let stored = stored.to_string_lossy().to_string();
+    
+    let db = &state.db;
+    let mut config = db.load_config()?;
+    let mut template = db.render_template_or_default(config.default_render_template)?;
+    template.layout.watermark_path = Some(stored.clone());
+    match template.id {
+        Some(_) => db.update_render_template(&template)?,
+        None => {
+            template.name = "Default".to_string();
+            let template_id = db.insert_render_template(&template)?;
+            config.default_render_template = Some(template_id);
+            db.save_config(&config)?;
+        }
+    }
+    Ok(stored)
+}
+
+/// Copy a file into app data and make it `asset` of a render template, or
+/// drop the asset when `path` is `None`. Returns the stored path.
+#[tauri::command]
+fn set_template_asset(
+    state: State<AppState>,
+    template_id: i64,
+    asset: render::TemplateAsset,
+    path: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let db = &state.db;
+    let mut template = db.render_template_or_default(Some(template_id))?;
+    if template.id.is_none() {
+        return Err(AppError::not_found("Render template", template_id));
+    }
+    let source = path.as_deref().map(std::path::Path::new);
+    if let Some(source) = source {
+        if !source.is_file() {
+            return Err(AppError::Validation(format!("Template asset not found: {}", source.display())));
+        }
+    }
+    let dir = state.app_dir.join("templates").join(template_id.to_string());
+    let stored = template.layout.store_asset(&dir, asset, source)?;
+    db.update_render_template(&template)?;
+    Ok(stored)
+}
+
+/// List the sound effects scripts can call for with `[sfx:name]`
+#[tauri::command]
+fn list_sfx(state: State<AppState>) -> Result<Vec<SoundEffect>, AppError> {
+    state.db.list_sound_effects().map_err(AppError::from)
+}
+
+/// Copy a sound effect into app data and add it to the library as `name`
+/// (the file name when omitted), replacing an effect of that name
+#[tauri::command]
+fn import_sfx(state: State<AppState>, path: String, name: Option<String>, volume_db: Option<f64>) -> Result<SoundEffect, AppError> {
+    let source = std::path::Path::new(&path);
+    if !source.is_file() {
+        return Err(AppError::Validation(format!("Sound effect file not found: {}", path)));
+    }
+    let name = name
+        .or_else(|| source.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+        .replace(' ', "-");
+    if !sfx::is_valid_name(&name) {
+        return Err(AppError::Validation(format!(
+            "Sound effect name '{}' may only use letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+    let dir = state.app_dir.join("sfx");
+    std::fs::create_dir_all(&dir)?;
+    let stored = dir.join(format!("{}.{}", name, extension.to_lowercase()));
+    std::fs::copy(source, &stored)?;
+    
+    let mut effect = SoundEffect {
+        id: None,
+        name,
+        path: stored.to_string_lossy().to_string(),
+        volume_db: volume_db.unwrap_or(0.0),
+    };
+    effect.id = Some(state.db.upsert_sound_effect(&effect)?);
+    Ok(effect)
+}
+
+/// Add a track to the background music library
+#[tauri::command]
+fn add_music(state: State<AppState>, track: MusicTrack) -> Result<i64, AppError> {
+    if !std::path::Path::new(&track.path).is_file() {
+        return Err(AppError::Validation(format!("Music file not found: {}", track.path)));
+    }
+    let db = &state.db;
+    db.insert_music(&track).map_err(AppError::from)
+}
+
+/// List the background music library
+#[tauri::command]
+fn list_music(state: State<AppState>) -> Result<Vec<MusicTrack>, AppError> {
+    state.db.list_music().map_err(AppError::from)
+}
+
+/// Update a music library track
+#[tauri::command]
+fn update_music(state: State<AppState>, track: MusicTrack) -> Result<(), AppError> {
+    state.db.update_music(&track).map_err(AppError::from)
+}
+
+/// Remove a track from the music library
+#[tauri::command]
+fn delete_music(state: State<AppState>, music_id: i64) -> Result<(), AppError> {
+    state.db.delete_music(music_id).map_err(AppError::from)
+}
+
+/// Add an outgoing webhook, generating its signing secret if none is given
+#[tauri::command]
+fn add_webhook(state: State<AppState>, mut webhook: Webhook) -> Result<i64, AppError> {
+    if !webhook.url.starts_with("https://") && !webhook.url.starts_with("http://") {
+        return Err(AppError::Validation("Webhook URL must start with http:// or https://".into()));
+    }
+    if webhook.secret.trim().is_empty() {
+        webhook.secret = webhooks::generate_secret();
+    }
+    let db = &state.db;
+    db.insert_webhook(&webhook).map_err(AppError::from)
+}
+
+/// List outgoing webhooks
+#[tauri::command]
+fn list_webhooks(state: State<AppState>) -> Result<Vec<Webhook>, AppError> {
+    state.db.list_webhooks().map_err(AppError::from)
+}
+
+/// Delete an outgoing webhook
+#[tauri::command]
+fn delete_webhook(state: State<AppState>, webhook_id: i64) -> Result<(), AppError> {
+    state.db.delete_webhook(webhook_id).map_err(AppError::from)
+}
+
+/// Send a `webhook.test` event to a webhook once, without retrying
+#[tauri::command]
+async fn test_webhook(state: State<'_, AppState>, webhook_id: i64) -> Result<(), AppError> {
+    let webhook = state.db.get_webhook(webhook_id)?
+        .ok_or_else(|| AppError::not_found("Webhook", webhook_id))?;
+    webhooks::send(&webhook, WebhookEvent::Test, &serde_json::json!({ "webhook_id": webhook_id }))
+        .await
+        .map_err(AppError::Http)
+}
+
+/// Outcome of a connection test
+#[derive(Debug, Serialize)]
+struct TestResult {
+    ok: bool,
+    /// HTTP status of the last request, `None` when it never got a response
+    status_code: Option<u16>,
+    latency_ms: u64,
+    /// Why the test failed, or what was found when it passed
+    detail: String,
+}
+
+impl TestResult {
+    fn failed(status_code: Option<u16>, started: Instant, detail: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            status_code,
+            latency_ms: started.elapsed().as_millis() as u64,
+            detail: detail.into(),
+        }
+    }
+    
+    fn passed(status_code: u16, started: Instant, detail: impl Into<String>) -> Self {
+        Self { ok: true, ..Self::failed(Some(status_code), started, detail) }
+    }
 }
 
 /// Test YouTube API key
 #[tauri::command]
-async fn test_youtube_api(api_key: String) -> Result<bool, String> {
-    let url = format!(
-        "https://www.googleapis.com/youtube/v3/videos?part=id&id=dQw4w9WgXcQ&key={}",
-        api_key
-    );
-    let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    Ok(response.status().is_success())
+async fn test_youtube_api(api_key: String) -> Result<TestResult, AppError> {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        error: ApiError,
+    }
+    #[derive(Deserialize)]
+    struct ApiError {
+        message: String,
+        #[serde(default)]
+        errors: Vec<ErrorReason>,
+    }
+    #[derive(Deserialize)]
+    struct ErrorReason {
+        reason: String,
+    }
+    let started = Instant::now();
+    let request = http::client()
+        .get("https://www.googleapis.com/youtube/v3/videos")
+        .query(&[("part", "id"), ("id", "dQw4w9WgXcQ"), ("key", &api_key)])
+        .timeout(http::PROBE_TIMEOUT);
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(TestResult::failed(None, started, e.to_string())),
+    };
+    let status = response.status().as_u16();
+    if response.status().is_success() {
+        return Ok(TestResult::passed(status, started, "The API key works"));
+    }
+    let body: Option<ErrorBody> = response.json().await.ok();
+    let reason = body.as_ref().and_then(|b| b.error.errors.first()).map(|e| e.reason.as_str());
+    let detail = match reason {
+        Some("quotaExceeded" | "dailyLimitExceeded") => "The key works, but today's API quota is used up".to_string(),
+        Some("rateLimitExceeded") => "The key works, but it is being rate limited".to_string(),
+        Some("accessNotConfigured") => "YouTube Data API v3 is not enabled for this key's project".to_string(),
+        _ => match body {
+            Some(body) => body.error.message,
+            None => format!("YouTube returned {}", status),
+        },
+    };
+    Ok(TestResult::failed(Some(status), started, detail))
 }
 
-/// Test Telegram bot token
+/// Test Telegram bot token and, when given, that the bot can reach `chat_id`
 #[tauri::command]
-async fn test_telegram_bot(token: String) -> Result<bool, String> {
-    let url = format!("https://api.telegram.org/bot{}/getMe", token);
-    let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    Ok(response.status().is_success())
+async fn test_telegram_bot(token: String, chat_id: Option<String>) -> Result<TestResult, AppError> {
+    #[derive(Deserialize)]
+    struct Reply {
+        ok: bool,
+        description: Option<String>,
+        result: Option<serde_json::Value>,
+    }
+    let started = Instant::now();
+    let call = |method: &str, query: Vec<(&'static str, String)>| {
+        http::client()
+            .get(format!("https://api.telegram.org/bot{}/{}", token, method))
+            .query(&query)
+            .timeout(http::PROBE_TIMEOUT)
+            .send()
+    };
+    let response = match call("getMe", Vec::new()).await {
+        Ok(response) => response,
+        Err(e) => return Ok(TestResult::failed(None, started, e.to_string())),
+    };
+    let status = response.status().as_u16();
+    let me: Reply = match response.json().await {
+        Ok(me) => me,
+        Err(e) => return Ok(TestResult::failed(Some(status), started, e.to_string())),
+    };
+    if !me.ok {
+        let detail = me.description.unwrap_or_else(|| "The bot token was rejected".to_string());
+        return Ok(TestResult::failed(Some(status), started, detail));
+    }
+    let username = me.result.as_ref().and_then(|bot| bot["username"].as_str()).unwrap_or("bot").to_string();
+    let Some(chat_id) = chat_id.filter(|id| !id.trim().is_empty()) else {
+        return Ok(TestResult::passed(status, started, format!("Connected as @{}", username)));
+    };
+    
+    let response = match call("getChat", vec![("chat_id", chat_id.trim().to_string())]).await {
+        Ok(response) => response,
+        Err(e) => return Ok(TestResult::failed(None, started, e.to_string())),
+    };
+    let status = response.status().as_u16();
+    let chat: Reply = match response.json().await {
+        Ok(chat) => chat,
+        Err(e) => return Ok(TestResult::failed(Some(status), started, e.to_string())),
+    };
+    if !chat.ok {
+        let detail = format!(
+            "@{} can't reach chat {}: {}",
+            username,
+            chat_id,
+            chat.description.unwrap_or_default()
+        );
+        return Ok(TestResult::failed(Some(status), started, detail));
+    }
+    let title = chat.result.as_ref()
+        .and_then(|chat| chat["title"].as_str().or(chat["username"].as_str()).or(chat["first_name"].as_str()))
+        .unwrap_or(&chat_id)
+        .to_string();
+    Ok(TestResult::passed(status, started, format!("@{} can post to {}", username, title)))
 }
 
 /// Test Ollama endpoint
 #[tauri::command]
-async fn test_ollama(endpoint: String) -> Result<bool, String> {
-    let url = format!("{}/api/tags", endpoint);
-    let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    Ok(response.status().is_success())
+async fn test_ollama(endpoint: String) -> Result<TestResult, AppError> {
+    let started = Instant::now();
+    let url = format!("{}/api/tags", endpoint.trim_end_matches('/'));
+    let response = match http::client().get(&url).timeout(http::PROBE_TIMEOUT).send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(TestResult::failed(None, started, e.to_string())),
+    };
+    let status = response.status().as_u16();
+    if !response.status().is_success() {
+        return Ok(TestResult::failed(Some(status), started, format!("Ollama returned {}", status)));
+    }
+    let tags: serde_json::Value = match response.json().await {
+        Ok(tags) => tags,
+        Err(e) => return Ok(TestResult::failed(Some(status), started, format!("Not an Ollama server: {}", e))),
+    };
+    let models = tags["models"].as_array().map(Vec::len).unwrap_or(0);
+    Ok(TestResult::passed(status, started, format!("{} models installed", models)))
+}
+
+/// Models Ollama has loaded and their VRAM use
+#[tauri::command]
+async fn get_ollama_status(state: State<'_, AppState>) -> Result<llm::OllamaStatus, AppError> {
+    let config = state.db.load_config()?;
+    Ok(llm::ollama(&state.clients, &config).status().await?)
+}
+
+/// Test a Stable Diffusion endpoint
+#[tauri::command]
+async fn test_image_backend(backend: ImageBackend, endpoint: String) -> Result<bool, AppError> {
+    image_gen::test_backend(backend, &endpoint).await.map_err(AppError::Http)
 }
 
 // ==================== App Entry Point ====================
@@ -67,25 +2971,273 @@ async fn test_ollama(endpoint: String) -> Result<bool, String> {
 pub fn run() {
     // Get app data directory
     let app_dir = dirs::data_local_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .unwrap_or_else(|| PathBuf::from("."))
         .join("shotauto");
     
     std::fs::create_dir_all(&app_dir).expect("Failed to create app data directory");
+    logging::init(&app_dir);
     
-    let db_path = app_dir.join("shotauto.db");
-    let db = Database::new(db_path).expect("Failed to initialize database");
-    
-    tauri::Builder::default()
+    let app = tauri::Builder::default()
+        // Registered first so a second launch hands over and exits before
+        // its worker starts; two workers on one SQLite file fight over jobs
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            tracing::info!("Second launch forwarded {} argument(s)", args.len().saturating_sub(1));
+            tray::show_main_window(app);
+            open_launch_args(app, args);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
-        .manage(AppState { db: Mutex::new(db) })
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![autostart::MINIMIZED_ARG]),
+        ))
+        .setup(move |app| {
+            // Opened here rather than before the builder so a second launch,
+            // which the single-instance plugin ends first, never migrates or
+            // converts the database under the running app
+            let db_path = app_dir.join("shotauto.db");
+            #[cfg(feature = "sqlcipher")]
+            let db = encryption::open(db_path)?;
+            #[cfg(not(feature = "sqlcipher"))]
+            let db = Database::new(db_path)?;
+            app.manage(AppState { db, app_dir, clients: Clients::default() });
+            resilience::init(app.handle().clone());
+            notifications::init(app.handle().clone());
+            tray::init(app.handle())?;
+            // The window is created hidden so a login start never flashes it;
+            // started on login it stays in the tray and lets the pipeline run
+            if !autostart::started_minimized() {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                }
+            }
+            // The login item may have been removed outside the app
+            if let Ok(config) = app.state::<AppState>().db.load_config() {
+                if let Err(e) = autostart::apply(app.handle(), config.launch_on_startup) {
+                    tracing::warn!("Failed to update the login item: {}", e);
+                }
+            }
+            // Installed builds register the scheme through the bundle;
+            // this covers running from the build dir
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            if let Err(e) = app.deep_link().register_all() {
+                tracing::warn!("Failed to register the {}:// scheme: {}", deep_link::SCHEME, e);
+            }
+            // Each link is handled in one place per platform. macOS hands
+            // links to the running app as events and never in the arguments.
+            // Windows and Linux pass them as arguments, to this launch or to
+            // the single-instance callback, which the plugin also reports
+            // through `on_open_url`, so that isn't listened to there.
+            #[cfg(target_os = "macos")]
+            {
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    open_links(&handle, event.urls().iter().map(|url| url.to_string()).collect());
+                });
+            }
+            #[cfg(not(target_os = "macos"))]
+            open_launch_args(app.handle(), std::env::args().collect());
+            pipeline::start(app.handle().clone());
+            tauri::async_runtime::spawn(trends::run(app.handle().clone()));
+            tauri::async_runtime::spawn(telegram::listen(app.handle().clone()));
+            tauri::async_runtime::spawn(analytics::run(app.handle().clone()));
+            tauri::async_runtime::spawn(backups::run(app.handle().clone()));
+            tauri::async_runtime::spawn(maintenance::run(app.handle().clone()));
+            tauri::async_runtime::spawn(health::run(app.handle().clone()));
+            tauri::async_runtime::spawn(api_server::run(app.handle().clone()));
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            // Keep the automation running in the tray instead of quitting
+            tauri::WindowEvent::CloseRequested { api, .. } => {
+                // The state is only managed once `setup` has opened the database
+                let close_to_tray = window.try_state::<AppState>()
+                    .and_then(|state| state.db.load_config().ok())
+                    .map(|config| config.close_to_tray)
+                    .unwrap_or(true);
+                if close_to_tray {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             get_config,
             save_config,
+            delete_config,
+            validate_config,
+            export_config,
+            import_config,
             get_stats,
+            get_queue_status,
+            set_queue_paused,
+            run_now,
+            get_transfer_status,
+            fetch_trends,
+            add_trend_from_url,
+            backup_database,
+            restore_database,
+            get_encryption_status,
+            encrypt_database,
+            export_data,
+            export_short_bundle,
+            import_short_bundle,
+            get_storage_stats,
+            cleanup_storage,
+            get_cache_stats,
+            clear_cache,
+            check_script,
+            transcribe_trend,
+            download_source,
+            get_trend_history,
+            list_trends,
+            set_trend_note,
+            toggle_trend_star,
+            skip_trend,
+            preview_prompt,
+            generate_script,
+            create_job,
+            create_dependent_job,
+            create_jobs_batch,
+            find_similar,
+            list_dead_jobs,
+            requeue_dead_job,
+            get_job_details,
+            rerun_stage,
+            get_job_logs,
+            get_stage_metrics,
+            get_metrics_series,
+            get_activity,
+            get_cost_report,
+            get_logs,
+            open_log_folder,
+            add_prompt_template,
+            list_prompt_templates,
+            update_prompt_template,
+            delete_prompt_template,
+            send_short_to_telegram,
+            send_short_to_discord,
+            set_short_instagram,
+            publish_to_instagram,
+            back_up_to_drive,
+            send_short_to_sftp,
+            test_sftp,
+            forget_sftp_host_key,
+            set_sftp_secret,
+            list_publications,
+            get_short_performance,
+            get_top_topics,
+            list_variants,
+            get_hook_style_report,
+            refresh_short_stats,
+            list_awaiting_approval,
+            approve_short,
+            reject_short,
+            regenerate_short,
+            delete_short,
+            restore_short,
+            delete_job,
+            restore_job,
+            list_trash,
+            empty_trash,
+            add_example,
+            list_examples,
+            update_example,
+            delete_example,
+            list_voices,
+            synthesize_short,
+            preview_voice,
+            get_short_media_url,
+            render_short,
+            rerender_short,
+            draft_render,
+            generate_thumbnail,
+            detect_encoders,
+            check_dependencies,
+            health_check,
+            seed_demo_data,
+            clear_demo_data,
+            install_ffmpeg,
+            segment_short,
+            list_scenes,
+            list_sfx,
+            import_sfx,
+            add_music,
+            list_music,
+            update_music,
+            delete_music,
+            add_render_template,
+            list_render_templates,
+            update_render_template,
+            delete_render_template,
+            set_watermark,
+            set_template_asset,
+            connect_youtube,
+            connect_drive,
+            create_drive_folder,
+            list_profiles,
+            create_profile,
+            duplicate_profile,
+            switch_profile,
+            delete_profile,
+            list_accounts,
+            update_account,
+            delete_account,
+            set_job_account,
+            upload_short,
+            list_my_playlists,
+            preview_upload_metadata,
+            suggest_hashtags,
+            set_upload_metadata,
+            add_upload_template,
+            list_upload_templates,
+            update_upload_template,
+            delete_upload_template,
+            schedule_short,
+            get_publish_calendar,
+            add_webhook,
+            list_webhooks,
+            delete_webhook,
+            test_webhook,
             test_youtube_api,
             test_telegram_bot,
             test_ollama,
+            get_ollama_status,
+            test_image_backend,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!());
+    // A database that can't be opened or encrypted ends up here; say why
+    // in the log instead of panicking
+    let app = match app {
+        Ok(app) => app,
+        Err(e) => {
+            tracing::error!("Failed to start: {}", e);
+            eprintln!("ShotAuto failed to start: {}", e);
+            std::process::exit(1);
+        }
+    };
+    app.run(|app, event| {
+        // Let running jobs reach a checkpoint before quitting; the exit
+        // requested once they have goes through
+        if let tauri::RunEvent::ExitRequested { api, .. } = event {
+            if !pipeline::has_stopped() {
+                api.prevent_exit();
+                if pipeline::begin_shutdown() {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let grace = app.try_state::<AppState>()
+                            .and_then(|state| state.db.load_config().ok())
+                            .map(|config| config.shutdown_grace_secs)
+                            .unwrap_or(30);
+                        if !pipeline::drain(Duration::from_secs(grace)).await {
+                            tracing::warn!("Quitting with jobs still running; they are requeued once their leases expire");
+                        }
+                        app.exit(0);
+                    });
+                }
+            }
+        }
+    });
 }
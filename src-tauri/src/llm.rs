@@ -0,0 +1,386 @@
+// LLM client for ShotAuto (Ollama, OpenAI)
+use crate::clients::Clients;
+use crate::costs::{self, CostKind};
+use crate::db::{Config, LlmBackend};
+use crate::resilience::{self, Service};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// Sampling parameters passed through to the backend; `None` leaves the
+/// backend's default in place
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplingParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Fixed seed for reproducible output
+    pub seed: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    /// Left out to only load the model
+    #[serde(skip_serializing_if = "str::is_empty")]
+    prompt: &'a str,
+    stream: bool,
+    options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+/// A model Ollama has in memory, per `/api/ps`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedModel {
+    pub name: String,
+    /// Memory the model takes, in bytes
+    pub size: u64,
+    /// Part of `size` held in VRAM
+    #[serde(default)]
+    pub size_vram: u64,
+    /// When Ollama unloads it unless it is used again
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// Models Ollama has loaded and the VRAM they use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaStatus {
+    pub models: Vec<LoadedModel>,
+    /// Whether the configured model is among them
+    pub model_loaded: bool,
+    pub vram_bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PsResponse {
+    #[serde(default)]
+    models: Vec<LoadedModel>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    total_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: String,
+}
+
+/// A text completion backend
+pub trait LlmClient {
+    /// Run a single non-streaming completion of `prompt`
+    async fn complete(&self, prompt: &str, params: &SamplingParams) -> Result<String, String>;
+}
+
+/// A model served by Ollama's `/api/generate`
+pub struct Ollama {
+    pub http: Client,
+    pub endpoint: String,
+    pub model: String,
+    /// Sent as `keep_alive` so the model stays loaded between jobs
+    pub keep_alive: Option<String>,
+}
+
+impl Ollama {
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint.trim_end_matches('/'), path)
+    }
+    
+    /// Load the model without generating anything, so the next job doesn't
+    /// wait for a cold start
+    pub async fn warm_up(&self) -> Result<(), String> {
+        let request = GenerateRequest {
+            model: &self.model,
+            prompt: "",
+            stream: false,
+            options: OllamaOptions::default(),
+            keep_alive: self.keep_alive.as_deref(),
+        };
+        let response = self.http
+            .post(self.url("/api/generate"))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Ollama returned {}", response.status()));
+        }
+        Ok(())
+    }
+    
+    /// Models loaded right now, from `/api/ps`
+    pub async fn status(&self) -> Result<OllamaStatus, String> {
+        let response = self.http
+            .get(self.url("/api/ps"))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Ollama returned {}", response.status()));
+        }
+        let body: PsResponse = response.json().await
+            .map_err(|e| format!("Ollama returned an unexpected response: {}", e))?;
+        // Ollama reports `llama3.2` as `llama3.2:latest`
+        let model_loaded = body.models.iter().any(|m| {
+            m.name == self.model || m.name.strip_suffix(":latest") == Some(self.model.as_str())
+        });
+        Ok(OllamaStatus {
+            vram_bytes: body.models.iter().map(|m| m.size_vram).sum(),
+            model_loaded,
+            models: body.models,
+        })
+    }
+}
+
+impl LlmClient for Ollama {
+    async fn complete(&self, prompt: &str, params: &SamplingParams) -> Result<String, String> {
+        let url = self.url("/api/generate");
+        let request = GenerateRequest {
+            model: &self.model,
+            prompt,
+            stream: false,
+            options: OllamaOptions {
+                temperature: params.temperature,
+                top_p: params.top_p,
+                num_predict: params.max_tokens,
+                seed: params.seed,
+            },
+            keep_alive: self.keep_alive.as_deref(),
+        };
+        
+        let request = self.http
+            .post(&url)
+            .json(&request);
+        let response = resilience::send(Service::Ollama, request).await?;
+        
+        if !response.status().is_success() {
+            return Err(format!("Ollama returned {}", response.status()));
+        }
+        
+        let body: GenerateResponse = response.json().await
+            .map_err(|e| format!("Ollama returned an unexpected response: {}", e))?;
+        Ok(body.response)
+    }
+}
+
+/// A model behind the OpenAI chat completions API
+pub struct OpenAi {
+    pub http: Client,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl LlmClient for OpenAi {
+    async fn complete(&self, prompt: &str, params: &SamplingParams) -> Result<String, String> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![ChatMessage { role: "user", content: prompt }],
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_tokens: params.max_tokens,
+            seed: params.seed,
+        };
+        
+        let response = self.http
+            .post(format!("{}/chat/completions", self.base_url.trim_end_matches('/')))
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("OpenAI returned {}: {}", status, body));
+        }
+        
+        let body: ChatResponse = response.json().await
+            .map_err(|e| format!("OpenAI returned an unexpected response: {}", e))?;
+        if let Some(usage) = &body.usage {
+            costs::charge(CostKind::LlmTokens, usage.total_tokens as f64);
+        }
+        body.choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content)
+            .ok_or_else(|| "OpenAI returned no choices".to_string())
+    }
+}
+
+/// Any configured backend, so callers can pick one at runtime
+pub enum Llm {
+    Ollama(Ollama),
+    OpenAi(OpenAi),
+}
+
+impl LlmClient for Llm {
+    async fn complete(&self, prompt: &str, params: &SamplingParams) -> Result<String, String> {
+        match self {
+            Llm::Ollama(llm) => llm.complete(prompt, params).await,
+            Llm::OpenAi(llm) => llm.complete(prompt, params).await,
+        }
+    }
+}
+
+/// Build the configured backend
+pub fn client(clients: &Clients, config: &Config) -> Result<Llm, String> {
+    match config.llm_backend {
+        LlmBackend::Ollama => Ok(Llm::Ollama(ollama(clients, config))),
+        LlmBackend::OpenAi => Ok(Llm::OpenAi(OpenAi {
+            http: clients.http.clone(),
+            base_url: clients.openai_url.clone(),
+            api_key: config.openai_api_key.clone().ok_or("openai_api_key is not configured")?,
+            model: config.openai_model.clone(),
+        })),
+    }
+}
+
+/// The configured Ollama model, whichever backend is selected
+pub fn ollama(clients: &Clients, config: &Config) -> Ollama {
+    let keep_alive = config.ollama_keep_alive.trim();
+    Ollama {
+        http: clients.http.clone(),
+        endpoint: config.ollama_endpoint.clone(),
+        model: config.ollama_model.clone(),
+        keep_alive: (!keep_alive.is_empty()).then(|| keep_alive.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockServer, Reply};
+    use std::time::Duration;
+    
+    fn openai(server: &MockServer) -> Llm {
+        let config = Config {
+            llm_backend: LlmBackend::OpenAi,
+            openai_api_key: Some("sk-test".to_string()),
+            ..Default::default()
+        };
+        Clients::at(&server.url(), server.http()).llm(&config).unwrap()
+    }
+    
+    fn ollama(server: &MockServer) -> Llm {
+        let config = Config {
+            llm_backend: LlmBackend::Ollama,
+            ollama_endpoint: server.url(),
+            ..Default::default()
+        };
+        Clients::at(&server.url(), server.http()).llm(&config).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn openai_returns_the_first_choice() {
+        let server = MockServer::start(Reply::json(200, r#"{"choices": [{"message": {"content": "A script"}}]}"#)).await;
+        assert_eq!(openai(&server).complete("Write", &SamplingParams::default()).await, Ok("A script".to_string()));
+        assert!(server.requests()[0].starts_with("POST /chat/completions"));
+    }
+    
+    #[tokio::test]
+    async fn openai_rate_limit_is_an_error() {
+        let server = MockServer::start(Reply::json(429, r#"{"error": {"message": "Rate limit reached"}}"#)).await;
+        let err = openai(&server).complete("Write", &SamplingParams::default()).await.unwrap_err();
+        assert!(err.contains("429") && err.contains("Rate limit reached"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn openai_malformed_json_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"choices": "#)).await;
+        let err = openai(&server).complete("Write", &SamplingParams::default()).await.unwrap_err();
+        assert!(err.contains("unexpected response"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn openai_without_choices_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"choices": []}"#)).await;
+        assert!(openai(&server).complete("Write", &SamplingParams::default()).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn ollama_malformed_json_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"done": true}"#)).await;
+        let err = ollama(&server).complete("Write", &SamplingParams::default()).await.unwrap_err();
+        assert!(err.contains("unexpected response"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn ollama_keeps_the_model_loaded() {
+        let server = MockServer::start(Reply::json(200, r#"{"response": "A script"}"#)).await;
+        ollama(&server).complete("Write", &SamplingParams::default()).await.unwrap();
+        assert!(server.requests()[0].contains(r#""keep_alive":"30m""#), "{}", server.requests()[0]);
+    }
+    
+    #[tokio::test]
+    async fn ollama_status_sums_vram() {
+        let body = r#"{"models": [
+            {"name": "llama3.2:latest", "size": 3000, "size_vram": 2000, "expires_at": "2026-10-16T12:30:00Z"},
+            {"name": "nomic-embed-text:latest", "size": 500, "size_vram": 500}
+        ]}"#;
+        let server = MockServer::start(Reply::json(200, body)).await;
+        let config = Config { ollama_endpoint: server.url(), ..Default::default() };
+        let status = super::ollama(&Clients::at(&server.url(), server.http()), &config).status().await.unwrap();
+        assert!(status.model_loaded);
+        assert_eq!((status.models.len(), status.vram_bytes), (2, 2500));
+        assert!(server.requests()[0].starts_with("GET /api/ps"));
+    }
+    
+    #[tokio::test]
+    async fn ollama_timeout_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"response": "A script"}"#).delayed(Duration::from_secs(2))).await;
+        assert!(ollama(&server).complete("Write", &SamplingParams::default()).await.is_err());
+    }
+}
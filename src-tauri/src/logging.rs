@@ -0,0 +1,184 @@
+// App log: tracing output written to size-rotated files in the app data dir
+use crate::db::LogLevel;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+const FILE_NAME: &str = "shotauto.log";
+
+/// Size at which the log is rotated
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept next to the current one, `shotauto.log.1` the newest
+const KEEP_FILES: usize = 4;
+
+/// Folder the log files are written to
+pub fn log_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join("logs")
+}
+
+/// Send tracing output to stdout and the log files, and log panics. Our own
+/// messages are kept from debug level, other crates' from info.
+pub fn init(app_dir: &Path) {
+    let dir = log_dir(app_dir);
+    let file = match RotatingFile::open(&dir) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("Failed to open the log in {}: {}", dir.display(), e);
+            None
+        }
+    };
+    let targets = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), LevelFilter::DEBUG)
+        .with_default(LevelFilter::INFO);
+    let file_layer = file.map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+    });
+    // Span fields are formatted once and shared by both layers, so stdout
+    // goes without colours too
+    let _ = tracing_subscriber::registry()
+        .with(targets)
+        .with(tracing_subscriber::fmt::layer().with_ansi(false))
+        .with(file_layer)
+        .try_init();
+    
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        tracing::error!("Panic: {}", info);
+        default_hook(info);
+    }));
+}
+
+/// Appends to `shotauto.log`, moving it to `shotauto.log.1` (and older
+/// files one number up) once it reaches `MAX_FILE_BYTES`
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(FILE_NAME))?;
+        let written = file.metadata()?.len();
+        Ok(Self { dir: dir.to_path_buf(), file, written })
+    }
+    
+    fn rotate(&mut self) -> io::Result<()> {
+        let _ = std::fs::remove_file(rotated_path(&self.dir, KEEP_FILES));
+        for n in (1..KEEP_FILES).rev() {
+            let _ = std::fs::rename(rotated_path(&self.dir, n), rotated_path(&self.dir, n + 1));
+        }
+        std::fs::rename(self.dir.join(FILE_NAME), rotated_path(&self.dir, 1))?;
+        *self = Self::open(&self.dir)?;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_FILE_BYTES {
+            // Keep writing to the old file rather than losing the message
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate the log: {}", e);
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn rotated_path(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{}.{}", FILE_NAME, n))
+}
+
+/// One message from the log files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    /// RFC 3339, UTC
+    pub timestamp: String,
+    pub level: LogLevel,
+    /// Message with the spans and module it came from, e.g.
+    /// `job{id=4 stage="render"}: shotauto_lib::pipeline: Finished render`
+    pub message: String,
+}
+
+/// The newest `limit` messages at `level` or above whose text contains
+/// `filter` (ignoring case), oldest first. Blocking; reads every log file.
+pub fn read(app_dir: &Path, level: LogLevel, limit: usize, filter: Option<&str>) -> Vec<LogLine> {
+    let dir = log_dir(app_dir);
+    let filter = filter.map(str::to_lowercase).filter(|f| !f.is_empty());
+    let mut paths: Vec<PathBuf> = (1..=KEEP_FILES).rev().map(|n| rotated_path(&dir, n)).collect();
+    paths.push(dir.join(FILE_NAME));
+    
+    let mut lines: VecDeque<LogLine> = VecDeque::with_capacity(limit);
+    let mut keep = |line: LogLine| {
+        let wanted = line.level >= level
+            && filter.as_ref().is_none_or(|f| line.message.to_lowercase().contains(f));
+        if wanted && limit > 0 {
+            if lines.len() == limit {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    };
+    for path in paths {
+        let Ok(file) = File::open(&path) else { continue };
+        let mut current: Option<LogLine> = None;
+        for text in BufReader::new(file).lines().map_while(Result::ok) {
+            match parse(&text) {
+                Some(line) => {
+                    if let Some(done) = current.replace(line) {
+                        keep(done);
+                    }
+                }
+                // Messages spanning several lines
+                None => {
+                    if let Some(line) = current.as_mut() {
+                        line.message.push('\n');
+                        line.message.push_str(&text);
+                    }
+                }
+            }
+        }
+        if let Some(done) = current {
+            keep(done);
+        }
+    }
+    lines.into()
+}
+
+/// Split a line of the default tracing format, "<timestamp> <LEVEL> <message>"
+fn parse(text: &str) -> Option<LogLine> {
+    let (timestamp, rest) = text.split_once(' ')?;
+    DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let (level, message) = rest.trim_start().split_once(' ')?;
+    let level = match level {
+        "TRACE" | "DEBUG" => LogLevel::Debug,
+        "INFO" => LogLevel::Info,
+        "WARN" => LogLevel::Warn,
+        "ERROR" => LogLevel::Error,
+        _ => return None,
+    };
+    Some(LogLine {
+        timestamp: timestamp.to_string(),
+        level,
+        message: message.to_string(),
+    })
+}
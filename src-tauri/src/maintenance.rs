@@ -0,0 +1,153 @@
+// Daily pruning of old rows and media files under the retention settings
+use crate::artifact_store;
+use crate::db::Config;
+use crate::storage;
+use crate::AppState;
+use chrono::{Local, NaiveDate, Utc};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// How often to check whether today's maintenance has run
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Folders under the app data dir holding per-short media
+const MEDIA_DIRS: [&str; 4] = ["audio", "renders", "thumbnails", "images"];
+
+/// Files younger than this are never treated as orphans: a render in
+/// progress writes its file before the short row points at it
+const ORPHAN_GRACE: Duration = Duration::from_secs(24 * 3600);
+
+/// What one maintenance pass archived and removed
+#[derive(Debug, Default)]
+pub struct MaintenanceReport {
+    pub trends_archived: usize,
+    pub trends_deleted: usize,
+    pub jobs_deleted: usize,
+    pub files_deleted: usize,
+    pub bytes_freed: u64,
+}
+
+/// Run maintenance once a day and check free disk space every hour, for as
+/// long as the app runs
+pub async fn run(app: AppHandle) {
+    let mut last_run: Option<NaiveDate> = None;
+    let mut disk_low = false;
+    loop {
+        let state = app.state::<AppState>();
+        if let Ok(config) = state.db.load_config() {
+            disk_low = storage::warn_if_low(&app, &config, &state.app_dir, disk_low);
+        }
+        
+        let today = Local::now().date_naive();
+        if last_run != Some(today) {
+            match run_once(&state).await {
+                Ok(report) => tracing::info!(
+                    "Maintenance archived {} trends and deleted {} trends, {} jobs and {} files ({} MB)",
+                    report.trends_archived,
+                    report.trends_deleted,
+                    report.jobs_deleted,
+                    report.files_deleted,
+                    report.bytes_freed / 1_000_000
+                ),
+                Err(e) => tracing::warn!("Maintenance failed: {}", e),
+            }
+            last_run = Some(today);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Archive trends past `archive_trends_days`, delete rows past
+/// `keep_done_jobs_days` and `keep_trends_days`, then media files past
+/// `keep_rendered_files_days` and files no row points at. With
+/// `keep_rendered_files_days` at 0 no media file is deleted.
+pub async fn run_once(state: &AppState) -> Result<MaintenanceReport, String> {
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    let db = state.db.clone();
+    let (trends_archived, (jobs_deleted, objects), trends_deleted) = {
+        let config = config.clone();
+        tauri::async_runtime::spawn_blocking(move || -> rusqlite::Result<(usize, (usize, Vec<String>), usize)> {
+            let archived = match config.archive_trends_days {
+                0 => 0,
+                days => db.archive_stale_trends(Utc::now() - chrono::Duration::days(days as i64))?,
+            };
+            // Jobs first: trends are only deleted once no job refers to them
+            let jobs = match config.keep_done_jobs_days {
+                0 => (0, Vec::new()),
+                days => db.prune_finished_jobs(Utc::now() - chrono::Duration::days(days as i64))?,
+            };
+            let trends = match config.keep_trends_days {
+                0 => 0,
+                days => db.prune_trends(Utc::now() - chrono::Duration::days(days as i64))?,
+            };
+            Ok((archived, jobs, trends))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?
+    };
+    
+    let mut report = MaintenanceReport { trends_archived, trends_deleted, jobs_deleted, ..Default::default() };
+    // Local files of the deleted jobs go with the orphan sweep below, if
+    // files are swept at all
+    for url in &objects {
+        if let Err(e) = artifact_store::delete(&config.artifact_store, &state.app_dir, url).await {
+            tracing::warn!("Failed to delete {}: {}", url, e);
+        }
+    }
+    sweep_media(state, &config, &mut report).await?;
+    Ok(report)
+}
+
+/// Delete media files that are too old or that no short or scene uses.
+/// Nothing is deleted while files are kept forever.
+async fn sweep_media(state: &AppState, config: &Config, report: &mut MaintenanceReport) -> Result<(), String> {
+    let max_age = match config.keep_rendered_files_days {
+        0 => return Ok(()),
+        days => Duration::from_secs(days as u64 * 24 * 3600),
+    };
+    let mut referenced: HashSet<String> = state.db.referenced_media_paths().map_err(|e| e.to_string())?;
+    // Without `keep_local_renders`, copies fetched back from the bucket for
+    // a delivery are only kept until they count as orphans
+    if config.keep_local_renders {
+        artifact_store::add_local_copies(&state.app_dir, &mut referenced);
+    }
+    
+    for dir in MEDIA_DIRS {
+        let Ok(mut entries) = tokio::fs::read_dir(state.app_dir.join(dir)).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or_default();
+            let path = entry.path().to_string_lossy().to_string();
+            let expired = age > max_age;
+            let orphaned = age > ORPHAN_GRACE && !referenced.contains(&path);
+            if !expired && !orphaned {
+                continue;
+            }
+            
+            if referenced.contains(&path) {
+                state.db.clear_media_path(&path).map_err(|e| e.to_string())?;
+            }
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                tracing::warn!("Failed to delete {}: {}", path, e);
+                continue;
+            }
+            report.files_deleted += 1;
+            report.bytes_freed += metadata.len();
+        }
+    }
+    Ok(())
+}
+
@@ -0,0 +1,68 @@
+// Temporary public hosting for platforms that fetch videos by URL
+use crate::http;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Where videos are put so a platform can fetch them
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MediaHost {
+    #[default]
+    None,
+    /// Any server accepting HTTP PUT and DELETE (WebDAV, Nextcloud, a
+    /// public-write bucket): files go to `upload_url/<name>` and are
+    /// fetched from `public_url/<name>`
+    HttpPut {
+        upload_url: String,
+        public_url: String,
+        /// Sent as the Authorization header, e.g. "Bearer ..." or "Basic ..."
+        #[serde(default)]
+        authorization: Option<String>,
+    },
+}
+
+/// A file put on the host
+pub struct HostedFile {
+    pub url: String,
+    upload_url: String,
+}
+
+/// Put `path` on the host under `name`
+pub async fn put(host: &MediaHost, path: &Path, name: &str) -> Result<HostedFile, String> {
+    let MediaHost::HttpPut { upload_url, public_url, authorization } = host else {
+        return Err("No media host is configured".to_string());
+    };
+    let upload_url = format!("{}/{}", upload_url.trim_end_matches('/'), name);
+    let bytes = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
+    let mut request = http::client()
+        .put(&upload_url)
+        .header(reqwest::header::CONTENT_TYPE, "video/mp4")
+        .body(bytes);
+    if let Some(authorization) = authorization {
+        request = request.header(reqwest::header::AUTHORIZATION, authorization);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Media host upload failed ({})", response.status()));
+    }
+    Ok(HostedFile {
+        url: format!("{}/{}", public_url.trim_end_matches('/'), name),
+        upload_url,
+    })
+}
+
+/// Delete a hosted file once the platform has fetched it
+pub async fn remove(host: &MediaHost, file: HostedFile) -> Result<(), String> {
+    let MediaHost::HttpPut { authorization, .. } = host else {
+        return Ok(());
+    };
+    let mut request = http::client().delete(&file.upload_url);
+    if let Some(authorization) = authorization {
+        request = request.header(reqwest::header::AUTHORIZATION, authorization);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Media host delete failed ({})", response.status()));
+    }
+    Ok(())
+}
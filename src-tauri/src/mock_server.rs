@@ -0,0 +1,123 @@
+// Local HTTP server answering requests with canned replies, for testing the
+// API clients' error handling
+use reqwest::Client;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Time limit of `MockServer::http`; replies delayed longer time out
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Clone)]
+pub struct Reply {
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+    delay: Duration,
+}
+
+impl Reply {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self { status, body: body.to_string(), headers: Vec::new(), delay: Duration::ZERO }
+    }
+    
+    /// Send this header along as well
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+    
+    /// Wait this long before answering
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+pub struct MockServer {
+    url: String,
+    /// Request line and body of every request received
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(reply: Reply) -> Self {
+        Self::sequence(vec![reply]).await
+    }
+    
+    /// Answer requests with `replies` in order, repeating the last one
+    pub async fn sequence(replies: Vec<Reply>) -> Self {
+        let replies = Arc::new(replies);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, replies.clone(), received.clone()));
+            }
+        });
+        Self { url, requests }
+    }
+    
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+    
+    /// An HTTP client with a short time limit
+    pub fn http(&self) -> Client {
+        Client::builder().timeout(CLIENT_TIMEOUT).build().unwrap()
+    }
+    
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn serve(mut stream: TcpStream, replies: Arc<Vec<Reply>>, requests: Arc<Mutex<Vec<String>>>) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    // Read the headers, then as much body as Content-Length announces
+    let (head_len, content_length) = loop {
+        let Ok(n) = stream.read(&mut chunk).await else { return };
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).to_lowercase();
+            let content_length = head.lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            break (end + 4, content_length);
+        }
+    };
+    while buf.len() < head_len + content_length {
+        let Ok(n) = stream.read(&mut chunk).await else { return };
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let request_line = String::from_utf8_lossy(&buf[..head_len]).lines().next().unwrap_or("").to_string();
+    let body = String::from_utf8_lossy(&buf[head_len..]).to_string();
+    let reply = {
+        let mut requests = requests.lock().unwrap();
+        requests.push(format!("{}\n{}", request_line, body));
+        replies[(requests.len() - 1).min(replies.len() - 1)].clone()
+    };
+    
+    tokio::time::sleep(reply.delay).await;
+    let headers: String = reply.headers.iter().map(|(name, value)| format!("{}: {}\r\n", name, value)).collect();
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        reply.status,
+        reply.body.len(),
+        headers,
+        reply.body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,74 @@
+// Content moderation for generated scripts
+use crate::clients::Clients;
+use crate::db::Config;
+use crate::llm::{LlmClient, SamplingParams};
+
+/// Instructions given to the LLM policy check. The model must answer with
+/// `OK` or `FLAG: <reason>` on the first line.
+const POLICY_PROMPT: &str = "You are a content moderator for short-form videos. \
+Check the script below for hate speech, harassment, sexual content, graphic violence, \
+self-harm, dangerous activities, medical or financial misinformation, and copyright-infringing lyrics. \
+Answer with exactly `OK` if the script is acceptable, otherwise `FLAG: <one-line reason>`.\n\nScript:\n";
+
+/// Outcome of moderating a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Approved,
+    /// Script must be reviewed by a human before rendering; holds the reason
+    Flagged(String),
+}
+
+/// Return the first banned word found in the script (case-insensitive, whole words)
+pub fn find_banned_word<'a>(script: &str, banned_words: &'a [String]) -> Option<&'a str> {
+    let words: Vec<String> = script
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    
+    banned_words
+        .iter()
+        .map(|b| b.trim())
+        .filter(|b| !b.is_empty())
+        .find(|banned| {
+            let banned_lower = banned.to_lowercase();
+            let phrase: Vec<&str> = banned_lower.split_whitespace().collect();
+            words.windows(phrase.len()).any(|w| w.iter().zip(&phrase).all(|(a, b)| a == b))
+        })
+}
+
+/// Ask the LLM whether the script violates the content policy
+pub async fn check_policy(clients: &Clients, config: &Config, script: &str) -> Result<Verdict, String> {
+    let prompt = format!("{}{}", POLICY_PROMPT, script);
+    // Deterministic answers keep the verdict stable across re-checks
+    let params = SamplingParams { temperature: Some(0.0), ..Default::default() };
+    let answer = clients.llm(config)?.complete(&prompt, &params).await?;
+    let first_line = answer.trim().lines().next().unwrap_or("").trim();
+    
+    if first_line.eq_ignore_ascii_case("ok") {
+        Ok(Verdict::Approved)
+    } else if let Some(reason) = first_line
+        .strip_prefix("FLAG:")
+        .or_else(|| first_line.strip_prefix("flag:"))
+    {
+        Ok(Verdict::Flagged(format!("LLM policy check: {}", reason.trim())))
+    } else {
+        // An answer we can't parse is treated as a flag rather than silently passed
+        Ok(Verdict::Flagged(format!("LLM policy check returned an unexpected answer: {}", first_line)))
+    }
+}
+
+/// Run every configured check on a generated script.
+/// Call this after generation and before the job moves on to rendering;
+/// a `Flagged` verdict should put the job into `needs_review`.
+pub async fn moderate_script(clients: &Clients, config: &Config, script: &str) -> Result<Verdict, String> {
+    if let Some(word) = find_banned_word(script, &config.banned_words) {
+        return Ok(Verdict::Flagged(format!("Banned word: {}", word)));
+    }
+    
+    if config.llm_moderation_enabled {
+        return check_policy(clients, config, script).await;
+    }
+    
+    Ok(Verdict::Approved)
+}
@@ -0,0 +1,30 @@
+// Background music mixing
+use crate::db::{Database, MusicTrack, Short, Trend};
+
+/// Filtergraph that lowers the music by `volume_db`, ducks it under the
+/// narration with a sidechain compressor, and mixes the two. Reads the
+/// `narration` and `music` stream labels and writes `out`; the mix lasts as
+/// long as the narration, so the music input can be looped.
+pub fn ducking_filter(narration: &str, music: &str, volume_db: f64, out: &str) -> String {
+    format!(
+        "[{music}]volume={volume_db}dB[bgm];\
+         [{narration}]asplit=2[voice][key];\
+         [bgm][key]sidechaincompress=threshold=0.03:ratio=8:attack=20:release=350[ducked];\
+         [voice][ducked]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[{out}]"
+    )
+}
+
+/// Track for a short: the one it already uses, otherwise a random track
+/// tagged with the trend's category (or any track), remembered on the short
+pub fn select_for_short(db: &Database, short: &Short, trend: &Trend) -> rusqlite::Result<Option<MusicTrack>> {
+    if let Some(music_id) = short.music_id {
+        if let Some(track) = db.get_music(music_id)? {
+            return Ok(Some(track));
+        }
+    }
+    let track = db.pick_music(trend.category.as_deref())?;
+    if let (Some(short_id), Some(track)) = (short.id, &track) {
+        db.set_short_music(short_id, track.id)?;
+    }
+    Ok(track)
+}
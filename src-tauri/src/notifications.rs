@@ -0,0 +1,83 @@
+// Desktop notifications for pipeline events
+use crate::db::Config;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Which events show a desktop notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub short_rendered: bool,
+    pub job_failed: bool,
+    /// The YouTube API quota ran out for the day
+    pub quota_exhausted: bool,
+    /// A short or script waits for someone to review it
+    pub approval_needed: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            short_rendered: true,
+            job_failed: true,
+            quota_exhausted: true,
+            approval_needed: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    ShortRendered,
+    JobFailed,
+    QuotaExhausted,
+    ApprovalNeeded,
+}
+
+impl Event {
+    fn enabled(&self, settings: &NotificationSettings) -> bool {
+        match self {
+            Event::ShortRendered => settings.short_rendered,
+            Event::JobFailed => settings.job_failed,
+            Event::QuotaExhausted => settings.quota_exhausted,
+            Event::ApprovalNeeded => settings.approval_needed,
+        }
+    }
+    
+    fn title(&self) -> &'static str {
+        match self {
+            Event::ShortRendered => "Short rendered",
+            Event::JobFailed => "Job failed",
+            Event::QuotaExhausted => "YouTube quota used up",
+            Event::ApprovalNeeded => "Approval needed",
+        }
+    }
+}
+
+/// Handle notifications are shown through; set once at startup
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Show a notification for `event` unless it is turned off in `config`.
+/// With a `job_id`, the notification carries it as `extra.job_id` so the
+/// UI's action listener can open that job when it is clicked.
+pub fn notify(config: &Config, event: Event, job_id: Option<i64>, body: &str) {
+    if !event.enabled(&config.notifications) {
+        return;
+    }
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let mut builder = app.notification().builder().title(event.title()).body(body);
+    if let Some(job_id) = job_id {
+        builder = builder.extra("job_id", job_id);
+    }
+    if let Err(e) = builder.show() {
+        tracing::warn!("Failed to show a notification: {}", e);
+    }
+}
@@ -0,0 +1,736 @@
+// Background job pipeline: each stage has its own queue and concurrency limit
+use crate::costs;
+use crate::db::{Actor, ApprovalStatus, Config, Database, Job, JobStatus, LlmBackend, LogLevel, PipelineStep};
+use crate::notifications::{self, Event};
+use crate::resilience::{self, Service};
+use crate::telegram::TelegramClient;
+use crate::webhooks::{self, WebhookEvent};
+use crate::{llm, moderation, AppState, RenderMode};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tracing::Instrument;
+
+/// How often a stage queue looks for work while it is idle or full
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a claimed job stays reserved without a lease renewal
+const JOB_LEASE_SECS: i64 = 60;
+
+/// How often a running job's lease is renewed
+const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(20);
+
+/// While set, no new jobs are claimed; running ones finish
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+    tracing::info!("Queue {}", if paused { "paused" } else { "resumed" });
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// Unix time until which `run_windows` is ignored, set by `run_now`
+static RUN_NOW_UNTIL: AtomicI64 = AtomicI64::new(0);
+
+/// Run jobs and fetch trends for the next `duration` even outside `run_windows`
+pub fn run_now(duration: chrono::Duration) {
+    let until = chrono::Utc::now() + duration;
+    RUN_NOW_UNTIL.store(until.timestamp(), Ordering::SeqCst);
+    tracing::info!("Running outside the run windows until {}", until.with_timezone(&chrono::Local).format("%H:%M"));
+}
+
+/// Whether the pipeline may run now under `config.run_windows`
+pub fn in_run_window(config: &Config) -> bool {
+    chrono::Utc::now().timestamp() < RUN_NOW_UNTIL.load(Ordering::SeqCst) || config.run_windows.allows_now()
+}
+
+/// Jobs running in this process, across all stages
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Set once the app starts quitting; never cleared
+fn shutdown_signal() -> &'static watch::Sender<bool> {
+    static SIGNAL: OnceLock<watch::Sender<bool>> = OnceLock::new();
+    SIGNAL.get_or_init(|| watch::channel(false).0)
+}
+
+pub fn is_shutting_down() -> bool {
+    *shutdown_signal().borrow()
+}
+
+/// Set once every job has stopped after a shutdown
+static STOPPED: AtomicBool = AtomicBool::new(false);
+
+/// Stop claiming jobs and tell the running ones to stop at their next
+/// checkpoint. Returns false if shutdown had already begun.
+pub fn begin_shutdown() -> bool {
+    !shutdown_signal().send_replace(true)
+}
+
+/// Wait for the running jobs to stop after `begin_shutdown`. Jobs still
+/// running `grace` after the signal are cut off, which kills their child
+/// processes, and handed back to their queue to resume on the next start.
+/// Returns false if some were still running at the end.
+pub async fn drain(grace: Duration) -> bool {
+    let running = RUNNING.load(Ordering::SeqCst);
+    if running > 0 {
+        tracing::info!("Waiting up to {} s for {} running job(s) to stop", grace.as_secs(), running);
+    }
+    // Jobs cut off at `grace` still write their status back
+    let deadline = Instant::now() + grace + Duration::from_secs(5);
+    while RUNNING.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    STOPPED.store(true, Ordering::SeqCst);
+    RUNNING.load(Ordering::SeqCst) == 0
+}
+
+/// Wait up to `timeout` for the jobs running in this process to finish, as
+/// after pausing the queue. Returns false if some are still running.
+pub async fn wait_idle(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while RUNNING.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    RUNNING.load(Ordering::SeqCst) == 0
+}
+
+/// Whether shutdown has finished waiting for the jobs
+pub fn has_stopped() -> bool {
+    STOPPED.load(Ordering::SeqCst)
+}
+
+/// Resolves `grace` after shutdown starts
+async fn shutdown_deadline(grace: Duration) {
+    let mut signal = shutdown_signal().subscribe();
+    let _ = signal.wait_for(|shutting_down| *shutting_down).await;
+    tokio::time::sleep(grace).await;
+}
+
+/// What the queue is doing, for the tray icon and dashboard
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueState {
+    Idle,
+    Working,
+    Paused,
+    /// Dead jobs are waiting for someone to look at them
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub state: QueueState,
+    pub paused: bool,
+    /// Jobs wait for the next run window
+    pub outside_run_window: bool,
+    pub queued: i64,
+    pub running: i64,
+    pub dead: i64,
+}
+
+/// Current queue status
+pub fn status(db: &Database) -> rusqlite::Result<QueueStatus> {
+    let counts = db.count_jobs_by_status()?;
+    let count = |statuses: &[JobStatus]| -> i64 {
+        statuses.iter().filter_map(|status| counts.get(status.as_str())).sum()
+    };
+    let queued = count(&[Stage::Generation.queued_status(), Stage::Render.queued_status()]);
+    let running = count(&[Stage::Generation.running_status(), Stage::Render.running_status()]);
+    let dead = count(&[JobStatus::Dead]);
+    let paused = is_paused();
+    let outside_run_window = !in_run_window(&db.load_config()?);
+    let state = if dead > 0 {
+        QueueState::Error
+    } else if paused {
+        QueueState::Paused
+    } else if running > 0 {
+        QueueState::Working
+    } else {
+        QueueState::Idle
+    };
+    Ok(QueueStatus { state, paused, outside_run_window, queued, running, dead })
+}
+
+/// Pipeline stage with its own queue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// Scripts, moderation and narration; mostly waiting on the LLM and TTS
+    Generation,
+    /// Video rendering and thumbnails; CPU bound
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 2] = [Stage::Generation, Stage::Render];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Generation => "generation",
+            Stage::Render => "render",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.as_str() == s)
+    }
+    
+    /// The stage that runs a pipeline step
+    pub fn of(step: PipelineStep) -> Stage {
+        match step {
+            PipelineStep::Script | PipelineStep::Narration | PipelineStep::Metadata => Stage::Generation,
+            PipelineStep::Video => Stage::Render,
+        }
+    }
+    
+    /// Status of jobs waiting for this stage
+    pub fn queued_status(&self) -> JobStatus {
+        match self {
+            Stage::Generation => JobStatus::Pending,
+            Stage::Render => JobStatus::Generated,
+        }
+    }
+    
+    /// Status of jobs this stage is working on
+    fn running_status(&self) -> JobStatus {
+        match self {
+            Stage::Generation => JobStatus::Generating,
+            Stage::Render => JobStatus::Rendering,
+        }
+    }
+    
+    /// Status of jobs this stage has finished with
+    fn finished_status(&self) -> JobStatus {
+        match self {
+            Stage::Generation => JobStatus::Generated,
+            Stage::Render => JobStatus::Done,
+        }
+    }
+    
+    /// Time limit for one job in this stage, `None` when disabled
+    fn timeout(&self, config: &Config) -> Option<Duration> {
+        let secs = match self {
+            Stage::Generation => config.generation_timeout_secs,
+            Stage::Render => config.render_timeout_secs,
+        };
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+    
+    fn max_concurrent(&self, config: &Config) -> usize {
+        let limit = match self {
+            Stage::Generation => config.max_concurrent_generations,
+            Stage::Render => config.max_concurrent_renders,
+        };
+        limit.max(1) as usize
+    }
+    
+    /// External services a job in this stage can't do without
+    fn services(&self, config: &Config) -> Vec<Service> {
+        match self {
+            Stage::Generation if config.llm_backend == LlmBackend::Ollama => vec![Service::Ollama, Service::Tts],
+            Stage::Generation => vec![Service::Tts],
+            Stage::Render => Vec::new(),
+        }
+    }
+}
+
+/// Writes to a job's log in the database and mirrors every entry to the
+/// app log. Work run outside the pipeline gets a logger without a job, which
+/// only writes to the app log.
+pub struct JobLogger<'a> {
+    db: &'a Database,
+    job_id: Option<i64>,
+    stage: Option<Stage>,
+}
+
+impl<'a> JobLogger<'a> {
+    pub fn new(db: &'a Database, job_id: i64, stage: Stage) -> Self {
+        Self { db, job_id: Some(job_id), stage: Some(stage) }
+    }
+    
+    /// Logger for commands that run outside a job
+    pub fn none(db: &'a Database) -> Self {
+        Self { db, job_id: None, stage: None }
+    }
+    
+    pub fn debug(&self, message: impl AsRef<str>) {
+        self.log(LogLevel::Debug, message.as_ref());
+    }
+    
+    pub fn info(&self, message: impl AsRef<str>) {
+        self.log(LogLevel::Info, message.as_ref());
+    }
+    
+    pub fn warn(&self, message: impl AsRef<str>) {
+        self.log(LogLevel::Warn, message.as_ref());
+    }
+    
+    pub fn error(&self, message: impl AsRef<str>) {
+        self.log(LogLevel::Error, message.as_ref());
+    }
+    
+    fn log(&self, level: LogLevel, message: &str) {
+        // The job's span names it in the app log
+        match level {
+            LogLevel::Debug => tracing::debug!("{}", message),
+            LogLevel::Info => tracing::info!("{}", message),
+            LogLevel::Warn => tracing::warn!("{}", message),
+            LogLevel::Error => tracing::error!("{}", message),
+        }
+        let Some(job_id) = self.job_id else {
+            return;
+        };
+        let stage = self.stage.map(|s| s.as_str());
+        let written = self.db.insert_job_log(job_id, level, stage, message);
+        if let Err(e) = written {
+            tracing::warn!("Failed to write log for job {}: {}", job_id, e);
+        }
+    }
+}
+
+/// How a stage left a job
+enum Outcome {
+    Finished,
+    /// Stop here until someone has looked at the job
+    NeedsReview(String),
+    /// Stopped for shutdown; the job goes back to its queue as it was
+    Interrupted,
+}
+
+fn lease() -> chrono::Duration {
+    chrono::Duration::seconds(JOB_LEASE_SECS)
+}
+
+/// Start one queue per stage. Jobs are claimed under a worker id unique to
+/// this process, so several app instances can share one database.
+pub fn start(app: AppHandle) {
+    let worker_id: Arc<str> = format!("{}-{}", std::process::id(), chrono::Utc::now().timestamp_millis()).into();
+    tauri::async_runtime::spawn(warm_up(app.clone()));
+    for stage in Stage::ALL {
+        tauri::async_runtime::spawn(run_queue(app.clone(), stage, worker_id.clone()));
+    }
+}
+
+/// Load the Ollama model ahead of the first job, when it is the LLM backend
+async fn warm_up(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let config = match state.db.load_config() {
+        Ok(config) => config,
+        Err(e) => return tracing::warn!("Skipping the Ollama warm-up: {}", e),
+    };
+    if config.llm_backend != LlmBackend::Ollama || !config.ollama_warm_up {
+        return;
+    }
+    let started = Instant::now();
+    match llm::ollama(&state.clients, &config).warm_up().await {
+        Ok(()) => tracing::info!("Loaded {} in {:.1}s", config.ollama_model, started.elapsed().as_secs_f64()),
+        Err(e) => tracing::warn!("Failed to warm up {}: {}", config.ollama_model, e),
+    }
+}
+
+/// Claim queued jobs for `stage` while it has free slots, running each on its own task
+async fn run_queue(app: AppHandle, stage: Stage, worker_id: Arc<str>) {
+    let active = Arc::new(AtomicUsize::new(0));
+    loop {
+        match claim(&app, stage, &worker_id, active.load(Ordering::SeqCst)) {
+            Ok(Some(job)) => {
+                active.fetch_add(1, Ordering::SeqCst);
+                RUNNING.fetch_add(1, Ordering::SeqCst);
+                let app = app.clone();
+                let active = active.clone();
+                let worker_id = worker_id.clone();
+                // Everything logged while the job runs, in any module, is tagged with it
+                let span = tracing::info_span!("job", id = job.id.unwrap_or_default(), stage = stage.as_str());
+                tauri::async_runtime::spawn(async move {
+                    run_job(&app, stage, &worker_id, job).instrument(span).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    RUNNING.fetch_sub(1, Ordering::SeqCst);
+                });
+                // There may be more work and free slots; look again straight away
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("{} queue: {}", stage.as_str(), e),
+        }
+        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+    }
+}
+
+/// Claim the next queued job for `stage`, unless `active` jobs already fill
+/// its limit. The limit is read on every call so config changes apply live.
+/// Jobs whose worker let its lease lapse are put back in the queue first.
+fn claim(app: &AppHandle, stage: Stage, worker_id: &str, active: usize) -> Result<Option<Job>, String> {
+    let state = app.state::<AppState>();
+    let db = &state.db;
+    let requeued = db.requeue_expired_jobs(stage.running_status(), stage.queued_status())
+        .map_err(|e| e.to_string())?;
+    if requeued > 0 {
+        tracing::info!("Requeued {} {} jobs with expired leases", requeued, stage.as_str());
+    }
+    
+    let config = db.load_config().map_err(|e| e.to_string())?;
+    if is_paused() || is_shutting_down() || active >= stage.max_concurrent(&config) || !in_run_window(&config) {
+        return Ok(None);
+    }
+    // Leave jobs queued while a service they need is down, rather than
+    // spending their retries on it
+    if stage.services(&config).into_iter().any(resilience::is_open) {
+        return Ok(None);
+    }
+    let claimed = db.claim_next_job(stage.queued_status(), stage.running_status(), worker_id, lease(), config.prioritize_starred_trends)
+        .map_err(|e| e.to_string())?;
+    Ok(claimed.map(|(job, _)| job))
+}
+
+async fn run_job(app: &AppHandle, stage: Stage, worker_id: &str, job: Job) {
+    let state = app.state::<AppState>();
+    let Some(job_id) = job.id else {
+        return;
+    };
+    let log = JobLogger::new(&state.db, job_id, stage);
+    log.info(format!("Started {} (attempt {}) on worker {}", stage.as_str(), job.retry_count + 1, worker_id));
+    let config = match state.db.load_config().map_err(|e| e.to_string()) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("Failed to load config for job {}: {}", job_id, e);
+            return;
+        }
+    };
+    
+    // Dropping the stage future on timeout or shutdown kills its child
+    // processes, which are all spawned with kill_on_drop
+    let limit = stage.timeout(&config);
+    let progress = Progress { state: &state, config: &config, job_id, dry_run: job.dry_run };
+    let work = async {
+        let stage_work = async {
+            match stage {
+                Stage::Generation => generate(&state, &job, &log, &progress).await,
+                Stage::Render => render(&state, job_id, &log, &progress).await,
+            }
+        };
+        let stage_work = timed(&state, job_id, stage.as_str(), stage_work);
+        match limit {
+            Some(limit) => tokio::time::timeout(limit, stage_work).await.unwrap_or_else(|_| {
+                Err(format!("{} stage timed out after {} s", stage.as_str(), limit.as_secs()))
+            }),
+            None => stage_work.await,
+        }
+    };
+    let mut work = Box::pin(costs::track(state.db.clone(), job_id, config.unit_prices.clone(), work));
+    let cut_off = shutdown_deadline(Duration::from_secs(config.shutdown_grace_secs));
+    tokio::pin!(cut_off);
+    
+    // Renew the lease while the stage runs; if another worker has taken the
+    // job over in the meantime, drop it without touching its status
+    let mut renew = tokio::time::interval(LEASE_RENEW_INTERVAL);
+    renew.tick().await;
+    let result = loop {
+        tokio::select! {
+            result = &mut work => break result,
+            _ = &mut cut_off => {
+                log.warn("Shutdown grace period is over, cutting the stage off mid-step");
+                break Ok(Outcome::Interrupted);
+            }
+            _ = renew.tick() => {
+                let held = state.db.renew_job_lease(job_id, worker_id, lease()).map_err(|e| e.to_string());
+                match held {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        log.warn("Lost the lease to another worker, abandoning the job");
+                        return;
+                    }
+                    Err(e) => tracing::warn!("Failed to renew lease on job {}: {}", job_id, e),
+                }
+            }
+        }
+    };
+    
+    match &result {
+        Ok(Outcome::Finished) => log.info(format!("Finished {}", stage.as_str())),
+        Ok(Outcome::NeedsReview(reason)) => {
+            log.warn(format!("Held for review: {}", reason));
+            notifications::notify(&config, Event::ApprovalNeeded, Some(job_id), &format!("Job #{} held for review: {}", job_id, reason));
+        }
+        Ok(Outcome::Interrupted) => log.info(format!("Stopped {} for shutdown, will resume on the next start", stage.as_str())),
+        Err(e) => log.error(e),
+    }
+    // Dropping the stage kills its child processes
+    drop(work);
+    if let Ok(Outcome::Interrupted) = result {
+        if let Err(e) = finish(&state.db, &config, stage, worker_id, &job, result) {
+            tracing::warn!("Failed to release job {}: {}", job_id, e);
+        }
+        return;
+    }
+    let status = match (&result, stage) {
+        (Ok(Outcome::Finished), Stage::Generation) => format!("⏳ Job #{}: generated, waiting to render", job_id),
+        (Ok(Outcome::Finished), Stage::Render) => format!("✅ Job #{}: done", job_id),
+        (Ok(Outcome::NeedsReview(reason)), _) => format!("⏸ Job #{}: held for review ({})", job_id, reason),
+        (Ok(Outcome::Interrupted), _) => unreachable!("interrupted jobs are released above"),
+        (Err(e), _) if will_retry(&config, &job) => format!(
+            "🔁 Job #{}: {} failed on attempt {}, retrying: {}",
+            job_id,
+            stage.as_str(),
+            job.retry_count + 1,
+            e
+        ),
+        (Err(e), _) => format!("❌ Job #{}: {} failed: {}", job_id, stage.as_str(), e),
+    };
+    progress.post(&status).await;
+    let failed = match &result {
+        Err(e) if !will_retry(&config, &job) => Some(e.clone()),
+        _ => None,
+    };
+    let updated = finish(&state.db, &config, stage, worker_id, &job, result);
+    if let Err(e) = updated {
+        tracing::warn!("Failed to update job {}: {}", job_id, e);
+    }
+    if let Some(error) = failed {
+        notifications::notify(&config, Event::JobFailed, Some(job_id), &format!("Job #{} failed in {}: {}", job_id, stage.as_str(), error));
+        if job.dry_run {
+            return;
+        }
+        webhooks::dispatch(&state, WebhookEvent::JobFailed, serde_json::json!({
+            "job_id": job_id,
+            "trend_id": job.trend_id,
+            "stage": stage.as_str(),
+            "attempts": job.retry_count + 1,
+            "error": error,
+        }));
+    }
+}
+
+/// Await `work` and record its duration under `step` if it succeeds
+async fn timed<T, E: Into<String>>(
+    state: &AppState,
+    job_id: i64,
+    step: &str,
+    work: impl Future<Output = Result<T, E>>,
+) -> Result<T, String> {
+    let started = Instant::now();
+    let result = work.await.map_err(Into::into)?;
+    let duration_ms = started.elapsed().as_millis() as i64;
+    let recorded = state.db.record_metric(job_id, step, duration_ms).map_err(|e| e.to_string());
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record {} metric for job {}: {}", step, job_id, e);
+    }
+    Ok(result)
+}
+
+/// Record how a stage left a job. Failures are logged to the job's error
+/// history and retried until `max_retries` is used up; then the job is dead.
+fn finish(
+    db: &Database,
+    config: &Config,
+    stage: Stage,
+    worker_id: &str,
+    job: &Job,
+    result: Result<Outcome, String>,
+) -> rusqlite::Result<()> {
+    let job_id = job.id.unwrap_or_default();
+    match result {
+        Ok(Outcome::Finished) => db.update_job_status(job_id, stage.finished_status(), None),
+        Ok(Outcome::NeedsReview(reason)) => db.update_job_status(job_id, JobStatus::NeedsReview, Some(&reason)),
+        Ok(Outcome::Interrupted) => db.release_job(job_id, worker_id, stage.queued_status()),
+        Err(e) => {
+            db.record_job_error(job_id, job.retry_count + 1, stage.as_str(), &e)?;
+            if will_retry(config, job) {
+                tracing::warn!("Job {} failed in {}, retrying: {}", job_id, stage.as_str(), e);
+                db.retry_job(job_id, stage.queued_status(), &e)
+            } else {
+                tracing::warn!("Job {} failed in {} on its last attempt: {}", job_id, stage.as_str(), e);
+                db.update_job_status(job_id, JobStatus::Dead, Some(&e))
+            }
+        }
+    }
+}
+
+/// Whether a failure of the current attempt leaves the job another one
+fn will_retry(config: &Config, job: &Job) -> bool {
+    job.retry_count < config.max_retries as i32
+}
+
+/// Posts a job's status to the Telegram chat, editing one message per job so
+/// the chat isn't flooded. Does nothing unless `telegram_progress_updates` is
+/// on and the bot is configured; failures are only logged.
+struct Progress<'a> {
+    state: &'a AppState,
+    config: &'a Config,
+    job_id: i64,
+    /// Dry-run jobs report progress to the app log only
+    dry_run: bool,
+}
+
+impl Progress<'_> {
+    /// Report `done` of `total` steps through `stage`. Generation is the
+    /// first half of a job and rendering the second.
+    async fn step(&self, stage: Stage, done: usize, total: usize) {
+        let (base, doing) = match stage {
+            Stage::Generation => (0, "generating"),
+            Stage::Render => (50, "rendering"),
+        };
+        let percent = base + done * 50 / total.max(1);
+        self.post(&format!("⏳ Job #{}: {} {}%…", self.job_id, doing, percent)).await;
+    }
+    
+    async fn post(&self, text: &str) {
+        if !self.config.telegram_progress_updates || self.dry_run {
+            return;
+        }
+        let (Some(token), Some(chat_id)) = (
+            self.config.telegram_bot_token.as_deref(),
+            self.config.telegram_chat_id.as_deref(),
+        ) else {
+            return;
+        };
+        if let Err(e) = self.send(token, chat_id, text).await {
+            tracing::warn!("Telegram progress update for job {} failed: {}", self.job_id, e);
+        }
+    }
+    
+    async fn send(&self, token: &str, chat_id: &str, text: &str) -> Result<(), String> {
+        let existing = {
+            let db = &self.state.db;
+            db.get_job_telegram_message(self.job_id).map_err(|e| e.to_string())?
+        };
+        let bot = self.state.clients.telegram(token);
+        if let Some(message_id) = existing {
+            // Post a fresh message if the old one was deleted from the chat
+            match bot.edit_message_text(chat_id, message_id, text).await {
+                Ok(()) => return Ok(()),
+                Err(e) if e.contains("message to edit not found") => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let message_id = bot.send_message(chat_id, text, None).await?;
+        let db = &self.state.db;
+        db.set_job_telegram_message(self.job_id, message_id).map_err(|e| e.to_string())
+    }
+}
+
+/// Write, check and narrate a script for each of the job's languages, or
+/// each variant of them, then prepare the metadata to upload them with.
+/// Work already done by an earlier attempt is kept.
+async fn generate(state: &AppState, job: &Job, log: &JobLogger<'_>, progress: &Progress<'_>) -> Result<Outcome, String> {
+    let job_id = job.id.ok_or("Job has no id")?;
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    let mut shorts = state.db.get_shorts_for_job(job_id).map_err(|e| e.to_string())?;
+    if shorts.is_empty() {
+        state.db.create_shorts_for_languages(job_id, &config.target_languages, config.variants_per_language())
+            .map_err(|e| e.to_string())?;
+        shorts = state.db.get_shorts_for_job(job_id).map_err(|e| e.to_string())?;
+    }
+    
+    let steps = shorts.len() * 2;
+    progress.step(Stage::Generation, 0, steps).await;
+    if job.checkpoint < Some(PipelineStep::Script) {
+        for (i, short) in shorts.iter().enumerate() {
+            if is_shutting_down() {
+                return Ok(Outcome::Interrupted);
+            }
+            let short_id = short.id.ok_or("Short has no id")?;
+            // A script is only stored once it has passed moderation or been
+            // sent for review, so a stored one is never checked again
+            if short.script.is_none() {
+                let write = crate::write_script(state, job.trend_id, &short.language, short.hook_style, log);
+                let script = timed(state, job_id, "script", write).await?;
+                let verdict = moderation::moderate_script(&state.clients, &config, &script).await?;
+                state.db.set_short_script(short_id, &script).map_err(|e| e.to_string())?;
+                if let moderation::Verdict::Flagged(reason) = verdict {
+                    return Ok(Outcome::NeedsReview(format!("{} script: {}", short.language, reason)));
+                }
+            }
+            progress.step(Stage::Generation, i + 1, steps).await;
+        }
+        checkpoint(state, job_id, PipelineStep::Script)?;
+    }
+    
+    if job.checkpoint < Some(PipelineStep::Narration) {
+        for (i, short) in shorts.iter().enumerate() {
+            if is_shutting_down() {
+                return Ok(Outcome::Interrupted);
+            }
+            if short.audio_path.is_none() {
+                let short_id = short.id.ok_or("Short has no id")?;
+                timed(state, job_id, "narration", crate::synthesize(state, short_id, log)).await?;
+            }
+            progress.step(Stage::Generation, shorts.len() + i + 1, steps).await;
+        }
+        checkpoint(state, job_id, PipelineStep::Narration)?;
+    }
+    
+    // Title, hashtags and description follow from the script, so they come last
+    if job.checkpoint < Some(PipelineStep::Metadata) {
+        for short in shorts.iter().filter(|short| short.upload_metadata.is_none()) {
+            let short_id = short.id.ok_or("Short has no id")?;
+            let metadata = crate::prepare_upload_metadata(&state.db, short_id).map_err(|e| e.to_string())?;
+            log.debug(format!("{} upload title: {}", short.language, metadata.title));
+        }
+        checkpoint(state, job_id, PipelineStep::Metadata)?;
+    }
+    Ok(Outcome::Finished)
+}
+
+/// Persist that `step` is finished for every short of the job, so a later
+/// attempt or a rerun of a later step carries on after it
+fn checkpoint(state: &AppState, job_id: i64, step: PipelineStep) -> Result<(), String> {
+    state.db.set_job_checkpoint(job_id, step).map_err(|e| e.to_string())
+}
+
+/// Render every short of the job that has no video yet, then deliver the
+/// ones that don't need approval
+async fn render(state: &AppState, job_id: i64, log: &JobLogger<'_>, progress: &Progress<'_>) -> Result<Outcome, String> {
+    let shorts = state.db.get_shorts_for_job(job_id).map_err(|e| e.to_string())?;
+    let mut rendered = Vec::new();
+    progress.step(Stage::Render, 0, shorts.len()).await;
+    for (i, short) in shorts.iter().enumerate() {
+        if is_shutting_down() {
+            return Ok(Outcome::Interrupted);
+        }
+        if short.video_path.is_none() {
+            let short_id = short.id.ok_or("Short has no id")?;
+            timed(state, job_id, "video", crate::render_video(state, short_id, RenderMode::Final(None), log)).await?;
+            rendered.push(short_id);
+        }
+        progress.step(Stage::Render, i + 1, shorts.len()).await;
+    }
+    checkpoint(state, job_id, PipelineStep::Video)?;
+    
+    // Shorts held for approval are delivered by approve_short instead; fresh
+    // ones go to the Telegram chat for review. Destinations a short already
+    // reached are skipped by deliver_short.
+    let shorts = state.db.get_shorts_for_job(job_id).map_err(|e| e.to_string())?;
+    if !rendered.is_empty() {
+        notifications::notify(
+            progress.config,
+            Event::ShortRendered,
+            Some(job_id),
+            &format!("Job #{}: {} short(s) rendered", job_id, rendered.len()),
+        );
+    }
+    let mut awaiting = 0;
+    for short in &shorts {
+        let Some(short_id) = short.id else { continue };
+        if short.is_deliverable() {
+            crate::deliver_short(state, short_id, Actor::Autopilot, log).await;
+        } else if short.approval == Some(ApprovalStatus::AwaitingApproval) && rendered.contains(&short_id) {
+            crate::send_for_review(state, short_id, log).await;
+            awaiting += 1;
+        }
+    }
+    if awaiting > 0 {
+        notifications::notify(
+            progress.config,
+            Event::ApprovalNeeded,
+            Some(job_id),
+            &format!("Job #{}: {} short(s) waiting for approval", job_id, awaiting),
+        );
+    }
+    Ok(Outcome::Finished)
+}
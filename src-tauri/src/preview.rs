@@ -0,0 +1,97 @@
+// Asset protocol URLs for playing a short's media in the UI
+use crate::db::Short;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Which of a short's files to preview
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Audio,
+    Video,
+    Thumbnail,
+}
+
+impl MediaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaKind::Audio => "audio",
+            MediaKind::Video => "video",
+            MediaKind::Thumbnail => "thumbnail",
+        }
+    }
+    
+    pub fn path<'a>(&self, short: &'a Short) -> Option<&'a str> {
+        match self {
+            MediaKind::Audio => short.audio_path.as_deref(),
+            MediaKind::Video => short.video_path.as_deref(),
+            MediaKind::Thumbnail => short.thumbnail_path.as_deref(),
+        }
+    }
+}
+
+/// `path` resolved, as long as it is an existing file under `app_dir`.
+/// Symlinks and `..` are resolved first so neither can point outside it.
+pub fn resolve(app_dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = app_dir.canonicalize().map_err(|e| e.to_string())?;
+    let resolved = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("{} is not available: {}", path, e))?;
+    if !resolved.starts_with(&root) || !resolved.is_file() {
+        return Err(format!("{} is not a file in the app data directory", path));
+    }
+    Ok(resolved)
+}
+
+/// URL the webview loads `path` from, as `convertFileSrc` builds it
+pub fn asset_url(path: &Path) -> String {
+    let encoded = encode_component(&path.to_string_lossy());
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+/// Percent-encode like JavaScript's `encodeURIComponent`
+fn encode_component(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn encodes_like_convert_file_src() {
+        assert_eq!(
+            encode_component("/home/me/shotauto/videos/short 1 (final).mp4"),
+            "%2Fhome%2Fme%2Fshotauto%2Fvideos%2Fshort%201%20(final).mp4"
+        );
+        assert_eq!(encode_component("é"), "%C3%A9");
+    }
+    
+    #[test]
+    fn rejects_files_outside_the_app_dir() {
+        let root = std::env::temp_dir().join(format!("shotauto-preview-test-{}", std::process::id()));
+        let app_dir = root.join("app");
+        std::fs::create_dir_all(app_dir.join("videos")).unwrap();
+        std::fs::write(app_dir.join("videos/1.mp4"), b"video").unwrap();
+        std::fs::write(root.join("secret.txt"), b"secret").unwrap();
+        
+        assert!(resolve(&app_dir, &app_dir.join("videos/1.mp4").to_string_lossy()).is_ok());
+        assert!(resolve(&app_dir, &app_dir.join("videos/../../secret.txt").to_string_lossy()).is_err());
+        assert!(resolve(&app_dir, &app_dir.join("videos").to_string_lossy()).is_err());
+        assert!(resolve(&app_dir, &app_dir.join("videos/missing.mp4").to_string_lossy()).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
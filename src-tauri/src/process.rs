@@ -0,0 +1,25 @@
+// External tool execution (ffmpeg, yt-dlp, whisper.cpp, ...)
+use std::process::Output;
+use tokio::process::Command;
+
+/// Run an external tool to completion, turning a non-zero exit into an
+/// error carrying the last lines of its stderr
+pub async fn run(command: &mut Command) -> Result<Output, String> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let output = command
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} exited with {}: {}", program, output.status, stderr_tail(&output, 5)));
+    }
+    Ok(output)
+}
+
+/// Last `lines` lines of a process's stderr
+pub fn stderr_tail(output: &Output, lines: usize) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let all: Vec<&str> = stderr.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
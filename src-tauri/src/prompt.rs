@@ -0,0 +1,331 @@
+// Prompt templates for script generation
+use crate::db::{Config, Database, Example, Trend};
+use crate::llm::SamplingParams;
+use serde::{Deserialize, Serialize};
+
+/// Default script prompt. Placeholders: `{title}`, `{channel}`, `{category}`,
+/// `{views}`, `{transcript}`, `{language}`, `{examples}`, plus the voice
+/// placeholders `{audience}`, `{tone}`, `{banned_topics}`, `{cta}` and
+/// `{channel_handle}`.
+pub const DEFAULT_SCRIPT_TEMPLATE: &str = "Write a YouTube Shorts narration script (under 60 seconds when read aloud) \
+inspired by the trending video \"{title}\" from {channel}.\n\n\
+Transcript of the original video:\n{transcript}\n\n\
+{examples}\
+Open with a one-sentence hook, keep sentences short, and end with a question for the viewer. \
+Write the script in {language}. Return only the narration text.";
+
+/// Placeholders filled from `ChannelVoice`, in prompt and upload templates alike
+const VOICE_PLACEHOLDERS: &[&str] = &["{audience}", "{tone}", "{banned_topics}", "{cta}", "{channel_handle}"];
+
+/// Who the channel makes shorts for and how it talks to them. Part of the
+/// config, so each profile has its own. Not to be confused with a niche,
+/// the trend category templates and accounts are matched on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelVoice {
+    /// Who the shorts are for, e.g. "busy parents who cook on weeknights"
+    pub audience: String,
+    /// How the narration sounds, e.g. "upbeat and a little nerdy"
+    pub tone: String,
+    /// Subjects scripts stay away from
+    pub banned_topics: Vec<String>,
+    /// Call to action ending scripts and descriptions
+    pub cta: String,
+    /// The channel's handle, with or without the leading @
+    pub channel_handle: String,
+}
+
+impl ChannelVoice {
+    /// The handle with its @, or empty when none is set
+    pub fn handle(&self) -> String {
+        match self.channel_handle.trim().trim_start_matches('@') {
+            "" => String::new(),
+            handle => format!("@{}", handle),
+        }
+    }
+    
+    fn banned_topics(&self) -> String {
+        self.banned_topics.iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    
+    /// Whether `template` places any part of the voice itself
+    pub fn is_placed_in(template: &str) -> bool {
+        VOICE_PLACEHOLDERS.iter().any(|p| template.contains(p))
+    }
+    
+    /// Fill the voice placeholders in `text`
+    pub fn fill(&self, text: &str) -> String {
+        text.replace("{audience}", self.audience.trim())
+            .replace("{tone}", self.tone.trim())
+            .replace("{banned_topics}", &self.banned_topics())
+            .replace("{cta}", self.cta.trim())
+            .replace("{channel_handle}", &self.handle())
+    }
+    
+    /// Instructions spelling out the voice, for templates that don't place
+    /// it themselves. Empty when nothing is set.
+    fn guidance(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.audience.trim().is_empty() {
+            lines.push(format!("The audience is {}.", self.audience.trim()));
+        }
+        if !self.tone.trim().is_empty() {
+            lines.push(format!("Use a tone that is {}.", self.tone.trim()));
+        }
+        let banned = self.banned_topics();
+        if !banned.is_empty() {
+            lines.push(format!("Do not mention these topics: {}.", banned));
+        }
+        if !self.cta.trim().is_empty() {
+            lines.push(format!("End with this call to action: \"{}\"", self.cta.trim()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Kind of opening line a script variant is asked for, so A/B variants
+/// differ where it matters most
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStyle {
+    Question,
+    BoldClaim,
+    SurprisingFact,
+    Story,
+    Countdown,
+}
+
+impl HookStyle {
+    /// Styles in the order variants are given them
+    pub const ALL: [HookStyle; 5] = [
+        HookStyle::Question,
+        HookStyle::BoldClaim,
+        HookStyle::SurprisingFact,
+        HookStyle::Story,
+        HookStyle::Countdown,
+    ];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookStyle::Question => "question",
+            HookStyle::BoldClaim => "bold_claim",
+            HookStyle::SurprisingFact => "surprising_fact",
+            HookStyle::Story => "story",
+            HookStyle::Countdown => "countdown",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "bold_claim" => HookStyle::BoldClaim,
+            "surprising_fact" => HookStyle::SurprisingFact,
+            "story" => HookStyle::Story,
+            "countdown" => HookStyle::Countdown,
+            _ => HookStyle::Question,
+        }
+    }
+    
+    /// What the prompt asks the first sentence to be
+    fn instruction(&self) -> &'static str {
+        match self {
+            HookStyle::Question => "a question the viewer wants answered",
+            HookStyle::BoldClaim => "a bold, confident claim",
+            HookStyle::SurprisingFact => "a surprising fact or number",
+            HookStyle::Story => "the start of a short personal story",
+            HookStyle::Countdown => "a promise of a quick list, like \"3 things you didn't know\"",
+        }
+    }
+}
+
+/// Everything a script prompt can draw on
+pub struct PromptContext<'a> {
+    pub trend: &'a Trend,
+    pub voice: &'a ChannelVoice,
+    /// Hook a script variant must open with
+    pub hook_style: Option<HookStyle>,
+    /// Output language code
+    pub language: &'a str,
+    /// Few-shot examples, best match first
+    pub examples: &'a [Example],
+    /// Sound effects in the library the script may call for
+    pub sound_effects: &'a [String],
+}
+
+/// Fill a template's placeholders from the prompt context.
+/// So older custom templates keep working, ones without `{examples}` get the
+/// examples put before the template body, and ones without `{language}` or
+/// voice placeholders get those sections appended.
+pub fn render(template: &str, ctx: &PromptContext) -> String {
+    let name = language_name(ctx.language);
+    let examples = format_examples(ctx.examples);
+    let mut prompt = fill(&ctx.voice.fill(template), ctx.trend)
+        .replace("{language}", name)
+        .replace("{examples}", &examples);
+    if !template.contains("{examples}") && !examples.is_empty() {
+        prompt = format!("{}{}", examples, prompt);
+    }
+    let guidance = ctx.voice.guidance();
+    if !ChannelVoice::is_placed_in(template) && !guidance.is_empty() {
+        prompt.push_str(&format!("\n\n{}", guidance));
+    }
+    if !template.contains("{language}") && ctx.language != "en" {
+        prompt.push_str(&format!("\n\nWrite the script in {}.", name));
+    }
+    if let Some(style) = ctx.hook_style {
+        prompt.push_str(&format!("\n\nMake the first sentence {}.", style.instruction()));
+    }
+    if !ctx.sound_effects.is_empty() {
+        prompt.push_str(&format!(
+            "\n\nYou may add a sound effect by writing [sfx:name] where it should play. Available: {}.",
+            ctx.sound_effects.join(", ")
+        ));
+    }
+    prompt
+}
+
+/// Build the script prompt for a trend along with the sampling parameters to send it with.
+/// A niche-specific template wins over a catch-all one, which wins over the
+/// template in `Config`.
+pub fn build_script_prompt(
+    db: &Database,
+    config: &Config,
+    trend: &Trend,
+    language: &str,
+    hook_style: Option<HookStyle>,
+) -> rusqlite::Result<(String, SamplingParams)> {
+    let niche = trend.category.as_deref();
+    let (template, params) = match db.prompt_template_for_niche(niche)? {
+        Some(t) => {
+            let params = t.sampling();
+            (t.body, params)
+        }
+        None => (config.script_prompt_template.clone(), SamplingParams::default()),
+    };
+    let examples = db.top_examples(niche, config.examples_per_prompt)?;
+    let sound_effects: Vec<String> = db.list_sound_effects()?.into_iter().map(|e| e.name).collect();
+    let ctx = PromptContext {
+        trend,
+        voice: &config.voice,
+        hook_style,
+        language,
+        examples: &examples,
+        sound_effects: &sound_effects,
+    };
+    Ok((render(&template, &ctx), params))
+}
+
+/// Few-shot block showing the model scripts in the style we want
+fn format_examples(examples: &[Example]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("Here are example scripts in the style we want:\n\n");
+    for (i, example) in examples.iter().enumerate() {
+        block.push_str(&format!("Example {} ({}):\n{}\n\n", i + 1, example.title, example.script.trim()));
+    }
+    block
+}
+
+/// Human-readable name for a language code, used in prompts
+pub fn language_name(code: &str) -> &str {
+    match code {
+        "en" => "English",
+        "ko" => "Korean",
+        "ja" => "Japanese",
+        "zh" => "Chinese",
+        "es" => "Spanish",
+        "pt" => "Portuguese",
+        "fr" => "French",
+        "de" => "German",
+        "it" => "Italian",
+        "ru" => "Russian",
+        "hi" => "Hindi",
+        "id" => "Indonesian",
+        "vi" => "Vietnamese",
+        "th" => "Thai",
+        "tr" => "Turkish",
+        "ar" => "Arabic",
+        other => other,
+    }
+}
+
+fn fill(template: &str, trend: &Trend) -> String {
+    template
+        .replace("{title}", &trend.title)
+        .replace("{channel}", trend.channel.as_deref().unwrap_or("an unknown channel"))
+        .replace("{category}", trend.category.as_deref().unwrap_or("general"))
+        .replace("{views}", &trend.views.map(|v| v.to_string()).unwrap_or_else(|| "unknown".to_string()))
+        .replace("{transcript}", trend.transcript.as_deref().unwrap_or("(no transcript available)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trends::topic_trend;
+    
+    fn example(title: &str, script: &str) -> Example {
+        Example { id: None, niche: String::new(), title: title.to_string(), script: script.to_string(), created_at: None }
+    }
+    
+    fn context<'a>(trend: &'a Trend, voice: &'a ChannelVoice, language: &'a str, examples: &'a [Example]) -> PromptContext<'a> {
+        PromptContext { trend, voice, hook_style: None, language, examples, sound_effects: &[] }
+    }
+    
+    #[test]
+    fn placeholders_are_filled_from_the_trend_and_voice() {
+        let trend = topic_trend("Pasta");
+        let voice = ChannelVoice {
+            audience: "home cooks".into(),
+            tone: "calm".into(),
+            channel_handle: "chef".into(),
+            ..Default::default()
+        };
+        let prompt = render("{title} ({category}) for {audience}, {tone}. Follow {channel_handle}", &context(&trend, &voice, "en", &[]));
+        assert_eq!(prompt, "Pasta (general) for home cooks, calm. Follow @chef");
+    }
+    
+    #[test]
+    fn older_templates_get_examples_voice_and_language_added() {
+        let trend = topic_trend("Pasta");
+        let voice = ChannelVoice { tone: "upbeat".into(), ..Default::default() };
+        let examples = [example("Old hit", " Script body \n")];
+        let prompt = render("Write about {title}.", &context(&trend, &voice, "ko", &examples));
+        assert_eq!(
+            prompt,
+            "Here are example scripts in the style we want:\n\nExample 1 (Old hit):\nScript body\n\n\
+             Write about Pasta.\n\nUse a tone that is upbeat.\n\nWrite the script in Korean."
+        );
+    }
+    
+    #[test]
+    fn placed_sections_are_not_added_again() {
+        let trend = topic_trend("Pasta");
+        let voice = ChannelVoice { cta: "Subscribe".into(), ..Default::default() };
+        let examples = [example("Old hit", "Script body")];
+        let prompt = render("{examples}{title} in {language}. {cta}", &context(&trend, &voice, "ja", &examples));
+        assert!(prompt.starts_with("Here are example scripts"));
+        assert!(prompt.ends_with("Pasta in Japanese. Subscribe"));
+        assert!(!prompt.contains("call to action"));
+    }
+    
+    #[test]
+    fn hook_style_and_sound_effects_follow_the_template() {
+        let trend = topic_trend("Pasta");
+        let voice = ChannelVoice::default();
+        let sound_effects = ["whoosh".to_string(), "ding".to_string()];
+        let ctx = PromptContext {
+            hook_style: Some(HookStyle::BoldClaim),
+            sound_effects: &sound_effects,
+            ..context(&trend, &voice, "en", &[])
+        };
+        assert_eq!(
+            render("{title}", &ctx),
+            "Pasta\n\nMake the first sentence a bold, confident claim.\n\n\
+             You may add a sound effect by writing [sfx:name] where it should play. Available: whoosh, ding."
+        );
+    }
+}
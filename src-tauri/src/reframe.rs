@@ -0,0 +1,164 @@
+// Cropping landscape source footage to the vertical frame around its subject
+use crate::db::Config;
+use crate::process::run;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+/// Footage sampled when looking for black bars and the subject, in seconds
+const SAMPLE_SECS: u32 = 30;
+
+/// How footage that doesn't fit the frame is cropped to it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReframeMode {
+    /// Scale to fill and keep the middle
+    #[default]
+    Center,
+    /// Remove black bars burned into the footage, then keep the middle
+    Cropdetect,
+    /// Remove black bars, then keep the detected faces in view. Needs the
+    /// `face-detection` build feature and `face_model_path`; keeps the
+    /// middle otherwise.
+    Subject,
+}
+
+/// Picture inside a clip's black bars, in source pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Where to crop a clip: its picture area and the horizontal center of
+/// its subject, as a fraction of the area's width
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reframe {
+    pub area: Option<Area>,
+    pub focus_x: f64,
+}
+
+impl Default for Reframe {
+    fn default() -> Self {
+        Self { area: None, focus_x: 0.5 }
+    }
+}
+
+impl Reframe {
+    /// Filter chain that fills a `width`x`height` frame, keeping the
+    /// subject as close to the middle as the footage allows
+    pub fn filter(&self, width: u32, height: u32) -> String {
+        let area = self.area
+            .map(|a| format!("crop={}:{}:{}:{},", a.width, a.height, a.x, a.y))
+            .unwrap_or_default();
+        let x = if self.focus_x == 0.5 {
+            "(iw-ow)/2".to_string()
+        } else {
+            format!("'max(0,min(iw-ow,iw*{:.4}-ow/2))'", self.focus_x.clamp(0.0, 1.0))
+        };
+        format!("{area}scale={width}:{height}:force_original_aspect_ratio=increase,crop={width}:{height}:{x}:(ih-oh)/2")
+    }
+}
+
+/// Work out how to crop `video` under `mode`
+pub async fn analyze(config: &Config, mode: ReframeMode, video: &Path) -> Result<Reframe, String> {
+    if mode == ReframeMode::Center {
+        return Ok(Reframe::default());
+    }
+    let area = detect_area(config, video).await?;
+    let focus_x = match mode {
+        ReframeMode::Subject => match subject_focus(config, video, area).await {
+            Ok(Some(focus_x)) => focus_x,
+            Ok(None) => 0.5,
+            Err(e) => {
+                tracing::warn!("Keeping the middle of {}: {}", video.display(), e);
+                0.5
+            }
+        },
+        _ => 0.5,
+    };
+    Ok(Reframe { area, focus_x })
+}
+
+/// Picture area of `video` per cropdetect, or `None` when it has no bars
+async fn detect_area(config: &Config, video: &Path) -> Result<Option<Area>, String> {
+    let output = run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-t", &SAMPLE_SECS.to_string(), "-i"])
+        .arg(video)
+        .args(["-vf", "fps=2,cropdetect=limit=24:round=2:reset=0", "-f", "null", "-"]))
+    .await?;
+    let area = parse_cropdetect(&String::from_utf8_lossy(&output.stderr));
+    let size = probe_size(config, video).await?;
+    Ok(area.filter(|a| (a.width, a.height) != size && a.width > 0 && a.height > 0))
+}
+
+/// Last `crop=w:h:x:y` cropdetect reported; with `reset=0` it covers every frame seen
+fn parse_cropdetect(stderr: &str) -> Option<Area> {
+    let crop = stderr.lines().rev().find_map(|line| line.split("crop=").nth(1))?;
+    let mut parts = crop.split_whitespace().next()?.split(':').map(|p| p.parse::<u32>().ok());
+    Some(Area {
+        width: parts.next()??,
+        height: parts.next()??,
+        x: parts.next()??,
+        y: parts.next()??,
+    })
+}
+
+async fn probe_size(config: &Config, video: &Path) -> Result<(u32, u32), String> {
+    let output = run(Command::new(config.ffprobe())
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0:s=x"])
+        .arg(video))
+    .await?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.trim()
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| format!("ffprobe returned an invalid size: {}", text.trim()))
+}
+
+/// Horizontal center of the faces in `video`, or `None` when there are none
+#[cfg(feature = "face-detection")]
+async fn subject_focus(config: &Config, video: &Path, area: Option<Area>) -> Result<Option<f64>, String> {
+    let model = config.face_model_path.as_deref()
+        .ok_or("Subject reframing needs a face detection model")?;
+    let frames = crate::faces::sample_frames(config, video, area, SAMPLE_SECS).await?;
+    let model = model.to_string();
+    tokio::task::spawn_blocking(move || crate::faces::focus_x(&model, &frames))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "face-detection"))]
+async fn subject_focus(_config: &Config, _video: &Path, _area: Option<Area>) -> Result<Option<f64>, String> {
+    Err("This build has no face detection; build with the face-detection feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn reads_the_last_cropdetect_line() {
+        let stderr = "[Parsed_cropdetect_1 @ 0x1] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:1 t:0.5 limit:0.09 crop=1920:800:0:140\n\
+                      [Parsed_cropdetect_1 @ 0x1] x1:0 x2:1919 y1:138 y2:941 w:1920 h:804 x:0 y:138 pts:2 t:1.0 limit:0.09 crop=1920:804:0:138\n\
+                      frame=   60 fps=0.0 q=-0.0 Lsize=N/A";
+        assert_eq!(parse_cropdetect(stderr), Some(Area { width: 1920, height: 804, x: 0, y: 138 }));
+        assert_eq!(parse_cropdetect("no crop here"), None);
+    }
+    
+    #[test]
+    fn crops_around_the_focus() {
+        assert_eq!(
+            Reframe::default().filter(1080, 1920),
+            "scale=1080:1920:force_original_aspect_ratio=increase,crop=1080:1920:(iw-ow)/2:(ih-oh)/2"
+        );
+        let reframe = Reframe { area: Some(Area { width: 1920, height: 800, x: 0, y: 140 }), focus_x: 0.25 };
+        assert_eq!(
+            reframe.filter(1080, 1920),
+            "crop=1920:800:0:140,scale=1080:1920:force_original_aspect_ratio=increase,\
+             crop=1080:1920:'max(0,min(iw-ow,iw*0.2500-ow/2))':(ih-oh)/2"
+        );
+    }
+}
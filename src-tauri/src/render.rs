@@ -0,0 +1,895 @@
+// Video rendering for ShotAuto
+use crate::audio;
+use crate::captions::{self, CaptionStyle};
+use crate::db::{Config, LengthFix, RenderTemplate, VideoEncoder};
+use crate::music;
+use crate::process::run;
+use crate::reframe::{self, Reframe, ReframeMode};
+use crate::sfx;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Frame width that template pixel sizes (watermark, margins) refer to
+const REFERENCE_WIDTH: u32 = 1080;
+/// Gap between the watermark and the frame edges, in reference pixels
+const WATERMARK_MARGIN: u32 = 40;
+/// Final zoom factor of the Ken Burns effect on slides
+const KEN_BURNS_ZOOM: f64 = 1.2;
+
+/// End-card text size at `REFERENCE_WIDTH`
+const END_CARD_FONT_SIZE: u32 = 90;
+/// Quieter than this counts as silence when a short's end is trimmed
+const SILENCE_NOISE_DB: f64 = -45.0;
+/// Shortest gap that counts as silence, in seconds
+const SILENCE_MIN_SECS: f64 = 0.3;
+/// Silence kept after the last word when the rest is trimmed
+const SILENCE_PAD_SECS: f64 = 0.25;
+/// Fastest a short is sped up to fit the limit; beyond this it sounds rushed
+const MAX_SPEEDUP: f64 = 1.1;
+/// Kept free under the limit so rounding in the container can't tip a
+/// short over it
+const LENGTH_MARGIN_SECS: f64 = 0.2;
+
+/// What fills the frame behind the captions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundKind {
+    /// Flat `background_color`
+    #[default]
+    Color,
+    /// Vertical gradient from `background_color` to `gradient_color`
+    Gradient,
+    /// Still image at `background_path`, scaled to fill
+    Image,
+    /// Video at `background_path`, looped and scaled to fill
+    Video,
+}
+
+/// Frame corner the watermark is pinned to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl WatermarkPosition {
+    /// `overlay` filter coordinates for a frame `width` pixels wide
+    fn overlay_xy(&self, width: u32) -> String {
+        let m = WATERMARK_MARGIN * width / REFERENCE_WIDTH;
+        match self {
+            WatermarkPosition::TopLeft => format!("{m}:{m}"),
+            WatermarkPosition::TopRight => format!("W-w-{m}:{m}"),
+            WatermarkPosition::BottomLeft => format!("{m}:H-h-{m}"),
+            WatermarkPosition::BottomRight => format!("W-w-{m}:H-h-{m}"),
+        }
+    }
+}
+
+/// Call to action shown over the last seconds of the narrated body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndCard {
+    /// Text such as "Follow {channel_handle} for part two"; voice placeholders are filled
+    pub text: String,
+    /// Graphic (PNG with transparency) centered below the text
+    pub image_path: Option<String>,
+    /// How long before the end of the narration it appears
+    pub seconds: f64,
+}
+
+impl Default for EndCard {
+    fn default() -> Self {
+        Self {
+            text: "Subscribe for more".to_string(),
+            image_path: None,
+            seconds: 3.0,
+        }
+    }
+}
+
+/// File a render template can point at, set with `set_template_asset`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateAsset {
+    Background,
+    Watermark,
+    Intro,
+    Outro,
+    EndCardImage,
+}
+
+impl TemplateAsset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateAsset::Background => "background",
+            TemplateAsset::Watermark => "watermark",
+            TemplateAsset::Intro => "intro",
+            TemplateAsset::Outro => "outro",
+            TemplateAsset::EndCardImage => "end_card_image",
+        }
+    }
+}
+
+/// Visual layout of a render template, stored as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateLayout {
+    pub background: BackgroundKind,
+    /// Background color as `#RRGGBB`
+    pub background_color: String,
+    /// Second gradient stop as `#RRGGBB`
+    pub gradient_color: String,
+    /// Image or video for the `image`/`video` backgrounds
+    pub background_path: Option<String>,
+    /// Overrides the caption style from config when set
+    pub caption_style: Option<CaptionStyle>,
+    /// Channel logo (PNG with transparency) overlaid on every frame
+    #[serde(alias = "logo_path")]
+    pub watermark_path: Option<String>,
+    #[serde(alias = "logo_position")]
+    pub watermark_position: WatermarkPosition,
+    /// Watermark width in pixels; height keeps the aspect ratio
+    #[serde(alias = "logo_width")]
+    pub watermark_width: u32,
+    /// 0.0 (invisible) to 1.0 (opaque)
+    pub watermark_opacity: f32,
+    /// Clip played before the narrated body
+    pub intro_path: Option<String>,
+    /// Clip played after the narrated body
+    pub outro_path: Option<String>,
+    /// Call to action over the end of the narrated body
+    pub end_card: Option<EndCard>,
+    /// How video backgrounds and clips are cropped to the frame
+    pub reframe_mode: ReframeMode,
+}
+
+impl Default for TemplateLayout {
+    fn default() -> Self {
+        Self {
+            background: BackgroundKind::Color,
+            background_color: "#101018".to_string(),
+            gradient_color: "#2A1A4A".to_string(),
+            background_path: None,
+            caption_style: None,
+            watermark_path: None,
+            watermark_position: WatermarkPosition::TopRight,
+            watermark_width: 180,
+            watermark_opacity: 0.8,
+            intro_path: None,
+            outro_path: None,
+            end_card: None,
+            reframe_mode: ReframeMode::Center,
+        }
+    }
+}
+
+impl TemplateLayout {
+    /// Caption style for this template, falling back to the configured one
+    pub fn caption_style<'a>(&'a self, config: &'a Config) -> &'a CaptionStyle {
+        self.caption_style.as_ref().unwrap_or(&config.caption_style)
+    }
+    
+    /// File `asset` points at, if any
+    pub fn asset(&self, asset: TemplateAsset) -> Option<&str> {
+        match asset {
+            TemplateAsset::Background => self.background_path.as_deref(),
+            TemplateAsset::Watermark => self.watermark_path.as_deref(),
+            TemplateAsset::Intro => self.intro_path.as_deref(),
+            TemplateAsset::Outro => self.outro_path.as_deref(),
+            TemplateAsset::EndCardImage => self.end_card.as_ref().and_then(|card| card.image_path.as_deref()),
+        }
+    }
+
+    /// Copy `source` into `dir` as the template's `asset` and point the
+    /// layout at the copy, or drop the asset with `None`. The copy it
+    /// pointed at before is removed, such as one with another extension;
+    /// files outside `dir` are left alone.
+    pub fn store_asset(&mut self, dir: &Path, asset: TemplateAsset, source: Option<&Path>) -> std::io::Result<Option<String>> {
+        let stored = match source {
+            Some(source) => {
+                let extension = source.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+                std::fs::create_dir_all(dir)?;
+                let stored = dir.join(format!("{}.{}", asset.as_str(), extension));
+                // Copying a file onto itself would truncate it
+                if source != stored {
+                    std::fs::copy(source, &stored)?;
+                }
+                Some(stored)
+            }
+            None => None,
+        };
+        if let Some(previous) = self.asset(asset).map(PathBuf::from) {
+            if previous.starts_with(dir) && Some(&previous) != stored.as_ref() {
+                let _ = std::fs::remove_file(&previous);
+            }
+        }
+        let stored = stored.map(|path| path.to_string_lossy().to_string());
+        self.set_asset(asset, stored.clone());
+        Ok(stored)
+    }
+
+    /// Point `asset` at `path`, or drop it with `None`. Setting the end-card
+    /// image turns the end card on with default text.
+    pub fn set_asset(&mut self, asset: TemplateAsset, path: Option<String>) {
+        match asset {
+            TemplateAsset::Background => self.background_path = path,
+            TemplateAsset::Watermark => self.watermark_path = path,
+            TemplateAsset::Intro => self.intro_path = path,
+            TemplateAsset::Outro => self.outro_path = path,
+            TemplateAsset::EndCardImage => match (&mut self.end_card, path) {
+                (Some(card), path) => card.image_path = path,
+                (None, Some(path)) => {
+                    self.end_card = Some(EndCard { image_path: Some(path), ..Default::default() });
+                }
+                (None, None) => {}
+            },
+        }
+    }
+}
+
+/// Output format of a render. Config holds a list of named presets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderPreset {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    /// Constant-quality target on the x264 CRF scale (lower is better)
+    pub crf: u8,
+    /// Fixed video bitrate; overrides `crf` when set
+    pub bitrate_kbps: Option<u32>,
+    /// Encoder for this preset; the configured `video_encoder` when unset
+    pub encoder: Option<VideoEncoder>,
+    /// x264 speed/size tradeoff, `ultrafast` to `veryslow`
+    pub speed: String,
+}
+
+impl Default for RenderPreset {
+    fn default() -> Self {
+        Self {
+            name: "1080p30".to_string(),
+            width: 1080,
+            height: 1920,
+            fps: 30,
+            crf: 23,
+            bitrate_kbps: None,
+            encoder: None,
+            speed: "medium".to_string(),
+        }
+    }
+}
+
+impl RenderPreset {
+    /// Name of the quick, lower-quality preset used for previews
+    pub const PREVIEW: &'static str = "preview";
+    
+    /// Presets a fresh install starts with
+    pub fn defaults() -> Vec<RenderPreset> {
+        vec![
+            RenderPreset::default(),
+            RenderPreset { name: "1080p60".to_string(), fps: 60, ..Default::default() },
+            RenderPreset { name: "720p30".to_string(), width: 720, height: 1280, ..Default::default() },
+            RenderPreset {
+                name: Self::PREVIEW.to_string(),
+                width: 540,
+                height: 960,
+                crf: 30,
+                speed: "veryfast".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+    
+    /// 360p x264 ultrafast for draft renders; quick to encode on any machine
+    pub fn draft() -> RenderPreset {
+        RenderPreset {
+            name: "draft".to_string(),
+            width: 360,
+            height: 640,
+            crf: 32,
+            encoder: Some(VideoEncoder::Libx264),
+            speed: "ultrafast".to_string(),
+            ..Default::default()
+        }
+    }
+    
+    /// Bitrate for encoders without a constant-quality mode: about 0.1 bits
+    /// per pixel at CRF 23, doubling every 6 CRF steps like x264
+    fn estimated_kbps(&self) -> u32 {
+        let bits_per_pixel = 0.1 * 2f64.powf((23.0 - self.crf as f64) / 6.0);
+        (self.width as f64 * self.height as f64 * self.fps as f64 * bits_per_pixel / 1000.0) as u32
+    }
+}
+
+/// `#RRGGBB` to ffmpeg's `0xRRGGBB`
+fn ffmpeg_color(color: &str) -> String {
+    format!("0x{}", color.trim().trim_start_matches('#'))
+}
+
+/// Zoom/pan filter turning a still image into `duration` seconds of video.
+/// Alternate slides zoom out instead of in so cuts don't feel repetitive.
+fn ken_burns(preset: &RenderPreset, duration: f64, zoom_out: bool) -> String {
+    let frames = ((duration * preset.fps as f64).round() as u32).max(1);
+    let step = (KEN_BURNS_ZOOM - 1.0) / frames as f64;
+    let zoom = if zoom_out {
+        format!("if(eq(on,0),{KEN_BURNS_ZOOM},max(zoom-{step:.6},1))")
+    } else {
+        format!("min(zoom+{step:.6},{KEN_BURNS_ZOOM})")
+    };
+    // Upscale first so the slow zoom doesn't jitter on whole-pixel steps
+    format!(
+        "scale={w2}:{h2}:force_original_aspect_ratio=increase,crop={w2}:{h2},\
+         zoompan=z='{zoom}':x='iw/2-(iw/zoom/2)':y='ih/2-(ih/zoom/2)':d={frames}:s={w}x{h}:fps={fps},setsar=1",
+        w2 = preset.width * 2, h2 = preset.height * 2, w = preset.width, h = preset.height, fps = preset.fps
+    )
+}
+
+/// What a slide shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlideKind {
+    /// Still image, animated with a Ken Burns zoom
+    Image,
+    /// Clip, looped if it is shorter than the slide
+    Video,
+}
+
+/// A visual shown for part of the short, one per scene
+#[derive(Debug, Clone)]
+pub struct Slide {
+    pub path: PathBuf,
+    pub kind: SlideKind,
+    /// Seconds on screen
+    pub duration: f64,
+}
+
+/// Media that goes into a rendered short
+pub struct RenderInput<'a> {
+    pub narration: &'a Path,
+    /// Background music, looped and ducked under the narration
+    pub music: Option<&'a Path>,
+    /// Sound effects the script calls for, mixed in over the narration
+    pub effects: &'a [sfx::Cue],
+    /// ASS captions burned into the video
+    pub subtitles: Option<&'a Path>,
+    /// Per-scene visuals shown in sequence instead of the template background
+    pub slides: &'a [Slide],
+}
+
+/// Render a vertical short with `template` in the format of `preset` and
+/// return its duration in seconds
+pub async fn render_short(
+    config: &Config,
+    template: &RenderTemplate,
+    preset: &RenderPreset,
+    input: &RenderInput<'_>,
+    output: &Path,
+) -> Result<f64, String> {
+    let layout = &template.layout;
+    let encoder = preset.encoder.unwrap_or(config.video_encoder);
+    let framing = reframe_clips(config, layout, input).await;
+    if layout.intro_path.is_none() && layout.outro_path.is_none() {
+        with_encoder_fallback(encoder, |encoder| render_body(config, layout, preset, input, &framing, output, encoder)).await?;
+        return probe_duration(config, output).await;
+    }
+    
+    // Render the narrated part on its own, then stitch the bookends around it
+    let body = output.with_extension("body.mp4");
+    with_encoder_fallback(encoder, |encoder| render_body(config, layout, preset, input, &framing, &body, encoder)).await?;
+    let mut sequence = Vec::new();
+    sequence.extend(layout.intro_path.as_ref().map(PathBuf::from));
+    sequence.push(body.clone());
+    sequence.extend(layout.outro_path.as_ref().map(PathBuf::from));
+    let result = with_encoder_fallback(encoder, |encoder| concat_clips(config, preset, &sequence, output, encoder)).await;
+    let _ = tokio::fs::remove_file(&body).await;
+    result?;
+    
+    probe_duration(config, output).await
+}
+
+/// Test pattern with silence, `seconds` long, in the preset's format. Stands
+/// in for the real render in dry-run mode.
+pub async fn render_placeholder(config: &Config, preset: &RenderPreset, seconds: f64, output: &Path) -> Result<f64, String> {
+    let mut command = Command::new(config.ffmpeg());
+    command
+        .args(["-hide_banner", "-y", "-f", "lavfi", "-i"])
+        .arg(format!("testsrc2=s={}x{}:r={}:d={}", preset.width, preset.height, preset.fps, seconds))
+        .args(["-f", "lavfi", "-i", "anullsrc=r=48000:cl=stereo"])
+        .args(["-t", &seconds.to_string(), "-pix_fmt", "yuv420p"])
+        .args(encoder_args(VideoEncoder::Libx264, preset))
+        .args(["-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart"])
+        .arg(output);
+    run(&mut command).await?;
+    probe_duration(config, output).await
+}
+
+/// How to crop each video the body shows, by path. Clips that can't be
+/// analyzed are left out and keep the middle.
+async fn reframe_clips(config: &Config, layout: &TemplateLayout, input: &RenderInput<'_>) -> HashMap<PathBuf, Reframe> {
+    let mut framing = HashMap::new();
+    if layout.reframe_mode == ReframeMode::Center {
+        return framing;
+    }
+    let background = match layout.background {
+        BackgroundKind::Video if input.slides.is_empty() => layout.background_path.as_ref().map(PathBuf::from),
+        _ => None,
+    };
+    let clips = input.slides.iter()
+        .filter(|slide| slide.kind == SlideKind::Video)
+        .map(|slide| slide.path.clone())
+        .chain(background);
+    for clip in clips {
+        if framing.contains_key(&clip) {
+            continue;
+        }
+        match reframe::analyze(config, layout.reframe_mode, &clip).await {
+            Ok(reframe) => {
+                framing.insert(clip, reframe);
+            }
+            Err(e) => tracing::warn!("Keeping the middle of {}: {}", clip.display(), e),
+        }
+    }
+    framing
+}
+
+/// Background, watermark, captions and mixed audio
+async fn render_body(
+    config: &Config,
+    layout: &TemplateLayout,
+    preset: &RenderPreset,
+    input: &RenderInput<'_>,
+    framing: &HashMap<PathBuf, Reframe>,
+    output: &Path,
+    encoder: VideoEncoder,
+) -> Result<(), String> {
+    let (width, height, fps) = (preset.width, preset.height, preset.fps);
+    let mut command = Command::new(config.ffmpeg());
+    command.args(["-hide_banner", "-y"]);
+    
+    // Input 0 is the narration; the rest are numbered as they are added
+    command.arg("-i").arg(input.narration);
+    let mut next_input = 1;
+    let mut filters = Vec::new();
+    // Scale to fill the frame, cropping where `framing` says for analyzed clips
+    let fill = |path: Option<&Path>| {
+        path.and_then(|p| framing.get(p))
+            .copied()
+            .unwrap_or_default()
+            .filter(width, height)
+    };
+    
+    if input.slides.is_empty() {
+        let background_path = || {
+            layout.background_path.as_deref()
+                .ok_or_else(|| "Render template has no background file".to_string())
+        };
+        match layout.background {
+            BackgroundKind::Color => {
+                command.args(["-f", "lavfi", "-i"]).arg(format!(
+                    "color=c={}:s={}x{}:r={}",
+                    ffmpeg_color(&layout.background_color), width, height, fps
+                ));
+            }
+            BackgroundKind::Gradient => {
+                command.args(["-f", "lavfi", "-i"]).arg(format!(
+                    "gradients=s={}x{}:r={}:c0={}:c1={}:nb_colors=2:x0=0:y0=0:x1=0:y1={}",
+                    width, height, fps,
+                    ffmpeg_color(&layout.background_color),
+                    ffmpeg_color(&layout.gradient_color),
+                    height
+                ));
+            }
+            BackgroundKind::Image => {
+                command.args(["-loop", "1", "-framerate", &fps.to_string(), "-i"]).arg(background_path()?);
+            }
+            BackgroundKind::Video => {
+                command.args(["-stream_loop", "-1", "-i"]).arg(background_path()?);
+            }
+        }
+        let video = match layout.background {
+            BackgroundKind::Video => layout.background_path.as_deref().map(Path::new),
+            _ => None,
+        };
+        filters.push(format!("[{next_input}:v]{},setsar=1,fps={fps}[bg]", fill(video)));
+        next_input += 1;
+    } else {
+        let mut labels = String::new();
+        for (i, slide) in input.slides.iter().enumerate() {
+            let filter = match slide.kind {
+                SlideKind::Image => {
+                    command.arg("-i").arg(&slide.path);
+                    ken_burns(preset, slide.duration, i % 2 == 1)
+                }
+                SlideKind::Video => {
+                    command
+                        .args(["-stream_loop", "-1", "-t", &format!("{:.3}", slide.duration), "-i"])
+                        .arg(&slide.path);
+                    format!("{},setsar=1,fps={fps}", fill(Some(&slide.path)))
+                }
+            };
+            // Stills and clips decode to different pixel formats; concat needs one
+            filters.push(format!("[{next_input}:v]{filter},format=yuv420p[s{i}]"));
+            labels.push_str(&format!("[s{i}]"));
+            next_input += 1;
+        }
+        // Hold the last slide until the narration ends; -shortest trims the rest
+        filters.push(format!(
+            "{labels}concat=n={}:v=1:a=0,tpad=stop_mode=clone:stop=-1[bg]",
+            input.slides.len()
+        ));
+    }
+    
+    let music_input = input.music.map(|music| {
+        command.args(["-stream_loop", "-1", "-i"]).arg(music);
+        next_input += 1;
+        next_input - 1
+    });
+    let first_effect_input = next_input;
+    for cue in input.effects {
+        command.arg("-i").arg(&cue.path);
+        next_input += 1;
+    }
+    let watermark_input = layout.watermark_path.as_ref().map(|watermark| {
+        command.arg("-i").arg(watermark);
+        next_input += 1;
+        next_input - 1
+    });
+    
+    let end_card = match &layout.end_card {
+        Some(card) => Some((card, probe_duration(config, input.narration).await?)),
+        None => None,
+    };
+    let end_card_input = end_card.and_then(|(card, _)| card.image_path.as_ref()).map(|image| {
+        command.arg("-i").arg(image);
+        next_input += 1;
+        next_input - 1
+    });
+    
+    let mut video_out = "[bg]".to_string();
+    let mut audio_out = "0:a".to_string();
+    
+    if let Some(index) = watermark_input {
+        filters.push(format!(
+            "[{index}:v]scale={}:-1,format=rgba,colorchannelmixer=aa={:.2}[wm];{video_out}[wm]overlay={}[vwm]",
+            layout.watermark_width * width / REFERENCE_WIDTH,
+            layout.watermark_opacity.clamp(0.0, 1.0),
+            layout.watermark_position.overlay_xy(width)
+        ));
+        video_out = "[vwm]".to_string();
+    }
+    if let Some(subtitles) = input.subtitles {
+        let path = captions::escape_filter_path(&subtitles.to_string_lossy());
+        filters.push(format!("{video_out}ass={}[vout]", path));
+        video_out = "[vout]".to_string();
+    }
+    let end_card_text = output.with_extension("endcard.txt");
+    if let Some((card, narration_secs)) = end_card {
+        let enable = format!("enable='gte(t,{:.3})'", (narration_secs - card.seconds).max(0.0));
+        if let Some(index) = end_card_input {
+            filters.push(format!(
+                "[{index}:v]scale={}:-1[ec];{video_out}[ec]overlay=(W-w)/2:H*0.3:{enable}[vec]",
+                width / 2
+            ));
+            video_out = "[vec]".to_string();
+        }
+        let text = config.voice.fill(&card.text);
+        if !text.trim().is_empty() {
+            // drawtext reads the text from a file, which sidesteps filter escaping
+            tokio::fs::write(&end_card_text, text.trim()).await.map_err(|e| e.to_string())?;
+            filters.push(format!(
+                "{video_out}drawtext=textfile={}:expansion=none:font='{}':fontsize={}:\
+                 fontcolor=white:borderw={}:bordercolor=black:x=(w-text_w)/2:y=h*0.2:{enable}[vcta]",
+                captions::escape_filter_path(&end_card_text.to_string_lossy()),
+                layout.caption_style(config).font.replace('\'', ""),
+                END_CARD_FONT_SIZE * width / REFERENCE_WIDTH,
+                (6 * width / REFERENCE_WIDTH).max(1),
+            ));
+            video_out = "[vcta]".to_string();
+        }
+    }
+    if let Some(index) = music_input {
+        filters.push(music::ducking_filter("0:a", &format!("{index}:a"), config.music_volume_db, "aout"));
+        audio_out = "[aout]".to_string();
+    }
+    if !input.effects.is_empty() {
+        let base = audio_out.trim_matches(['[', ']']).to_string();
+        filters.push(sfx::mix_filter(&base, input.effects, first_effect_input, "asfx"));
+        audio_out = "[asfx]".to_string();
+    }
+    
+    command
+        .arg("-filter_complex")
+        .arg(filters.join(";"))
+        .args(["-map", &video_out, "-map", &audio_out])
+        .args(encoder_args(encoder, preset))
+        .args(["-c:a", "aac", "-b:a", "192k", "-shortest", "-movflags", "+faststart"])
+        .arg(output);
+    let result = run(&mut command).await;
+    let _ = tokio::fs::remove_file(&end_card_text).await;
+    result.map(|_| ())
+}
+
+/// Join clips end to end, letterboxing each into the output frame.
+/// Clips without an audio track get silence so the concat stays in sync.
+async fn concat_clips(
+    config: &Config,
+    preset: &RenderPreset,
+    clips: &[PathBuf],
+    output: &Path,
+    encoder: VideoEncoder,
+) -> Result<(), String> {
+    let mut command = Command::new(config.ffmpeg());
+    command.args(["-hide_banner", "-y"]);
+    for clip in clips {
+        command.arg("-i").arg(clip);
+    }
+    
+    let mut filters = Vec::new();
+    let mut segments = String::new();
+    for (i, clip) in clips.iter().enumerate() {
+        filters.push(format!(
+            "[{i}:v]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps},format=yuv420p[v{i}]",
+            w = preset.width, h = preset.height, fps = preset.fps
+        ));
+        if has_audio(config, clip).await? {
+            filters.push(format!("[{i}:a]aformat=sample_rates=48000:channel_layouts=stereo[a{i}]"));
+        } else {
+            let duration = probe_duration(config, clip).await?;
+            filters.push(format!("anullsrc=r=48000:cl=stereo,atrim=duration={duration:.3}[a{i}]"));
+        }
+        segments.push_str(&format!("[v{i}][a{i}]"));
+    }
+    filters.push(format!("{segments}concat=n={}:v=1:a=1[vout][aout]", clips.len()));
+    
+    command
+        .arg("-filter_complex")
+        .arg(filters.join(";"))
+        .args(["-map", "[vout]", "-map", "[aout]"])
+        .args(encoder_args(encoder, preset))
+        .args(["-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart"])
+        .arg(output);
+    run(&mut command).await?;
+    Ok(())
+}
+
+/// Codec and rate control arguments for `encoder` at the preset's quality
+fn encoder_args(encoder: VideoEncoder, preset: &RenderPreset) -> Vec<String> {
+    let mut args: Vec<String> = vec!["-c:v".into(), encoder.as_str().into()];
+    let crf = preset.crf.to_string();
+    let bitrate = preset.bitrate_kbps.map(|kbps| format!("{}k", kbps));
+    let estimated = format!("{}k", preset.estimated_kbps());
+    let rate: Vec<&str> = match (encoder, bitrate.as_deref()) {
+        (VideoEncoder::Libx264, None) => vec!["-preset", &preset.speed, "-crf", &crf],
+        (VideoEncoder::Libx264, Some(b)) => vec!["-preset", &preset.speed, "-b:v", b],
+        (VideoEncoder::Nvenc, None) => vec!["-preset", "p5", "-rc", "vbr", "-cq", &crf, "-b:v", "0"],
+        (VideoEncoder::Nvenc, Some(b)) => vec!["-preset", "p5", "-b:v", b],
+        (VideoEncoder::Qsv, None) => vec!["-preset", "medium", "-global_quality", &crf],
+        (VideoEncoder::Qsv, Some(b)) => vec!["-preset", "medium", "-b:v", b],
+        (VideoEncoder::VideoToolbox, Some(b)) => vec!["-b:v", b],
+        // No constant-quality mode on Intel Macs, so derive a bitrate
+        (VideoEncoder::VideoToolbox, None) => vec!["-b:v", &estimated],
+    };
+    args.extend(rate.into_iter().map(String::from));
+    let pix_fmt = if encoder == VideoEncoder::Qsv { "nv12" } else { "yuv420p" };
+    args.extend(["-pix_fmt".to_string(), pix_fmt.to_string()]);
+    args
+}
+
+/// Run an encode with `encoder`, retrying with libx264 if a hardware
+/// encoder fails (missing driver, unsupported GPU, ...)
+async fn with_encoder_fallback<F, Fut>(encoder: VideoEncoder, encode: F) -> Result<(), String>
+where
+    F: Fn(VideoEncoder) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    match encode(encoder).await {
+        Err(e) if encoder != VideoEncoder::Libx264 => {
+            tracing::warn!("{} failed, falling back to libx264: {}", encoder.as_str(), e);
+            encode(VideoEncoder::Libx264).await
+        }
+        result => result,
+    }
+}
+
+/// Encoders this ffmpeg build lists and that can actually encode a test clip
+/// on this machine. libx264 is always first when available.
+pub async fn detect_encoders(config: &Config) -> Result<Vec<VideoEncoder>, String> {
+    let output = run(Command::new(config.ffmpeg()).args(["-hide_banner", "-encoders"])).await?;
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let listed: Vec<&str> = listing
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .collect();
+    
+    let mut available = Vec::new();
+    for encoder in VideoEncoder::ALL {
+        if !listed.contains(&encoder.as_str()) {
+            continue;
+        }
+        // Listed only means compiled in; hardware encoders also need a device
+        let probe = run(Command::new(config.ffmpeg())
+            .args(["-hide_banner", "-f", "lavfi", "-i", "color=s=256x256:d=0.2"])
+            .args(encoder_args(encoder, &RenderPreset::default()))
+            .args(["-f", "null", "-"]))
+        .await;
+        if probe.is_ok() {
+            available.push(encoder);
+        }
+    }
+    Ok(available)
+}
+
+/// Whether a media file has at least one audio stream
+async fn has_audio(config: &Config, path: &Path) -> Result<bool, String> {
+    let output = run(Command::new(config.ffprobe())
+        .args(["-v", "error", "-select_streams", "a", "-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(path))
+    .await?;
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+/// What `fit_length` did to a short
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthCheck {
+    /// Within the limit as rendered
+    Fits,
+    /// Trailing silence was cut, leaving this many seconds
+    Trimmed(f64),
+    /// Sped up by the factor, leaving the given seconds
+    SpedUp { factor: f64, duration: f64 },
+    /// Still over the limit with every fix `length_fix` allows
+    TooLong,
+}
+
+/// Bring the short at `video`, `duration` seconds long, within
+/// `max_short_secs` the way `length_fix` allows, replacing the file. Cuts
+/// the silence after the last word first, then speeds what is left up by
+/// at most `MAX_SPEEDUP`.
+pub async fn fit_length(config: &Config, preset: &RenderPreset, video: &Path, duration: f64) -> Result<LengthCheck, String> {
+    let limit = config.max_short_secs as f64;
+    if duration <= limit {
+        return Ok(LengthCheck::Fits);
+    }
+    if config.length_fix == LengthFix::Fail {
+        return Ok(LengthCheck::TooLong);
+    }
+    let target = limit - LENGTH_MARGIN_SECS;
+    let silence = audio::trailing_silence_start(config.ffmpeg(), video, duration, SILENCE_NOISE_DB, SILENCE_MIN_SECS).await?;
+    let end = silence.map_or(duration, |start| (start + SILENCE_PAD_SECS).min(duration));
+    let factor = end / target;
+    let fitted = video.with_extension("fit.mp4");
+    let check = if end <= target {
+        trim(config, video, end, &fitted).await?;
+        LengthCheck::Trimmed(probe_duration(config, &fitted).await?)
+    } else if config.length_fix == LengthFix::Auto && factor <= MAX_SPEEDUP {
+        let encoder = preset.encoder.unwrap_or(config.video_encoder);
+        with_encoder_fallback(encoder, |encoder| speed_up(config, preset, video, end, factor, &fitted, encoder)).await?;
+        LengthCheck::SpedUp { factor, duration: probe_duration(config, &fitted).await? }
+    } else {
+        return Ok(LengthCheck::TooLong);
+    };
+    tokio::fs::rename(&fitted, video).await.map_err(|e| e.to_string())?;
+    Ok(check)
+}
+
+/// The first `seconds` of `input`, streams copied
+async fn trim(config: &Config, input: &Path, seconds: f64, output: &Path) -> Result<(), String> {
+    run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-y", "-i"])
+        .arg(input)
+        .args(["-t", &format!("{:.3}", seconds), "-c", "copy", "-movflags", "+faststart"])
+        .arg(output))
+    .await?;
+    Ok(())
+}
+
+/// The first `seconds` of `input` played `factor` times as fast, pitch
+/// kept, re-encoded in the preset's format
+async fn speed_up(
+    config: &Config,
+    preset: &RenderPreset,
+    input: &Path,
+    seconds: f64,
+    factor: f64,
+    output: &Path,
+    encoder: VideoEncoder,
+) -> Result<(), String> {
+    let filter = format!("[0:v]setpts=PTS/{f:.4}[v];[0:a]atempo={f:.4}[a]", f = factor);
+    run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-y", "-t", &format!("{:.3}", seconds), "-i"])
+        .arg(input)
+        .args(["-filter_complex", &filter, "-map", "[v]", "-map", "[a]", "-r", &preset.fps.to_string()])
+        .args(encoder_args(encoder, preset))
+        .args(["-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart"])
+        .arg(output))
+    .await?;
+    Ok(())
+}
+
+/// Container duration in seconds, via ffprobe
+pub async fn probe_duration(config: &Config, path: &Path) -> Result<f64, String> {
+    let output = run(Command::new(config.ffprobe())
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path))
+    .await?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.trim()
+        .parse()
+        .map_err(|_| format!("ffprobe returned an invalid duration: {}", text.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::scratch_dir;
+
+    #[test]
+    fn setting_the_end_card_image_turns_the_card_on() {
+        let mut layout = TemplateLayout::default();
+        layout.set_asset(TemplateAsset::EndCardImage, None);
+        assert!(layout.end_card.is_none());
+
+        layout.set_asset(TemplateAsset::EndCardImage, Some("card.png".to_string()));
+        let card = layout.end_card.as_ref().unwrap();
+        assert_eq!(card.text, EndCard::default().text);
+        assert_eq!(layout.asset(TemplateAsset::EndCardImage), Some("card.png"));
+
+        // Dropping the image keeps the card and its text
+        layout.set_asset(TemplateAsset::EndCardImage, None);
+        assert!(layout.end_card.is_some());
+        assert_eq!(layout.asset(TemplateAsset::EndCardImage), None);
+    }
+
+    #[test]
+    fn replacing_an_asset_removes_the_old_copy() {
+        let root = scratch_dir("template-assets");
+        let dir = root.join("templates").join("1");
+        std::fs::create_dir_all(&root).unwrap();
+        let png = root.join("logo.PNG");
+        let jpg = root.join("logo.jpg");
+        std::fs::write(&png, b"png").unwrap();
+        std::fs::write(&jpg, b"jpg").unwrap();
+
+        let mut layout = TemplateLayout::default();
+        let first = layout.store_asset(&dir, TemplateAsset::Watermark, Some(&png)).unwrap().unwrap();
+        assert_eq!(Path::new(&first), dir.join("watermark.png"));
+        assert_eq!(std::fs::read(&first).unwrap(), b"png");
+
+        let second = layout.store_asset(&dir, TemplateAsset::Watermark, Some(&jpg)).unwrap().unwrap();
+        assert_eq!(Path::new(&second), dir.join("watermark.jpg"));
+        assert!(!Path::new(&first).exists());
+        assert_eq!(layout.asset(TemplateAsset::Watermark), Some(second.as_str()));
+
+        // Storing the copy again must not truncate or remove it
+        layout.store_asset(&dir, TemplateAsset::Watermark, Some(Path::new(&second))).unwrap();
+        assert_eq!(std::fs::read(&second).unwrap(), b"jpg");
+
+        assert_eq!(layout.store_asset(&dir, TemplateAsset::Watermark, None).unwrap(), None);
+        assert!(!Path::new(&second).exists());
+        assert!(png.exists() && jpg.exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn an_asset_outside_the_template_folder_is_kept() {
+        let root = scratch_dir("template-assets");
+        let dir = root.join("templates").join("1");
+        std::fs::create_dir_all(&root).unwrap();
+        let outside = root.join("intro.mp4");
+        let replacement = root.join("new-intro.mov");
+        std::fs::write(&outside, b"old").unwrap();
+        std::fs::write(&replacement, b"new").unwrap();
+
+        let mut layout = TemplateLayout { intro_path: Some(outside.to_string_lossy().to_string()), ..Default::default() };
+        layout.store_asset(&dir, TemplateAsset::Intro, Some(&replacement)).unwrap();
+        assert!(outside.exists());
+        assert_eq!(layout.asset(TemplateAsset::Intro).map(PathBuf::from), Some(dir.join("intro.mov")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
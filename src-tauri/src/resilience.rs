@@ -0,0 +1,240 @@
+// Per-service rate limits and circuit breakers for the external services
+use crate::http;
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Consecutive failures that open a service's breaker
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open breaker fails calls fast before letting one through
+const OPEN_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Service {
+    YouTube,
+    Drive,
+    Telegram,
+    Ollama,
+    Tts,
+}
+
+impl Service {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Service::YouTube => "youtube",
+            Service::Drive => "drive",
+            Service::Telegram => "telegram",
+            Service::Ollama => "ollama",
+            Service::Tts => "tts",
+        }
+    }
+    
+    /// Sustained requests per second and the burst allowed on top
+    fn rate_limit(&self) -> (f64, f64) {
+        match self {
+            Service::YouTube => (5.0, 10.0),
+            Service::Drive => (5.0, 10.0),
+            // Bots may send about one message a second to a chat
+            Service::Telegram => (1.0, 5.0),
+            Service::Ollama => (2.0, 4.0),
+            Service::Tts => (3.0, 5.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Breaker {
+    Closed,
+    /// Failing fast until the instant
+    Open(Instant),
+    /// One trial call is in flight; its result closes or reopens the breaker
+    HalfOpen,
+}
+
+struct ServiceState {
+    tokens: f64,
+    refilled_at: Instant,
+    failures: u32,
+    breaker: Breaker,
+}
+
+fn states() -> &'static Mutex<HashMap<Service, ServiceState>> {
+    static STATES: OnceLock<Mutex<HashMap<Service, ServiceState>>> = OnceLock::new();
+    STATES.get_or_init(Default::default)
+}
+
+/// Handle events are emitted through; set once at startup
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Let breaker changes be reported to the UI as `service:degraded` and
+/// `service:recovered` events
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn with_state<T>(service: Service, f: impl FnOnce(&mut ServiceState) -> T) -> T {
+    let mut states = states().lock().unwrap_or_else(|e| e.into_inner());
+    let state = states.entry(service).or_insert_with(|| ServiceState {
+        tokens: service.rate_limit().1,
+        refilled_at: Instant::now(),
+        failures: 0,
+        breaker: Breaker::Closed,
+    });
+    f(state)
+}
+
+/// Whether calls to `service` are currently failing fast
+pub fn is_open(service: Service) -> bool {
+    with_state(service, |state| match state.breaker {
+        Breaker::Open(until) => Instant::now() < until,
+        Breaker::HalfOpen => true,
+        Breaker::Closed => false,
+    })
+}
+
+/// Send an HTTP request to `service`, through `http::send`. Connection
+/// errors, timeouts, 429 and 5xx responses count as failures; other error
+/// statuses are the caller's problem and count as the service working.
+pub async fn send(service: Service, request: RequestBuilder) -> Result<Response, String> {
+    let _admitted = admit(service).await?;
+    let result = http::send(request).await;
+    match &result {
+        Ok(response) if response.status().is_server_error() || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+            record_failure(service, &format!("{} returned {}", service.as_str(), response.status()))
+        }
+        Ok(_) => record_success(service),
+        Err(e) if e.is_connect() || e.is_timeout() => record_failure(service, &e.to_string()),
+        Err(_) => record_success(service),
+    }
+    result.map_err(|e| e.to_string())
+}
+
+/// Run `work` against `service`; any error counts as a failure. For services
+/// not reached over HTTP, such as the edge-tts process.
+pub async fn call<T>(service: Service, work: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    let _admitted = admit(service).await?;
+    let result = work.await;
+    match &result {
+        Ok(_) => record_success(service),
+        Err(e) => record_failure(service, e),
+    }
+    result
+}
+
+/// Held for the length of an admitted call. A trial call dropped before
+/// its result was recorded, say by a stage timeout or shutdown, leaves the
+/// breaker open but ready to let the next call through.
+struct Admitted {
+    service: Service,
+    trial: bool,
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        if self.trial {
+            with_state(self.service, |state| {
+                if state.breaker == Breaker::HalfOpen {
+                    state.breaker = Breaker::Open(Instant::now());
+                }
+            });
+        }
+    }
+}
+
+/// Fail fast while the breaker is open, then wait for a rate limit token
+async fn admit(service: Service) -> Result<Admitted, String> {
+    let now = Instant::now();
+    let trial = with_state(service, |state| match state.breaker {
+        Breaker::Closed => Ok(false),
+        Breaker::Open(until) if now >= until => {
+            state.breaker = Breaker::HalfOpen;
+            Ok(true)
+        }
+        Breaker::Open(until) => Err(format!(
+            "{} is unavailable after repeated failures; retrying in {} s",
+            service.as_str(),
+            until.duration_since(now).as_secs() + 1
+        )),
+        Breaker::HalfOpen => Err(format!("{} is unavailable; checking whether it is back", service.as_str())),
+    })?;
+    let admitted = Admitted { service, trial };
+    
+    loop {
+        let wait = with_state(service, |state| {
+            let (rate, burst) = service.rate_limit();
+            let now = Instant::now();
+            state.tokens = (state.tokens + now.duration_since(state.refilled_at).as_secs_f64() * rate).min(burst);
+            state.refilled_at = now;
+            if state.tokens >= 1.0 {
+                state.tokens -= 1.0;
+                None
+            } else {
+                Some(Duration::from_secs_f64((1.0 - state.tokens) / rate))
+            }
+        });
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => return Ok(admitted),
+        }
+    }
+}
+
+fn record_success(service: Service) {
+    let recovered = with_state(service, |state| {
+        state.failures = 0;
+        std::mem::replace(&mut state.breaker, Breaker::Closed) != Breaker::Closed
+    });
+    if recovered {
+        tracing::info!("{} is working again", service.as_str());
+        if let Some(app) = APP.get() {
+            let _ = app.emit("service:recovered", serde_json::json!({ "service": service }));
+        }
+    }
+}
+
+fn record_failure(service: Service, error: &str) {
+    let tripped = with_state(service, |state| {
+        state.failures += 1;
+        let trip = state.breaker == Breaker::HalfOpen || (state.breaker == Breaker::Closed && state.failures >= FAILURE_THRESHOLD);
+        if trip {
+            state.breaker = Breaker::Open(Instant::now() + OPEN_DURATION);
+        }
+        trip.then_some(state.failures)
+    });
+    if let Some(failures) = tripped {
+        tracing::warn!("{} failed {} times in a row, pausing calls: {}", service.as_str(), failures, error);
+        if let Some(app) = APP.get() {
+            let _ = app.emit("service:degraded", serde_json::json!({
+                "service": service,
+                "failures": failures,
+                "retry_in_secs": OPEN_DURATION.as_secs(),
+                "error": error,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn a_dropped_trial_call_lets_the_next_one_through() {
+        let service = Service::Tts;
+        with_state(service, |state| state.breaker = Breaker::Open(Instant::now()));
+        let trial = admit(service).await.unwrap();
+        assert!(admit(service).await.is_err());
+        drop(trial);
+        
+        let retried = admit(service).await.unwrap();
+        record_success(service);
+        drop(retried);
+        assert!(!is_open(service));
+    }
+}
@@ -0,0 +1,179 @@
+// Scene segmentation: splitting a short's narration into timed visual scenes
+use crate::cache::{self, CacheKind};
+use crate::clients::Clients;
+use crate::db::{Config, Database, ImageBackend, Scene, StockProvider};
+use crate::image_gen;
+use crate::llm::{LlmClient, SamplingParams};
+use crate::render::{Slide, SlideKind};
+use crate::stock;
+use crate::timing::{self, SpeechTiming, TimedText};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Speaking rate used to size scenes before narration exists
+const WORDS_PER_SECOND: f64 = 2.5;
+
+/// Scenes shorter than this are merged with the next sentence
+const MIN_SCENE_SECS: f64 = 4.0;
+
+const DESCRIBE_TEMPLATE: &str = "Below are the numbered scenes of a vertical YouTube Short \
+narration. For each scene, write a Stable Diffusion prompt for a background image \
+(comma-separated visual descriptors: subject, setting, lighting, style; no text, logos or real \
+people's names) and a one- or two-word stock footage search term.\n\
+Reply with only a JSON array holding one object per scene, in order: \
+[{\"visual_prompt\": \"...\", \"stock_keyword\": \"...\"}]\n\n{scenes}";
+
+#[derive(Deserialize)]
+struct SceneVisual {
+    visual_prompt: String,
+    stock_keyword: String,
+}
+
+/// Whether any backend is configured to produce per-scene visuals
+pub fn visuals_enabled(config: &Config) -> bool {
+    config.image_backend != ImageBackend::Disabled || config.stock_provider != StockProvider::Disabled
+}
+
+/// Group narration sentences into spans of at least `MIN_SCENE_SECS`
+fn split(timing: &SpeechTiming) -> Vec<TimedText> {
+    let mut spans: Vec<TimedText> = Vec::new();
+    for sentence in &timing.sentences {
+        match spans.last_mut() {
+            Some(span) if span.end - span.start < MIN_SCENE_SECS => merge(span, sentence),
+            _ => spans.push(sentence.clone()),
+        }
+    }
+    // A short trailing span reads better folded into the previous one
+    if spans.len() > 1 && spans.last().is_some_and(|s| s.end - s.start < MIN_SCENE_SECS) {
+        let last = spans.pop().unwrap();
+        merge(spans.last_mut().unwrap(), &last);
+    }
+    spans
+}
+
+fn merge(span: &mut TimedText, next: &TimedText) {
+    span.text.push(' ');
+    span.text.push_str(&next.text);
+    span.end = next.end;
+}
+
+/// Split a script into scenes. Uses the narration timing when the short has
+/// audio, otherwise sizes scenes from the word count. Visual prompts and stock
+/// keywords come from the LLM, falling back to the scene's own keywords.
+pub async fn segment(clients: &Clients, config: &Config, short_id: i64, script: &str, timing: Option<&SpeechTiming>) -> Vec<Scene> {
+    let estimated;
+    let timing = match timing {
+        Some(t) => t,
+        None => {
+            let words = script.split_whitespace().count() as f64;
+            estimated = timing::estimate(script, words / WORDS_PER_SECOND);
+            &estimated
+        }
+    };
+    let spans = split(timing);
+    
+    let visuals = describe(clients, config, &spans).await.unwrap_or_else(|e| {
+        tracing::warn!("Scene descriptions failed for short {}, using keywords: {}", short_id, e);
+        spans.iter()
+            .map(|span| {
+                let keyword = stock::keywords(&span.text, 2).join(" ");
+                SceneVisual { visual_prompt: span.text.clone(), stock_keyword: keyword }
+            })
+            .collect()
+    });
+    
+    spans.iter()
+        .zip(visuals)
+        .enumerate()
+        .map(|(i, (span, visual))| {
+            // Each scene runs until the next one starts, so pauses are covered
+            let start = if i == 0 { 0.0 } else { span.start };
+            let end = spans.get(i + 1).map(|next| next.start).unwrap_or(span.end);
+            Scene {
+                id: None,
+                short_id,
+                position: i as i64,
+                text: span.text.clone(),
+                start_sec: start,
+                duration_sec: (end - start).max(0.5),
+                visual_prompt: visual.visual_prompt,
+                stock_keyword: visual.stock_keyword,
+                media_path: None,
+            }
+        })
+        .collect()
+}
+
+/// Ask the LLM for one visual prompt and stock keyword per span
+async fn describe(clients: &Clients, config: &Config, spans: &[TimedText]) -> Result<Vec<SceneVisual>, String> {
+    let numbered: Vec<String> = spans.iter()
+        .enumerate()
+        .map(|(i, span)| format!("{}. {}", i + 1, span.text))
+        .collect();
+    let prompt = DESCRIBE_TEMPLATE.replace("{scenes}", &numbered.join("\n"));
+    let params = SamplingParams {
+        temperature: Some(0.7),
+        ..Default::default()
+    };
+    let response = clients.llm(config)?.complete(&prompt, &params).await?;
+    
+    // Models like to wrap JSON in prose or code fences
+    let json = response.find('[')
+        .zip(response.rfind(']'))
+        .map(|(start, end)| &response[start..=end])
+        .ok_or("LLM reply contains no JSON array")?;
+    let visuals: Vec<SceneVisual> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if visuals.len() != spans.len() {
+        return Err(format!("LLM described {} scenes, expected {}", visuals.len(), spans.len()));
+    }
+    Ok(visuals)
+}
+
+/// Image or clip for a scene: a generated image when an image backend is
+/// configured, otherwise stock footage for the scene's keyword. Both come
+/// from the media cache when the same visual was fetched before.
+pub async fn fetch_media(config: &Config, db: &Database, app_dir: &Path, scene: &Scene) -> Result<Option<PathBuf>, String> {
+    if config.image_backend != ImageBackend::Disabled {
+        let key = image_gen::cache_key(config, &scene.visual_prompt);
+        if let Some(cached) = cache::lookup(db, CacheKind::Image, &key)? {
+            return Ok(Some(cached.path));
+        }
+        let path = cache::scratch_path(app_dir, CacheKind::Image, "png").await?;
+        image_gen::generate(config, &scene.visual_prompt, &path).await?;
+        return cache::store(db, config, app_dir, CacheKind::Image, &key, &path, None).await.map(Some);
+    }
+    stock::fetch_clip(config, db, app_dir, &scene.stock_keyword).await
+}
+
+/// Slides for the renderer. Scenes without media repeat the previous visual;
+/// empty when no scene has any.
+pub fn slides(scenes: &[Scene]) -> Vec<Slide> {
+    let mut slides: Vec<Slide> = Vec::new();
+    for scene in scenes {
+        match &scene.media_path {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                let kind = if path.extension().is_some_and(|e| e == "png") {
+                    SlideKind::Image
+                } else {
+                    SlideKind::Video
+                };
+                slides.push(Slide { path, kind, duration: scene.duration_sec });
+            }
+            None => {
+                if let Some(previous) = slides.last_mut() {
+                    previous.duration += scene.duration_sec;
+                }
+            }
+        }
+    }
+    // Leading scenes without media go to the first visual
+    if let Some(first) = slides.first_mut() {
+        let covered: f64 = scenes.iter()
+            .take_while(|s| s.media_path.is_none())
+            .map(|s| s.duration_sec)
+            .sum();
+        first.duration += covered;
+    }
+    slides
+}
@@ -0,0 +1,231 @@
+// Posting schedule and pipeline run windows, in the machine's local time
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Slots closer than this are skipped; YouTube rejects a `publishAt` that is
+/// already in the past by the time the upload finishes
+const MIN_LEAD: Duration = Duration::minutes(30);
+
+/// How far ahead free slots are searched for
+const HORIZON_DAYS: i64 = 60;
+
+/// Daily times at which approved shorts are published
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostingSchedule {
+    /// Assign slots automatically; otherwise shorts without `publish_at`
+    /// go public on upload
+    pub enabled: bool,
+    /// Local times of day, "HH:MM"
+    pub slots: Vec<String>,
+}
+
+impl Default for PostingSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            slots: vec!["09:00".to_string(), "13:00".to_string(), "19:00".to_string()],
+        }
+    }
+}
+
+impl PostingSchedule {
+    /// Slot times of day, sorted; unparseable entries are ignored
+    fn times(&self) -> Vec<NaiveTime> {
+        let mut times: Vec<NaiveTime> = self.slots
+            .iter()
+            .filter_map(|s| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok())
+            .collect();
+        times.sort();
+        times.dedup();
+        times
+    }
+    
+    /// Slot instants from `from` up to `until`, in order
+    pub fn slots_between(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let times = self.times();
+        let mut slots = Vec::new();
+        let mut day = from.with_timezone(&Local).date_naive();
+        while let Some(start) = Local.from_local_datetime(&day.and_time(NaiveTime::MIN)).earliest() {
+            if start.with_timezone(&Utc) > until {
+                break;
+            }
+            for time in &times {
+                // A slot that falls in a DST gap doesn't exist that day
+                let Some(slot) = Local.from_local_datetime(&day.and_time(*time)).earliest() else {
+                    continue;
+                };
+                let slot = slot.with_timezone(&Utc);
+                if slot >= from && slot <= until {
+                    slots.push(slot);
+                }
+            }
+            let Some(next) = day.succ_opt() else { break };
+            day = next;
+        }
+        slots
+    }
+    
+    /// Earliest slot from now on that isn't in `taken`
+    pub fn next_free_slot(&self, taken: &[DateTime<Utc>]) -> Option<DateTime<Utc>> {
+        let from = Utc::now() + MIN_LEAD;
+        self.slots_between(from, from + Duration::days(HORIZON_DAYS))
+            .into_iter()
+            .find(|slot| !taken.contains(slot))
+    }
+}
+
+/// Hours of the week in which trends are fetched and jobs run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunWindows {
+    /// Only run inside `windows`; otherwise run at any time
+    pub enabled: bool,
+    pub windows: Vec<RunWindow>,
+}
+
+/// A daily span of local time. An `end` at or before `start` runs past
+/// midnight into the next day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunWindow {
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+    /// Days the window starts on, such as "mon" or "saturday"; empty for every day
+    pub days: Vec<String>,
+}
+
+impl Default for RunWindow {
+    fn default() -> Self {
+        Self { start: "01:00".to_string(), end: "07:00".to_string(), days: Vec::new() }
+    }
+}
+
+impl RunWindow {
+    /// Whether `at` falls in the window; a window that doesn't parse never does
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let parse = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+        let (Some(start), Some(end)) = (parse(&self.start), parse(&self.end)) else {
+            return false;
+        };
+        let time = at.time();
+        if start < end {
+            self.runs_on(at.weekday()) && time >= start && time < end
+        } else {
+            (self.runs_on(at.weekday()) && time >= start) || (self.runs_on(at.weekday().pred()) && time < end)
+        }
+    }
+    
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.iter().any(|d| d.trim().parse::<Weekday>() == Ok(day))
+    }
+}
+
+impl RunWindows {
+    /// Whether the pipeline may run at local time `at`
+    pub fn allows(&self, at: NaiveDateTime) -> bool {
+        !self.enabled || self.windows.iter().any(|window| window.contains(at))
+    }
+    
+    /// Whether the pipeline may run now
+    pub fn allows_now(&self) -> bool {
+        self.allows(Local::now().naive_local())
+    }
+    
+    /// Reject windows with a time or day name that doesn't parse, which
+    /// would otherwise never match
+    pub fn validate(&self) -> Result<(), String> {
+        for window in &self.windows {
+            for time in [&window.start, &window.end] {
+                if NaiveTime::parse_from_str(time.trim(), "%H:%M").is_err() {
+                    return Err(format!("Run window time '{}' is not HH:MM", time));
+                }
+            }
+            if let Some(day) = window.days.iter().find(|d| d.trim().parse::<Weekday>().is_err()) {
+                return Err(format!("Run window day '{}' is not a day of the week", day));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // October 2026 starts on a Thursday; the 5th is a Monday
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+    
+    fn windows(windows: Vec<RunWindow>) -> RunWindows {
+        RunWindows { enabled: true, windows }
+    }
+    
+    fn local(day: u32, time: &str) -> DateTime<Utc> {
+        Local.from_local_datetime(&at(day, time)).earliest().unwrap().with_timezone(&Utc)
+    }
+    
+    #[test]
+    fn slots_come_in_order_every_day() {
+        let schedule = PostingSchedule {
+            enabled: true,
+            slots: ["19:00", "09:00", "not a time", " 09:00 "].map(String::from).to_vec(),
+        };
+        assert_eq!(
+            schedule.slots_between(local(5, "10:00"), local(7, "09:00")),
+            vec![local(5, "19:00"), local(6, "09:00"), local(6, "19:00"), local(7, "09:00")]
+        );
+    }
+    
+    #[test]
+    fn next_free_slot_skips_taken_and_imminent_slots() {
+        let schedule = PostingSchedule::default();
+        let first = schedule.next_free_slot(&[]).unwrap();
+        assert!(first >= Utc::now() + MIN_LEAD - Duration::seconds(1));
+        let second = schedule.next_free_slot(&[first]).unwrap();
+        assert!(second > first);
+        let third = schedule.slots_between(second + Duration::seconds(1), second + Duration::days(1))[0];
+        assert_eq!(schedule.next_free_slot(&[first, second]), Some(third));
+        
+        let unparseable = PostingSchedule { enabled: true, slots: vec!["noon".to_string()] };
+        assert_eq!(unparseable.next_free_slot(&[]), None);
+    }
+    
+    #[test]
+    fn night_window_runs_past_midnight() {
+        let night = windows(vec![RunWindow { start: "22:00".into(), end: "06:00".into(), days: Vec::new() }]);
+        assert!(night.allows(at(5, "23:30")));
+        assert!(night.allows(at(6, "05:59")));
+        assert!(!night.allows(at(6, "06:00")));
+        assert!(!night.allows(at(6, "12:00")));
+        assert!(RunWindows::default().allows(at(6, "12:00")));
+    }
+    
+    #[test]
+    fn weekday_window_skips_weekends() {
+        let weekdays = windows(vec![RunWindow {
+            start: "01:00".into(),
+            end: "07:00".into(),
+            days: ["mon", "tue", "wed", "thu", "fri"].map(String::from).to_vec(),
+        }]);
+        assert!(weekdays.allows(at(9, "03:00")));
+        assert!(!weekdays.allows(at(10, "03:00")));
+        assert!(!weekdays.allows(at(9, "08:00")));
+    }
+    
+    #[test]
+    fn windows_with_unknown_days_or_times_are_rejected() {
+        let window = |start: &str, days: &[&str]| RunWindow {
+            start: start.into(),
+            end: "07:00".into(),
+            days: days.iter().map(|d| d.to_string()).collect(),
+        };
+        assert!(windows(vec![window("01:00", &["mon", "Saturday"])]).validate().is_ok());
+        assert!(windows(vec![window("01:00", &["mon", "funday"])]).validate().is_err());
+        assert!(windows(vec![window("1am", &[])]).validate().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+// Delivery of finished shorts to the user's own server over SFTP
+use crate::transfers::{self, Direction};
+use base64::Engine;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use ssh2::{HashType, Session, Sftp};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Keychain service the password or key passphrase is stored under
+const KEYCHAIN_SERVICE: &str = "shotauto";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Bytes per SFTP write; the upload cap is applied after each
+const WRITE_CHUNK: usize = 64 * 1024;
+
+/// Server shorts are copied to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SftpTarget {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Private key file; without one the keychain secret is the password
+    pub key_path: Option<String>,
+    /// Directory each short goes to, with `{date}` and `{short_id}` filled in
+    pub remote_dir: String,
+    /// `SHA256:...` fingerprint of the server's host key, pinned by the first
+    /// successful `test_sftp` and cleared by `forget_sftp_host_key`
+    pub host_key: Option<String>,
+}
+
+impl Default for SftpTarget {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 22,
+            username: String::new(),
+            key_path: None,
+            remote_dir: "shorts/{date}/{short_id}".to_string(),
+            host_key: None,
+        }
+    }
+}
+
+impl SftpTarget {
+    pub fn is_configured(&self) -> bool {
+        self.enabled && !self.host.trim().is_empty() && !self.username.trim().is_empty()
+    }
+    
+    /// `user@host:port`, as publications record it
+    pub fn destination(&self) -> String {
+        format!("{}@{}:{}", self.username, self.host, self.port)
+    }
+    
+    /// The remote directory for a short delivered on `date`, always
+    /// '/'-separated whatever the local OS. Relative paths are under the
+    /// login directory; `..` is refused.
+    pub fn remote_dir(&self, date: NaiveDate, short_id: i64) -> Result<String, String> {
+        let dir = self.remote_dir
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{short_id}", &short_id.to_string());
+        let dir = dir.trim();
+        let segments: Vec<&str> = dir.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+        if segments.contains(&"..") {
+            return Err(format!("SFTP directory {} may not contain ..", dir));
+        }
+        let joined = segments.join("/");
+        Ok(if dir.starts_with('/') { format!("/{}", joined) } else { joined })
+    }
+    
+    fn keychain_user(&self) -> String {
+        format!("sftp:{}", self.destination())
+    }
+}
+
+/// The password, or the private key's passphrase, from the OS keychain
+pub fn secret(target: &SftpTarget) -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, &target.keychain_user()).map_err(|e| e.to_string())?;
+    match entry.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read the SFTP credentials from the keychain: {}", e)),
+    }
+}
+
+/// Store or, with `None`, forget the keychain secret for `target`
+pub fn set_secret(target: &SftpTarget, secret: Option<&str>) -> Result<(), String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, &target.keychain_user()).map_err(|e| e.to_string())?;
+    let result = match secret {
+        Some(secret) => entry.set_password(secret),
+        None => match entry.delete_password() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        },
+    };
+    result.map_err(|e| format!("Failed to save the SFTP credentials to the keychain: {}", e))
+}
+
+/// Outcome of `test_sftp`
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpTest {
+    /// Fingerprint of the server's host key
+    pub host_key: String,
+    /// Where relative remote directories start
+    pub home: String,
+}
+
+/// A logged-in SFTP session
+pub struct Connection {
+    sftp: Sftp,
+    host_key: String,
+    // The channel needs the session alive
+    _session: Session,
+}
+
+/// Connect and log in. A pinned host key has to match; without one, any key
+/// is accepted and reported for pinning. Blocking.
+pub fn connect(target: &SftpTarget) -> Result<Connection, String> {
+    let address = (target.host.trim(), target.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", target.host, e))?
+        .next()
+        .ok_or_else(|| format!("{} has no address", target.host))?;
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to {}: {}", target.destination(), e))?;
+    let mut session = Session::new().map_err(|e| e.to_string())?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+    session.handshake().map_err(|e| format!("SSH handshake with {} failed: {}", target.host, e))?;
+    
+    let host_key = session
+        .host_key_hash(HashType::Sha256)
+        .map(fingerprint)
+        .ok_or("Server sent no host key")?;
+    if let Some(ref pinned) = target.host_key {
+        if *pinned != host_key {
+            return Err(format!(
+                "Host key of {} changed from {} to {}; if this is expected, forget the old key and test the connection again",
+                target.host, pinned, host_key
+            ));
+        }
+    }
+    
+    let secret = secret(target)?;
+    match target.key_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(key) => session.userauth_pubkey_file(&target.username, None, Path::new(key), secret.as_deref()),
+        None => {
+            let password = secret.ok_or("No SFTP password is stored in the keychain")?;
+            session.userauth_password(&target.username, &password)
+        }
+    }
+    .map_err(|e| format!("SFTP login as {} failed: {}", target.username, e))?;
+    if !session.authenticated() {
+        return Err(format!("SFTP login as {} failed", target.username));
+    }
+    let sftp = session.sftp().map_err(|e| format!("Server has no SFTP subsystem: {}", e))?;
+    Ok(Connection { sftp, host_key, _session: session })
+}
+
+/// Log in and resolve the home directory. Blocking.
+pub fn test(target: &SftpTarget) -> Result<SftpTest, String> {
+    let connection = connect(target)?;
+    let home = connection.sftp.realpath(Path::new(".")).map_err(|e| e.to_string())?;
+    Ok(SftpTest {
+        host_key: connection.host_key,
+        home: home.to_string_lossy().to_string(),
+    })
+}
+
+/// OpenSSH-style `SHA256:<base64>` fingerprint
+fn fingerprint(hash: &[u8]) -> String {
+    format!("SHA256:{}", base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash))
+}
+
+impl Connection {
+    /// Upload `files`, as (local path, remote name), into `dir`, creating it.
+    /// Each file is written under a temporary name and renamed when complete.
+    /// Blocking; `runtime` paces the writes to the upload cap.
+    pub fn upload(
+        &self,
+        runtime: &tokio::runtime::Handle,
+        limit: Option<u64>,
+        dir: &str,
+        files: &[(PathBuf, String)],
+    ) -> Result<(), String> {
+        self.create_dir_all(dir)?;
+        for (local, name) in files {
+            let dest = remote_join(dir, name);
+            let partial = remote_join(dir, &format!("{}.part", name));
+            let (dest, partial) = (Path::new(&dest), Path::new(&partial));
+            let mut source = File::open(local).map_err(|e| format!("Failed to open {}: {}", local.display(), e))?;
+            let total = source.metadata().map(|m| m.len()).ok();
+            let transfer = transfers::start(Direction::Upload, name.clone(), total);
+            let mut remote = self.sftp.create(partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+            let mut buf = vec![0u8; WRITE_CHUNK];
+            loop {
+                let n = source.read(&mut buf).map_err(|e| e.to_string())?;
+                if n == 0 {
+                    break;
+                }
+                remote.write_all(&buf[..n]).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+                runtime.block_on(transfer.advance(n as u64, limit));
+            }
+            drop(remote);
+            // SFTP v3 servers won't rename over an existing file
+            let _ = self.sftp.unlink(dest);
+            self.sftp.rename(partial, dest, None).map_err(|e| format!("Failed to rename {}: {}", partial.display(), e))?;
+        }
+        Ok(())
+    }
+    
+    fn create_dir_all(&self, dir: &str) -> Result<(), String> {
+        let mut path = if dir.starts_with('/') { "/".to_string() } else { String::new() };
+        for segment in dir.split('/').filter(|s| !s.is_empty()) {
+            path = remote_join(&path, segment);
+            if self.sftp.stat(Path::new(&path)).is_err() {
+                self.sftp.mkdir(Path::new(&path), 0o755).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `name` inside the remote directory `dir`. Remote paths are joined with
+/// '/' rather than `Path::join`, which uses backslashes on Windows.
+fn remote_join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn fills_in_the_remote_dir() {
+        let target = SftpTarget {
+            host: "example.com".to_string(),
+            username: "deploy".to_string(),
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(target.remote_dir(date, 42).unwrap(), "shorts/2026-03-09/42");
+        assert_eq!(target.destination(), "deploy@example.com:22");
+        
+        let absolute = SftpTarget { remote_dir: " /var/www//./{date}/ ".to_string(), ..target.clone() };
+        assert_eq!(absolute.remote_dir(date, 42).unwrap(), "/var/www/2026-03-09");
+        
+        let escaping = SftpTarget { remote_dir: "/var/www/../../etc/{short_id}".to_string(), ..target };
+        assert!(escaping.remote_dir(date, 42).is_err());
+    }
+    
+    #[test]
+    fn joins_remote_paths_with_slashes() {
+        assert_eq!(remote_join("shorts/2026-03-09", "short_1.mp4"), "shorts/2026-03-09/short_1.mp4");
+        assert_eq!(remote_join("/", "shorts"), "/shorts");
+        assert_eq!(remote_join("", "shorts"), "shorts");
+    }
+}
@@ -0,0 +1,144 @@
+// Sound-effect markers in scripts (`[sfx:whoosh]`) and mixing the effects
+// they name into the render where they sit in the narration
+use crate::db::SoundEffect;
+use crate::timing::SpeechTiming;
+use std::path::PathBuf;
+
+const MARKER_PREFIX: &str = "[sfx:";
+
+/// A marker in a script: the effect it names and how many spoken words come before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub name: String,
+    pub word: usize,
+}
+
+/// Effect to mix into a render, starting `at` seconds in
+#[derive(Debug, Clone)]
+pub struct Cue {
+    pub path: PathBuf,
+    pub at: f64,
+    pub volume_db: f64,
+}
+
+/// Library names are lowercase letters, digits, `-` and `_`, so markers stay unambiguous
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Split a script into the text to speak and its markers. Markers are
+/// matched case-insensitively; an unclosed `[sfx:` is left as text.
+pub fn parse(script: &str) -> (String, Vec<Marker>) {
+    let mut spoken = String::new();
+    let mut markers = Vec::new();
+    let mut rest = script;
+    // ASCII lowercasing keeps byte offsets, so positions carry over to `rest`
+    while let Some(start) = rest.to_ascii_lowercase().find(MARKER_PREFIX) {
+        let Some(len) = rest[start..].find(']') else { break };
+        spoken.push_str(&rest[..start]);
+        let name = rest[start + MARKER_PREFIX.len()..start + len].trim().to_lowercase();
+        if !name.is_empty() {
+            markers.push(Marker { name, word: spoken.split_whitespace().count() });
+        }
+        rest = &rest[start + len + 1..];
+    }
+    spoken.push_str(rest);
+    // Removing a marker leaves doubled spaces behind
+    let spoken = spoken
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    (spoken.trim().to_string(), markers)
+}
+
+/// The script without its markers, for the TTS engine and anywhere else it is read
+pub fn strip(script: &str) -> String {
+    parse(script).0
+}
+
+/// Cues for a script's markers, timed by its narration's word timing, and
+/// the names the library has no effect for
+pub fn place(script: &str, timing: &SpeechTiming, library: &[SoundEffect]) -> (Vec<Cue>, Vec<String>) {
+    let (spoken, markers) = parse(script);
+    let total = spoken.split_whitespace().count();
+    let mut cues = Vec::new();
+    let mut unknown = Vec::new();
+    for marker in markers {
+        match library.iter().find(|effect| effect.name == marker.name) {
+            Some(effect) => cues.push(Cue {
+                path: PathBuf::from(&effect.path),
+                at: word_start(timing, marker.word, total),
+                volume_db: effect.volume_db,
+            }),
+            None => unknown.push(marker.name),
+        }
+    }
+    (cues, unknown)
+}
+
+/// When the `index`th of the script's `total` spoken words starts. Engine
+/// and aligned timing may split words differently from the script, so the
+/// index is scaled to the timing's own word count.
+fn word_start(timing: &SpeechTiming, index: usize, total: usize) -> f64 {
+    let words = &timing.words;
+    let Some(last) = words.last() else { return 0.0 };
+    if index >= total {
+        return last.end;
+    }
+    let index = if words.len() == total { index } else { index * words.len() / total };
+    words.get(index).map(|w| w.start).unwrap_or(last.end)
+}
+
+/// Filtergraph that delays each of `cues`, read from inputs `first_input`
+/// onward, to its time and mixes them over the `audio` stream label into
+/// `out`. The mix lasts as long as `audio`.
+pub fn mix_filter(audio: &str, cues: &[Cue], first_input: usize, out: &str) -> String {
+    let mut filters = Vec::new();
+    let mut labels = format!("[{audio}]");
+    for (i, cue) in cues.iter().enumerate() {
+        let delay_ms = (cue.at.max(0.0) * 1000.0).round() as u64;
+        filters.push(format!(
+            "[{}:a]volume={}dB,adelay={delay_ms}:all=1[sfx{i}]",
+            first_input + i,
+            cue.volume_db
+        ));
+        labels.push_str(&format!("[sfx{i}]"));
+    }
+    filters.push(format!(
+        "{labels}amix=inputs={}:duration=first:dropout_transition=0:normalize=0[{out}]",
+        cues.len() + 1
+    ));
+    filters.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing;
+    
+    fn effect(name: &str) -> SoundEffect {
+        SoundEffect { id: None, name: name.to_string(), path: format!("/sfx/{}.mp3", name), volume_db: -6.0 }
+    }
+    
+    #[test]
+    fn strips_markers_and_counts_the_words_before_them() {
+        let (spoken, markers) = parse("[sfx:Whoosh] Wait for it.  [SFX: ding ] Boom!\nDone [sfx:");
+        assert_eq!(spoken, "Wait for it. Boom!\nDone [sfx:");
+        assert_eq!(markers, vec![
+            Marker { name: "whoosh".to_string(), word: 0 },
+            Marker { name: "ding".to_string(), word: 3 },
+        ]);
+    }
+    
+    #[test]
+    fn places_cues_at_the_following_word() {
+        let script = "One two [sfx:ding] three four [sfx:boom] [sfx:missing]";
+        let timing = timing::estimate(&strip(script), 4.0);
+        let (cues, unknown) = place(script, &timing, &[effect("ding"), effect("boom")]);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].at, timing.words[2].start);
+        assert!((cues[1].at - 4.0).abs() < 1e-9, "{}", cues[1].at);
+        assert_eq!(unknown, vec!["missing".to_string()]);
+    }
+}
@@ -0,0 +1,98 @@
+// Near-duplicate detection: how much a trend's words overlap the shorts
+// already made
+use crate::db::{Database, Trend};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Words too common to say anything about what a video is about
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "do", "for", "from", "has", "have", "how", "i",
+    "if", "in", "is", "it", "its", "just", "my", "no", "not", "of", "on", "or", "so", "that", "the", "this",
+    "to", "was", "we", "what", "when", "why", "will", "with", "you", "your",
+];
+
+/// Matches beyond this many aren't returned
+const MAX_MATCHES: usize = 10;
+
+/// An earlier short that covers much the same ground as a trend
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarShort {
+    pub short_id: i64,
+    pub job_id: i64,
+    pub trend_id: i64,
+    pub title: String,
+    /// Share of words in common, from 0 to 1
+    pub score: f64,
+}
+
+/// Lowercased words of `text` without punctuation or stopwords
+pub fn tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Jaccard similarity of two word sets; 0 when either is empty
+pub fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let common = a.intersection(b).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+/// Shorts at least `threshold` similar to `trend`, most similar first, one
+/// per job. Titles are compared with titles; a trend's transcript, when it
+/// has one, with earlier transcripts and scripts.
+pub fn find_similar(db: &Database, trend: &Trend, threshold: f64) -> rusqlite::Result<Vec<SimilarShort>> {
+    let title = tokens(&trend.title);
+    let transcript = trend.transcript.as_deref().map(tokens);
+    // Shorts of a job share a trend; tokenize each trend once
+    let mut trend_tokens: HashMap<i64, (HashSet<String>, Option<HashSet<String>>)> = HashMap::new();
+    let mut best: HashMap<i64, SimilarShort> = HashMap::new();
+    for short in db.list_produced_shorts()? {
+        let (other_title, other_transcript) = trend_tokens.entry(short.trend_id).or_insert_with(|| {
+            (tokens(&short.title), short.transcript.as_deref().map(tokens))
+        });
+        let mut score = similarity(&title, other_title);
+        if let Some(transcript) = &transcript {
+            score = score.max(similarity(transcript, &tokens(&short.script)));
+            if let Some(other) = other_transcript {
+                score = score.max(similarity(transcript, other));
+            }
+        }
+        if score <= 0.0 || score < threshold {
+            continue;
+        }
+        if best.get(&short.job_id).is_some_and(|s| s.score >= score) {
+            continue;
+        }
+        best.insert(short.job_id, SimilarShort {
+            short_id: short.short_id,
+            job_id: short.job_id,
+            trend_id: short.trend_id,
+            title: short.title,
+            score,
+        });
+    }
+    let mut matches: Vec<_> = best.into_values().collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.short_id.cmp(&a.short_id)));
+    matches.truncate(MAX_MATCHES);
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn ignores_case_punctuation_and_stopwords() {
+        let a = tokens("Why Octopuses Are Smarter Than You Think!");
+        let b = tokens("octopuses: smarter than you'd think");
+        assert_eq!(similarity(&a, &b), 0.8);
+        assert_eq!(similarity(&a, &tokens("The 5-Minute Pasta Trick")), 0.0);
+        assert_eq!(similarity(&a, &HashSet::new()), 0.0);
+    }
+}
@@ -0,0 +1,210 @@
+// Stock footage backgrounds from Pexels/Pixabay
+use crate::cache::{self, CacheKind};
+use crate::costs::{self, CostKind};
+use crate::db::{Config, Database, StockProvider};
+use crate::http;
+use crate::transfers::{self, Direction};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Clips shorter than this would loop too visibly behind a short
+const MIN_CLIP_SECS: u32 = 5;
+
+/// Common words that make poor search terms
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "an", "and", "any", "are", "as", "at", "be",
+    "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does", "doing",
+    "down", "even", "every", "for", "from", "get", "got", "had", "has", "have", "he", "her", "here",
+    "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "know", "like", "make",
+    "more", "most", "much", "my", "never", "new", "no", "not", "now", "of", "off", "on", "one",
+    "only", "or", "other", "our", "out", "over", "really", "see", "she", "so", "some", "still",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "thing", "things",
+    "this", "those", "through", "to", "too", "up", "us", "very", "was", "way", "we", "were", "what",
+    "when", "where", "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// A downloadable vertical clip found by a provider
+#[derive(Debug, Clone)]
+struct StockClip {
+    id: u64,
+    url: String,
+}
+
+/// Most frequent meaningful words in `text`, most frequent first
+pub fn keywords(text: &str, limit: usize) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|w| w.trim_matches('\'').to_lowercase())
+        .filter(|w| w.chars().count() > 2 && !STOPWORDS.contains(&w.as_str()))
+        .filter(|w| !w.chars().all(|c| c.is_numeric()));
+    for (position, word) in words.enumerate() {
+        counts.entry(word).or_insert((0, position)).0 += 1;
+    }
+    // Ties go to the word that appears first, which is usually the topic
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.1.1.cmp(&b.1.1)));
+    ranked.into_iter().take(limit).map(|(word, _)| word).collect()
+}
+
+/// Find a vertical clip for `query`, download it into the media cache, and
+/// return its path. A query answered before reuses its clip without
+/// searching again. Multi-word queries fall back to their first word.
+/// `None` when stock footage is disabled or nothing matched.
+pub async fn fetch_clip(config: &Config, db: &Database, app_dir: &Path, query: &str) -> Result<Option<PathBuf>, String> {
+    if config.stock_provider == StockProvider::Disabled || query.trim().is_empty() {
+        return Ok(None);
+    }
+    
+    let mut queries = vec![query.trim()];
+    if let Some(first) = query.split_whitespace().next().filter(|w| *w != query.trim()) {
+        queries.push(first);
+    }
+    for query in queries {
+        let key = cache::key(&[config.stock_provider.as_str(), &query.to_lowercase()]);
+        if let Some(cached) = cache::lookup(db, CacheKind::Stock, &key)? {
+            return Ok(Some(cached.path));
+        }
+        if let Some(clip) = search(config, query).await?.into_iter().next() {
+            tracing::debug!("Downloading {} clip {} for '{}'", config.stock_provider.as_str(), clip.id, query);
+            let path = download(config, &clip, app_dir).await?;
+            return cache::store(db, config, app_dir, CacheKind::Stock, &key, &path, None).await.map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Search the configured provider for vertical clips
+async fn search(config: &Config, query: &str) -> Result<Vec<StockClip>, String> {
+    match config.stock_provider {
+        StockProvider::Disabled => Ok(Vec::new()),
+        StockProvider::Pexels => {
+            let key = config.pexels_api_key.as_deref().ok_or("Pexels API key not configured")?;
+            search_pexels(key, query).await
+        }
+        StockProvider::Pixabay => {
+            let key = config.pixabay_api_key.as_deref().ok_or("Pixabay API key not configured")?;
+            search_pixabay(key, query).await
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PexelsResponse {
+    videos: Vec<PexelsVideo>,
+}
+
+#[derive(Deserialize)]
+struct PexelsVideo {
+    id: u64,
+    duration: u32,
+    video_files: Vec<PexelsFile>,
+}
+
+#[derive(Deserialize)]
+struct PexelsFile {
+    link: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    file_type: Option<String>,
+}
+
+async fn search_pexels(api_key: &str, query: &str) -> Result<Vec<StockClip>, String> {
+    let request = http::client()
+        .get("https://api.pexels.com/videos/search")
+        .header("Authorization", api_key)
+        .query(&[("query", query), ("orientation", "portrait"), ("per_page", "15")]);
+    let response = http::send(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    costs::charge(CostKind::StockRequests, 1.0);
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Pexels search failed ({}): {}", status, body));
+    }
+    let result: PexelsResponse = response.json().await.map_err(|e| e.to_string())?;
+    
+    Ok(result.videos
+        .into_iter()
+        .filter(|v| v.duration >= MIN_CLIP_SECS)
+        .filter_map(|video| {
+            // Portrait MP4 closest to the 1920px output height
+            let file = video.video_files
+                .into_iter()
+                .filter(|f| f.file_type.as_deref() == Some("video/mp4"))
+                .filter_map(|f| Some((f.width?, f.height?, f.link)))
+                .filter(|(w, h, _)| h > w)
+                .min_by_key(|(_, h, _)| h.abs_diff(1920))?;
+            Some(StockClip { id: video.id, url: file.2 })
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct PixabayResponse {
+    hits: Vec<PixabayVideo>,
+}
+
+#[derive(Deserialize)]
+struct PixabayVideo {
+    id: u64,
+    duration: u32,
+    videos: HashMap<String, PixabayFile>,
+}
+
+#[derive(Deserialize)]
+struct PixabayFile {
+    url: String,
+    width: u32,
+    height: u32,
+}
+
+async fn search_pixabay(api_key: &str, query: &str) -> Result<Vec<StockClip>, String> {
+    let request = http::client()
+        .get("https://pixabay.com/api/videos/")
+        .query(&[("key", api_key), ("q", query), ("per_page", "20"), ("safesearch", "true")]);
+    let response = http::send(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    costs::charge(CostKind::StockRequests, 1.0);
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Pixabay search failed ({}): {}", status, body));
+    }
+    let result: PixabayResponse = response.json().await.map_err(|e| e.to_string())?;
+    
+    // Pixabay has no orientation filter for videos, so drop landscape clips here
+    Ok(result.hits
+        .into_iter()
+        .filter(|v| v.duration >= MIN_CLIP_SECS)
+        .filter_map(|video| {
+            let file = video.videos
+                .into_values()
+                .filter(|f| !f.url.is_empty() && f.height > f.width)
+                .min_by_key(|f| f.height.abs_diff(1920))?;
+            Some(StockClip { id: video.id, url: file.url })
+        })
+        .collect())
+}
+
+/// Download a clip to a scratch file for `cache::store`
+async fn download(config: &Config, clip: &StockClip, app_dir: &Path) -> Result<PathBuf, String> {
+    let path = cache::scratch_path(app_dir, CacheKind::Stock, "mp4").await?;
+    let mut response = http::send(http::client().get(&clip.url)).await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Stock footage download failed ({})", response.status()));
+    }
+    let transfer = transfers::start(Direction::Download, format!("stock {}", clip.id), response.content_length());
+    let limit = Direction::Download.limit(config);
+    let mut file = tokio::fs::File::create(&path).await.map_err(|e| e.to_string())?;
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+        transfer.advance(chunk.len() as u64, limit).await;
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -0,0 +1,161 @@
+// Disk usage of the app's files, manual cleanup and the low disk warning
+use crate::backups;
+use crate::cache;
+use crate::db::{Config, Database};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+
+/// Where draft renders are written; they are previews and never uploaded
+pub fn drafts_dir() -> PathBuf {
+    std::env::temp_dir().join("shotauto").join("drafts")
+}
+
+/// A temp directory path no other call gets, for one call's intermediate
+/// files. The caller creates it and removes it when done.
+pub fn scratch_dir(prefix: &str) -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join("shotauto").join(format!("{}-{}-{}", prefix, std::process::id(), n))
+}
+
+/// Bytes used by each kind of file the app keeps
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageStats {
+    /// Database file with its write-ahead log
+    pub database_bytes: u64,
+    pub renders_bytes: u64,
+    /// Narration audio
+    pub audio_bytes: u64,
+    /// Thumbnails and generated scene images
+    pub images_bytes: u64,
+    /// Media cache: source videos, stock clips, generated images and raw narration
+    pub cache_bytes: u64,
+    pub drafts_bytes: u64,
+    pub backups_bytes: u64,
+    /// Free space on the disk holding the app data dir, if it could be read
+    pub free_bytes: Option<u64>,
+}
+
+/// What `cleanup` should delete
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupOptions {
+    pub drafts: bool,
+    /// The whole media cache; fetched or generated again when needed
+    pub source_cache: bool,
+    /// Narration, renders, thumbnails and scene images of rejected shorts
+    pub rejected_shorts: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub files_deleted: usize,
+    pub bytes_freed: u64,
+}
+
+/// Measure the app's folders. Blocking; walks every file.
+pub fn stats(app_dir: &Path, db_path: &Path) -> StorageStats {
+    let db_name = db_path.as_os_str().to_string_lossy();
+    StorageStats {
+        database_bytes: ["", "-wal", "-shm"]
+            .iter()
+            .filter_map(|suffix| std::fs::metadata(format!("{}{}", db_name, suffix)).ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        renders_bytes: dir_size(&app_dir.join("renders")),
+        audio_bytes: dir_size(&app_dir.join("audio")),
+        images_bytes: dir_size(&app_dir.join("thumbnails")) + dir_size(&app_dir.join("images")),
+        cache_bytes: dir_size(&cache::root(app_dir)) + dir_size(&app_dir.join("sources")) + dir_size(&app_dir.join("stock")),
+        drafts_bytes: dir_size(&drafts_dir()),
+        backups_bytes: dir_size(&backups::dir(app_dir)),
+        free_bytes: fs2::available_space(app_dir).ok(),
+    }
+}
+
+/// Delete what `options` asks for. Blocking.
+pub fn cleanup(db: &Database, app_dir: &Path, options: &CleanupOptions) -> Result<CleanupReport, String> {
+    let mut report = CleanupReport::default();
+    if options.drafts {
+        clear_dir(&drafts_dir(), &mut report);
+    }
+    if options.source_cache {
+        let cleared = cache::clear(db, app_dir, None)?;
+        report.files_deleted += cleared.files_deleted;
+        report.bytes_freed += cleared.bytes_freed;
+        // Downloads from before the media cache
+        clear_dir(&app_dir.join("sources"), &mut report);
+        clear_dir(&app_dir.join("stock"), &mut report);
+    }
+    if options.rejected_shorts {
+        for path in db.rejected_short_media_paths().map_err(|e| e.to_string())? {
+            if cache::contains(app_dir, Path::new(&path)) {
+                continue;
+            }
+            db.clear_media_path(&path).map_err(|e| e.to_string())?;
+            remove_file(Path::new(&path), &mut report);
+        }
+    }
+    Ok(report)
+}
+
+/// Send `low-disk-space` when free space drops below `low_disk_warning_mb`.
+/// `was_low` is the previous result, so one drop is reported once.
+pub fn warn_if_low(app: &AppHandle, config: &Config, app_dir: &Path, was_low: bool) -> bool {
+    if config.low_disk_warning_mb == 0 {
+        return false;
+    }
+    let Ok(free_bytes) = fs2::available_space(app_dir) else {
+        return was_low;
+    };
+    let threshold_bytes = config.low_disk_warning_mb as u64 * 1024 * 1024;
+    let low = free_bytes < threshold_bytes;
+    if low && !was_low {
+        tracing::warn!("Only {} MB free on the app data disk", free_bytes / 1024 / 1024);
+        let _ = app.emit("low-disk-space", serde_json::json!({
+            "free_bytes": free_bytes,
+            "threshold_bytes": threshold_bytes,
+        }));
+    }
+    low
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Delete every file in `dir`, leaving the folder itself
+pub fn clear_dir(dir: &Path, report: &mut CleanupReport) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_file()) {
+            remove_file(&entry.path(), report);
+        }
+    }
+}
+
+/// Delete a file and count it in `report`; one already gone is skipped
+pub fn remove_file(path: &Path, report: &mut CleanupReport) {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    match std::fs::remove_file(path) {
+        Ok(()) => {
+            report.files_deleted += 1;
+            report.bytes_freed += size;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Failed to delete {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,340 @@
+// Telegram delivery and bot commands for ShotAuto
+use crate::db::Actor;
+use crate::resilience::{self, Service};
+use crate::AppState;
+use reqwest::{Client, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How long a `getUpdates` call waits for new messages
+const LONG_POLL_SECS: u64 = 50;
+
+/// Wait before polling again after an error or while the bot isn't configured
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Caption for a delivered short. The language tag lets subscribers of a
+/// multi-language setup tell the versions apart.
+pub fn build_caption(title: &str, language: &str) -> String {
+    format!("🎬 {}\n🌐 {}", title, language.to_uppercase())
+}
+
+/// Caption for a short sent for review; replies to it drive the approval
+pub fn build_review_caption(title: &str, language: &str) -> String {
+    format!(
+        "{}\n\n👀 Awaiting approval. Reply with /approve, /reject <reason> or /regenerate",
+        build_caption(title, language)
+    )
+}
+
+/// The Bot API calls the app makes
+pub trait TelegramClient {
+    /// Upload a rendered video to a chat with `sendVideo`, returning the id
+    /// of the sent message. `thumbnail` must be a JPEG of at most 320x320.
+    async fn send_video(
+        &self,
+        chat_id: &str,
+        video_path: &Path,
+        caption: &str,
+        thumbnail: Option<&Path>,
+    ) -> Result<i64, String>;
+    
+    /// Send a text message, optionally as a reply, returning its id
+    async fn send_message(&self, chat_id: &str, text: &str, reply_to: Option<i64>) -> Result<i64, String>;
+    
+    /// Replace the text of a message the bot sent earlier. Editing to the
+    /// same text counts as success.
+    async fn edit_message_text(&self, chat_id: &str, message_id: i64, text: &str) -> Result<(), String>;
+    
+    /// Fetch updates after `offset`, waiting up to `LONG_POLL_SECS` for one to arrive
+    async fn get_updates(&self, offset: i64) -> Result<Vec<Update>, String>;
+}
+
+/// The Bot API of one bot
+pub struct TelegramApi {
+    pub http: Client,
+    pub base_url: String,
+    pub token: String,
+}
+
+impl TelegramApi {
+    fn url(&self, method: &str) -> String {
+        format!("{}/bot{}/{}", self.base_url.trim_end_matches('/'), self.token, method)
+    }
+}
+
+/// Check a Bot API response and decode its `result`
+async fn read_result<T: DeserializeOwned>(method: &str, response: Response) -> Result<T, String> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Telegram {} failed ({}): {}", method, status, body));
+    }
+    let body: ApiResponse<T> = response.json().await
+        .map_err(|e| format!("Telegram {} returned an unexpected response: {}", method, e))?;
+    Ok(body.result)
+}
+
+impl TelegramClient for TelegramApi {
+    async fn send_video(
+        &self,
+        chat_id: &str,
+        video_path: &Path,
+        caption: &str,
+        thumbnail: Option<&Path>,
+    ) -> Result<i64, String> {
+        let bytes = tokio::fs::read(video_path).await.map_err(|e| e.to_string())?;
+        let file_name = video_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "short.mp4".to_string());
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str("video/mp4")
+            .map_err(|e| e.to_string())?;
+        let mut form = reqwest::multipart::Form::new()
+            .text("chat_id", chat_id.to_string())
+            .text("caption", caption.to_string())
+            .text("supports_streaming", "true")
+            .part("video", part);
+        if let Some(thumbnail) = thumbnail {
+            let bytes = tokio::fs::read(thumbnail).await.map_err(|e| e.to_string())?;
+            let part = reqwest::multipart::Part::bytes(bytes)
+                .file_name("thumbnail.jpg")
+                .mime_str("image/jpeg")
+                .map_err(|e| e.to_string())?;
+            form = form.part("thumbnail", part);
+        }
+        
+        let request = self.http
+            .post(self.url("sendVideo"))
+            .multipart(form);
+        let response = resilience::send(Service::Telegram, request).await?;
+        let sent: Message = read_result("sendVideo", response).await?;
+        Ok(sent.message_id)
+    }
+    
+    async fn send_message(&self, chat_id: &str, text: &str, reply_to: Option<i64>) -> Result<i64, String> {
+        let mut body = serde_json::json!({ "chat_id": chat_id, "text": text });
+        if let Some(message_id) = reply_to {
+            body["reply_parameters"] = serde_json::json!({ "message_id": message_id });
+        }
+        let request = self.http
+            .post(self.url("sendMessage"))
+            .json(&body);
+        let response = resilience::send(Service::Telegram, request).await?;
+        let sent: Message = read_result("sendMessage", response).await?;
+        Ok(sent.message_id)
+    }
+    
+    async fn edit_message_text(&self, chat_id: &str, message_id: i64, text: &str) -> Result<(), String> {
+        let request = self.http
+            .post(self.url("editMessageText"))
+            .json(&serde_json::json!({ "chat_id": chat_id, "message_id": message_id, "text": text }));
+        let response = resilience::send(Service::Telegram, request).await?;
+        match read_result::<serde_json::Value>("editMessageText", response).await {
+            Err(e) if e.contains("message is not modified") => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+    
+    async fn get_updates(&self, offset: i64) -> Result<Vec<Update>, String> {
+        let request = self.http
+            .get(self.url("getUpdates"))
+            .query(&[
+                ("offset", offset.to_string()),
+                ("timeout", LONG_POLL_SECS.to_string()),
+                ("allowed_updates", "[\"message\"]".to_string()),
+            ])
+            .timeout(Duration::from_secs(LONG_POLL_SECS + 10));
+        let response = resilience::send(Service::Telegram, request).await?;
+        read_result("getUpdates", response).await
+    }
+}
+
+// ==================== Bot Commands ====================
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    message_id: i64,
+    chat: Chat,
+    text: Option<String>,
+    reply_to_message: Option<Box<Message>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Chat {
+    id: i64,
+    username: Option<String>,
+}
+
+/// Command sent to the bot in reply to a short's video
+#[derive(Debug, Clone, PartialEq)]
+pub enum BotCommand {
+    Approve,
+    Reject(Option<String>),
+    Regenerate,
+}
+
+impl BotCommand {
+    /// Parse "/approve", "/reject too long" or "/regenerate@ShotAutoBot"
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (command, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let command = command.split('@').next().unwrap_or(command);
+        match command {
+            "/approve" => Some(BotCommand::Approve),
+            "/reject" => Some(BotCommand::Reject(Some(rest.trim().to_string()).filter(|r| !r.is_empty()))),
+            "/regenerate" => Some(BotCommand::Regenerate),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `chat` is the configured chat, given as a numeric id or @username
+fn is_configured_chat(chat_id: &str, chat: &Chat) -> bool {
+    match chat_id.strip_prefix('@') {
+        Some(username) => chat.username.as_deref().is_some_and(|u| u.eq_ignore_ascii_case(username)),
+        None => chat_id.trim() == chat.id.to_string(),
+    }
+}
+
+/// Long-poll the bot for commands for as long as the app runs. Only
+/// messages from the configured chat are acted on; everything else is
+/// acknowledged and dropped.
+pub async fn listen(app: AppHandle) {
+    let mut offset = 0;
+    let mut polled_token = String::new();
+    loop {
+        let state = app.state::<AppState>();
+        let config = state.db.load_config().map_err(|e| e.to_string());
+        let (token, chat_id) = match config {
+            Ok(config) => match (config.telegram_bot_token, config.telegram_chat_id) {
+                (Some(token), Some(chat_id)) => (token, chat_id),
+                _ => {
+                    tokio::time::sleep(IDLE_INTERVAL).await;
+                    continue;
+                }
+            },
+            Err(e) => {
+                tracing::warn!("Telegram bot: {}", e);
+                tokio::time::sleep(IDLE_INTERVAL).await;
+                continue;
+            }
+        };
+        // Update ids are per bot
+        if token != polled_token {
+            offset = 0;
+            polled_token = token.clone();
+        }
+        
+        let bot = state.clients.telegram(&token);
+        let updates = match bot.get_updates(offset).await {
+            Ok(updates) => updates,
+            Err(e) => {
+                tracing::warn!("Telegram bot: {}", e);
+                tokio::time::sleep(IDLE_INTERVAL).await;
+                continue;
+            }
+        };
+        for update in updates {
+            offset = offset.max(update.update_id + 1);
+            let Some(message) = update.message else { continue };
+            if !is_configured_chat(&chat_id, &message.chat) {
+                continue;
+            }
+            let Some(command) = message.text.as_deref().and_then(BotCommand::parse) else { continue };
+            let reply = handle_command(&state, &message, command).await.unwrap_or_else(|e| format!("⚠️ {}", e));
+            if let Err(e) = bot.send_message(&chat_id, &reply, Some(message.message_id)).await {
+                tracing::warn!("Telegram bot reply failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Run a command against the short whose video `message` replies to,
+/// returning the text to answer with
+async fn handle_command(state: &AppState, message: &Message, command: BotCommand) -> Result<String, String> {
+    let replied_to = message
+        .reply_to_message
+        .as_ref()
+        .ok_or("Reply to a short's video to approve, reject or regenerate it")?;
+    let short_id = state.db.find_short_by_telegram_message(replied_to.message_id).map_err(|e| e.to_string())?
+        .ok_or("That message isn't a short ShotAuto sent")?;
+    
+    match command {
+        BotCommand::Approve => {
+            crate::approve(state, short_id, Actor::TelegramBot).await?;
+            Ok(format!("✅ Short {} approved", short_id))
+        }
+        BotCommand::Reject(reason) => {
+            crate::reject(state, short_id, reason.as_deref(), Actor::TelegramBot)?;
+            Ok(format!("🚫 Short {} rejected", short_id))
+        }
+        BotCommand::Regenerate => {
+            crate::regenerate(state, short_id, Actor::TelegramBot)?;
+            Ok(format!("🔄 Short {} queued for regeneration", short_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::Clients;
+    use crate::mock_server::{MockServer, Reply};
+    
+    fn bot(server: &MockServer) -> TelegramApi {
+        Clients::at(&server.url(), server.http()).telegram("123:abc")
+    }
+    
+    #[tokio::test]
+    async fn send_message_returns_the_message_id() {
+        let server = MockServer::start(Reply::json(200, r#"{"ok": true, "result": {"message_id": 42, "chat": {"id": 7}}}"#)).await;
+        assert_eq!(bot(&server).send_message("7", "hi", Some(3)).await, Ok(42));
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /bot123:abc/sendMessage"), "{}", request);
+        assert!(request.contains(r#""reply_parameters":{"message_id":3}"#), "{}", request);
+    }
+    
+    #[tokio::test]
+    async fn rate_limited_send_is_an_error() {
+        let server = MockServer::start(Reply::json(429, r#"{"ok": false, "error_code": 429, "description": "Too Many Requests: retry after 5"}"#)).await;
+        let err = bot(&server).send_message("7", "hi", None).await.unwrap_err();
+        assert!(err.contains("Too Many Requests"), "{}", err);
+        // Sends aren't retried: a retry could post the message twice
+        assert_eq!(server.requests().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn malformed_json_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"ok": true, "result": [{"update_id": "#)).await;
+        let err = bot(&server).get_updates(0).await.unwrap_err();
+        assert!(err.contains("unexpected response"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn unchanged_edit_counts_as_success() {
+        let server = MockServer::start(Reply::json(400, r#"{"ok": false, "description": "Bad Request: message is not modified"}"#)).await;
+        assert_eq!(bot(&server).edit_message_text("7", 42, "same").await, Ok(()));
+    }
+    
+    #[tokio::test]
+    async fn timeout_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"ok": true, "result": {"message_id": 42, "chat": {"id": 7}}}"#).delayed(Duration::from_secs(2))).await;
+        assert!(bot(&server).send_message("7", "hi", None).await.is_err());
+    }
+}
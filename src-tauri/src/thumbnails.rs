@@ -0,0 +1,82 @@
+// Thumbnail (poster frame) generation for rendered shorts
+use crate::captions;
+use crate::db::{Config, ThumbnailMode};
+use crate::process::run;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Frames the `thumbnail` filter compares; covers the first ~10 s at 30 fps
+const CANDIDATE_FRAMES: u32 = 300;
+
+/// Characters per line when wrapping the title
+const TITLE_LINE_CHARS: usize = 16;
+
+/// Telegram rejects thumbnails larger than 320 px on either side
+const TELEGRAM_THUMB_MAX: u32 = 320;
+
+/// Write a JPEG poster frame for `video` to `output`. `Frame` keeps the most
+/// representative frame as is; `Title` darkens it and writes the title over it.
+pub async fn generate(config: &Config, video: &Path, title: &str, output: &Path) -> Result<(), String> {
+    let pick = format!("thumbnail={}", CANDIDATE_FRAMES);
+    let filter = match config.thumbnail_mode {
+        ThumbnailMode::Frame => pick,
+        ThumbnailMode::Title => {
+            // drawtext reads the text from a file, which sidesteps filter escaping
+            let text_file = output.with_extension("txt");
+            tokio::fs::write(&text_file, wrap(title, TITLE_LINE_CHARS))
+                .await
+                .map_err(|e| e.to_string())?;
+            format!(
+                "{pick},eq=brightness=-0.25,drawtext=textfile={}:expansion=none:font='{}':fontsize=110:\
+                 fontcolor=white:borderw=8:bordercolor=black:line_spacing=24:x=(w-text_w)/2:y=(h-text_h)/2",
+                captions::escape_filter_path(&text_file.to_string_lossy()),
+                config.caption_style.font.replace('\'', ""),
+            )
+        }
+    };
+    
+    let result = run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-y", "-i"])
+        .arg(video)
+        .args(["-vf", &filter, "-frames:v", "1", "-q:v", "2"])
+        .arg(output))
+    .await;
+    if config.thumbnail_mode == ThumbnailMode::Title {
+        let _ = tokio::fs::remove_file(output.with_extension("txt")).await;
+    }
+    result.map(|_| ())
+}
+
+/// Small copy of a thumbnail that fits Telegram's limits
+pub async fn telegram_thumb(config: &Config, thumbnail: &Path, output: &Path) -> Result<(), String> {
+    run(Command::new(config.ffmpeg())
+        .args(["-hide_banner", "-y", "-i"])
+        .arg(thumbnail)
+        .args([
+            "-vf",
+            &format!(
+                "scale={max}:{max}:force_original_aspect_ratio=decrease",
+                max = TELEGRAM_THUMB_MAX
+            ),
+            "-q:v",
+            "4",
+        ])
+        .arg(output))
+    .await?;
+    Ok(())
+}
+
+/// Break `text` into lines of at most `width` characters at word boundaries
+fn wrap(text: &str, width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,272 @@
+// Speech timing (word and sentence timestamps) for caption sync
+use crate::db::Config;
+use crate::process::run;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+/// A span of spoken text with start/end in seconds from the start of the audio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedText {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// How the timing was obtained, from most to least precise
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingSource {
+    /// Reported by the TTS engine
+    Engine,
+    /// Forced alignment of the generated audio with whisper.cpp
+    Alignment,
+    /// Spread over the audio duration in proportion to word length
+    Estimated,
+}
+
+/// Word- and sentence-level timing for a narration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeechTiming {
+    pub source: TimingSource,
+    pub sentences: Vec<TimedText>,
+    pub words: Vec<TimedText>,
+}
+
+impl SpeechTiming {
+    /// Build from sentence spans, spreading each sentence's words across it
+    pub fn from_sentences(source: TimingSource, sentences: Vec<TimedText>) -> Self {
+        let words = sentences
+            .iter()
+            .flat_map(|s| distribute(&s.text, s.start, s.end))
+            .collect();
+        Self { source, sentences, words }
+    }
+    
+    /// Build from word spans, grouping words into sentences at terminal punctuation
+    pub fn from_words(source: TimingSource, words: Vec<TimedText>) -> Self {
+        let mut sentences = Vec::new();
+        let mut current: Vec<&TimedText> = Vec::new();
+        for word in &words {
+            current.push(word);
+            if word.text.ends_with(['.', '!', '?', '。', '！', '？']) {
+                sentences.push(join(&current));
+                current.clear();
+            }
+        }
+        if !current.is_empty() {
+            sentences.push(join(&current));
+        }
+        Self { source, sentences, words }
+    }
+    
+    /// Move the timestamps to match the audio with `cuts`, spans of the
+    /// original in order, taken out. Times inside a cut land where it was.
+    pub fn remove_spans(&mut self, cuts: &[(f64, f64)]) {
+        let shift = |t: f64| {
+            let mut removed = 0.0;
+            for &(start, end) in cuts {
+                if t >= end {
+                    removed += end - start;
+                } else {
+                    return t.min(start) - removed;
+                }
+            }
+            t - removed
+        };
+        for span in self.sentences.iter_mut().chain(self.words.iter_mut()) {
+            span.start = shift(span.start);
+            span.end = shift(span.end);
+        }
+    }
+}
+
+fn join(words: &[&TimedText]) -> TimedText {
+    TimedText {
+        text: words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+        start: words.first().map(|w| w.start).unwrap_or(0.0),
+        end: words.last().map(|w| w.end).unwrap_or(0.0),
+    }
+}
+
+/// Split `text` into words and spread them over `[start, end]` by character count
+fn distribute(text: &str, start: f64, end: f64) -> Vec<TimedText> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    // +1 per word stands in for the gap between words
+    let total: usize = words.iter().map(|w| w.chars().count() + 1).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let per_unit = (end - start).max(0.0) / total as f64;
+    let mut cursor = start;
+    words
+        .into_iter()
+        .map(|w| {
+            let length = (w.chars().count() + 1) as f64 * per_unit;
+            let span = TimedText { text: w.to_string(), start: cursor, end: cursor + length };
+            cursor += length;
+            span
+        })
+        .collect()
+}
+
+/// Timing estimated from the script and the measured audio duration
+pub fn estimate(script: &str, duration: f64) -> SpeechTiming {
+    SpeechTiming::from_words(TimingSource::Estimated, distribute(script, 0.0, duration))
+}
+
+/// Parse SRT or WebVTT cues
+pub fn parse_subtitles(content: &str) -> Vec<TimedText> {
+    let mut cues = Vec::new();
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((from, to)) = line.split_once("-->") else {
+            continue;
+        };
+        let (Some(start), Some(end)) = (parse_timestamp(from), parse_timestamp(to)) else {
+            continue;
+        };
+        let mut text = Vec::new();
+        while let Some(next) = lines.peek() {
+            if next.trim().is_empty() {
+                break;
+            }
+            text.push(next.trim().to_string());
+            lines.next();
+        }
+        if !text.is_empty() {
+            cues.push(TimedText { text: text.join(" "), start, end });
+        }
+    }
+    cues
+}
+
+/// Write `cues` as SRT
+pub fn to_srt(cues: &[TimedText]) -> String {
+    let timestamp = |seconds: f64| {
+        let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+        format!("{:02}:{:02}:{:02},{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+    };
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| format!("{}\n{} --> {}\n{}\n\n", i + 1, timestamp(cue.start), timestamp(cue.end), cue.text))
+        .collect()
+}
+
+/// `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm`, ignoring VTT cue settings
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let value = value.split_whitespace().next()?.replace(',', ".");
+    let parts: Vec<&str> = value.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        _ => return None,
+    };
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Timing from engine subtitle cues, which may be per word or per sentence
+pub fn from_cues(cues: Vec<TimedText>) -> Option<SpeechTiming> {
+    if cues.is_empty() {
+        return None;
+    }
+    let words: usize = cues.iter().map(|c| c.text.split_whitespace().count()).sum();
+    if words as f64 / cues.len() as f64 <= 1.5 {
+        Some(SpeechTiming::from_words(TimingSource::Engine, cues))
+    } else {
+        Some(SpeechTiming::from_sentences(TimingSource::Engine, cues))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperOutput {
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperSegment {
+    offsets: WhisperOffsets,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperOffsets {
+    from: u64,
+    to: u64,
+}
+
+/// Align narration audio with whisper.cpp word-level segments. When whisper
+/// hears the same number of words as the script, the script's spelling is kept.
+pub async fn align_with_whisper(config: &Config, audio: &Path, script: &str) -> Result<SpeechTiming, String> {
+    let whisper = config.whisper_cpp_path.as_deref().unwrap_or("whisper-cli");
+    let model = config
+        .whisper_model_path
+        .as_deref()
+        .ok_or("whisper_model_path is not configured")?;
+    
+    // Several shorts can be narrated at once; each alignment gets its own dir
+    let work_dir = storage::scratch_dir("align");
+    tokio::fs::create_dir_all(&work_dir).await.map_err(|e| e.to_string())?;
+    let wav = work_dir.join("narration.wav");
+    let out_base = work_dir.join("alignment");
+    
+    let result = async {
+        run(Command::new(config.ffmpeg())
+            .args(["-hide_banner", "-y", "-i"])
+            .arg(audio)
+            .args(["-ar", "16000", "-ac", "1"])
+            .arg(&wav))
+        .await?;
+        run(Command::new(whisper)
+            .args(["-m", model, "-ml", "1", "-sow", "-np", "-oj", "-l", "auto"])
+            .arg("-f")
+            .arg(&wav)
+            .arg("-of")
+            .arg(&out_base))
+        .await?;
+        let json = tokio::fs::read_to_string(out_base.with_extension("json"))
+            .await
+            .map_err(|e| format!("Failed to read whisper.cpp output: {}", e))?;
+        serde_json::from_str::<WhisperOutput>(&json).map_err(|e| e.to_string())
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    
+    let mut words: Vec<TimedText> = result?
+        .transcription
+        .into_iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .map(|s| TimedText {
+            text: s.text.trim().to_string(),
+            start: s.offsets.from as f64 / 1000.0,
+            end: s.offsets.to as f64 / 1000.0,
+        })
+        .collect();
+    
+    let script_words: Vec<&str> = script.split_whitespace().collect();
+    if script_words.len() == words.len() {
+        for (word, text) in words.iter_mut().zip(script_words) {
+            word.text = text.to_string();
+        }
+    }
+    Ok(SpeechTiming::from_words(TimingSource::Alignment, words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn captions_round_trip_through_srt() {
+        let cues = vec![
+            TimedText { text: "Cats can't taste sugar.".to_string(), start: 0.0, end: 1.25 },
+            TimedText { text: "Here's why.".to_string(), start: 1.25, end: 3661.5 },
+        ];
+        let srt = to_srt(&cues);
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:01,250\nCats can't taste sugar.\n\n2\n"));
+        let parsed = parse_subtitles(&srt);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[1].text.as_str(), parsed[1].end), ("Here's why.", 3661.5));
+    }
+}
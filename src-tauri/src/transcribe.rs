@@ -0,0 +1,116 @@
+// Source-video transcription for ShotAuto
+use crate::db::{Config, TranscriptionBackend};
+use crate::http;
+use crate::process::run;
+use crate::storage;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Transcripts longer than this are cut so they fit comfortably in the LLM context
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+
+#[derive(Debug, Deserialize)]
+struct OpenAiTranscription {
+    text: String,
+}
+
+/// Download a video's audio and transcribe it with the configured backend
+pub async fn transcribe_video(config: &Config, video_id: &str) -> Result<String, String> {
+    if config.transcription_backend == TranscriptionBackend::Disabled {
+        return Err("Transcription is disabled".to_string());
+    }
+    
+    let work_dir = storage::scratch_dir("transcribe");
+    tokio::fs::create_dir_all(&work_dir).await.map_err(|e| e.to_string())?;
+    
+    let result = async {
+        let audio = download_audio(config, video_id, &work_dir).await?;
+        let text = match config.transcription_backend {
+            TranscriptionBackend::WhisperCpp => transcribe_whisper_cpp(config, &audio, &work_dir).await?,
+            TranscriptionBackend::OpenAi => transcribe_openai(config, &audio).await?,
+            TranscriptionBackend::Disabled => unreachable!(),
+        };
+        Ok(truncate(text.trim(), MAX_TRANSCRIPT_CHARS))
+    }
+    .await;
+    
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+/// Fetch the audio track with yt-dlp and convert it to 16 kHz mono WAV,
+/// the format whisper.cpp expects
+async fn download_audio(config: &Config, video_id: &str, work_dir: &Path) -> Result<PathBuf, String> {
+    let url = format!("https://www.youtube.com/watch?v={}", video_id);
+    run(Command::new(config.yt_dlp())
+        .args(["-f", "bestaudio", "--no-playlist", "-x", "--audio-format", "wav"])
+        .args(["--postprocessor-args", "ffmpeg:-ar 16000 -ac 1"])
+        .arg("-o")
+        .arg(work_dir.join("audio.%(ext)s"))
+        .arg(&url))
+    .await?;
+    
+    let audio = work_dir.join("audio.wav");
+    if !audio.exists() {
+        return Err("yt-dlp finished but produced no audio file".to_string());
+    }
+    Ok(audio)
+}
+
+async fn transcribe_whisper_cpp(config: &Config, audio: &Path, work_dir: &Path) -> Result<String, String> {
+    let whisper = config.whisper_cpp_path.as_deref().unwrap_or("whisper-cli");
+    let model = config
+        .whisper_model_path
+        .as_deref()
+        .ok_or("whisper_model_path is not configured")?;
+    let out_base = work_dir.join("transcript");
+    
+    run(Command::new(whisper)
+        .args(["-m", model, "-nt", "-np", "-otxt", "-l", "auto"])
+        .arg("-f")
+        .arg(audio)
+        .arg("-of")
+        .arg(&out_base))
+    .await?;
+    
+    tokio::fs::read_to_string(out_base.with_extension("txt"))
+        .await
+        .map_err(|e| format!("Failed to read whisper.cpp output: {}", e))
+}
+
+async fn transcribe_openai(config: &Config, audio: &Path) -> Result<String, String> {
+    let api_key = config.openai_api_key.as_deref().ok_or("openai_api_key is not configured")?;
+    let bytes = tokio::fs::read(audio).await.map_err(|e| e.to_string())?;
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::multipart::Form::new()
+        .text("model", "whisper-1")
+        .part("file", part);
+    
+    let response = http::client()
+        .post("https://api.openai.com/v1/audio/transcriptions")
+        .bearer_auth(api_key)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("OpenAI transcription failed ({}): {}", status, body));
+    }
+    
+    let body: OpenAiTranscription = response.json().await.map_err(|e| e.to_string())?;
+    Ok(body.text)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => text[..idx].to_string(),
+        None => text.to_string(),
+    }
+}
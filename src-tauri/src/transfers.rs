@@ -0,0 +1,196 @@
+// Bandwidth caps shared by every download and upload, and the rates they run at
+use crate::db::Config;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Rates are averaged over this much recent progress
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+impl Direction {
+    /// Cap in bytes per second, or `None` for no cap
+    pub fn limit(&self, config: &Config) -> Option<u64> {
+        let kb = match self {
+            Direction::Download => config.download_limit_kb,
+            Direction::Upload => config.upload_limit_kb,
+        };
+        (kb > 0).then(|| kb as u64 * 1024)
+    }
+}
+
+/// A transfer in progress
+#[derive(Debug, Clone, Serialize)]
+pub struct Transfer {
+    pub direction: Direction,
+    pub label: String,
+    pub transferred_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub bytes_per_sec: f64,
+}
+
+/// Current rates against their caps
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferStatus {
+    pub download_bytes_per_sec: f64,
+    pub upload_bytes_per_sec: f64,
+    /// KB/s, 0 for no cap
+    pub download_limit_kb: u32,
+    pub upload_limit_kb: u32,
+    pub transfers: Vec<Transfer>,
+}
+
+struct Active {
+    direction: Direction,
+    label: String,
+    total: Option<u64>,
+    /// Bytes transferred so far, sampled over the last `RATE_WINDOW`
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl Active {
+    fn transferred(&self) -> u64 {
+        self.samples.back().map(|&(_, bytes)| bytes).unwrap_or(0)
+    }
+    
+    fn record(&mut self, transferred: u64, total: Option<u64>) {
+        let now = Instant::now();
+        self.samples.push_back((now, transferred));
+        while self.samples.len() > 2 && now - self.samples[0].0 > RATE_WINDOW {
+            self.samples.pop_front();
+        }
+        if total.is_some() {
+            self.total = total;
+        }
+    }
+    
+    fn rate(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(&(start, first)), Some(&(end, last))) if end > start => {
+                (last - first) as f64 / (end - start).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    active: HashMap<u64, Active>,
+    /// When each direction's cap lets the next byte through
+    next_free: HashMap<Direction, Instant>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Tracks one transfer until dropped
+pub struct Handle {
+    id: u64,
+    direction: Direction,
+}
+
+/// Start tracking a transfer of `total` bytes, if known
+pub fn start(direction: Direction, label: impl Into<String>, total: Option<u64>) -> Handle {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let active = Active {
+        direction,
+        label: label.into(),
+        total,
+        samples: VecDeque::from([(Instant::now(), 0)]),
+    };
+    registry().lock().unwrap().active.insert(id, active);
+    Handle { id, direction }
+}
+
+impl Handle {
+    /// Record progress made by someone else, such as yt-dlp
+    pub fn progress(&self, transferred: u64, total: Option<u64>) {
+        if let Some(active) = registry().lock().unwrap().active.get_mut(&self.id) {
+            active.record(transferred, total);
+        }
+    }
+    
+    /// Record `bytes` more transferred, then wait as long as the direction's
+    /// cap needs. Transfers running together share the cap.
+    pub async fn advance(&self, bytes: u64, limit: Option<u64>) {
+        let until = {
+            let mut registry = registry().lock().unwrap();
+            if let Some(active) = registry.active.get_mut(&self.id) {
+                active.record(active.transferred() + bytes, None);
+            }
+            let Some(limit) = limit.filter(|&limit| limit > 0) else {
+                return;
+            };
+            let now = Instant::now();
+            let next = registry.next_free.entry(self.direction).or_insert(now);
+            *next = (*next).max(now) + Duration::from_secs_f64(bytes as f64 / limit as f64);
+            *next
+        };
+        tokio::time::sleep_until(until).await;
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = registry().lock() {
+            registry.active.remove(&self.id);
+        }
+    }
+}
+
+/// Transfers in progress and the rates they add up to
+pub fn status(config: &Config) -> TransferStatus {
+    let registry = registry().lock().unwrap();
+    let transfers: Vec<Transfer> = registry.active
+        .values()
+        .map(|active| Transfer {
+            direction: active.direction,
+            label: active.label.clone(),
+            transferred_bytes: active.transferred(),
+            total_bytes: active.total,
+            bytes_per_sec: active.rate(),
+        })
+        .collect();
+    let rate = |direction: Direction| -> f64 {
+        transfers.iter().filter(|t| t.direction == direction).map(|t| t.bytes_per_sec).sum()
+    };
+    TransferStatus {
+        download_bytes_per_sec: rate(Direction::Download),
+        upload_bytes_per_sec: rate(Direction::Upload),
+        download_limit_kb: config.download_limit_kb,
+        upload_limit_kb: config.upload_limit_kb,
+        transfers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn cap_paces_the_transfer() {
+        let started = Instant::now();
+        let transfer = start(Direction::Upload, "test", Some(4096));
+        for _ in 0..4 {
+            transfer.advance(1024, Some(8192)).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(500));
+        let current = status(&Config::default());
+        assert_eq!(current.transfers.iter().find(|t| t.label == "test").unwrap().transferred_bytes, 4096);
+        drop(transfer);
+        assert!(status(&Config::default()).transfers.iter().all(|t| t.label != "test"));
+    }
+}
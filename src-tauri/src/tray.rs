@@ -0,0 +1,103 @@
+// System tray icon with the queue status and quick actions
+use crate::health;
+use crate::pipeline::{self, QueueState, QueueStatus};
+use crate::trends;
+use crate::AppState;
+use std::time::Duration;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the tray re-reads the queue status
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+const PAUSE: &str = "pause";
+const FETCH_TRENDS: &str = "fetch_trends";
+const OPEN_DASHBOARD: &str = "open_dashboard";
+const QUIT: &str = "quit";
+
+/// Add the tray icon and keep its status up to date
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "Idle", false, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(app, PAUSE, "Pause Queue", true, pipeline::is_paused(), None::<&str>)?;
+    let menu = Menu::with_items(app, &[
+        &status,
+        &PredefinedMenuItem::separator(app)?,
+        &pause,
+        &MenuItem::with_id(app, FETCH_TRENDS, "Fetch Trends Now", true, None::<&str>)?,
+        &MenuItem::with_id(app, OPEN_DASHBOARD, "Open Dashboard", true, None::<&str>)?,
+        &PredefinedMenuItem::separator(app)?,
+        &MenuItem::with_id(app, QUIT, "Quit", true, None::<&str>)?,
+    ])?;
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("ShotAuto")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                open_dashboard(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    let tray = builder.build(app)?;
+    tauri::async_runtime::spawn(refresh(app.clone(), tray, status, pause));
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        PAUSE => {
+            pipeline::set_paused(!pipeline::is_paused());
+            health::queue_toggled();
+            if let Ok(status) = pipeline::status(&app.state::<AppState>().db) {
+                let _ = app.emit("queue:status", status);
+            }
+        }
+        FETCH_TRENDS => trends::fetch_now(),
+        OPEN_DASHBOARD => open_dashboard(app),
+        QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Show, unminimize and focus the main window
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_dashboard(app: &AppHandle) {
+    show_main_window(app);
+    let _ = app.emit("tray:open-dashboard", ());
+}
+
+async fn refresh(app: AppHandle, tray: TrayIcon, status: MenuItem<tauri::Wry>, pause: CheckMenuItem<tauri::Wry>) {
+    loop {
+        match pipeline::status(&app.state::<AppState>().db) {
+            Ok(queue) => {
+                let text = describe(&queue);
+                let _ = status.set_text(&text);
+                let _ = pause.set_checked(queue.paused);
+                let _ = tray.set_tooltip(Some(format!("ShotAuto: {}", text)));
+            }
+            Err(e) => tracing::warn!("Failed to read the queue status for the tray: {}", e),
+        }
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+fn describe(queue: &QueueStatus) -> String {
+    match queue.state {
+        QueueState::Error => format!("⚠ {} dead job(s) need attention", queue.dead),
+        QueueState::Paused => format!("⏸ Paused, {} queued", queue.queued),
+        QueueState::Working => format!("▶ Working: {} running, {} queued", queue.running, queue.queued),
+        QueueState::Idle if queue.outside_run_window => format!("☾ Outside run hours, {} queued", queue.queued),
+        QueueState::Idle => "● Idle".to_string(),
+    }
+}
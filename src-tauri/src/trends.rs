@@ -0,0 +1,190 @@
+// Trending videos polled from the YouTube Data API
+use crate::db::{Database, Trend, TrendSource, TrendState};
+use crate::dry_run;
+use crate::youtube_api::{Video, VideoList, YoutubeClient};
+use crate::{pipeline, AppState};
+use chrono::{DateTime, Utc};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// Wait before looking again when there is no API key or the fetch failed
+const RETRY_INTERVAL: Duration = Duration::from_secs(600);
+
+fn wake() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+/// Fetch trending videos straight away after `fetch_now`, and with
+/// `auto_fetch_trends` on also every `poll_interval_secs` for as long as the
+/// app runs. Outside the run windows the poller only looks again every
+/// `RETRY_INTERVAL`.
+pub async fn run(app: AppHandle) {
+    loop {
+        let state = app.state::<AppState>();
+        if !state.db.load_config().map(|config| config.auto_fetch_trends).unwrap_or(false) {
+            // Check the setting again now and then so turning it on takes effect
+            if tokio::time::timeout(RETRY_INTERVAL, wake().notified()).await.is_ok() {
+                match refresh(&state).await {
+                    Ok(Some(count)) => tracing::info!("Fetched {} trending videos", count),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Fetching trends failed: {}", e),
+                }
+            }
+            continue;
+        }
+        let open = state.db.load_config().map(|config| pipeline::in_run_window(&config)).unwrap_or(true);
+        let wait = if !open {
+            RETRY_INTERVAL
+        } else {
+            match refresh(&state).await {
+                Ok(Some(count)) => {
+                    tracing::info!("Fetched {} trending videos", count);
+                    state.db.load_config()
+                        .map(|config| Duration::from_secs(config.poll_interval_secs.max(60)))
+                        .unwrap_or(RETRY_INTERVAL)
+                }
+                Ok(None) => RETRY_INTERVAL,
+                Err(e) => {
+                    tracing::warn!("Fetching trends failed: {}", e);
+                    RETRY_INTERVAL
+                }
+            }
+        };
+        let _ = tokio::time::timeout(wait, wake().notified()).await;
+    }
+}
+
+/// Have the poller fetch trends now instead of at its next interval
+pub fn fetch_now() {
+    wake().notify_one();
+}
+
+/// Store the current most popular videos as trends. Returns how many were
+/// fetched, or `None` when no API key is configured.
+pub async fn refresh(state: &AppState) -> Result<Option<usize>, String> {
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    if config.dry_run {
+        let list: VideoList = serde_json::from_str(dry_run::TRENDING_FIXTURE).map_err(|e| e.to_string())?;
+        return store(state, &list.items, true).map(Some);
+    }
+    if config.youtube_api_key.is_none() {
+        return Ok(None);
+    }
+    let videos = state.clients.youtube(&config)?.most_popular().await?;
+    store(state, &videos, false).map(Some)
+}
+
+/// Store fetched videos as trends; `dry_run` marks ones from the fixture
+fn store(state: &AppState, videos: &[Video], dry_run: bool) -> Result<usize, String> {
+    let fetched_at = Utc::now();
+    let trends: Vec<Trend> = videos.iter()
+        .map(|video| Trend { dry_run, ..to_trend(video, fetched_at) })
+        .collect();
+    state.db.insert_trends_batch(&trends).map_err(|e| e.to_string())?;
+    Ok(videos.len())
+}
+
+fn to_trend(video: &Video, fetched_at: DateTime<Utc>) -> Trend {
+    Trend {
+        id: None,
+        video_id: video.id.clone(),
+        title: video.snippet.title.clone(),
+        channel: video.snippet.channel_title.clone(),
+        views: video.statistics.as_ref()
+            .and_then(|s| s.view_count.as_deref())
+            .and_then(|v| v.parse().ok()),
+        category: video.snippet.category_id.clone(),
+        fetched_at,
+        transcript: None,
+        tags: video.snippet.tags.clone(),
+        source: TrendSource::YouTube,
+        notes: None,
+        starred: false,
+        state: TrendState::New,
+        skip_reason: None,
+        dry_run: false,
+    }
+}
+
+/// The video id in a YouTube link (`watch?v=`, `youtu.be/`, `shorts/`),
+/// or a bare 11-character id
+pub fn video_id_from_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let is_id = |s: &str| s.len() == 11 && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_id(url) {
+        return Some(url.to_string());
+    }
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.").trim_start_matches("m.");
+    let id = match host {
+        "youtu.be" => parsed.path_segments()?.next().map(str::to_string),
+        "youtube.com" | "music.youtube.com" => {
+            let mut segments = parsed.path_segments()?;
+            match segments.next() {
+                Some("watch") => parsed.query_pairs().find(|(k, _)| k == "v").map(|(_, v)| v.into_owned()),
+                Some("shorts" | "embed" | "live") => segments.next().map(str::to_string),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    id.filter(|id| is_id(id))
+}
+
+/// A batch input as a link, or `None` when it is a topic. Links may leave
+/// out the scheme.
+pub fn as_link(input: &str) -> Option<String> {
+    let input = input.trim();
+    if input.contains("://") {
+        Some(input.to_string())
+    } else if ["www.", "m.youtube.", "youtube.", "youtu.be/"].iter().any(|p| input.starts_with(p)) {
+        Some(format!("https://{}", input))
+    } else {
+        None
+    }
+}
+
+/// Store a free-text topic as a trend, returning its id. The same topic,
+/// in any case and spacing, is stored once.
+pub fn add_topic(db: &Database, topic: &str) -> rusqlite::Result<i64> {
+    db.upsert_trend(&topic_trend(topic))
+}
+
+/// The trend a free-text topic is stored as
+pub fn topic_trend(topic: &str) -> Trend {
+    let topic = topic.split_whitespace().collect::<Vec<_>>().join(" ");
+    Trend {
+        id: None,
+        video_id: format!("topic:{}", topic.to_lowercase()),
+        title: topic,
+        channel: None,
+        views: None,
+        category: None,
+        fetched_at: Utc::now(),
+        transcript: None,
+        tags: Vec::new(),
+        source: TrendSource::ManualTopic,
+        notes: None,
+        starred: false,
+        state: TrendState::New,
+        skip_reason: None,
+        dry_run: false,
+    }
+}
+
+/// Look up the video a link points to and store it as a trend, returning its
+/// id. A video already stored is only refreshed.
+pub async fn add_from_url(state: &AppState, url: &str) -> Result<i64, String> {
+    let video_id = video_id_from_url(url)
+        .ok_or_else(|| format!("Not a YouTube video link: {}", url))?;
+    let config = state.db.load_config().map_err(|e| e.to_string())?;
+    let videos = state.clients.youtube(&config)?.videos(&[&video_id]).await?;
+    let video = videos.first()
+        .ok_or_else(|| format!("Video {} not found", video_id))?;
+    let trend_id = state.db.upsert_trend(&to_trend(video, Utc::now())).map_err(|e| e.to_string())?;
+    tracing::info!("Added trend {} from {}", trend_id, url);
+    Ok(trend_id)
+}
@@ -0,0 +1,280 @@
+// Text-to-speech engines for ShotAuto
+use crate::clients::Clients;
+use crate::costs::{self, CostKind};
+use crate::db::{Config, TtsBackend};
+use crate::http;
+use crate::resilience::{self, Service};
+use crate::timing::{self, SpeechTiming};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Public key used by the Edge browser's read-aloud feature
+const EDGE_TRUSTED_CLIENT_TOKEN: &str = "6A5AA1D4EAFF4E9FB37E23D68491D6F4";
+
+/// Sample used by `preview` when the caller gives no text
+const DEFAULT_PREVIEW_TEXT: &str = "Did you know? This is how your next short will sound.";
+
+/// Voices offered by the OpenAI speech API (not exposed through an endpoint)
+const OPENAI_VOICES: &[&str] = &[
+    "alloy", "ash", "ballad", "coral", "echo", "fable", "nova", "onyx", "sage", "shimmer",
+];
+
+/// A voice as shown in the UI's voice picker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Voice {
+    pub backend: TtsBackend,
+    pub id: String,
+    pub name: String,
+    /// BCP-47 locale such as `en-US`; `None` for multilingual voices
+    pub locale: Option<String>,
+    pub gender: Option<String>,
+}
+
+/// A speech synthesis backend
+pub trait TtsEngine {
+    /// Synthesize `text` with `voice` into an MP3 file at `output`.
+    /// Engines that report word or sentence boundaries return that timing.
+    async fn synthesize(&self, text: &str, voice: &str, output: &Path) -> Result<Option<SpeechTiming>, String>;
+    
+    /// Voices this backend can use
+    async fn list_voices(&self) -> Result<Vec<Voice>, String>;
+}
+
+/// OpenAI `audio/speech` API
+pub struct OpenAiTts {
+    pub http: Client,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl TtsEngine for OpenAiTts {
+    async fn synthesize(&self, text: &str, voice: &str, output: &Path) -> Result<Option<SpeechTiming>, String> {
+        let request = self.http
+            .post(format!("{}/audio/speech", self.base_url.trim_end_matches('/')))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "model": self.model,
+                "input": text,
+                "voice": voice,
+                "response_format": "mp3",
+            }));
+        let response = resilience::send(Service::Tts, request).await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("OpenAI TTS failed ({}): {}", status, body));
+        }
+        costs::charge(CostKind::TtsCharacters, text.chars().count() as f64);
+        
+        let audio = response.bytes().await.map_err(|e| e.to_string())?;
+        tokio::fs::write(output, &audio).await.map_err(|e| e.to_string())?;
+        // The speech API reports no timing
+        Ok(None)
+    }
+    
+    async fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        Ok(OPENAI_VOICES
+            .iter()
+            .map(|v| Voice {
+                backend: TtsBackend::OpenAi,
+                id: v.to_string(),
+                name: v.to_string(),
+                locale: None,
+                gender: None,
+            })
+            .collect())
+    }
+}
+
+/// Microsoft Edge read-aloud voices. Synthesis goes through the `edge-tts`
+/// CLI, which handles the service's websocket protocol; no key is needed.
+pub struct EdgeTts {
+    /// `edge-tts` binary; falls back to `edge-tts` on PATH
+    pub binary: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EdgeVoice {
+    short_name: String,
+    friendly_name: Option<String>,
+    locale: String,
+    gender: Option<String>,
+}
+
+impl TtsEngine for EdgeTts {
+    async fn synthesize(&self, text: &str, voice: &str, output: &Path) -> Result<Option<SpeechTiming>, String> {
+        let subtitles = output.with_extension("srt");
+        // edge-tts is a client for Microsoft's online service
+        resilience::call(Service::Tts, async {
+            let result = Command::new(&self.binary)
+                .args(["--voice", voice, "--text", text])
+                .arg("--write-media")
+                .arg(output)
+                .arg("--write-subtitles")
+                .arg(&subtitles)
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to start {}: {}", self.binary, e))?;
+            if !result.status.success() {
+                return Err(format!(
+                    "edge-tts exited with {}: {}",
+                    result.status,
+                    String::from_utf8_lossy(&result.stderr).trim()
+                ));
+            }
+            Ok(())
+        })
+        .await?;
+        
+        // edge-tts writes word boundaries (older releases) or sentence cues (newer)
+        let cues = tokio::fs::read_to_string(&subtitles).await.unwrap_or_default();
+        let _ = tokio::fs::remove_file(&subtitles).await;
+        Ok(timing::from_cues(timing::parse_subtitles(&cues)))
+    }
+    
+    async fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        let url = format!(
+            "https://speech.platform.bing.com/consumer/speech/synthesize/readaloud/voices/list?trustedclienttoken={}",
+            EDGE_TRUSTED_CLIENT_TOKEN
+        );
+        let response = http::send(http::client().get(&url)).await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Edge voice list returned {}", response.status()));
+        }
+        let voices: Vec<EdgeVoice> = response.json().await.map_err(|e| e.to_string())?;
+        Ok(voices
+            .into_iter()
+            .map(|v| Voice {
+                backend: TtsBackend::Edge,
+                name: v.friendly_name.unwrap_or_else(|| v.short_name.clone()),
+                id: v.short_name,
+                locale: Some(v.locale),
+                gender: v.gender,
+            })
+            .collect())
+    }
+}
+
+/// Any configured engine, so callers can pick one at runtime
+pub enum Engine {
+    OpenAi(OpenAiTts),
+    Edge(EdgeTts),
+}
+
+impl TtsEngine for Engine {
+    async fn synthesize(&self, text: &str, voice: &str, output: &Path) -> Result<Option<SpeechTiming>, String> {
+        match self {
+            Engine::OpenAi(e) => e.synthesize(text, voice, output).await,
+            Engine::Edge(e) => e.synthesize(text, voice, output).await,
+        }
+    }
+    
+    async fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        match self {
+            Engine::OpenAi(e) => e.list_voices().await,
+            Engine::Edge(e) => e.list_voices().await,
+        }
+    }
+}
+
+/// Build the engine for a backend from the current config
+pub fn engine(clients: &Clients, config: &Config, backend: TtsBackend) -> Result<Engine, String> {
+    match backend {
+        TtsBackend::OpenAi => Ok(Engine::OpenAi(OpenAiTts {
+            http: clients.http.clone(),
+            base_url: clients.openai_url.clone(),
+            api_key: config
+                .openai_api_key
+                .clone()
+                .ok_or("openai_api_key is not configured")?,
+            model: config.openai_tts_model.clone(),
+        })),
+        TtsBackend::Edge => Ok(Engine::Edge(EdgeTts {
+            binary: config.edge_tts_path.clone().unwrap_or_else(|| "edge-tts".to_string()),
+        })),
+    }
+}
+
+/// Synthesize a short sample into the temp preview directory and return its path.
+/// Previews are cached by backend, voice and text, so re-auditioning is instant.
+pub async fn preview(clients: &Clients, config: &Config, backend: TtsBackend, voice: &str, sample_text: &str) -> Result<PathBuf, String> {
+    let text = if sample_text.trim().is_empty() { DEFAULT_PREVIEW_TEXT } else { sample_text.trim() };
+    
+    let mut hasher = DefaultHasher::new();
+    (backend.as_str(), voice, text).hash(&mut hasher);
+    let dir = std::env::temp_dir().join("shotauto").join("voice-previews");
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{:016x}.mp3", hasher.finish()));
+    
+    if !path.exists() {
+        engine(clients, config, backend)?.synthesize(text, voice, &path).await?;
+    }
+    Ok(path)
+}
+
+/// Voice for a language: the per-language mapping first, then the default voice
+pub fn voice_for_language(config: &Config, language: &str) -> Option<String> {
+    config
+        .language_voices
+        .get(language)
+        .cloned()
+        .or_else(|| config.tts_voice.clone())
+}
+
+/// Voices from every backend that is usable with the current config.
+/// A backend that fails (e.g. no API key) is skipped rather than failing the whole list.
+pub async fn list_all_voices(clients: &Clients, config: &Config) -> Vec<Voice> {
+    let mut voices = Vec::new();
+    for backend in TtsBackend::ALL {
+        let result = match engine(clients, config, backend) {
+            Ok(engine) => engine.list_voices().await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(mut list) => voices.append(&mut list),
+            Err(e) => tracing::warn!("Skipping {} voices: {}", backend.as_str(), e),
+        }
+    }
+    voices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockServer, Reply};
+    use std::time::Duration;
+    
+    fn openai(server: &MockServer) -> Engine {
+        let config = Config { openai_api_key: Some("sk-test".to_string()), ..Default::default() };
+        Clients::at(&server.url(), server.http()).tts(&config, TtsBackend::OpenAi).unwrap()
+    }
+    
+    fn output(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("shotauto-tts-test-{}-{}.mp3", std::process::id(), name))
+    }
+    
+    #[tokio::test]
+    async fn openai_rate_limit_is_an_error_and_writes_nothing() {
+        let server = MockServer::start(Reply::json(429, r#"{"error": {"message": "Rate limit reached"}}"#)).await;
+        let path = output("429");
+        let err = openai(&server).synthesize("Hello", "alloy", &path).await.unwrap_err();
+        assert!(err.contains("429"), "{}", err);
+        assert!(!path.exists());
+    }
+    
+    #[tokio::test]
+    async fn openai_timeout_is_an_error() {
+        let server = MockServer::start(Reply::json(200, "").delayed(Duration::from_secs(2))).await;
+        let path = output("timeout");
+        assert!(openai(&server).synthesize("Hello", "alloy", &path).await.is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
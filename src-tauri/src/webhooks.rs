@@ -0,0 +1,138 @@
+// Outgoing webhooks: signed JSON POSTs on pipeline events
+use crate::crypto::{hex, hmac_sha256};
+use crate::db::Webhook;
+use crate::http;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Delays between delivery attempts; a webhook gets one more try than there
+/// are entries
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+    Duration::from_secs(300),
+];
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "X-ShotAuto-Signature";
+
+/// Pipeline event a webhook can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum WebhookEvent {
+    /// A job used up its retries and is dead
+    #[serde(rename = "job.failed")]
+    JobFailed,
+    #[serde(rename = "short.rendered")]
+    ShortRendered,
+    #[serde(rename = "short.uploaded")]
+    ShortUploaded,
+    /// Sent by `test_webhook` regardless of subscriptions
+    #[serde(rename = "webhook.test")]
+    Test,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::JobFailed => "job.failed",
+            WebhookEvent::ShortRendered => "short.rendered",
+            WebhookEvent::ShortUploaded => "short.uploaded",
+            WebhookEvent::Test => "webhook.test",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: WebhookEvent,
+    created_at: String,
+    data: &'a serde_json::Value,
+}
+
+/// Send `event` to every webhook subscribed to it. Deliveries run in the
+/// background and are retried. Announcing an upload counts as publishing
+/// the short, so those end up in its publications, one row per webhook
+/// host; other failures only reach the app log.
+pub fn dispatch(state: &AppState, event: WebhookEvent, data: serde_json::Value) {
+    let webhooks = match state.db.webhooks_for_event(event).map_err(|e| e.to_string()) {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            tracing::warn!("Failed to load webhooks for {}: {}", event.as_str(), e);
+            return;
+        }
+    };
+    let short_id = data.get("short_id")
+        .and_then(serde_json::Value::as_i64)
+        .filter(|_| event == WebhookEvent::ShortUploaded);
+    for webhook in webhooks {
+        let data = data.clone();
+        let db = state.db.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut result = send(&webhook, event, &data).await;
+            for delay in RETRY_DELAYS {
+                if result.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(delay).await;
+                result = send(&webhook, event, &data).await;
+            }
+            if let Some(short_id) = short_id {
+                let outcome = result.as_ref().map(|_| event.as_str()).map_err(String::as_str);
+                if let Err(e) = db.record_publication(short_id, "webhook", &destination(&webhook.url), outcome) {
+                    tracing::warn!("Failed to record webhook delivery of short {}: {}", short_id, e);
+                }
+            }
+            if let Err(e) = result {
+                tracing::warn!("Webhook {} gave up on {}: {}", webhook.url, event.as_str(), e);
+            }
+        });
+    }
+}
+
+/// Host a webhook posts to, as its publications record it. Paths of
+/// webhook URLs often carry tokens, so they are left out.
+fn destination(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "webhook".to_string())
+}
+
+/// POST one event to one webhook. Any 2xx response counts as delivered.
+pub async fn send(webhook: &Webhook, event: WebhookEvent, data: &serde_json::Value) -> Result<(), String> {
+    let body = serde_json::to_vec(&Payload {
+        event,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        data,
+    })
+    .map_err(|e| e.to_string())?;
+    let response = http::client()
+        .post(&webhook.url)
+        .timeout(Duration::from_secs(30))
+        .header("Content-Type", "application/json")
+        .header("X-ShotAuto-Event", event.as_str())
+        .header(SIGNATURE_HEADER, format!("sha256={}", sign(&webhook.secret, &body)))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// HMAC-SHA256 of `body`, hex encoded
+fn sign(secret: &str, body: &[u8]) -> String {
+    hex(&hmac_sha256(secret.as_bytes(), body))
+}
+
+/// Random 32-byte secret from the OS CSPRNG, hex encoded. Used as webhook
+/// HMAC keys and OAuth `state`, so it must not be guessable.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator unavailable");
+    hex(&bytes)
+}
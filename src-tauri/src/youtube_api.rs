@@ -0,0 +1,131 @@
+// YouTube Data API reads: trending videos and lookups by id
+use crate::resilience::{self, Service};
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Most popular videos fetched per poll; the API's maximum page size
+const PAGE_SIZE: &str = "50";
+
+/// A `videos.list` response
+#[derive(Debug, Deserialize)]
+pub struct VideoList {
+    #[serde(default)]
+    pub items: Vec<Video>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Video {
+    pub id: String,
+    pub snippet: Snippet,
+    #[serde(default)]
+    pub statistics: Option<Statistics>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub title: String,
+    pub channel_title: Option<String>,
+    pub category_id: Option<String>,
+    /// Keywords the uploader set; left out when there are none
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Counts come back as strings; hidden ones are left out
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub view_count: Option<String>,
+    pub like_count: Option<String>,
+    pub comment_count: Option<String>,
+}
+
+/// Read access to the YouTube Data API
+pub trait YoutubeClient {
+    /// The current most popular videos
+    async fn most_popular(&self) -> Result<Vec<Video>, String>;
+    
+    /// Up to 50 videos by id; deleted and private ones are left out
+    async fn videos(&self, ids: &[&str]) -> Result<Vec<Video>, String>;
+}
+
+/// The YouTube Data API v3, authenticated with an API key
+pub struct YoutubeApi {
+    pub http: Client,
+    pub base_url: String,
+    pub api_key: String,
+}
+
+impl YoutubeApi {
+    async fn list(&self, query: &[(&str, &str)]) -> Result<Vec<Video>, String> {
+        let request = self.http
+            .get(format!("{}/videos", self.base_url.trim_end_matches('/')))
+            .query(&[("part", "snippet,statistics"), ("key", self.api_key.as_str())])
+            .query(query);
+        let response = resilience::send(Service::YouTube, request).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("YouTube videos.list failed ({}): {}", status, body));
+        }
+        let list: VideoList = response.json().await.map_err(|e| format!("YouTube returned an unexpected response: {}", e))?;
+        Ok(list.items)
+    }
+}
+
+impl YoutubeClient for YoutubeApi {
+    async fn most_popular(&self) -> Result<Vec<Video>, String> {
+        self.list(&[("chart", "mostPopular"), ("maxResults", PAGE_SIZE)]).await
+    }
+    
+    async fn videos(&self, ids: &[&str]) -> Result<Vec<Video>, String> {
+        self.list(&[("id", &ids.join(","))]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::Clients;
+    use crate::db::Config;
+    use crate::mock_server::{MockServer, Reply};
+    use std::time::Duration;
+    
+    fn client(server: &MockServer) -> YoutubeApi {
+        let config = Config { youtube_api_key: Some("key".to_string()), ..Default::default() };
+        Clients::at(&server.url(), server.http()).youtube(&config).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn parses_videos() {
+        let server = MockServer::start(Reply::json(200, r#"{"items": [{"id": "abc", "snippet": {"title": "A video"}, "statistics": {"viewCount": "12"}}]}"#)).await;
+        let videos = client(&server).videos(&["abc"]).await.unwrap();
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].snippet.title, "A video");
+        assert!(server.requests()[0].starts_with("GET /videos?part=snippet%2Cstatistics&key=key&id=abc"));
+    }
+    
+    #[tokio::test]
+    async fn quota_exceeded_is_an_error_after_retrying() {
+        let server = MockServer::start(Reply::json(429, r#"{"error": {"message": "quotaExceeded"}}"#)).await;
+        let err = client(&server).most_popular().await.unwrap_err();
+        assert!(err.contains("429"), "{}", err);
+        assert!(err.contains("quotaExceeded"), "{}", err);
+        // GETs are retried on 429
+        assert_eq!(server.requests().len(), 3);
+    }
+    
+    #[tokio::test]
+    async fn malformed_json_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"items": [{"id": 5}"#)).await;
+        let err = client(&server).most_popular().await.unwrap_err();
+        assert!(err.contains("unexpected response"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn timeout_is_an_error() {
+        let server = MockServer::start(Reply::json(200, r#"{"items": []}"#).delayed(Duration::from_secs(2))).await;
+        assert!(client(&server).videos(&["abc"]).await.is_err());
+    }
+}
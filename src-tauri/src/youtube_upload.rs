@@ -0,0 +1,695 @@
+// YouTube uploads through the Data API, authorized with OAuth
+use crate::db::{Config, Short, Trend, TrendSource, UploadTemplate};
+use crate::hashtags::{self, SHORTS_TAG};
+use crate::http;
+use crate::prompt::ChannelVoice;
+use crate::resilience::{self, Service};
+use crate::sfx;
+use crate::transfers::{self, Direction};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const UPLOAD_URL: &str = "https://www.googleapis.com/upload/youtube/v3/videos";
+const THUMBNAIL_URL: &str = "https://www.googleapis.com/upload/youtube/v3/thumbnails/set";
+const API_URL: &str = "https://www.googleapis.com/youtube/v3";
+
+/// Chunks of a resumable upload must be multiples of this, except the last
+const UPLOAD_CHUNK_UNIT: usize = 256 * 1024;
+
+/// Seconds of upload at the cap each chunk of a capped upload holds
+const UPLOAD_CHUNK_SECS: u64 = 10;
+
+/// Bytes a capped upload is paced in
+const UPLOAD_PIECE: usize = 16 * 1024;
+
+/// What a resumable session answers to each chunk but the last
+const RESUME_INCOMPLETE: reqwest::StatusCode = reqwest::StatusCode::PERMANENT_REDIRECT;
+
+/// Uploading and managing playlists both need the full scope
+pub const SCOPE: &str = "https://www.googleapis.com/auth/youtube";
+
+/// How long the consent page may stay open before connecting gives up
+const AUTH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// YouTube's limit on video titles, in characters
+const MAX_TITLE_CHARS: usize = 100;
+
+/// Error reasons meaning the API quota is used up for the day
+const QUOTA_REASONS: &[&str] = &["quotaExceeded", "dailyLimitExceeded", "uploadLimitExceeded"];
+
+/// Title used when no upload template applies
+const DEFAULT_TITLE: &str = "{title}";
+
+/// Description used when no upload template applies
+const DEFAULT_DESCRIPTION: &str = "{script}\n\n{hashtags}";
+
+/// YouTube's limit on descriptions, in bytes
+const MAX_DESCRIPTION_BYTES: usize = 5000;
+
+/// Hashtags `{hashtags}` adds after `#Shorts`; YouTube only shows the
+/// first three above the title
+const MAX_HASHTAGS: usize = 3;
+
+/// YouTube's limit on all tags together, in characters
+const MAX_TAGS_CHARS: usize = 500;
+
+/// Title, description and tags sent with an upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+impl VideoMetadata {
+    /// Fill an upload template for a short, or the built-in one without a
+    /// template. Placeholders: `{title}`, `{hook}`, `{script}`, `{language}`,
+    /// `{hashtags}`, `{source_credit}`, `{affiliate_links}`, and the voice's
+    /// `{audience}`, `{tone}`, `{banned_topics}`, `{cta}`, `{channel_handle}`.
+    /// Descriptions that place no part of the voice end with its call to
+    /// action and handle.
+    pub fn build(template: Option<&UploadTemplate>, short: &Short, trend: &Trend, voice: &ChannelVoice) -> Self {
+        let tags = template.map(|t| t.tags.clone()).unwrap_or_default();
+        let hashtags: Vec<String> = hashtags::suggest(trend, &tags).into_iter().take(MAX_HASHTAGS + 1).collect();
+        // A topic typed in by hand has no video to credit
+        let source_credit = match trend.source {
+            TrendSource::YouTube => format!(
+                "Inspired by \"{}\"{}: https://www.youtube.com/watch?v={}",
+                trend.title,
+                trend.channel.as_deref().map(|c| format!(" by {}", c)).unwrap_or_default(),
+                trend.video_id
+            ),
+            TrendSource::ManualTopic | TrendSource::Demo => String::new(),
+        };
+        let hook = short.hook().unwrap_or_default();
+        let fill = |text: &str| {
+            voice.fill(text)
+                .replace("{title}", &trend.title)
+                .replace("{hook}", &hook)
+                .replace("{script}", &sfx::strip(short.script.as_deref().unwrap_or_default()))
+                .replace("{language}", &short.language)
+                .replace("{hashtags}", &hashtags.join(" "))
+                .replace("{source_credit}", &source_credit)
+                .replace("{affiliate_links}", template.map(|t| t.affiliate_links.trim()).unwrap_or_default())
+        };
+        let title = fill(template.map(|t| t.title.as_str()).unwrap_or(DEFAULT_TITLE));
+        let description_template = template.map(|t| t.description.as_str()).unwrap_or(DEFAULT_DESCRIPTION);
+        let mut description = fill(description_template);
+        if !ChannelVoice::is_placed_in(description_template) {
+            let sign_off = [voice.cta.trim().to_string(), voice.handle()].join(" ");
+            if !sign_off.trim().is_empty() {
+                description = format!("{}\n\n{}", description.trim_end(), sign_off.trim());
+            }
+        }
+        Self { title, description, tags }.optimized()
+    }
+    
+    /// Bring metadata within what YouTube accepts and files as a Short: the
+    /// title within 100 characters, cut between words; `#Shorts` in the title
+    /// or description; the description within 5000 bytes; and tags without
+    /// blanks or repeats, within 500 characters together.
+    pub fn optimized(self) -> Self {
+        let title = fit_title(clean(&self.title).trim());
+        let description = clean(&self.description);
+        let mut description = truncate_bytes(description.trim(), MAX_DESCRIPTION_BYTES).trim_end().to_string();
+        if !has_shorts_tag(&title) && !has_shorts_tag(&description) {
+            let room = MAX_DESCRIPTION_BYTES - SHORTS_TAG.len() - 2;
+            description = format!("{}\n\n{}", truncate_bytes(&description, room).trim_end(), SHORTS_TAG)
+                .trim()
+                .to_string();
+        }
+        let mut seen = std::collections::HashSet::new();
+        let mut chars = 0;
+        let tags = self.tags.iter()
+            .map(|tag| clean(tag).trim().to_string())
+            .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+            .take_while(|tag| {
+                chars += tag.chars().count();
+                chars <= MAX_TAGS_CHARS
+            })
+            .collect();
+        Self { title, description, tags }
+    }
+}
+
+/// `title` cut to YouTube's limit at the last space that fits
+fn fit_title(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let cut: String = title.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > 0 => &cut[..end],
+        _ => cut.as_str(),
+    };
+    cut.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '-')).to_string()
+}
+
+fn has_shorts_tag(text: &str) -> bool {
+    text.split_whitespace().any(|word| word.eq_ignore_ascii_case(SHORTS_TAG))
+}
+
+/// YouTube rejects angle brackets in titles and descriptions
+fn clean(text: &str) -> String {
+    text.replace(['<', '>'], "")
+}
+
+fn truncate_bytes(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Channel an account uploads to
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub id: String,
+    pub title: String,
+}
+
+/// Playlist on the authorized account's channel
+#[derive(Debug, Clone, Serialize)]
+pub struct Playlist {
+    pub id: String,
+    pub title: String,
+    pub item_count: u32,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+}
+
+/// OAuth client of the user's Google Cloud project
+fn client(config: &Config) -> Result<(&str, &str), String> {
+    let client_id = config.youtube_client_id.as_deref().ok_or("YouTube OAuth client ID is not configured")?;
+    let client_secret = config.youtube_client_secret.as_deref().ok_or("YouTube OAuth client secret is not configured")?;
+    Ok((client_id, client_secret))
+}
+
+/// Run Google's installed-app consent flow for `scope`: `open_browser` is
+/// given the consent URL, which redirects back to a one-shot listener on
+/// localhost. Returns the refresh token.
+pub async fn authorize(config: &Config, scope: &str, open_browser: impl FnOnce(&str) -> Result<(), String>) -> Result<String, String> {
+    let (client_id, client_secret) = client(config)?;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.map_err(|e| e.to_string())?;
+    let redirect_uri = format!("http://127.0.0.1:{}", listener.local_addr().map_err(|e| e.to_string())?.port());
+    let state = crate::webhooks::generate_secret();
+    let url = reqwest::Url::parse_with_params(AUTH_URL, &[
+        ("client_id", client_id),
+        ("redirect_uri", &redirect_uri),
+        ("response_type", "code"),
+        ("scope", scope),
+        // offline + consent makes Google hand out a refresh token every time
+        ("access_type", "offline"),
+        ("prompt", "consent"),
+        ("state", &state),
+    ])
+    .map_err(|e| e.to_string())?;
+    open_browser(url.as_str())?;
+    
+    let code = tokio::time::timeout(AUTH_TIMEOUT, receive_code(&listener, &state))
+        .await
+        .map_err(|_| "Timed out waiting for Google sign-in".to_string())??;
+    let response = http::client()
+        .post(TOKEN_URL)
+        .form(&[
+            ("code", code.as_str()),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("redirect_uri", &redirect_uri),
+            ("grant_type", "authorization_code"),
+        ])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let tokens: TokenResponse = parse(response, "token exchange").await?;
+    tokens.refresh_token.ok_or_else(|| "Google did not return a refresh token".to_string())
+}
+
+/// Accept redirects until one carries our `state`, answering each with a page
+/// the user can close
+async fn receive_code(listener: &tokio::net::TcpListener, state: &str) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+        let mut buf = vec![0u8; 8192];
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let Some(path) = request.lines().next().and_then(|line| line.split_whitespace().nth(1)) else {
+            continue;
+        };
+        let Ok(url) = reqwest::Url::parse(&format!("http://127.0.0.1{}", path)) else {
+            continue;
+        };
+        let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.to_string());
+        if param("state").as_deref() != Some(state) {
+            // Favicon requests and the like
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+            continue;
+        }
+        let (body, result) = match (param("code"), param("error")) {
+            (Some(code), _) => ("ShotAuto is connected to your Google account. You can close this window.", Ok(code)),
+            (None, error) => (
+                "Google sign-in was cancelled. You can close this window.",
+                Err(format!("Google sign-in failed: {}", error.unwrap_or_else(|| "no code returned".to_string()))),
+            ),
+        };
+        let page = format!("<html><body><p>{}</p></body></html>", body);
+        let _ = stream
+            .write_all(format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+            ).as_bytes())
+            .await;
+        return result;
+    }
+}
+
+/// Fresh access token for an account's refresh token
+pub async fn access_token(config: &Config, refresh_token: &str) -> Result<String, String> {
+    let (client_id, client_secret) = client(config)?;
+    let request = http::client()
+        .post(TOKEN_URL)
+        .form(&[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ]);
+    let response = resilience::send(Service::YouTube, request).await?;
+    let tokens: TokenResponse = parse(response, "token refresh").await?;
+    Ok(tokens.access_token)
+}
+
+/// Channel of the authorized account
+pub async fn my_channel(access_token: &str) -> Result<Channel, String> {
+    #[derive(Deserialize)]
+    struct Snippet {
+        title: String,
+    }
+    #[derive(Deserialize)]
+    struct Item {
+        id: String,
+        snippet: Snippet,
+    }
+    #[derive(Deserialize)]
+    struct Channels {
+        #[serde(default)]
+        items: Vec<Item>,
+    }
+    let request = http::client()
+        .get(format!("{}/channels", API_URL))
+        .query(&[("part", "snippet"), ("mine", "true")])
+        .bearer_auth(access_token);
+    let response = resilience::send(Service::YouTube, request).await?;
+    let channels: Channels = parse(response, "channels.list").await?;
+    let item = channels.items.into_iter().next().ok_or("This Google account has no YouTube channel")?;
+    Ok(Channel { id: item.id, title: item.snippet.title })
+}
+
+/// Upload a video with a resumable session, returning its video id. With
+/// `publish_at` the video stays private and YouTube publishes it then;
+/// otherwise it is public straight away.
+pub async fn upload_video(
+    config: &Config,
+    access_token: &str,
+    video_path: &Path,
+    metadata: &VideoMetadata,
+    publish_at: Option<DateTime<Utc>>,
+) -> Result<String, String> {
+    let bytes = tokio::fs::read(video_path).await.map_err(|e| e.to_string())?;
+    if bytes.is_empty() {
+        return Err(format!("{} is empty", video_path.display()));
+    }
+    let mut status = serde_json::json!({
+        "privacyStatus": "public",
+        "selfDeclaredMadeForKids": false,
+    });
+    if let Some(publish_at) = publish_at {
+        status["privacyStatus"] = "private".into();
+        status["publishAt"] = publish_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true).into();
+    }
+    let resource = serde_json::json!({
+        "snippet": {
+            "title": metadata.title,
+            "description": metadata.description,
+            "tags": metadata.tags,
+            // People & Blogs, YouTube's default
+            "categoryId": "22",
+        },
+        "status": status,
+    });
+    
+    let client = http::client();
+    let request = client
+        .post(UPLOAD_URL)
+        .query(&[("uploadType", "resumable"), ("part", "snippet,status")])
+        .bearer_auth(access_token)
+        .header("X-Upload-Content-Type", "video/mp4")
+        .header("X-Upload-Content-Length", bytes.len().to_string())
+        .json(&resource);
+    let session = resilience::send(Service::YouTube, request).await?;
+    if !session.status().is_success() {
+        let status = session.status();
+        let body = session.text().await.unwrap_or_default();
+        return Err(format!("YouTube upload session failed ({}): {}", status, body));
+    }
+    let location = session
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or("YouTube did not return an upload URL")?
+        .to_string();
+    
+    #[derive(Deserialize)]
+    struct Video {
+        id: String,
+    }
+    let response = put_resumable(config, Service::YouTube, access_token, &location, video_path, &bytes, "video/mp4").await?;
+    let video: Video = parse(response, "video upload").await?;
+    Ok(video.id)
+}
+
+/// Send `bytes` to a resumable upload session at `location`, in chunks when
+/// uploads are capped. The whole upload takes one rate limit token and
+/// counts once toward the service's breaker. Returns the response to the
+/// final chunk; Drive speaks the same protocol as YouTube.
+pub async fn put_resumable(
+    config: &Config,
+    service: Service,
+    access_token: &str,
+    location: &str,
+    path: &Path,
+    bytes: &[u8],
+    content_type: &str,
+) -> Result<reqwest::Response, String> {
+    let label = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let transfer = Arc::new(transfers::start(Direction::Upload, label, Some(bytes.len() as u64)));
+    let limit = Direction::Upload.limit(config);
+    let chunk_size = chunk_size(bytes.len(), limit);
+    resilience::call(service, put_chunks(access_token, location, bytes, content_type, chunk_size, transfer, limit)).await
+}
+
+/// Capped uploads go up in chunks of about `UPLOAD_CHUNK_SECS` at the cap,
+/// in whole `UPLOAD_CHUNK_UNIT`s; uncapped ones in a single request
+fn chunk_size(len: usize, limit: Option<u64>) -> usize {
+    match limit {
+        Some(limit) => ((limit * UPLOAD_CHUNK_SECS) as usize / UPLOAD_CHUNK_UNIT).max(1) * UPLOAD_CHUNK_UNIT,
+        None => len,
+    }
+}
+
+async fn put_chunks(
+    access_token: &str,
+    location: &str,
+    bytes: &[u8],
+    content_type: &str,
+    chunk_size: usize,
+    transfer: Arc<transfers::Handle>,
+    limit: Option<u64>,
+) -> Result<reqwest::Response, String> {
+    let client = http::client();
+    let mut offset = 0;
+    loop {
+        let end = (offset + chunk_size).min(bytes.len());
+        let request = client
+            .put(location)
+            .bearer_auth(access_token)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header(reqwest::header::CONTENT_LENGTH, end - offset)
+            .header(reqwest::header::CONTENT_RANGE, format!("bytes {}-{}/{}", offset, end - 1, bytes.len()))
+            .body(paced(bytes[offset..end].to_vec(), transfer.clone(), limit));
+        let response = http::send(request).await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Upload failed ({}): {}", status, body));
+        }
+        if status != RESUME_INCOMPLETE {
+            return Ok(response);
+        }
+        // The session says how much it has; carry on from there
+        let received = received_bytes(response.headers());
+        if received <= offset {
+            return Err(format!("Upload session accepted none of bytes {}-{}", offset, end - 1));
+        }
+        offset = received;
+    }
+}
+
+/// `chunk` as a request body that goes out `UPLOAD_PIECE` bytes at a time,
+/// each piece once the upload cap lets it through, so a capped upload runs
+/// at an even pace instead of in bursts
+fn paced(chunk: Vec<u8>, transfer: Arc<transfers::Handle>, limit: Option<u64>) -> reqwest::Body {
+    let (tx, rx) = async_channel::bounded::<std::io::Result<Vec<u8>>>(1);
+    tokio::spawn(async move {
+        for piece in chunk.chunks(UPLOAD_PIECE) {
+            transfer.advance(piece.len() as u64, limit).await;
+            // The request has stopped reading its body
+            if tx.send(Ok(piece.to_vec())).await.is_err() {
+                break;
+            }
+        }
+    });
+    reqwest::Body::wrap_stream(rx)
+}
+
+/// Bytes a resumable session holds, from the `Range: bytes=0-N` of a 308
+fn received_bytes(headers: &reqwest::header::HeaderMap) -> usize {
+    headers
+        .get(reqwest::header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|range| range.rsplit('-').next()?.trim().parse::<usize>().ok())
+        .map(|last| last + 1)
+        .unwrap_or(0)
+}
+
+/// Set an uploaded video's custom thumbnail from a JPEG or PNG file. The
+/// channel has to be verified for YouTube to accept it.
+pub async fn set_thumbnail(access_token: &str, video_id: &str, path: &Path) -> Result<(), String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
+    let content_type = match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+    };
+    let request = http::client()
+        .post(THUMBNAIL_URL)
+        .query(&[("videoId", video_id)])
+        .bearer_auth(access_token)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(bytes);
+    let response = resilience::send(Service::YouTube, request).await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("thumbnails.set failed ({}): {}", status, body));
+    }
+    Ok(())
+}
+
+/// Append a video to one of the account's playlists
+pub async fn add_to_playlist(access_token: &str, playlist_id: &str, video_id: &str) -> Result<(), String> {
+    let resource = serde_json::json!({
+        "snippet": {
+            "playlistId": playlist_id,
+            "resourceId": {
+                "kind": "youtube#video",
+                "videoId": video_id,
+            },
+        },
+    });
+    let request = http::client()
+        .post(format!("{}/playlistItems", API_URL))
+        .query(&[("part", "snippet")])
+        .bearer_auth(access_token)
+        .json(&resource);
+    let response = resilience::send(Service::YouTube, request).await?;
+    parse::<serde_json::Value>(response, "playlistItems.insert").await?;
+    Ok(())
+}
+
+/// All playlists of the authorized account's channel
+pub async fn my_playlists(access_token: &str) -> Result<Vec<Playlist>, String> {
+    #[derive(Deserialize)]
+    struct Snippet {
+        title: String,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ContentDetails {
+        item_count: u32,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Item {
+        id: String,
+        snippet: Snippet,
+        content_details: Option<ContentDetails>,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Playlists {
+        #[serde(default)]
+        items: Vec<Item>,
+        next_page_token: Option<String>,
+    }
+    let client = http::client();
+    let mut playlists = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!("{}/playlists", API_URL))
+            .query(&[("part", "snippet,contentDetails"), ("mine", "true"), ("maxResults", "50")])
+            .bearer_auth(access_token);
+        if let Some(ref token) = page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+        let response = resilience::send(Service::YouTube, request).await?;
+        let page: Playlists = parse(response, "playlists.list").await?;
+        playlists.extend(page.items.into_iter().map(|item| Playlist {
+            id: item.id,
+            title: item.snippet.title,
+            item_count: item.content_details.map(|d| d.item_count).unwrap_or_default(),
+        }));
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return Ok(playlists),
+        }
+    }
+}
+
+/// Public link to a Short
+pub fn short_url(video_id: &str) -> String {
+    format!("https://youtube.com/shorts/{}", video_id)
+}
+
+/// Whether an API error says the quota is used up; it resets at midnight
+/// Pacific time
+pub fn is_quota_error(error: &str) -> bool {
+    QUOTA_REASONS.iter().any(|reason| error.contains(reason))
+}
+
+/// Deserialize a successful response, or turn an error response into a message
+async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response, what: &str) -> Result<T, String> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("YouTube {} failed ({}): {}", what, status, body));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockServer, Reply};
+    
+    fn metadata(title: &str, description: &str, tags: &[&str]) -> VideoMetadata {
+        VideoMetadata {
+            title: title.to_string(),
+            description: description.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+    
+    #[test]
+    fn titles_are_cut_between_words() {
+        assert_eq!(fit_title("Short title"), "Short title");
+        let words = "word ".repeat(19);
+        let title = format!("{}, tails", words.trim_end());
+        assert_eq!(title.chars().count(), 101);
+        assert_eq!(fit_title(&title), words.trim_end());
+    }
+    
+    #[test]
+    fn titles_are_cut_by_characters_not_bytes() {
+        let words = "가나다 ".repeat(30);
+        assert_eq!(fit_title(&words), "가나다 ".repeat(25).trim_end());
+        let unbroken = "가".repeat(150);
+        assert_eq!(fit_title(&unbroken), "가".repeat(MAX_TITLE_CHARS));
+    }
+    
+    #[test]
+    fn shorts_hashtag_is_added_once() {
+        let added = metadata("Pasta", "Three tricks", &[]).optimized();
+        assert_eq!(added.description, format!("Three tricks\n\n{}", SHORTS_TAG));
+        let in_title = metadata("Pasta #shorts", "Three tricks", &[]).optimized();
+        assert_eq!(in_title.description, "Three tricks");
+    }
+    
+    #[test]
+    fn long_descriptions_keep_the_hashtag_within_the_limit() {
+        let optimized = metadata("Pasta", &"가".repeat(2000), &[]).optimized();
+        assert!(optimized.description.len() <= MAX_DESCRIPTION_BYTES);
+        assert!(optimized.description.ends_with(&format!("\n\n{}", SHORTS_TAG)));
+    }
+    
+    #[test]
+    fn tags_are_cleaned_and_capped() {
+        let optimized = metadata("<b>Pasta</b>", "", &["Food", " food ", "", "<cooking>"]).optimized();
+        assert_eq!(optimized.title, "bPasta/b");
+        assert_eq!(optimized.tags, ["Food", "cooking"]);
+        
+        let long = ["x".repeat(300), "y".repeat(150), "z".repeat(100)];
+        let long: Vec<&str> = long.iter().map(String::as_str).collect();
+        assert_eq!(metadata("Pasta", "", &long).optimized().tags.len(), 2);
+    }
+    
+    #[test]
+    fn capped_uploads_go_up_in_whole_chunk_units() {
+        assert_eq!(chunk_size(5_000_000, None), 5_000_000);
+        assert_eq!(chunk_size(5_000_000, Some(1024)), UPLOAD_CHUNK_UNIT);
+        assert_eq!(chunk_size(5_000_000, Some(100 * 1024)), 3 * UPLOAD_CHUNK_UNIT);
+    }
+    
+    #[test]
+    fn received_bytes_come_from_the_range_header() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(received_bytes(&headers), 0);
+        headers.insert(reqwest::header::RANGE, "bytes=0-262143".parse().unwrap());
+        assert_eq!(received_bytes(&headers), 262144);
+        headers.insert(reqwest::header::RANGE, "bytes=0-".parse().unwrap());
+        assert_eq!(received_bytes(&headers), 0);
+    }
+    
+    /// Bodies of the chunks `server` received
+    fn chunks(server: &MockServer) -> Vec<String> {
+        server.requests().iter().map(|r| r.split_once('\n').unwrap().1.to_string()).collect()
+    }
+    
+    #[tokio::test]
+    async fn uploads_carry_on_from_what_the_session_received() {
+        let server = MockServer::sequence(vec![
+            Reply::json(308, "").header("Range", "bytes=0-2"),
+            Reply::json(308, "").header("Range", "bytes=0-6"),
+            Reply::json(200, r#"{"id":"abc"}"#),
+        ])
+        .await;
+        let transfer = Arc::new(transfers::start(Direction::Upload, "chunk test", Some(10)));
+        let response = put_chunks("token", &server.url(), b"abcdefghij", "video/mp4", 4, transfer, None).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(chunks(&server), ["abcd", "defg", "hij"]);
+    }
+    
+    #[tokio::test]
+    async fn a_session_that_takes_nothing_fails_the_upload() {
+        let server = MockServer::start(Reply::json(308, "")).await;
+        let transfer = Arc::new(transfers::start(Direction::Upload, "stalled test", Some(10)));
+        let result = put_chunks("token", &server.url(), b"abcdefghij", "video/mp4", 4, transfer, None).await;
+        assert!(result.is_err());
+        assert_eq!(chunks(&server), ["abcd"]);
+    }
+}
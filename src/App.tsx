@@ -1,5 +1,7 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { onAction } from "@tauri-apps/plugin-notification";
 import "./App.css";
 
 interface Config {
@@ -17,6 +19,58 @@ interface DashboardStats {
   failed_jobs: number;
 }
 
+/** Error returned by every Tauri command */
+interface AppError {
+  code:
+    | "db"
+    | "http"
+    | "ffmpeg"
+    | "llm"
+    | "validation"
+    | "not_found"
+    | "not_configured"
+    | "internal";
+  message: string;
+  retryable: boolean;
+}
+
+/** Returned by get_queue_status and set_queue_paused */
+interface QueueStatus {
+  state: "idle" | "working" | "paused" | "error";
+  paused: boolean;
+  queued: number;
+  running: number;
+  dead: number;
+}
+
+/** Result of the test_* connection commands */
+interface TestResult {
+  ok: boolean;
+  status_code: number | null;
+  latency_ms: number;
+  detail: string;
+}
+
+interface Toast {
+  message: string;
+  retry?: () => void;
+}
+
+const isAppError = (e: unknown): e is AppError =>
+  typeof e === "object" && e !== null && "code" in e && "message" in e;
+
+const errorMessage = (e: unknown): string => {
+  if (!isAppError(e)) return String(e);
+  switch (e.code) {
+    case "not_configured":
+      return `${e.message} — 설정 탭에서 입력하세요`;
+    case "ffmpeg":
+      return `ffmpeg 오류: ${e.message}`;
+    default:
+      return e.message;
+  }
+};
+
 type TabType = "dashboard" | "settings";
 
 function App() {
@@ -35,12 +89,91 @@ function App() {
     failed_jobs: 0,
   });
   const [isRunning, setIsRunning] = useState(false);
-  const [toast, setToast] = useState<string | null>(null);
+  const [toast, setToast] = useState<Toast | null>(null);
   const [testing, setTesting] = useState<string | null>(null);
 
   useEffect(() => {
     loadConfig();
     loadStats();
+    loadQueueStatus();
+  }, []);
+
+  useEffect(() => {
+    // The queue can also be paused from the tray
+    const unlistenStatus = listen<QueueStatus>("queue:status", (event) => {
+      setIsRunning(!event.payload.paused);
+    });
+    const unlistenOpen = listen("tray:open-dashboard", () => {
+      setTab("dashboard");
+      loadStats();
+      loadQueueStatus();
+    });
+    return () => {
+      unlistenStatus.then((fn) => fn());
+      unlistenOpen.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<{ free_bytes: number }>("low-disk-space", (event) => {
+      const freeMb = Math.round(event.payload.free_bytes / 1024 / 1024);
+      showToast(`⚠️ 디스크 공간 부족: ${freeMb} MB 남음`);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<{ service: string; retry_in_secs: number }>("service:degraded", (event) => {
+      const { service, retry_in_secs } = event.payload;
+      showToast(`⚠️ ${service} 연결 실패가 반복되어 ${retry_in_secs}초 동안 호출을 중단합니다`);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    // Clicking a desktop notification about a job opens that job
+    const listener = onAction((notification) => {
+      const jobId = notification.extra?.job_id;
+      if (typeof jobId !== "number") return;
+      setTab("dashboard");
+      showToast(`📌 작업 #${jobId}`);
+      loadStats();
+    });
+    return () => {
+      listener.then((l) => l.unregister());
+    };
+  }, []);
+
+  useEffect(() => {
+    // The background health monitor pauses the queue while a critical dependency is down
+    const unlisten = listen<{ ok: boolean; checks: { name: string; status: string; critical: boolean }[] }>(
+      "health:changed",
+      (event) => {
+        const down = event.payload.checks.filter((c) => c.critical && c.status === "down").map((c) => c.name);
+        if (down.length > 0) {
+          showToast(`⚠️ 필수 서비스 중단: ${down.join(", ")} — 큐를 일시 정지합니다`);
+        }
+        loadQueueStatus();
+      },
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    // Video links opened with the app are added as trends
+    const unlisten = listen<{ trend_id: number }>("trend:added", (event) => {
+      showToast(`➕ 트렌드 #${event.payload.trend_id} 추가됨`);
+      loadStats();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const loadConfig = async () => {
@@ -61,47 +194,79 @@ function App() {
     }
   };
 
+  const loadQueueStatus = async () => {
+    try {
+      const status = await invoke<QueueStatus>("get_queue_status");
+      setIsRunning(!status.paused);
+    } catch (e) {
+      console.error("Failed to load queue status:", e);
+    }
+  };
+
+  const toggleQueue = async () => {
+    try {
+      const status = await invoke<QueueStatus>("set_queue_paused", { paused: isRunning });
+      setIsRunning(!status.paused);
+    } catch (e) {
+      showError("❌ 대기열 전환 실패: ", e, toggleQueue);
+    }
+  };
+
   const saveConfig = async () => {
     try {
-      await invoke("save_config", { config });
+      const saved = await invoke<Config>("save_config", { config });
+      setConfig(saved);
       showToast("✅ 설정이 저장되었습니다");
     } catch (e) {
-      showToast("❌ 저장 실패: " + e);
+      showError("❌ 저장 실패: ", e, saveConfig);
     }
   };
 
   const testApi = async (type: "youtube" | "telegram" | "ollama") => {
     setTesting(type);
     try {
-      let result = false;
+      let result: TestResult | null = null;
       switch (type) {
         case "youtube":
           if (config.youtube_api_key) {
-            result = await invoke<boolean>("test_youtube_api", { apiKey: config.youtube_api_key });
+            result = await invoke<TestResult>("test_youtube_api", { apiKey: config.youtube_api_key });
           }
           break;
         case "telegram":
           if (config.telegram_bot_token) {
-            result = await invoke<boolean>("test_telegram_bot", { token: config.telegram_bot_token });
+            result = await invoke<TestResult>("test_telegram_bot", {
+              token: config.telegram_bot_token,
+              chatId: config.telegram_chat_id,
+            });
           }
           break;
         case "ollama":
-          result = await invoke<boolean>("test_ollama", { endpoint: config.ollama_endpoint });
+          result = await invoke<TestResult>("test_ollama", { endpoint: config.ollama_endpoint });
           break;
       }
-      showToast(result ? "✅ 연결 성공!" : "❌ 연결 실패");
+      if (result) {
+        const status = result.status_code ? ` (${result.status_code}, ${result.latency_ms}ms)` : "";
+        showToast(`${result.ok ? "✅ 연결 성공" : "❌ 연결 실패"}: ${result.detail}${status}`);
+      }
     } catch (e) {
-      showToast("❌ 테스트 실패: " + e);
+      showError("❌ 테스트 실패: ", e, () => testApi(type));
     } finally {
       setTesting(null);
     }
   };
 
   const showToast = (message: string) => {
-    setToast(message);
+    setToast({ message });
     setTimeout(() => setToast(null), 3000);
   };
 
+  /** Show a command error, with a retry button when trying again may help */
+  const showError = (prefix: string, e: unknown, retry: () => void) => {
+    const retryable = isAppError(e) && e.retryable;
+    setToast({ message: prefix + errorMessage(e), retry: retryable ? retry : undefined });
+    setTimeout(() => setToast(null), retryable ? 8000 : 3000);
+  };
+
   const updateConfig = (key: keyof Config, value: string | number) => {
     setConfig((prev) => ({ ...prev, [key]: value || null }));
   };
@@ -156,7 +321,7 @@ function App() {
             <div className="control-bar">
               <button
                 className={`btn ${isRunning ? "btn-secondary" : "btn-success"}`}
-                onClick={() => setIsRunning(!isRunning)}
+                onClick={toggleQueue}
               >
                 {isRunning ? "⏸️ 일시정지" : "▶️ 시작"}
               </button>
@@ -268,7 +433,22 @@ function App() {
         )}
       </main>
 
-      {toast && <div className="toast">{toast}</div>}
+      {toast && (
+        <div className="toast">
+          {toast.message}
+          {toast.retry && (
+            <button
+              className="btn btn-secondary btn-small toast-action"
+              onClick={() => {
+                setToast(null);
+                toast.retry?.();
+              }}
+            >
+              다시 시도
+            </button>
+          )}
+        </div>
+      )}
     </div>
   );
 }